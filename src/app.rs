@@ -8,12 +8,17 @@ use crossterm::{
 use ratatui::{backend::CrosstermBackend, Terminal};
 use std::collections::HashMap;
 use std::io;
+use std::sync::Arc;
 
 use crate::{claude, features, ide, shared, ui};
 use features::todos::{ProjectTodoStats, SessionTodos, TodoItem, TodoManager};
-use shared::{Config, I18n, ModernTheme, ThemeMode};
+use shared::{Config, DataChange, DataWatcher, I18n, ModernTheme, ThemeMode};
 use tokio::sync::mpsc;
 
+/// Full refresh interval while the filesystem watcher is running. Watched changes
+/// arrive immediately, so polling only catches what the watcher may have missed.
+const WATCHED_REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
 /// Available tabs in the application
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Tab {
@@ -22,6 +27,7 @@ pub enum Tab {
     Sessions = 2,
     Todos = 3,
     Quota = 4,
+    Blocks = 5,
 }
 
 /// Application view modes
@@ -39,6 +45,85 @@ pub struct IdeSelectionState {
     pub project_path: std::path::PathBuf,
 }
 
+/// Sessions listed on the Sessions tab, most recent first
+pub const RECENT_SESSIONS: usize = 20;
+
+/// A session transcript open on the Sessions tab
+#[derive(Debug, Clone)]
+pub struct TranscriptView {
+    pub session_id: String,
+    pub messages: Vec<claude::transcript::TranscriptMessage>,
+    /// Lines scrolled past at the top
+    pub scroll: usize,
+    /// Show tool inputs and outputs in full instead of collapsing them
+    pub expand_tool_output: bool,
+    /// Rendered lines, written by the renderer
+    pub line_count: usize,
+    /// Rendered line each turn starts on, written by the renderer
+    pub turn_offsets: Vec<usize>,
+    /// Lines that fit on screen, written by the renderer
+    pub page_height: usize,
+    /// Message to scroll to on the next render, such as a search hit
+    pub focus_message: Option<usize>,
+}
+
+impl TranscriptView {
+    fn new(session_id: String, messages: Vec<claude::transcript::TranscriptMessage>) -> Self {
+        Self {
+            session_id,
+            messages,
+            scroll: 0,
+            expand_tool_output: false,
+            line_count: 0,
+            turn_offsets: Vec::new(),
+            page_height: 0,
+            focus_message: None,
+        }
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.line_count.saturating_sub(self.page_height)
+    }
+
+    fn scroll_down(&mut self, lines: usize) {
+        self.scroll = self.scroll.saturating_add(lines).min(self.max_scroll());
+    }
+
+    fn scroll_up(&mut self, lines: usize) {
+        self.scroll = self.scroll.saturating_sub(lines);
+    }
+
+    /// Scroll to the start of the next turn
+    fn next_turn(&mut self) {
+        if let Some(offset) = self.turn_offsets.iter().find(|&&o| o > self.scroll) {
+            self.scroll = (*offset).min(self.max_scroll());
+        }
+    }
+
+    /// Scroll to the start of the current turn, or the previous one when already there
+    fn prev_turn(&mut self) {
+        if let Some(offset) = self.turn_offsets.iter().rev().find(|&&o| o < self.scroll) {
+            self.scroll = *offset;
+        }
+    }
+}
+
+/// The session search overlay opened with `/`
+#[derive(Debug, Clone, Default)]
+pub struct SearchView {
+    /// Query being typed
+    pub input: String,
+    /// Query the hits (or the running search) belong to
+    pub last_query: String,
+    pub hits: Vec<claude::search::SearchHit>,
+    pub selected: usize,
+    /// Whether a search is running in the background
+    pub searching: bool,
+    /// Session files indexed so far by the running search
+    pub progress: Option<claude::ScanProgress>,
+    pub error: Option<String>,
+}
+
 /// Background data loading message
 #[derive(Debug)]
 #[allow(dead_code)] // Allow unused variants during migration
@@ -46,6 +131,28 @@ pub enum DataLoadingMessage {
     ProjectAnalytics(String, claude::ProjectAnalytics),
     ProjectTodoStats(String, ProjectTodoStats),
     RefreshComplete(RefreshResult),
+    /// A watched session file changed; carries its reloaded project
+    /// (None when the project no longer has any sessions)
+    SessionChanged {
+        session_path: std::path::PathBuf,
+        project: Option<claude::Project>,
+    },
+    /// Today's usage, reset time and quota after watched session files changed
+    UsageChanged(claude::UsageStats, String, claude::QuotaStatus),
+    /// A watched todo file changed; carries the rescanned todos
+    TodosChanged(HashMap<String, Vec<SessionTodos>>),
+    /// Session files parsed so far by a background scan
+    ScanProgress(claude::ScanProgress),
+    /// Session files indexed so far by the search for `query`
+    SearchProgress {
+        query: String,
+        progress: claude::ScanProgress,
+    },
+    /// Hits of a background search, or why it failed
+    SearchResults {
+        query: String,
+        result: Result<Vec<claude::search::SearchHit>, String>,
+    },
 }
 
 /// Result of a background refresh operation
@@ -56,6 +163,7 @@ pub struct RefreshResult {
     pub project_todos: HashMap<String, Vec<SessionTodos>>,
     pub usage_stats: claude::UsageStats,
     pub reset_time_str: String,
+    pub quota: claude::QuotaStatus,
     pub selected_project_name: Option<String>,
 }
 
@@ -73,6 +181,8 @@ pub struct LoadingStates {
     pub project_switching: bool,
     pub data_refresh: bool,
     pub analytics_loading: HashMap<String, bool>,
+    /// Progress of a background session scan that is still parsing files
+    pub scan_progress: Option<claude::ScanProgress>,
     pub spinner_frame: usize,
     pub last_spinner_update: std::time::Instant,
 }
@@ -83,6 +193,8 @@ pub struct StatusMessage {
     pub text: String,
     pub timestamp: std::time::Instant,
     pub message_type: StatusType,
+    /// Minimum time the message stays up, for messages that must not be missed
+    pub hold: std::time::Duration,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -99,6 +211,7 @@ impl LoadingStates {
             project_switching: false,
             data_refresh: false,
             analytics_loading: HashMap::new(),
+            scan_progress: None,
             spinner_frame: 0,
             last_spinner_update: std::time::Instant::now(),
         }
@@ -117,7 +230,10 @@ impl LoadingStates {
     }
 
     pub fn is_loading(&self) -> bool {
-        self.project_switching || self.data_refresh || !self.analytics_loading.is_empty()
+        self.project_switching
+            || self.data_refresh
+            || !self.analytics_loading.is_empty()
+            || self.scan_progress.is_some()
     }
 }
 
@@ -129,6 +245,7 @@ impl Tab {
             2 => Tab::Sessions,
             3 => Tab::Todos,
             4 => Tab::Quota,
+            5 => Tab::Blocks,
             _ => Tab::Overview,
         }
     }
@@ -141,11 +258,12 @@ impl Tab {
             Tab::Sessions => "Sessions",
             Tab::Todos => "Todos",
             Tab::Quota => "Quota",
+            Tab::Blocks => "Blocks",
         }
     }
 
     pub fn count() -> usize {
-        5
+        6
     }
 }
 
@@ -157,6 +275,8 @@ pub struct App {
     pub claude_manager: claude::ClaudeDataManager,
     /// Todo data manager
     pub todo_manager: TodoManager,
+    /// Claude data directories being read, primary first
+    pub claude_dirs: Vec<std::path::PathBuf>,
     /// Application configuration
     pub config: Config,
     /// Application theme
@@ -171,6 +291,8 @@ pub struct App {
     pub usage_stats: claude::UsageStats,
     /// Time until next quota reset
     pub reset_time_str: String,
+    /// Usage of the active block and the past week against the plan's limits
+    pub quota: claude::QuotaStatus,
     /// Last refresh time for background updates
     last_refresh: std::time::Instant,
     /// Current active tab
@@ -193,6 +315,18 @@ pub struct App {
     pub view_mode: ViewMode,
     /// Cached global analytics to avoid expensive recalculation
     cached_global_analytics: Option<(claude::UsageAnalytics, std::time::Instant)>,
+    /// Cached block timeline, oldest block first
+    cached_blocks: Option<(Vec<claude::blocks::BlockSummary>, std::time::Instant)>,
+    /// Blocks scrolled past at the top of the Blocks tab
+    pub blocks_scroll: usize,
+    /// Selected session in the Sessions tab list
+    pub selected_session: usize,
+    /// Transcript open on the Sessions tab
+    pub transcript: Option<TranscriptView>,
+    /// Search overlay, while open
+    pub search: Option<SearchView>,
+    /// Full-text index of session transcripts, shared with background searches
+    search_index: claude::search::SharedSearchIndex,
     /// IDE selection state
     pub ide_selection_state: Option<IdeSelectionState>,
     /// Last selected project index to detect changes
@@ -205,25 +339,31 @@ pub struct App {
     #[allow(dead_code)] // Unused during migration
     data_loader_tx: Option<mpsc::UnboundedSender<DataLoadingRequest>>,
     data_loader_rx: mpsc::UnboundedReceiver<DataLoadingMessage>,
+    /// Sender for the results of on-demand background tasks such as searches
+    data_tx: mpsc::UnboundedSender<DataLoadingMessage>,
     /// Current status message
     pub status_message: Option<StatusMessage>,
     /// Background refresh task handle
     refresh_tx: Option<mpsc::UnboundedSender<()>>,
     /// Flag to track if background refresh is in progress
     pub background_refresh_in_progress: bool,
+    /// Filesystem watcher on the data directories (None when polling only)
+    watcher: Option<DataWatcher>,
+    /// Alerts already raised, so each fires once per window
+    alert_log: claude::alerts::AlertLog,
 }
 
 impl App {
-    /// Create a new App instance
-    pub async fn new() -> Result<Self> {
-        let mut claude_manager = claude::ClaudeDataManager::new()?;
+    /// Create a new App instance reading from the given Claude data directories
+    pub async fn new(claude_dirs: Vec<std::path::PathBuf>) -> Result<Self> {
+        let mut claude_manager = claude::ClaudeDataManager::new(&claude_dirs)?;
 
         // Update OpenRouter pricing cache in background if needed
         if let Err(e) = claude_manager.update_pricing_cache_if_needed().await {
             eprintln!("Warning: Failed to update pricing cache: {e}");
             // Continue with existing cache or fallback pricing
         }
-        let todo_manager = TodoManager::new()?;
+        let todo_manager = TodoManager::new(&claude_dirs)?;
         let config = Config::load()?;
         let theme = match config.theme_mode {
             ThemeMode::Dark => ModernTheme::dark(),
@@ -243,6 +383,7 @@ impl App {
         let projects = claude_manager.scan_projects()?;
         let usage_stats = claude_manager.calculate_today_usage()?;
         let reset_time_str = claude_manager.time_until_reset();
+        let quota = claude_manager.quota_status();
         let project_todos = todo_manager.scan_todos()?;
 
         let current_tab = Tab::from_index(config.current_tab);
@@ -258,6 +399,7 @@ impl App {
             should_quit: false,
             claude_manager,
             todo_manager,
+            claude_dirs,
             config,
             theme,
             i18n,
@@ -265,6 +407,7 @@ impl App {
             selected_project: 0,
             usage_stats,
             reset_time_str,
+            quota,
             last_refresh: std::time::Instant::now(),
             current_tab,
             project_todos,
@@ -276,23 +419,39 @@ impl App {
             tab_render_cache: HashMap::new(),
             view_mode: ViewMode::ProjectView,
             cached_global_analytics: None,
+            cached_blocks: None,
+            blocks_scroll: 0,
+            selected_session: 0,
+            transcript: None,
+            search: None,
+            search_index: claude::search::SearchIndex::shared(),
             ide_selection_state: None,
             last_selected_project: 0,
             needs_redraw: true,
             loading_states: LoadingStates::new(),
             data_loader_tx: Some(req_tx),
             data_loader_rx: data_rx,
+            data_tx: data_tx.clone(),
             status_message: None,
             refresh_tx: Some(refresh_tx),
             background_refresh_in_progress: false,
+            watcher: None,
+            alert_log: claude::alerts::AlertLog::path()
+                .map(|path| claude::alerts::AlertLog::load(&path))
+                .unwrap_or_default(),
         };
 
+        // Watch the data directories; if that fails, polling keeps the data current
+        app.watcher = app.spawn_watcher_task(data_tx.clone());
+
         // Spawn background refresh task
         app.spawn_background_refresh_task(refresh_rx, data_tx).await;
 
         // Preload data for initial project and nearby ones
         app.request_background_loading();
 
+        app.check_alerts();
+
         Ok(app)
     }
 
@@ -338,11 +497,50 @@ impl App {
                         self.loading_states.data_refresh = false;
                         self.needs_redraw = true;
                     }
+                    DataLoadingMessage::SessionChanged {
+                        session_path,
+                        project,
+                    } => {
+                        self.apply_session_change(&session_path, project);
+                        self.needs_redraw = true;
+                    }
+                    DataLoadingMessage::UsageChanged(usage_stats, reset_time_str, quota) => {
+                        self.usage_stats = usage_stats;
+                        self.reset_time_str = reset_time_str;
+                        self.quota = quota;
+                        self.cached_blocks = None;
+                        self.tab_render_cache.clear();
+                        self.check_alerts();
+                        self.needs_redraw = true;
+                    }
+                    DataLoadingMessage::TodosChanged(project_todos) => {
+                        self.project_todos = project_todos;
+                        self.cached_todo_stats.clear();
+                        self.tab_render_cache.clear();
+                        self.needs_redraw = true;
+                    }
+                    DataLoadingMessage::ScanProgress(progress) => {
+                        self.loading_states.scan_progress =
+                            (!progress.is_done()).then_some(progress);
+                        self.needs_redraw = true;
+                    }
+                    DataLoadingMessage::SearchProgress { query, progress } => {
+                        if let Some(view) =
+                            self.search.as_mut().filter(|view| view.last_query == query)
+                        {
+                            view.progress = (!progress.is_done()).then_some(progress);
+                            self.needs_redraw = true;
+                        }
+                    }
+                    DataLoadingMessage::SearchResults { query, result } => {
+                        self.apply_search_results(&query, result);
+                        self.needs_redraw = true;
+                    }
                 }
             }
 
             // Check if it's time to refresh (non-blocking)
-            let refresh_interval = self.config.refresh_interval();
+            let refresh_interval = self.refresh_interval();
             if self.last_refresh.elapsed() >= refresh_interval {
                 self.trigger_background_refresh();
             }
@@ -400,10 +598,16 @@ impl App {
             return Ok(());
         }
 
-        // Handle help overlay second
+        // The search overlay takes the keys while it is open
+        if self.search.is_some() {
+            self.handle_search_key(key);
+            return Ok(());
+        }
+
+        // Handle help overlay third
         if self.config.show_help {
             match key {
-                KeyCode::Char('?') | KeyCode::Char('/') | KeyCode::Esc => {
+                KeyCode::Char('?') | KeyCode::Esc => {
                     self.config.toggle_help();
                     let _ = self.config.save(); // Save config after change
                 }
@@ -412,6 +616,12 @@ impl App {
             return Ok(());
         }
 
+        // An open transcript takes the keys until it is closed
+        if self.transcript.is_some() {
+            self.handle_transcript_key(key)?;
+            return Ok(());
+        }
+
         // Normal key handling when help is not shown
         match key {
             KeyCode::Esc => {
@@ -445,12 +655,248 @@ impl App {
             KeyCode::Char('t') | KeyCode::Char('ㅅ') => self.toggle_theme()?,
             KeyCode::Char('g') | KeyCode::Char('ㅎ') => self.toggle_global_dashboard()?,
             KeyCode::Char('o') | KeyCode::Char('ㅗ') => self.show_ide_selection()?,
-            KeyCode::Char('?') | KeyCode::Char('/') => self.toggle_help()?,
+            KeyCode::Char('?') => self.toggle_help()?,
+            KeyCode::Char('/') => self.search = Some(SearchView::default()),
+            KeyCode::Char('p') | KeyCode::Char('ㅔ') if self.current_tab == Tab::Quota => {
+                self.toggle_pinned_block_limit();
+            }
+            KeyCode::PageDown if self.current_tab == Tab::Blocks => {
+                self.blocks_scroll = self.blocks_scroll.saturating_add(5);
+            }
+            KeyCode::PageUp if self.current_tab == Tab::Blocks => {
+                self.blocks_scroll = self.blocks_scroll.saturating_sub(5);
+            }
+            KeyCode::Char(']') if self.current_tab == Tab::Sessions => {
+                let count = self
+                    .selected_project()
+                    .map_or(0, |project| project.sessions.len().min(RECENT_SESSIONS));
+                if self.selected_session + 1 < count {
+                    self.selected_session += 1;
+                }
+            }
+            KeyCode::Char('[') if self.current_tab == Tab::Sessions => {
+                self.selected_session = self.selected_session.saturating_sub(1);
+            }
+            KeyCode::Enter if self.current_tab == Tab::Sessions => self.open_transcript(),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Handle keys while a transcript is open
+    fn handle_transcript_key(&mut self, key: KeyCode) -> Result<()> {
+        match key {
+            KeyCode::Char('?') => return self.toggle_help(),
+            KeyCode::Char('/') => {
+                self.search = Some(SearchView::default());
+                return Ok(());
+            }
+            _ => {}
+        }
+        let Some(view) = self.transcript.as_mut() else {
+            return Ok(());
+        };
+
+        let page = view.page_height.saturating_sub(1).max(1);
+        match key {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('ㅂ') => self.transcript = None,
+            KeyCode::Char('j') | KeyCode::Char('ㅓ') | KeyCode::Down => view.scroll_down(1),
+            KeyCode::Char('k') | KeyCode::Char('ㅏ') | KeyCode::Up => view.scroll_up(1),
+            KeyCode::PageDown | KeyCode::Char(' ') => view.scroll_down(page),
+            KeyCode::PageUp => view.scroll_up(page),
+            KeyCode::Char('g') | KeyCode::Home => view.scroll = 0,
+            KeyCode::Char('G') | KeyCode::End => view.scroll = view.max_scroll(),
+            KeyCode::Char('n') | KeyCode::Char('ㅜ') => view.next_turn(),
+            KeyCode::Char('p') | KeyCode::Char('ㅔ') => view.prev_turn(),
+            KeyCode::Char('e') | KeyCode::Char('ㄷ') => {
+                view.expand_tool_output = !view.expand_tool_output;
+            }
             _ => {}
         }
         Ok(())
     }
 
+    /// Open the transcript of the selected session
+    fn open_transcript(&mut self) {
+        let Some(session) = self
+            .selected_project()
+            .and_then(|project| project.sessions.get(self.selected_session))
+            .cloned()
+        else {
+            return;
+        };
+
+        match self.claude_manager.session_transcript(&session.path) {
+            Ok(messages) => self.transcript = Some(TranscriptView::new(session.id, messages)),
+            Err(e) => self.show_status(
+                &format!("Failed to read session transcript: {e}"),
+                StatusType::Error,
+            ),
+        }
+    }
+
+    /// Handle keys while the search overlay is open. Letters are typed into the
+    /// query, so only the arrow keys move through the hits.
+    fn handle_search_key(&mut self, key: KeyCode) {
+        let Some(view) = self.search.as_mut() else {
+            return;
+        };
+
+        match key {
+            KeyCode::Esc => self.search = None,
+            // A changed query is searched first; Enter again opens the selected hit
+            KeyCode::Enter if view.input != view.last_query => self.start_search(),
+            KeyCode::Enter => self.open_search_hit(),
+            KeyCode::Down if view.selected + 1 < view.hits.len() => view.selected += 1,
+            KeyCode::Up => view.selected = view.selected.saturating_sub(1),
+            KeyCode::Backspace => {
+                view.input.pop();
+            }
+            KeyCode::Char(c) => view.input.push(c),
+            _ => {}
+        }
+    }
+
+    /// Run the typed query in the background; the hits arrive as `SearchResults`
+    fn start_search(&mut self) {
+        let Some(view) = self.search.as_mut() else {
+            return;
+        };
+        view.last_query = view.input.clone();
+        view.hits.clear();
+        view.selected = 0;
+
+        let query = match claude::search::SearchQuery::parse(&view.input) {
+            Ok(query) => query,
+            Err(e) => {
+                view.error = Some(e.to_string());
+                return;
+            }
+        };
+        view.error = None;
+        view.searching = true;
+        view.progress = None;
+
+        let query_text = view.last_query.clone();
+        let claude_dirs = self.claude_dirs.clone();
+        let ingestor = self.claude_manager.ingestor();
+        let search_index = self.search_index.clone();
+        let projects = self.projects.clone();
+        let data_tx = self.data_tx.clone();
+
+        // The first search indexes every session, so it runs on the blocking pool
+        tokio::task::spawn_blocking(move || {
+            let progress_tx = data_tx.clone();
+            let progress_query = query_text.clone();
+            let on_progress = move |progress| {
+                let _ = progress_tx.send(DataLoadingMessage::SearchProgress {
+                    query: progress_query.clone(),
+                    progress,
+                });
+            };
+            let result = Self::perform_search(
+                &claude_dirs,
+                ingestor,
+                &search_index,
+                &projects,
+                &query,
+                &on_progress,
+            )
+            .map_err(|e| e.to_string());
+            let _ = data_tx.send(DataLoadingMessage::SearchResults {
+                query: query_text,
+                result,
+            });
+        });
+    }
+
+    /// Search every session, bringing the shared index up to date first
+    fn perform_search(
+        claude_dirs: &[std::path::PathBuf],
+        ingestor: claude::SharedIngestor,
+        search_index: &claude::search::SharedSearchIndex,
+        projects: &[claude::Project],
+        query: &claude::search::SearchQuery,
+        on_progress: &(dyn Fn(claude::ScanProgress) + Sync),
+    ) -> Result<Vec<claude::search::SearchHit>> {
+        let claude_manager = claude::ClaudeDataManager::with_ingestor(claude_dirs, ingestor)?;
+        let mut index = search_index
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Search index is unavailable"))?;
+        let hits = claude_manager.search_sessions(&mut index, projects, query, on_progress)?;
+        let _ = index.save_if_due();
+        Ok(hits)
+    }
+
+    /// Show the hits of a finished search, unless the query changed meanwhile
+    fn apply_search_results(
+        &mut self,
+        query: &str,
+        result: Result<Vec<claude::search::SearchHit>, String>,
+    ) {
+        let Some(view) = self.search.as_mut().filter(|view| view.last_query == query) else {
+            return;
+        };
+        view.searching = false;
+        view.progress = None;
+        match result {
+            Ok(hits) => view.hits = hits,
+            Err(e) => view.error = Some(e),
+        }
+    }
+
+    /// Open the selected hit's session on the Sessions tab, scrolled to the match
+    fn open_search_hit(&mut self) {
+        let Some(hit) = self
+            .search
+            .as_ref()
+            .and_then(|view| view.hits.get(view.selected))
+            .cloned()
+        else {
+            return;
+        };
+
+        let messages = match self.claude_manager.session_transcript(&hit.session_path) {
+            Ok(messages) => messages,
+            Err(e) => {
+                self.show_status(
+                    &format!("Failed to read session transcript: {e}"),
+                    StatusType::Error,
+                );
+                return;
+            }
+        };
+
+        if let Some(index) = self
+            .projects
+            .iter()
+            .position(|project| hit.session_path.parent() == Some(project.data_dir.as_path()))
+        {
+            if index != self.selected_project {
+                self.selected_project = index;
+                self.last_selected_project = index;
+                self.clear_tab_render_cache();
+                self.request_background_loading();
+            }
+            self.selected_session = self.projects[index]
+                .sessions
+                .iter()
+                .take(RECENT_SESSIONS)
+                .position(|session| session.id == hit.session_id)
+                .unwrap_or(0);
+        }
+
+        self.view_mode = ViewMode::ProjectView;
+        self.current_tab = Tab::Sessions;
+        self.config.set_current_tab(Tab::Sessions as usize);
+        let _ = self.config.save(); // Save config after change
+
+        let mut view = TranscriptView::new(hit.session_id, messages);
+        view.focus_message = Some(hit.message);
+        self.transcript = Some(view);
+        self.search = None;
+    }
+
     /// Handle refresh interval key presses
     fn handle_refresh_interval_key(&mut self, key: KeyCode) -> Result<()> {
         if let KeyCode::Char(c) = key {
@@ -478,6 +924,7 @@ impl App {
             Tab::Sessions => "Sessions",
             Tab::Todos => "Todos",
             Tab::Quota => "Quota",
+            Tab::Blocks => "Blocks",
         };
         self.show_status(&format!("Switched to {tab_name} tab"), StatusType::Info);
 
@@ -502,6 +949,7 @@ impl App {
             Tab::Sessions => "Sessions",
             Tab::Todos => "Todos",
             Tab::Quota => "Quota",
+            Tab::Blocks => "Blocks",
         };
         self.show_status(&format!("Switched to {tab_name} tab"), StatusType::Info);
 
@@ -521,6 +969,7 @@ impl App {
 
                 self.clear_tab_render_cache();
                 self.last_selected_project = self.selected_project;
+                self.selected_session = 0;
                 self.request_background_loading();
 
                 self.loading_states.project_switching = false;
@@ -546,6 +995,7 @@ impl App {
 
                 self.clear_tab_render_cache();
                 self.last_selected_project = self.selected_project;
+                self.selected_session = 0;
                 self.request_background_loading();
 
                 self.loading_states.project_switching = false;
@@ -604,42 +1054,32 @@ impl App {
 
     /// Get todo statistics for the currently selected project
     pub fn selected_project_todo_stats(&self) -> Option<ProjectTodoStats> {
-        if let Some(project) = self.selected_project() {
-            let project_path = project.path.to_string_lossy().to_string();
-
-            // Try exact match first
-            if let Some(project_todos) = self.project_todos.get(&project_path) {
-                return Some(self.todo_manager.calculate_project_stats(project_todos));
-            }
-
-            // Try to find a match by checking all keys
-            for (key, project_todos) in &self.project_todos {
-                if key == &project_path || key.ends_with(&project.name) {
-                    return Some(self.todo_manager.calculate_project_stats(project_todos));
-                }
-            }
-        }
-        None
+        self.selected_project_session_todos()
+            .map(|project_todos| self.todo_manager.calculate_project_stats(project_todos))
     }
 
     /// Get todos for the currently selected project
     pub fn selected_project_todos(&self) -> Vec<(String, TodoItem)> {
-        if let Some(project) = self.selected_project() {
-            let project_path = project.path.to_string_lossy().to_string();
+        self.selected_project_session_todos()
+            .map(|project_todos| self.todo_manager.get_project_todos_sorted(project_todos))
+            .unwrap_or_default()
+    }
 
-            // Try exact match first
-            if let Some(project_todos) = self.project_todos.get(&project_path) {
-                return self.todo_manager.get_project_todos_sorted(project_todos);
-            }
+    /// Get the todo files (one per session agent) of the currently selected project
+    pub fn selected_project_session_todos(&self) -> Option<&Vec<SessionTodos>> {
+        let project = self.selected_project()?;
+        let project_path = project.path.to_string_lossy().to_string();
 
-            // Try to find a match by checking all keys
-            for (key, project_todos) in &self.project_todos {
-                if key == &project_path || key.ends_with(&project.name) {
-                    return self.todo_manager.get_project_todos_sorted(project_todos);
-                }
-            }
+        // Try exact match first
+        if let Some(project_todos) = self.project_todos.get(&project_path) {
+            return Some(project_todos);
         }
-        Vec::new()
+
+        // Try to find a match by checking all keys
+        self.project_todos
+            .iter()
+            .find(|(key, _)| *key == &project_path || key.ends_with(&project.name))
+            .map(|(_, project_todos)| project_todos)
     }
 
     /// Toggle theme mode
@@ -687,6 +1127,26 @@ impl App {
         Ok(())
     }
 
+    /// Pin the inferred block limit as the configured one, or unpin a configured limit
+    fn toggle_pinned_block_limit(&mut self) {
+        let message = if let Some(pinned) = self.config.quota.block_tokens.take() {
+            format!("Unpinned block limit of {pinned} tokens")
+        } else if let Some(inferred) = self.quota.inferred {
+            self.config.quota.block_tokens = Some(inferred.tokens);
+            format!("Pinned block limit at {} tokens", inferred.tokens)
+        } else {
+            self.show_status(
+                "No completed blocks to infer a limit from",
+                StatusType::Warning,
+            );
+            return;
+        };
+
+        let _ = self.config.save(); // Save config after change
+        self.quota = self.quota.clone().with_settings(&self.config.quota);
+        self.show_status(&message, StatusType::Success);
+    }
+
     /// Toggle help overlay
     fn toggle_help(&mut self) -> Result<()> {
         self.config.toggle_help();
@@ -855,41 +1315,114 @@ impl App {
         Ok(&self.cached_global_analytics.as_ref().unwrap().0)
     }
 
+    /// Get the cached block timeline, rebuilding it once it is stale
+    pub fn get_usage_blocks(&mut self) -> &[claude::blocks::BlockSummary] {
+        const CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(30);
+
+        let should_refresh = self
+            .cached_blocks
+            .as_ref()
+            .map(|(_, timestamp)| timestamp.elapsed() > CACHE_TTL)
+            .unwrap_or(true);
+
+        if should_refresh {
+            let blocks = self.claude_manager.usage_blocks().unwrap_or_default();
+            self.cached_blocks = Some((blocks, std::time::Instant::now()));
+        }
+
+        self.cached_blocks
+            .as_ref()
+            .map(|(blocks, _)| blocks.as_slice())
+            .unwrap_or_default()
+    }
+
     /// Show a status message to the user
     pub fn show_status(&mut self, text: &str, status_type: StatusType) {
+        self.show_status_for(text, status_type, std::time::Duration::ZERO);
+    }
+
+    /// Show a status message that stays up for at least `hold`
+    fn show_status_for(&mut self, text: &str, status_type: StatusType, hold: std::time::Duration) {
         self.status_message = Some(StatusMessage {
             text: text.to_string(),
             timestamp: std::time::Instant::now(),
             message_type: status_type,
+            hold,
         });
         self.needs_redraw = true;
     }
 
-    /// Clear status message if it's older than the specified duration
+    /// Clear status message if it's older than the specified duration (or its hold time)
     pub fn update_status_message(&mut self, max_age: std::time::Duration) {
         if let Some(ref msg) = self.status_message {
-            if msg.timestamp.elapsed() > max_age {
+            if msg.timestamp.elapsed() > max_age.max(msg.hold) {
                 self.status_message = None;
                 self.needs_redraw = true;
             }
         }
     }
 
+    /// Raise the budget and block alerts crossed for the first time in their window:
+    /// a banner, plus the bell, desktop notification or command enabled in the config
+    fn check_alerts(&mut self) {
+        let now = chrono::Utc::now();
+        let fired: Vec<claude::alerts::Alert> =
+            claude::alerts::evaluate(&self.quota, &self.config.budgets, &self.config.alerts)
+                .into_iter()
+                .filter(|alert| self.alert_log.record(alert, now))
+                .collect();
+        if fired.is_empty() {
+            return;
+        }
+
+        // Losing the log only means an alert may repeat after a restart
+        if let Some(path) = claude::alerts::AlertLog::path() {
+            let _ = self.alert_log.save(&path);
+        }
+
+        let mut text = fired
+            .iter()
+            .map(|alert| alert.message.as_str())
+            .collect::<Vec<_>>()
+            .join(" · ");
+        let errors: Vec<anyhow::Error> = fired
+            .iter()
+            .filter_map(|alert| claude::alerts::notify(alert, &self.config.alerts).err())
+            .collect();
+        if let Some(e) = errors.first() {
+            text = format!("{text} ({e})");
+        }
+
+        let status_type = if fired.iter().any(|alert| alert.is_exceeded()) {
+            StatusType::Error
+        } else {
+            StatusType::Warning
+        };
+        self.show_status_for(&text, status_type, std::time::Duration::from_secs(10));
+    }
+
     /// Spawn background refresh task
     async fn spawn_background_refresh_task(
         &self,
         mut refresh_rx: mpsc::UnboundedReceiver<()>,
         data_tx: mpsc::UnboundedSender<DataLoadingMessage>,
     ) {
-        // Clone the paths we need for the background task
-        let claude_dir = dirs::home_dir()
-            .unwrap_or_else(|| std::path::PathBuf::from("."))
-            .join(".claude");
+        // Clone the paths we need for the background task, and share parsed
+        // session entries so each refresh only reads newly appended lines
+        let claude_dirs = self.claude_dirs.clone();
+        let ingestor = self.claude_manager.ingestor();
 
         tokio::spawn(async move {
             while (refresh_rx.recv().await).is_some() {
-                // Perform refresh in background
-                if let Ok(result) = Self::perform_background_refresh(&claude_dir).await {
+                // Perform refresh in background, reporting progress while files are parsed
+                let progress_tx = data_tx.clone();
+                let on_progress = move |progress| {
+                    let _ = progress_tx.send(DataLoadingMessage::ScanProgress(progress));
+                };
+                if let Ok(result) =
+                    Self::perform_background_refresh(&claude_dirs, ingestor.clone(), &on_progress)
+                        .await
+                {
                     let _ = data_tx.send(DataLoadingMessage::RefreshComplete(result));
                 }
             }
@@ -897,29 +1430,170 @@ impl App {
     }
 
     /// Perform background refresh without blocking UI
-    async fn perform_background_refresh(_claude_dir: &std::path::Path) -> Result<RefreshResult> {
+    async fn perform_background_refresh(
+        claude_dirs: &[std::path::PathBuf],
+        ingestor: claude::SharedIngestor,
+        on_progress: &(dyn Fn(claude::ScanProgress) + Sync),
+    ) -> Result<RefreshResult> {
         // Create new managers for background task
-        let mut claude_manager = claude::ClaudeDataManager::new()?;
-        let todo_manager = features::todos::TodoManager::new()?;
+        let mut claude_manager = claude::ClaudeDataManager::with_ingestor(claude_dirs, ingestor)?;
+        let todo_manager = features::todos::TodoManager::new(claude_dirs)?;
 
         // Update pricing cache if needed
         let _ = claude_manager.update_pricing_cache_if_needed().await;
 
-        // Perform refresh operations
+        // Perform refresh operations; scanning projects parses changed files first,
+        // so today's usage is computed from already ingested entries
+        let projects = claude_manager.scan_projects_with_progress(on_progress)?;
         let usage_stats = claude_manager.calculate_today_usage()?;
         let reset_time_str = claude_manager.time_until_reset();
-        let projects = claude_manager.scan_projects()?;
+        let quota = claude_manager.quota_status();
         let project_todos = todo_manager.scan_todos()?;
+        let _ = claude_manager.save_index_if_due();
 
         Ok(RefreshResult {
             projects,
             project_todos,
             usage_stats,
             reset_time_str,
+            quota,
             selected_project_name: None,
         })
     }
 
+    /// Start the filesystem watcher and a task that reloads whatever it reports.
+    /// Returns None if the data directories cannot be watched.
+    fn spawn_watcher_task(
+        &self,
+        data_tx: mpsc::UnboundedSender<DataLoadingMessage>,
+    ) -> Option<DataWatcher> {
+        let (change_tx, mut change_rx) = mpsc::unbounded_channel::<DataChange>();
+        let watcher = DataWatcher::start(&self.claude_dirs, move |change| {
+            let _ = change_tx.send(change);
+        })
+        .ok()?;
+
+        // One manager for every refresh, so config and pricing are loaded only once
+        let claude_dirs = Arc::new(self.claude_dirs.clone());
+        let claude_manager = Arc::new(
+            claude::ClaudeDataManager::with_ingestor(
+                &self.claude_dirs,
+                self.claude_manager.ingestor(),
+            )
+            .ok()?,
+        );
+
+        tokio::spawn(async move {
+            while let Some(first) = change_rx.recv().await {
+                // Claude writes several lines at once; reload each file once per burst
+                let mut changes = vec![first];
+                while let Ok(change) = change_rx.try_recv() {
+                    if !changes.contains(&change) {
+                        changes.push(change);
+                    }
+                }
+
+                // Reading and parsing the changed files blocks, so keep it off the runtime
+                let claude_dirs = claude_dirs.clone();
+                let claude_manager = claude_manager.clone();
+                let refresh = tokio::task::spawn_blocking(move || {
+                    Self::perform_change_refresh(&claude_dirs, &claude_manager, &changes)
+                });
+                if let Ok(Ok(messages)) = refresh.await {
+                    for message in messages {
+                        let _ = data_tx.send(message);
+                    }
+                }
+            }
+        });
+
+        Some(watcher)
+    }
+
+    /// Reload only the data affected by watched file changes
+    fn perform_change_refresh(
+        claude_dirs: &[std::path::PathBuf],
+        claude_manager: &claude::ClaudeDataManager,
+        changes: &[DataChange],
+    ) -> Result<Vec<DataLoadingMessage>> {
+        let mut messages = Vec::new();
+
+        let mut sessions_changed = false;
+        let mut todos_changed = false;
+        for change in changes {
+            match change {
+                DataChange::Session(session_path) => {
+                    sessions_changed = true;
+                    let project = session_path
+                        .parent()
+                        .and_then(|dir| claude_manager.load_project(dir).ok().flatten());
+                    messages.push(DataLoadingMessage::SessionChanged {
+                        session_path: session_path.clone(),
+                        project,
+                    });
+                }
+                DataChange::Todos(_) => todos_changed = true,
+            }
+        }
+
+        if sessions_changed {
+            messages.push(DataLoadingMessage::UsageChanged(
+                claude_manager.calculate_today_usage()?,
+                claude_manager.time_until_reset(),
+                claude_manager.quota_status(),
+            ));
+        }
+        if todos_changed {
+            let todo_manager = TodoManager::new(claude_dirs)?;
+            messages.push(DataLoadingMessage::TodosChanged(todo_manager.scan_todos()?));
+        }
+
+        Ok(messages)
+    }
+
+    /// Replace the project that owns a changed session file
+    fn apply_session_change(
+        &mut self,
+        session_path: &std::path::Path,
+        project: Option<claude::Project>,
+    ) {
+        let old_selected_name = self.selected_project_name();
+        let data_dir = session_path.parent();
+        let existing = self
+            .projects
+            .iter()
+            .position(|p| Some(p.data_dir.as_path()) == data_dir);
+
+        match (existing, project) {
+            (Some(index), Some(project)) => {
+                self.cached_analytics.remove(&project.name);
+                self.projects[index] = project;
+            }
+            (None, Some(project)) => self.projects.push(project),
+            (Some(index), None) => {
+                let removed = self.projects.remove(index);
+                self.cached_analytics.remove(&removed.name);
+            }
+            (None, None) => return,
+        }
+
+        claude::ClaudeDataManager::sort_projects_by_activity(&mut self.projects);
+        self.restore_selected_project(old_selected_name);
+
+        self.cached_daily_usage = None;
+        self.tab_render_cache.clear();
+    }
+
+    /// Interval between full refreshes; longer while the watcher delivers changes
+    fn refresh_interval(&self) -> std::time::Duration {
+        let configured = self.config.refresh_interval();
+        if self.watcher.is_some() {
+            configured.max(WATCHED_REFRESH_INTERVAL)
+        } else {
+            configured
+        }
+    }
+
     /// Trigger background refresh
     pub fn trigger_background_refresh(&mut self) {
         if !self.background_refresh_in_progress {
@@ -936,20 +1610,39 @@ impl App {
     /// Apply background refresh result to the application state
     fn apply_refresh_result(&mut self, result: RefreshResult) {
         // Store current selected project name for preservation
-        let old_selected_name =
-            if !self.projects.is_empty() && self.selected_project < self.projects.len() {
-                Some(self.projects[self.selected_project].name.clone())
-            } else {
-                None
-            };
+        let old_selected_name = self.selected_project_name();
 
         // Apply the refresh result
         self.projects = result.projects;
         self.project_todos = result.project_todos;
         self.usage_stats = result.usage_stats;
         self.reset_time_str = result.reset_time_str;
+        self.quota = result.quota;
+
+        self.restore_selected_project(old_selected_name);
+
+        // Update refresh time and clear caches
+        self.last_refresh = std::time::Instant::now();
+        self.cached_analytics.clear();
+        self.cached_todo_stats.clear();
+        self.cached_daily_usage = None;
+        self.cached_blocks = None;
+        self.tab_render_cache.clear();
+        self.last_cache_update = std::time::Instant::now();
+
+        // Refresh completed silently without status message
+        self.check_alerts();
+    }
 
-        // Restore selected project
+    /// Name of the selected project, used to keep the selection when the list changes
+    fn selected_project_name(&self) -> Option<String> {
+        self.projects
+            .get(self.selected_project)
+            .map(|project| project.name.clone())
+    }
+
+    /// Select the project with the given name again after the list was replaced or re-sorted
+    fn restore_selected_project(&mut self, old_selected_name: Option<String>) {
         self.selected_project = if let Some(old_name) = old_selected_name {
             self.projects
                 .iter()
@@ -966,22 +1659,17 @@ impl App {
                 self.projects.len() - 1
             };
         }
-
-        // Update refresh time and clear caches
-        self.last_refresh = std::time::Instant::now();
-        self.cached_analytics.clear();
-        self.cached_todo_stats.clear();
-        self.cached_daily_usage = None;
-        self.tab_render_cache.clear();
-        self.last_cache_update = std::time::Instant::now();
-
-        // Refresh completed silently without status message
     }
 
     /// Clean up resources before exiting
     async fn cleanup(&mut self) -> Result<()> {
         // Save current configuration before exiting
         self.config.save()?;
+        // Persist parsed sessions so the next start does not re-read them
+        let _ = self.claude_manager.save_index();
+        if let Ok(mut index) = self.search_index.lock() {
+            let _ = index.save();
+        }
         Ok(())
     }
 }
@@ -0,0 +1,323 @@
+//! Budget and block-usage alerts (the `alerts` config section)
+//!
+//! An alert is raised when the active block crosses one of the configured
+//! percentages of its limit, or a cost budget crosses one of its percentages.
+//! Each alert fires once per window: a threshold crossed in one block fires again
+//! in the next block, but not on every refresh. Fired alerts are remembered in the
+//! user cache directory so restarting the dashboard does not repeat them.
+//!
+//! Besides the dashboard banner, an alert can ring the terminal bell, send a
+//! desktop notification through `notify-send` (which talks to the D-Bus
+//! notification service) and run a command hook.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use super::budget::{BudgetPeriod, BudgetSettings};
+use super::quota::QuotaStatus;
+use crate::shared::write_atomically;
+
+/// How alerts are raised and at which percentages
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertSettings {
+    /// Percentages of the block limit that raise an alert
+    #[serde(default = "default_block_thresholds")]
+    pub block_thresholds: Vec<u32>,
+    /// Percentages of a cost budget that raise an alert
+    #[serde(default = "default_budget_thresholds")]
+    pub budget_thresholds: Vec<u32>,
+    /// Send a desktop notification with `notify-send`
+    #[serde(default)]
+    pub desktop: bool,
+    /// Ring the terminal bell
+    #[serde(default)]
+    pub bell: bool,
+    /// Shell command run for each alert, with the alert in `CC_ENHANCED_ALERT_*` variables
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+}
+
+impl Default for AlertSettings {
+    fn default() -> Self {
+        Self {
+            block_thresholds: default_block_thresholds(),
+            budget_thresholds: default_budget_thresholds(),
+            desktop: false,
+            bell: false,
+            command: None,
+        }
+    }
+}
+
+fn default_block_thresholds() -> Vec<u32> {
+    vec![50, 80, 95]
+}
+
+fn default_budget_thresholds() -> Vec<u32> {
+    vec![80, 100]
+}
+
+/// A threshold crossed in the current window
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alert {
+    /// What is being measured: "block", "daily_budget", "weekly_budget" or "monthly_budget"
+    pub key: String,
+    /// The window the threshold was crossed in: the block start or the budget period
+    pub window: String,
+    /// Highest threshold crossed, as a percentage
+    pub threshold: u32,
+    pub message: String,
+}
+
+impl Alert {
+    /// Whether the limit itself has been reached, not only approached
+    pub fn is_exceeded(&self) -> bool {
+        self.threshold >= 100
+    }
+}
+
+/// Highest threshold `percent` has reached
+fn crossed(thresholds: &[u32], percent: f64) -> Option<u32> {
+    thresholds
+        .iter()
+        .copied()
+        .filter(|threshold| percent >= *threshold as f64)
+        .max()
+}
+
+/// The highest threshold each measured window has crossed
+pub fn evaluate(
+    quota: &QuotaStatus,
+    budgets: &BudgetSettings,
+    settings: &AlertSettings,
+) -> Vec<Alert> {
+    let mut alerts = Vec::new();
+
+    if let Some(block) = &quota.block {
+        let percent = block.ratio().map(|ratio| ratio * 100.0);
+        if let (Some(percent), Some(limit)) = (percent, block.limit) {
+            if let Some(threshold) = crossed(&settings.block_thresholds, percent) {
+                alerts.push(Alert {
+                    key: "block".to_string(),
+                    window: block.start.to_rfc3339(),
+                    threshold,
+                    message: format!(
+                        "5-hour block at {percent:.0}% of its limit ({} of {limit} tokens)",
+                        block.tokens
+                    ),
+                });
+            }
+        }
+    }
+
+    for period in BudgetPeriod::ALL {
+        let Some(budget) = budgets.limit(period).filter(|budget| *budget > 0.0) else {
+            continue;
+        };
+        let spent = quota.spend.spent(period);
+        let percent = spent / budget * 100.0;
+        if let Some(threshold) = crossed(&settings.budget_thresholds, percent) {
+            let state = if percent >= 100.0 {
+                "exceeded".to_string()
+            } else {
+                format!("at {percent:.0}%")
+            };
+            let label = match period {
+                BudgetPeriod::Day => "Daily",
+                BudgetPeriod::Week => "Weekly",
+                BudgetPeriod::Month => "Monthly",
+            };
+            alerts.push(Alert {
+                key: format!("{}_budget", period.name()),
+                window: quota.spend.window(period),
+                threshold,
+                message: format!("{label} budget {state}: ${spent:.2} of ${budget:.2}"),
+            });
+        }
+    }
+
+    alerts
+}
+
+/// When an alert last fired
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FiredAlert {
+    pub window: String,
+    pub threshold: u32,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// The alerts fired so far, by key
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AlertLog {
+    fired: BTreeMap<String, FiredAlert>,
+}
+
+impl AlertLog {
+    /// Get the log file path under the user cache directory
+    pub fn path() -> Option<PathBuf> {
+        dirs::cache_dir().map(|dir| dir.join("cc-enhanced").join("alerts.json"))
+    }
+
+    /// Load the log, treating a missing or unreadable file as empty
+    pub fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save the log; several dashboards may share it
+    pub fn save(&self, path: &Path) -> Result<()> {
+        write_atomically(path, serde_json::to_string_pretty(self)?)
+    }
+
+    /// Record `alert` and return whether it is new: the first for its window,
+    /// or a higher threshold than the one already fired in that window
+    pub fn record(&mut self, alert: &Alert, now: DateTime<Utc>) -> bool {
+        let is_new = self.fired.get(&alert.key).map_or(true, |fired| {
+            fired.window != alert.window || alert.threshold > fired.threshold
+        });
+        if is_new {
+            self.fired.insert(
+                alert.key.clone(),
+                FiredAlert {
+                    window: alert.window.clone(),
+                    threshold: alert.threshold,
+                    timestamp: now,
+                },
+            );
+        }
+        is_new
+    }
+}
+
+/// Raise `alert` through the channels enabled in `settings` besides the dashboard
+/// banner. Notifiers run in the background; only a failure to start one is an error.
+pub fn notify(alert: &Alert, settings: &AlertSettings) -> Result<()> {
+    if settings.bell {
+        let mut stdout = std::io::stdout();
+        stdout.write_all(b"\x07")?;
+        stdout.flush()?;
+    }
+
+    if settings.desktop {
+        let urgency = if alert.is_exceeded() {
+            "critical"
+        } else {
+            "normal"
+        };
+        let child = Command::new("notify-send")
+            .args(["--app-name", "cc-enhanced", "--urgency", urgency])
+            .arg("cc-enhanced")
+            .arg(&alert.message)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| anyhow::anyhow!("Failed to run notify-send: {e}"))?;
+        reap(child);
+    }
+
+    if let Some(command) = &settings.command {
+        let child = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .env("CC_ENHANCED_ALERT_KEY", &alert.key)
+            .env("CC_ENHANCED_ALERT_WINDOW", &alert.window)
+            .env("CC_ENHANCED_ALERT_THRESHOLD", alert.threshold.to_string())
+            .env("CC_ENHANCED_ALERT_MESSAGE", &alert.message)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| anyhow::anyhow!("Failed to run alert command: {e}"))?;
+        reap(child);
+    }
+
+    Ok(())
+}
+
+/// Wait for a notifier on another thread so it does not linger as a zombie
+fn reap(mut child: std::process::Child) {
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::claude::blocks::BlockEntry;
+    use crate::claude::periods::PeriodSettings;
+    use crate::claude::quota::QuotaSettings;
+    use chrono::{Duration, Local, TimeZone};
+
+    fn status(block_tokens: u64, today_cost: f64) -> QuotaStatus {
+        let now = Local
+            .with_ymd_and_hms(2025, 6, 4, 12, 30, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+        let entries = [BlockEntry {
+            time: now - Duration::minutes(1),
+            tokens: block_tokens,
+            cost: today_cost,
+        }];
+        let settings = QuotaSettings {
+            block_tokens: Some(10_000),
+            ..Default::default()
+        };
+        QuotaStatus::compute(&entries, &settings, &PeriodSettings::default(), now)
+    }
+
+    #[test]
+    fn test_highest_crossed_thresholds() {
+        let budgets = BudgetSettings {
+            daily_usd: Some(10.0),
+            monthly_usd: Some(100.0),
+            ..Default::default()
+        };
+        let alerts = evaluate(&status(8_500, 12.0), &budgets, &AlertSettings::default());
+
+        let crossed: Vec<(&str, u32)> = alerts
+            .iter()
+            .map(|alert| (alert.key.as_str(), alert.threshold))
+            .collect();
+        assert_eq!(crossed, vec![("block", 80), ("daily_budget", 100)]);
+        assert!(alerts[0].message.contains("85%"));
+        assert_eq!(alerts[1].message, "Daily budget exceeded: $12.00 of $10.00");
+        assert!(alerts[1].is_exceeded());
+
+        assert!(evaluate(&status(100, 1.0), &budgets, &AlertSettings::default()).is_empty());
+    }
+
+    #[test]
+    fn test_alerts_fire_once_per_window() {
+        let now = Utc.with_ymd_and_hms(2025, 6, 1, 12, 0, 0).unwrap();
+        let alert = |window: &str, threshold| Alert {
+            key: "block".to_string(),
+            window: window.to_string(),
+            threshold,
+            message: String::new(),
+        };
+
+        let mut log = AlertLog::default();
+        assert!(log.record(&alert("a", 50), now));
+        assert!(!log.record(&alert("a", 50), now));
+        assert!(log.record(&alert("a", 80), now));
+        assert!(!log.record(&alert("a", 50), now));
+        // A new block starts over
+        assert!(log.record(&alert("b", 50), now));
+
+        let dir = std::env::temp_dir().join(format!("cc-enhanced-alerts-{}", std::process::id()));
+        let path = dir.join("alerts.json");
+        log.save(&path).unwrap();
+        assert_eq!(AlertLog::load(&path), log);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
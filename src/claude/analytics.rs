@@ -27,10 +27,10 @@ pub struct UsageAnalytics {
 #[derive(Debug, Clone)]
 pub struct DailyUsageDetail {
     pub date: String,
-    pub total_input_tokens: u32,
-    pub total_output_tokens: u32,
-    pub total_cache_creation_tokens: u32,
-    pub total_cache_read_tokens: u32,
+    pub total_input_tokens: u64,
+    pub total_output_tokens: u64,
+    pub total_cache_creation_tokens: u64,
+    pub total_cache_read_tokens: u64,
     pub total_cost: f64,
     pub session_count: usize,
     pub message_count: usize,
@@ -40,8 +40,8 @@ pub struct DailyUsageDetail {
 #[derive(Debug, Clone)]
 pub struct ModelUsageStats {
     pub model_name: String,
-    pub total_input_tokens: u32,
-    pub total_output_tokens: u32,
+    pub total_input_tokens: u64,
+    pub total_output_tokens: u64,
     pub total_cost: f64,
     pub usage_count: usize,
     pub first_used: String,
@@ -52,15 +52,15 @@ pub struct ModelUsageStats {
 #[derive(Debug, Clone)]
 pub struct HourlyUsage {
     pub hour: u8,
-    pub total_tokens: u32,
+    pub total_tokens: u64,
     pub total_cost: f64,
     pub message_count: usize,
 }
 
 #[derive(Debug, Clone)]
 pub struct CacheEfficiencyStats {
-    pub total_cache_creation_tokens: u32,
-    pub total_cache_read_tokens: u32,
+    pub total_cache_creation_tokens: u64,
+    pub total_cache_read_tokens: u64,
     pub cache_hit_rate: f64,
     pub cache_cost_savings: f64,
 }
@@ -79,7 +79,7 @@ pub struct CostBreakdown {
 #[derive(Debug, Clone)]
 pub struct ProjectUsageStats {
     pub project_name: String,
-    pub total_tokens: u32,
+    pub total_tokens: u64,
     pub total_cost: f64,
     pub session_count: usize,
     pub most_used_model: String,
@@ -93,7 +93,7 @@ pub struct SessionAnalytics {
     pub start_time: String,
     pub end_time: String,
     pub duration_minutes: f64,
-    pub total_tokens: u32,
+    pub total_tokens: u64,
     pub total_cost: f64,
     pub message_count: usize,
     pub models_used: Vec<String>,
@@ -221,6 +221,8 @@ impl AnalyticsCalculator {
                     message_type: message_type.to_string(),
                     message: message_content,
                     cwd: json.get("cwd").and_then(|c| c.as_str()).map(String::from),
+                    is_sidechain: json.get("isSidechain").and_then(|v| v.as_bool()).unwrap_or(false),
+                    agent_id: json.get("agentId").and_then(|a| a.as_str()).map(String::from),
                 });
             }
         }
@@ -295,8 +297,8 @@ impl AnalyticsCalculator {
 
     /// Analyze cache efficiency
     fn analyze_cache_efficiency(&self, messages: &[SessionMessage]) -> Result<CacheEfficiencyStats> {
-        let mut total_cache_creation = 0u32;
-        let mut total_cache_read = 0u32;
+        let mut total_cache_creation = 0u64;
+        let mut total_cache_read = 0u64;
 
         for message in messages {
             if let Some(usage) = &message.message.usage {
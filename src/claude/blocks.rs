@@ -0,0 +1,347 @@
+//! Splitting activity into Claude's 5-hour usage blocks
+//!
+//! A block starts at the top of the hour of the first message sent after the
+//! previous block ended and lasts five hours. Every message sent before it ends
+//! counts toward it, which is how plan limits are applied.
+//!
+//! The block timeline summarizes every block with its cost, models and projects,
+//! the idle gaps between blocks, and where the active block is heading.
+
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Length of a usage block
+pub const BLOCK_HOURS: i64 = 5;
+
+/// Length of a usage block as a duration
+pub fn block_duration() -> Duration {
+    Duration::hours(BLOCK_HOURS)
+}
+
+/// One message's share of a block: when it was sent, the tokens it counts (input
+/// plus output) and its cost; both are zero for lines without usage and for duplicate copies
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlockEntry {
+    pub time: DateTime<Utc>,
+    pub tokens: u64,
+    pub cost: f64,
+}
+
+/// A 5-hour usage block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsageBlock {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Time of the last message in the block
+    pub last_activity: DateTime<Utc>,
+    /// Input plus output tokens of the block's messages
+    pub tokens: u64,
+    /// Messages sent during the block
+    pub entries: usize,
+}
+
+impl UsageBlock {
+    /// Whether the block is still running at `now`
+    pub fn is_active_at(&self, now: DateTime<Utc>) -> bool {
+        self.end > now
+    }
+}
+
+/// Split entries into blocks, oldest first. Entries may come in any order.
+pub fn segment_blocks(mut entries: Vec<BlockEntry>) -> Vec<UsageBlock> {
+    entries.sort_by_key(|entry| entry.time);
+
+    let mut blocks: Vec<UsageBlock> = Vec::new();
+    for entry in entries {
+        match blocks.last_mut() {
+            Some(block) if entry.time < block.end => {
+                block.last_activity = entry.time;
+                block.tokens = block.tokens.saturating_add(entry.tokens);
+                block.entries += 1;
+            }
+            _ => {
+                let start = entry
+                    .time
+                    .duration_trunc(Duration::hours(1))
+                    .unwrap_or(entry.time);
+                blocks.push(UsageBlock {
+                    start,
+                    end: start + block_duration(),
+                    last_activity: entry.time,
+                    tokens: entry.tokens,
+                    entries: 1,
+                });
+            }
+        }
+    }
+    blocks
+}
+
+/// The block running at `now`, if any
+pub fn active_block(blocks: &[UsageBlock], now: DateTime<Utc>) -> Option<&UsageBlock> {
+    blocks.iter().find(|block| block.is_active_at(now))
+}
+
+/// One message on the block timeline: its block entry plus the model and project
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimelineEntry {
+    pub time: DateTime<Utc>,
+    /// Input plus output tokens, as in `BlockEntry`
+    pub tokens: u64,
+    pub cost: f64,
+    /// Model of an assistant message with usage
+    pub model: Option<String>,
+    /// Name of the project the session belongs to
+    pub project: String,
+}
+
+/// Tokens and cost one project spent in a block
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BlockProject {
+    pub name: String,
+    pub tokens: u64,
+    pub cost: f64,
+}
+
+/// Where the active block ends up if usage continues at its current rate
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct BlockProjection {
+    pub tokens: u64,
+    pub cost: f64,
+}
+
+/// A block on the timeline
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BlockSummary {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub last_activity: DateTime<Utc>,
+    pub tokens: u64,
+    pub cost: f64,
+    /// Messages sent during the block, with or without usage
+    pub messages: usize,
+    /// Models that answered during the block, by name
+    pub models: Vec<String>,
+    /// Projects that used the block, most tokens first
+    pub projects: Vec<BlockProject>,
+    pub is_active: bool,
+    /// Projected totals at the end of the block; only set while it is active
+    pub projection: Option<BlockProjection>,
+}
+
+/// Idle time between the end of one block and the start of the next
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct BlockGap {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl BlockGap {
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+}
+
+/// Summarize the blocks of `entries` (any order) up to `now`, oldest first
+pub fn summarize_blocks(mut entries: Vec<TimelineEntry>, now: DateTime<Utc>) -> Vec<BlockSummary> {
+    entries.retain(|entry| entry.time <= now);
+    entries.sort_by_key(|entry| entry.time);
+
+    let blocks = segment_blocks(
+        entries
+            .iter()
+            .map(|entry| BlockEntry {
+                time: entry.time,
+                tokens: entry.tokens,
+                cost: entry.cost,
+            })
+            .collect(),
+    );
+
+    // Entries are sorted, so each block takes the next `block.entries` of them
+    let mut entries = entries.into_iter();
+    blocks
+        .into_iter()
+        .map(|block| {
+            let block_entries: Vec<TimelineEntry> = entries.by_ref().take(block.entries).collect();
+            summarize_block(block, &block_entries, now)
+        })
+        .collect()
+}
+
+/// Add cost, models, projects and a projection to a segmented block
+fn summarize_block(
+    block: UsageBlock,
+    entries: &[TimelineEntry],
+    now: DateTime<Utc>,
+) -> BlockSummary {
+    let mut cost = 0.0;
+    let mut models = BTreeSet::new();
+    let mut projects: BTreeMap<&str, BlockProject> = BTreeMap::new();
+
+    for entry in entries {
+        cost += entry.cost;
+        if let Some(model) = &entry.model {
+            models.insert(model.clone());
+        }
+        if entry.tokens > 0 || entry.cost > 0.0 {
+            let project = projects
+                .entry(&entry.project)
+                .or_insert_with(|| BlockProject {
+                    name: entry.project.clone(),
+                    tokens: 0,
+                    cost: 0.0,
+                });
+            project.tokens = project.tokens.saturating_add(entry.tokens);
+            project.cost += entry.cost;
+        }
+    }
+
+    let mut projects: Vec<BlockProject> = projects.into_values().collect();
+    projects.sort_by(|a, b| b.tokens.cmp(&a.tokens).then_with(|| a.name.cmp(&b.name)));
+
+    let is_active = block.is_active_at(now);
+    let first_activity = entries.first().map_or(block.start, |entry| entry.time);
+    let projection = is_active.then(|| project_block(&block, first_activity, cost, now));
+
+    BlockSummary {
+        start: block.start,
+        end: block.end,
+        last_activity: block.last_activity,
+        tokens: block.tokens,
+        cost,
+        messages: block.entries,
+        models: models.into_iter().collect(),
+        projects,
+        is_active,
+        projection,
+    }
+}
+
+/// Extend the block's rate since its first message to the end of the block.
+/// At least a minute counts as elapsed so a block that just began is not wildly projected.
+fn project_block(
+    block: &UsageBlock,
+    first_activity: DateTime<Utc>,
+    cost: f64,
+    now: DateTime<Utc>,
+) -> BlockProjection {
+    let elapsed = (now - first_activity).num_seconds().max(60) as f64;
+    let remaining = (block.end - now).num_seconds().max(0) as f64;
+    let scale = 1.0 + remaining / elapsed;
+
+    BlockProjection {
+        tokens: (block.tokens as f64 * scale).round() as u64,
+        cost: cost * scale,
+    }
+}
+
+/// The idle periods between consecutive blocks, oldest first
+pub fn block_gaps(blocks: &[BlockSummary]) -> Vec<BlockGap> {
+    blocks
+        .windows(2)
+        .filter(|pair| pair[1].start > pair[0].end)
+        .map(|pair| BlockGap {
+            start: pair[0].end,
+            end: pair[1].start,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 6, 1, hour, minute, 0).unwrap()
+    }
+
+    fn entry(hour: u32, minute: u32, tokens: u64) -> BlockEntry {
+        BlockEntry {
+            time: at(hour, minute),
+            tokens,
+            cost: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_blocks_start_on_the_hour_and_last_five_hours() {
+        let blocks = segment_blocks(vec![
+            entry(14, 10, 50),
+            entry(9, 40, 100),
+            entry(13, 59, 200),
+            entry(14, 5, 0),
+        ]);
+
+        assert_eq!(blocks.len(), 2);
+        assert_eq!((blocks[0].start, blocks[0].end), (at(9, 0), at(14, 0)));
+        assert_eq!(blocks[0].tokens, 300);
+        assert_eq!(blocks[0].last_activity, at(13, 59));
+        // The next block starts at the hour of the first message after the gap
+        assert_eq!((blocks[1].start, blocks[1].end), (at(14, 0), at(19, 0)));
+        assert_eq!(blocks[1].tokens, 50);
+        assert_eq!(blocks[1].entries, 2);
+    }
+
+    fn timeline_entry(hour: u32, minute: u32, tokens: u64, project: &str) -> TimelineEntry {
+        TimelineEntry {
+            time: at(hour, minute),
+            tokens,
+            cost: tokens as f64 / 1_000.0,
+            model: (tokens > 0).then(|| "claude-sonnet-4".to_string()),
+            project: project.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_block_summaries_gaps_and_projection() {
+        let entries = vec![
+            timeline_entry(9, 40, 0, "web"),
+            timeline_entry(9, 45, 1_000, "web"),
+            timeline_entry(10, 0, 3_000, "api"),
+            timeline_entry(16, 0, 0, "api"),
+            timeline_entry(16, 30, 600, "api"),
+            // Not sent yet at `now`
+            timeline_entry(18, 0, 5_000, "api"),
+        ];
+        let blocks = summarize_blocks(entries, at(17, 0));
+
+        assert_eq!(blocks.len(), 2);
+        let first = &blocks[0];
+        assert_eq!((first.tokens, first.messages), (4_000, 3));
+        assert!((first.cost - 4.0).abs() < 1e-9);
+        assert_eq!(first.models, vec!["claude-sonnet-4".to_string()]);
+        let names: Vec<&str> = first.projects.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["api", "web"]);
+        assert!(!first.is_active && first.projection.is_none());
+
+        // The active block ran for an hour since its first message and has four left
+        let active = &blocks[1];
+        assert!(active.is_active);
+        let projection = active.projection.unwrap();
+        assert_eq!(projection.tokens, 3_000);
+        assert!((projection.cost - 3.0).abs() < 1e-9);
+
+        assert_eq!(
+            block_gaps(&blocks),
+            vec![BlockGap {
+                start: at(14, 0),
+                end: at(16, 0)
+            }]
+        );
+    }
+
+    #[test]
+    fn test_active_block() {
+        let blocks = segment_blocks(vec![entry(9, 40, 100), entry(15, 0, 10)]);
+
+        assert_eq!(
+            active_block(&blocks, at(16, 0)).map(|b| b.start),
+            Some(at(15, 0))
+        );
+        assert_eq!(active_block(&blocks, at(20, 0)), None);
+        assert!(segment_blocks(Vec::new()).is_empty());
+    }
+}
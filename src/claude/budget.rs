@@ -0,0 +1,176 @@
+//! Cost budgets (the `budgets` config section) and the spend measured against them
+//!
+//! Budgets apply to local periods: the day, the week and the billing month, aligned
+//! as configured in the `periods` section.
+
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::blocks::BlockEntry;
+use super::periods::{PeriodKind, PeriodSettings};
+
+/// Spending limits in US dollars; unset budgets are not tracked
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BudgetSettings {
+    /// Cost allowed per local calendar day
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily_usd: Option<f64>,
+    /// Cost allowed per week
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weekly_usd: Option<f64>,
+    /// Cost allowed per billing month
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monthly_usd: Option<f64>,
+}
+
+/// A budget period
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetPeriod {
+    Day,
+    Week,
+    Month,
+}
+
+impl BudgetPeriod {
+    pub const ALL: [BudgetPeriod; 3] = [BudgetPeriod::Day, BudgetPeriod::Week, BudgetPeriod::Month];
+
+    /// Lower-case name, used in alert keys
+    pub fn name(self) -> &'static str {
+        match self {
+            BudgetPeriod::Day => "daily",
+            BudgetPeriod::Week => "weekly",
+            BudgetPeriod::Month => "monthly",
+        }
+    }
+
+    /// First day of the period containing `date`
+    pub fn start(self, date: NaiveDate, periods: &PeriodSettings) -> NaiveDate {
+        match self {
+            BudgetPeriod::Day => date,
+            BudgetPeriod::Week => periods.period(PeriodKind::Week, date).start,
+            BudgetPeriod::Month => periods.period(PeriodKind::Month, date).start,
+        }
+    }
+
+    /// Identifies the period containing `date`, e.g. "2025-06-01", "2025-W22" or "2025-06"
+    pub fn window(self, date: NaiveDate, periods: &PeriodSettings) -> String {
+        let kind = match self {
+            BudgetPeriod::Day => return date.to_string(),
+            BudgetPeriod::Week => PeriodKind::Week,
+            BudgetPeriod::Month => PeriodKind::Month,
+        };
+        periods.label(kind, periods.period(kind, date))
+    }
+}
+
+impl BudgetSettings {
+    /// The budget for `period`, if one is set
+    pub fn limit(&self, period: BudgetPeriod) -> Option<f64> {
+        match period {
+            BudgetPeriod::Day => self.daily_usd,
+            BudgetPeriod::Week => self.weekly_usd,
+            BudgetPeriod::Month => self.monthly_usd,
+        }
+    }
+}
+
+/// Cost spent so far in the current day, week and month
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BudgetSpend {
+    /// Local date the periods are measured from
+    pub today: NaiveDate,
+    /// Alignment of the week and month
+    pub periods: PeriodSettings,
+    pub day: f64,
+    pub week: f64,
+    pub month: f64,
+}
+
+impl BudgetSpend {
+    /// Sum the cost of `entries` (any order, covering at least the current month) up to `now`
+    pub fn compute(entries: &[BlockEntry], periods: &PeriodSettings, now: DateTime<Utc>) -> Self {
+        let today = now.with_timezone(&Local).date_naive();
+        let mut spend = Self {
+            today,
+            periods: periods.clone(),
+            ..Default::default()
+        };
+
+        for entry in entries.iter().filter(|entry| entry.time <= now) {
+            let date = entry.time.with_timezone(&Local).date_naive();
+            if date >= BudgetPeriod::Day.start(today, periods) {
+                spend.day += entry.cost;
+            }
+            if date >= BudgetPeriod::Week.start(today, periods) {
+                spend.week += entry.cost;
+            }
+            if date >= BudgetPeriod::Month.start(today, periods) {
+                spend.month += entry.cost;
+            }
+        }
+        spend
+    }
+
+    /// Identifies the current `period`, for alerts that fire once per period
+    pub fn window(&self, period: BudgetPeriod) -> String {
+        period.window(self.today, &self.periods)
+    }
+
+    /// Cost spent in `period`
+    pub fn spent(&self, period: BudgetPeriod) -> f64 {
+        match period {
+            BudgetPeriod::Day => self.day,
+            BudgetPeriod::Week => self.week,
+            BudgetPeriod::Month => self.month,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn entry(month: u32, day: u32, cost: f64) -> BlockEntry {
+        BlockEntry {
+            time: Local
+                .with_ymd_and_hms(2025, month, day, 12, 0, 0)
+                .unwrap()
+                .with_timezone(&Utc),
+            tokens: 0,
+            cost,
+        }
+    }
+
+    #[test]
+    fn test_spend_per_period() {
+        // Wednesday 4 June 2025; the week started on Monday 2 June
+        let now = entry(6, 4, 0.0).time;
+        let entries = [
+            entry(5, 31, 8.0), // last month, last week
+            entry(6, 1, 4.0),  // this month, last week (Sunday)
+            entry(6, 2, 2.0),
+            entry(6, 4, 1.0),
+            entry(6, 5, 100.0), // after `now`
+        ];
+
+        let spend = BudgetSpend::compute(&entries, &PeriodSettings::default(), now);
+        assert_eq!(spend.spent(BudgetPeriod::Day), 1.0);
+        assert_eq!(spend.spent(BudgetPeriod::Week), 3.0);
+        assert_eq!(spend.spent(BudgetPeriod::Month), 7.0);
+
+        assert_eq!(spend.window(BudgetPeriod::Day), "2025-06-04");
+        assert_eq!(spend.window(BudgetPeriod::Week), "2025-W23");
+        assert_eq!(spend.window(BudgetPeriod::Month), "2025-06");
+
+        // Weeks from Sunday and billing months from the 2nd
+        let periods = PeriodSettings {
+            week_start: chrono::Weekday::Sun,
+            billing_day: 2,
+        };
+        let spend = BudgetSpend::compute(&entries, &periods, now);
+        assert_eq!(spend.spent(BudgetPeriod::Week), 7.0);
+        assert_eq!(spend.spent(BudgetPeriod::Month), 3.0);
+        assert_eq!(spend.window(BudgetPeriod::Month), "2025-06-02");
+    }
+}
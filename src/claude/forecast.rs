@@ -0,0 +1,287 @@
+//! Burn rate of the active block and when limits will be reached at that rate
+//!
+//! Rates are measured over sliding windows ending now (the last 15, 30 and 60
+//! minutes, cut off at the start of the active block). The longest window gives
+//! the expected time a limit is reached; the fastest and slowest windows bound it,
+//! which is the confidence band drawn on the Quota tab. Recent bursts or lulls
+//! widen the band, steady use narrows it.
+
+use chrono::{DateTime, Duration, Utc};
+
+use super::blocks::BlockEntry;
+use super::quota::WindowUsage;
+
+/// Lengths of the sliding windows rates are measured over, shortest first
+pub const WINDOW_MINUTES: [i64; 3] = [15, 30, 60];
+
+/// Usage rate over one sliding window
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowRate {
+    /// Length of the window, shorter than its nominal length early in a block
+    pub minutes: i64,
+    /// Input plus output tokens per minute
+    pub tokens_per_minute: f64,
+    pub cost_per_hour: f64,
+}
+
+/// Rates of the active block and today's spend, for forecasting
+#[derive(Debug, Clone, PartialEq)]
+pub struct BurnRate {
+    /// Rates over the windows that fit in the active block, shortest first;
+    /// empty between blocks
+    pub windows: Vec<WindowRate>,
+    /// Cost of today's messages so far
+    pub today_cost: f64,
+    /// When the rates were measured
+    pub now: DateTime<Utc>,
+    /// End of the local day, the deadline for the daily budget
+    pub day_end: DateTime<Utc>,
+}
+
+/// When a limit will be reached if usage continues at the measured rates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitForecast {
+    /// The limit is already used up
+    Reached,
+    /// Reached between `earliest` and `latest`, most likely at `expected`.
+    /// `expected` and `latest` are None when they fall after the deadline.
+    At {
+        earliest: DateTime<Utc>,
+        expected: Option<DateTime<Utc>>,
+        latest: Option<DateTime<Utc>>,
+        deadline: DateTime<Utc>,
+    },
+    /// Not reached at any window's rate before `deadline` (the block end or midnight)
+    NotBefore(DateTime<Utc>),
+}
+
+impl BurnRate {
+    /// Measure the rates of the active block starting at `block_start` (None between
+    /// blocks) and today's spend from `entries` (any order) at `now`
+    pub fn compute(
+        entries: &[BlockEntry],
+        block_start: Option<DateTime<Utc>>,
+        day_start: DateTime<Utc>,
+        day_end: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let today_cost = entries
+            .iter()
+            .filter(|entry| entry.time >= day_start && entry.time <= now)
+            .map(|entry| entry.cost)
+            .sum();
+
+        let windows = match block_start {
+            Some(block_start) => {
+                // Early in a block every window shrinks to the time since it started
+                let elapsed = (now - block_start).num_minutes().max(1);
+                let mut lengths: Vec<i64> = WINDOW_MINUTES
+                    .iter()
+                    .map(|minutes| (*minutes).min(elapsed))
+                    .collect();
+                lengths.dedup();
+                lengths
+                    .into_iter()
+                    .map(|minutes| window_rate(entries, minutes, now))
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+
+        Self {
+            windows,
+            today_cost,
+            now,
+            day_end,
+        }
+    }
+
+    /// Tokens per minute over the longest window
+    pub fn tokens_per_minute(&self) -> Option<f64> {
+        self.windows.last().map(|rate| rate.tokens_per_minute)
+    }
+
+    /// Cost per hour over the longest window
+    pub fn cost_per_hour(&self) -> Option<f64> {
+        self.windows.last().map(|rate| rate.cost_per_hour)
+    }
+
+    /// When the active block will reach its token limit; None without a block or limit
+    pub fn block_forecast(&self, block: &WindowUsage) -> Option<LimitForecast> {
+        let limit = block.limit?;
+        let rates: Vec<f64> = self
+            .windows
+            .iter()
+            .map(|rate| rate.tokens_per_minute / 60.0)
+            .collect();
+        Some(forecast(
+            limit as f64 - block.tokens as f64,
+            &rates,
+            self.now,
+            block.end,
+        ))
+    }
+
+    /// When today's spend will reach a daily budget of `budget` dollars
+    pub fn budget_forecast(&self, budget: f64) -> LimitForecast {
+        let rates: Vec<f64> = self
+            .windows
+            .iter()
+            .map(|rate| rate.cost_per_hour / 3600.0)
+            .collect();
+        forecast(budget - self.today_cost, &rates, self.now, self.day_end)
+    }
+}
+
+/// Rate over the `minutes` up to `now`
+fn window_rate(entries: &[BlockEntry], minutes: i64, now: DateTime<Utc>) -> WindowRate {
+    let start = now - Duration::minutes(minutes);
+    let (tokens, cost) = entries
+        .iter()
+        .filter(|entry| entry.time > start && entry.time <= now)
+        .fold((0u64, 0.0), |(tokens, cost), entry| {
+            (tokens.saturating_add(entry.tokens), cost + entry.cost)
+        });
+
+    WindowRate {
+        minutes,
+        tokens_per_minute: tokens as f64 / minutes as f64,
+        cost_per_hour: cost * 60.0 / minutes as f64,
+    }
+}
+
+/// When `remaining` units run out at each of `rates` (units per second, longest
+/// window last), as seen at `now` with the window closing at `deadline`
+fn forecast(
+    remaining: f64,
+    rates: &[f64],
+    now: DateTime<Utc>,
+    deadline: DateTime<Utc>,
+) -> LimitForecast {
+    if remaining <= 0.0 {
+        return LimitForecast::Reached;
+    }
+
+    // None when the rate does not reach the limit before the deadline
+    let window_secs = (deadline - now).num_seconds() as f64;
+    let reached_at = |rate: f64| {
+        let secs = remaining / rate;
+        (rate > 0.0 && secs <= window_secs).then(|| now + Duration::seconds(secs.round() as i64))
+    };
+
+    let fastest = rates.iter().copied().fold(0.0, f64::max);
+    let slowest = rates.iter().copied().fold(f64::INFINITY, f64::min);
+    let Some(earliest) = reached_at(fastest) else {
+        return LimitForecast::NotBefore(deadline);
+    };
+
+    LimitForecast::At {
+        earliest,
+        expected: rates.last().and_then(|rate| reached_at(*rate)),
+        latest: reached_at(slowest),
+        deadline,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 6, 1, hour, minute, 0).unwrap()
+    }
+
+    fn entry(hour: u32, minute: u32, tokens: u64, cost: f64) -> BlockEntry {
+        BlockEntry {
+            time: at(hour, minute),
+            tokens,
+            cost,
+        }
+    }
+
+    fn burn(entries: &[BlockEntry], block_start: Option<DateTime<Utc>>) -> BurnRate {
+        BurnRate::compute(entries, block_start, at(0, 0), at(23, 59), at(12, 0))
+    }
+
+    #[test]
+    fn test_window_rates() {
+        let entries = [
+            entry(8, 0, 50_000, 5.0), // an earlier block, still today
+            entry(11, 10, 6_000, 0.6),
+            entry(11, 50, 3_000, 0.3),
+        ];
+        let rate = burn(&entries, Some(at(11, 0)));
+
+        let lengths: Vec<i64> = rate.windows.iter().map(|w| w.minutes).collect();
+        assert_eq!(lengths, vec![15, 30, 60]);
+        assert!((rate.windows[0].tokens_per_minute - 200.0).abs() < 1e-9);
+        assert!((rate.tokens_per_minute().unwrap() - 150.0).abs() < 1e-9);
+        assert!((rate.cost_per_hour().unwrap() - 0.9).abs() < 1e-9);
+        assert!((rate.today_cost - 5.9).abs() < 1e-9);
+
+        // Twenty minutes into a block the windows shrink to fit
+        let early = burn(&entries, Some(at(11, 40)));
+        let lengths: Vec<i64> = early.windows.iter().map(|w| w.minutes).collect();
+        assert_eq!(lengths, vec![15, 20]);
+
+        assert!(burn(&entries, None).windows.is_empty());
+    }
+
+    #[test]
+    fn test_block_forecast_band() {
+        let entries = [entry(11, 10, 6_000, 0.6), entry(11, 50, 3_000, 0.3)];
+        let rate = burn(&entries, Some(at(11, 0)));
+        let block = |tokens, limit| WindowUsage {
+            start: at(11, 0),
+            end: at(16, 0),
+            tokens,
+            limit,
+        };
+
+        // 9,000 tokens left: 45 minutes at 200/min (15m), 60 at 150/min (60m), 90 at 100/min (30m)
+        match rate.block_forecast(&block(9_000, Some(18_000))).unwrap() {
+            LimitForecast::At {
+                earliest,
+                expected,
+                latest,
+                deadline,
+            } => {
+                assert_eq!(earliest, at(12, 45));
+                assert_eq!(expected, Some(at(13, 0)));
+                assert_eq!(latest, Some(at(13, 30)));
+                assert_eq!(deadline, at(16, 0));
+            }
+            other => panic!("expected a forecast, got {other:?}"),
+        }
+
+        assert_eq!(
+            rate.block_forecast(&block(9_000, Some(9_000))),
+            Some(LimitForecast::Reached)
+        );
+        assert_eq!(
+            rate.block_forecast(&block(9_000, Some(1_000_000))),
+            Some(LimitForecast::NotBefore(at(16, 0)))
+        );
+        assert_eq!(rate.block_forecast(&block(9_000, None)), None);
+    }
+
+    #[test]
+    fn test_budget_forecast() {
+        let entries = [entry(11, 10, 6_000, 0.6), entry(11, 50, 3_000, 0.3)];
+        let rate = burn(&entries, Some(at(11, 0)));
+
+        // $0.10 left at $0.90/h over the last hour is reached 400 seconds from now
+        match rate.budget_forecast(1.0) {
+            LimitForecast::At { expected, .. } => {
+                assert_eq!(expected, Some(at(12, 6) + Duration::seconds(40)))
+            }
+            other => panic!("expected a forecast, got {other:?}"),
+        }
+        assert_eq!(rate.budget_forecast(0.5), LimitForecast::Reached);
+        assert_eq!(
+            burn(&entries, None).budget_forecast(1.0),
+            LimitForecast::NotBefore(at(23, 59))
+        );
+    }
+}
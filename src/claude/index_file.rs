@@ -0,0 +1,101 @@
+//! Persistence of the on-disk indexes under the user cache directory
+//!
+//! The usage and search indexes are bincode files that start with a header of
+//! magic bytes and a schema version. An index written with another version, or
+//! one that fails to decode, is treated as missing and rebuilt from the session
+//! files. Dashboards rewrite an index at most once per `SAVE_INTERVAL`, so an
+//! active session does not rewrite it on every refresh.
+
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::shared::write_atomically;
+
+/// Minimum time between index writes during periodic refreshes
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Location of an index: `<cache dir>/cc-enhanced/<file_name>`
+pub fn default_path(file_name: &str) -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("cc-enhanced").join(file_name))
+}
+
+/// An index file with its header, and when it was last written
+#[derive(Debug)]
+pub struct IndexFile {
+    path: PathBuf,
+    magic: [u8; 8],
+    schema_version: u32,
+    last_saved: Option<Instant>,
+}
+
+impl IndexFile {
+    pub fn new(path: PathBuf, magic: [u8; 8], schema_version: u32) -> Self {
+        Self {
+            path,
+            magic,
+            schema_version,
+            last_saved: None,
+        }
+    }
+
+    /// Read the index, returning None if it is missing, corrupt or from another schema
+    pub fn read<T: DeserializeOwned>(&self) -> Option<T> {
+        let mut reader = BufReader::new(File::open(&self.path).ok()?);
+
+        let (magic, schema_version): ([u8; 8], u32) =
+            bincode::deserialize_from(&mut reader).ok()?;
+        if magic != self.magic || schema_version != self.schema_version {
+            return None;
+        }
+
+        bincode::deserialize_from(&mut reader).ok()
+    }
+
+    /// Replace the index with `data`
+    pub fn write<T: Serialize>(&mut self, data: &T) -> Result<()> {
+        let mut bytes = bincode::serialize(&(self.magic, self.schema_version))?;
+        bincode::serialize_into(&mut bytes, data)?;
+        write_atomically(&self.path, bytes)?;
+        self.last_saved = Some(Instant::now());
+        Ok(())
+    }
+
+    /// Whether the index has not been written within the save interval
+    pub fn save_due(&self) -> bool {
+        self.last_saved
+            .map_or(true, |saved| saved.elapsed() >= SAVE_INTERVAL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_from_other_schema_is_discarded() {
+        let path = std::env::temp_dir().join(format!("index_file_{}.bin", std::process::id()));
+
+        let mut index = IndexFile::new(path.clone(), *b"TESTINDX", 2);
+        assert!(index.save_due());
+        index.write(&vec![1u32, 2, 3]).unwrap();
+        assert!(!index.save_due());
+        assert_eq!(index.read::<Vec<u32>>(), Some(vec![1, 2, 3]));
+
+        assert!(IndexFile::new(path.clone(), *b"TESTINDX", 3)
+            .read::<Vec<u32>>()
+            .is_none());
+        assert!(IndexFile::new(path.clone(), *b"OTHERIDX", 2)
+            .read::<Vec<u32>>()
+            .is_none());
+
+        std::fs::write(&path, b"not an index").unwrap();
+        assert!(index.read::<Vec<u32>>().is_none());
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,811 @@
+//! Incremental ingestion of session JSONL files
+//!
+//! Claude Code only ever appends to a session file, so after the first read
+//! each file is tracked by (inode, size, mtime, offset) and later refreshes
+//! parse just the bytes written since. The parsed entries are kept compact:
+//! only the fields the usage aggregates read and the ids of tool calls and
+//! results, never the message content.
+//!
+//! The ingested state is persisted as a usage index under the user cache
+//! directory, so a new process starts from where the last one stopped.
+//!
+//! The index stores each message's token counts and the `costUSD` Claude Code
+//! recorded, but not a cost computed from the pricing tables. That cost is
+//! calculated when the entries are read, so a change to the pricing overrides,
+//! the configured providers or a fetched price list reprices every message
+//! without rebuilding the index.
+
+use anyhow::Result;
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::index_file::{self, IndexFile};
+use super::pricing_table::RequestTokens;
+use super::scan::{self, ScanProgress};
+use super::tools::{self, ToolBlock};
+
+/// Bumped whenever `IngestedFile`, `UsageEntry` or `UsageRecord` change shape
+pub const INDEX_SCHEMA_VERSION: u32 = 6;
+
+/// Marks a file as a cc-enhanced usage index
+const INDEX_MAGIC: [u8; 8] = *b"CCEINDEX";
+
+/// Agent name for subagent lines that do not record an `agentId`
+pub const UNKNOWN_SUBAGENT: &str = "subagent";
+
+/// Ingestor shared between the UI's data manager and background refresh tasks
+pub type SharedIngestor = Arc<Mutex<SessionIngestor>>;
+
+/// A timestamped transcript line, reduced to what usage aggregates need
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageEntry {
+    #[serde(with = "chrono::serde::ts_milliseconds")]
+    pub time: DateTime<Utc>,
+    /// `costUSD` as recorded by Claude Code (API-key users only)
+    pub cost_usd: Option<f64>,
+    /// Token usage, present on lines that carry `message.usage`
+    pub usage: Option<UsageRecord>,
+    /// Tool calls and tool results in the line's content
+    pub tools: Vec<ToolBlock>,
+}
+
+/// Normalized token usage of one message
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct UsageRecord {
+    /// Timestamp exactly as written in the transcript
+    pub timestamp: String,
+    pub message_type: String,
+    pub model: Option<String>,
+    pub cwd: Option<String>,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_tokens: u64,
+    /// Part of `cache_creation_tokens` written with the 1-hour cache TTL
+    pub cache_write_1h_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub service_tier: Option<String>,
+    /// `message.id` of the API response, repeated on every copy of the message
+    pub message_id: Option<String>,
+    /// `requestId` of the API call that produced the message
+    pub request_id: Option<String>,
+    /// `isSidechain`: written by a Task-tool subagent rather than the main thread
+    pub is_sidechain: bool,
+    /// `agentId` of the subagent, when Claude Code records one
+    pub agent_id: Option<String>,
+}
+
+impl UsageEntry {
+    /// Build an entry from a parsed transcript line. Lines without an RFC 3339
+    /// `timestamp` cannot be placed in time and are skipped.
+    pub fn from_value(value: &Value) -> Option<Self> {
+        let timestamp = value.get("timestamp")?.as_str()?;
+        let time = DateTime::parse_from_rfc3339(timestamp)
+            .ok()?
+            .with_timezone(&Utc);
+
+        Some(Self {
+            time,
+            cost_usd: cost_usd(value),
+            usage: UsageRecord::from_value(value),
+            tools: tools::tool_blocks(value),
+        })
+    }
+
+    /// Entry time truncated to whole seconds, as the block calculations expect
+    pub fn system_time(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.time.timestamp().max(0) as u64)
+    }
+
+    /// Calendar date of the entry in local time
+    pub fn local_date(&self) -> NaiveDate {
+        self.time.with_timezone(&Local).date_naive()
+    }
+
+    /// Identity of the billed API response, if the line records one
+    pub fn dedup_key(&self) -> Option<String> {
+        self.usage.as_ref().and_then(UsageRecord::dedup_key)
+    }
+}
+
+impl UsageRecord {
+    /// Extract `message.usage` (and the fields around it) from a transcript line
+    pub fn from_value(value: &Value) -> Option<Self> {
+        let message = value.get("message")?;
+        let usage = message.get("usage")?;
+        let tokens = |key: &str| usage.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+        let string = |v: Option<&Value>| v.and_then(|v| v.as_str()).map(|s| s.to_string());
+
+        Some(Self {
+            timestamp: string(value.get("timestamp")).unwrap_or_default(),
+            message_type: string(value.get("type")).unwrap_or_else(|| "unknown".to_string()),
+            model: string(message.get("model")),
+            cwd: string(value.get("cwd")),
+            input_tokens: tokens("input_tokens"),
+            output_tokens: tokens("output_tokens"),
+            cache_creation_tokens: tokens("cache_creation_input_tokens"),
+            cache_write_1h_tokens: usage
+                .get("cache_creation")
+                .and_then(|c| c.get("ephemeral_1h_input_tokens"))
+                .and_then(|v| v.as_u64())
+                .unwrap_or(0),
+            cache_read_tokens: tokens("cache_read_input_tokens"),
+            service_tier: string(usage.get("service_tier")),
+            message_id: string(message.get("id")),
+            request_id: string(value.get("requestId")),
+            is_sidechain: value
+                .get("isSidechain")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+            agent_id: string(value.get("agentId")),
+        })
+    }
+
+    /// Agent that produced the message; see [`agent_name`]
+    pub fn agent<'a>(&'a self, session_id: &'a str) -> &'a str {
+        agent_name(session_id, self.is_sidechain, self.agent_id.as_deref())
+    }
+
+    /// `message.id:requestId`, identical for every copy of the same response
+    pub fn dedup_key(&self) -> Option<String> {
+        dedup_key(self.message_id.as_deref(), self.request_id.as_deref())
+    }
+
+    /// Token counts as pricing sees them
+    pub fn request_tokens(&self) -> RequestTokens<'_> {
+        RequestTokens::new(
+            self.input_tokens,
+            self.output_tokens,
+            self.cache_creation_tokens,
+            self.cache_write_1h_tokens,
+            self.cache_read_tokens,
+            self.service_tier.as_deref(),
+        )
+    }
+
+    /// Whether any tokens were counted for this message
+    pub fn has_tokens(&self) -> bool {
+        self.input_tokens > 0
+            || self.output_tokens > 0
+            || self.cache_creation_tokens > 0
+            || self.cache_read_tokens > 0
+    }
+}
+
+/// Name of the agent that wrote a transcript line, matching the `{agent}` part of
+/// todo file names (`{session}-agent-{agent}.json`). The main thread is named after
+/// its session; subagents by their `agentId`.
+pub fn agent_name<'a>(
+    session_id: &'a str,
+    is_sidechain: bool,
+    agent_id: Option<&'a str>,
+) -> &'a str {
+    match (is_sidechain, agent_id) {
+        (false, _) => session_id,
+        (true, Some(agent_id)) => agent_id,
+        (true, None) => UNKNOWN_SUBAGENT,
+    }
+}
+
+/// Read `costUSD` from a transcript line
+pub fn cost_usd(value: &Value) -> Option<f64> {
+    value.get("costUSD").and_then(|v| v.as_f64())
+}
+
+/// Build the dedup key from a message id and request id; both are needed to
+/// tell copies of one response apart from distinct responses
+pub fn dedup_key(message_id: Option<&str>, request_id: Option<&str>) -> Option<String> {
+    Some(format!("{}:{}", message_id?, request_id?))
+}
+
+/// Drops repeated copies of the same API response while summing usage.
+///
+/// Streaming writes, resumed sessions that copy history and forked sessions
+/// can all repeat an assistant message, in the same file or across files.
+/// Anthropic bills each response once, so only its first copy is counted.
+/// Lines without both ids are always counted.
+#[derive(Debug, Default)]
+pub struct UsageDeduplicator {
+    seen: HashSet<String>,
+    duplicates: usize,
+}
+
+impl UsageDeduplicator {
+    /// Record a key and return whether its usage should be counted
+    pub fn first_seen(&mut self, key: Option<String>) -> bool {
+        let Some(key) = key else {
+            return true;
+        };
+
+        let first = self.seen.insert(key);
+        if !first {
+            self.duplicates += 1;
+        }
+        first
+    }
+
+    /// Number of copies dropped so far
+    pub fn duplicates(&self) -> usize {
+        self.duplicates
+    }
+}
+
+/// Read position and parsed contents of one session file
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct IngestedFile {
+    inode: u64,
+    size: u64,
+    modified: Option<SystemTime>,
+    /// Byte offset just past the last line that was parsed
+    offset: u64,
+    /// Number of non-empty lines read so far
+    pub line_count: usize,
+    /// Most recent `cwd` recorded in the file
+    pub last_cwd: Option<String>,
+    pub entries: Vec<UsageEntry>,
+}
+
+/// Identity and size of a file on disk, compared against what was last read
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileStamp {
+    inode: u64,
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+impl FileStamp {
+    pub fn of(metadata: &fs::Metadata) -> Self {
+        Self {
+            inode: file_id(metadata),
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+        }
+    }
+
+    /// Whether this is the file stamped as `previous`, grown by appending
+    pub fn grew_from(&self, previous: &FileStamp) -> bool {
+        previous.modified.is_some() && self.inode == previous.inode && self.size > previous.size
+    }
+}
+
+/// Call `on_line` with every complete line after `offset`, parsed when it is valid
+/// JSON, and advance `offset` past it. A line Claude is still writing has no newline
+/// yet and is left for the next read, unless it is already valid JSON (a file that
+/// ends without one).
+pub fn read_appended_lines(
+    path: &Path,
+    offset: &mut u64,
+    mut on_line: impl FnMut(&[u8], Option<Value>),
+) -> io::Result<()> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(*offset))?;
+    let mut reader = BufReader::new(file);
+    let mut line = Vec::new();
+
+    loop {
+        line.clear();
+        let read = reader.read_until(b'\n', &mut line)?;
+        if read == 0 {
+            break;
+        }
+
+        let parsed = serde_json::from_slice::<Value>(&line);
+        if !line.ends_with(b"\n") && parsed.is_err() {
+            break;
+        }
+
+        on_line(&line, parsed.ok());
+        *offset += read as u64;
+    }
+
+    Ok(())
+}
+
+impl IngestedFile {
+    /// Whether this state already reflects the file as stamped
+    fn is_current(&self, stamp: &FileStamp) -> bool {
+        self.modified.is_some()
+            && self.inode == stamp.inode
+            && self.size == stamp.size
+            && self.modified == stamp.modified
+    }
+
+    /// Read whatever changed since this state was last updated
+    fn update(&mut self, path: &Path, stamp: &FileStamp) -> io::Result<()> {
+        // Anything other than growth of the same file means it was replaced or
+        // rewritten, and the old offset no longer points at a line boundary
+        let previous = FileStamp {
+            inode: self.inode,
+            size: self.size,
+            modified: self.modified,
+        };
+        if !stamp.grew_from(&previous) {
+            *self = IngestedFile::default();
+        }
+
+        self.read_appended(path)?;
+        self.inode = stamp.inode;
+        self.size = stamp.size;
+        self.modified = stamp.modified;
+        Ok(())
+    }
+
+    /// Parse every complete line after `offset` and advance past it
+    fn read_appended(&mut self, path: &Path) -> io::Result<()> {
+        let mut offset = self.offset;
+        read_appended_lines(path, &mut offset, |line, value| {
+            if let Some(value) = value {
+                self.line_count += 1;
+                if let Some(cwd) = value.get("cwd").and_then(|v| v.as_str()) {
+                    self.last_cwd = Some(cwd.to_string());
+                }
+                if let Some(entry) = UsageEntry::from_value(&value) {
+                    self.entries.push(entry);
+                }
+            } else if line.iter().any(|b| !b.is_ascii_whitespace()) {
+                self.line_count += 1;
+            }
+        })?;
+        self.offset = offset;
+        Ok(())
+    }
+}
+
+/// Keeps parsed entries for every session file it has seen and re-reads
+/// only what was appended since the previous refresh
+#[derive(Debug, Default)]
+pub struct SessionIngestor {
+    files: HashMap<PathBuf, IngestedFile>,
+    /// Where the index is persisted (None keeps everything in memory)
+    index_file: Option<IndexFile>,
+    /// Whether `files` changed since the index was last written
+    dirty: bool,
+}
+
+impl SessionIngestor {
+    /// Create an empty in-memory ingestor that can be shared across threads
+    pub fn shared() -> SharedIngestor {
+        Arc::new(Mutex::new(Self::default()))
+    }
+
+    /// Open the usage index at its default location, falling back to memory only
+    pub fn open_default() -> Self {
+        match default_index_path() {
+            Some(path) => Self::open(path),
+            None => Self::default(),
+        }
+    }
+
+    /// Open a usage index. A missing, unreadable or outdated index starts empty
+    /// and is rewritten on the next save.
+    pub fn open(index_path: PathBuf) -> Self {
+        let index_file = IndexFile::new(index_path, INDEX_MAGIC, INDEX_SCHEMA_VERSION);
+
+        Self {
+            files: index_file.read().unwrap_or_default(),
+            index_file: Some(index_file),
+            dirty: false,
+        }
+    }
+
+    /// Bring one file up to date and return its ingested contents
+    pub fn refresh_file(&mut self, path: &Path) -> io::Result<&IngestedFile> {
+        let stamp = match fs::metadata(path) {
+            Ok(metadata) => FileStamp::of(&metadata),
+            Err(e) => {
+                self.dirty |= self.files.remove(path).is_some();
+                return Err(e);
+            }
+        };
+
+        let state = self.files.entry(path.to_path_buf()).or_default();
+        if !state.is_current(&stamp) {
+            self.dirty = true;
+            state.update(path, &stamp)?;
+        }
+
+        Ok(state)
+    }
+
+    /// Bring many files up to date, parsing the changed ones on a pool of worker threads.
+    /// `on_progress` counts only the files that had to be read. Files that cannot be
+    /// read are dropped and tried again from the start on the next refresh.
+    pub fn refresh_files(
+        &mut self,
+        paths: &[PathBuf],
+        on_progress: &(dyn Fn(ScanProgress) + Sync),
+    ) {
+        let mut stale = Vec::new();
+        for path in paths {
+            let Ok(metadata) = fs::metadata(path) else {
+                self.dirty |= self.files.remove(path).is_some();
+                continue;
+            };
+            let stamp = FileStamp::of(&metadata);
+            if self
+                .files
+                .get(path)
+                .is_some_and(|state| state.is_current(&stamp))
+            {
+                continue;
+            }
+            let state = self.files.remove(path).unwrap_or_default();
+            stale.push((path.clone(), stamp, state));
+        }
+
+        if stale.is_empty() {
+            return;
+        }
+        self.dirty = true;
+
+        let updated = scan::map_parallel(stale, on_progress, |(path, stamp, mut state)| {
+            let result = state.update(&path, &stamp);
+            (path, state, result)
+        });
+        for (path, state, result) in updated {
+            if result.is_ok() {
+                self.files.insert(path, state);
+            }
+        }
+    }
+
+    /// Drop state for files that no longer exist
+    pub fn forget_missing(&mut self) {
+        let before = self.files.len();
+        self.files.retain(|path, _| path.exists());
+        self.dirty |= self.files.len() != before;
+    }
+
+    /// Forget everything, so every file is parsed again from the start
+    pub fn clear(&mut self) {
+        self.files.clear();
+        self.dirty = true;
+    }
+
+    /// Number of tracked files and the entries parsed from them
+    pub fn totals(&self) -> (usize, usize) {
+        let entries = self.files.values().map(|file| file.entries.len()).sum();
+        (self.files.len(), entries)
+    }
+
+    /// Write the index if anything changed since it was last written
+    pub fn save(&mut self) -> Result<()> {
+        let Some(index_file) = &mut self.index_file else {
+            return Ok(());
+        };
+        if !self.dirty {
+            return Ok(());
+        }
+
+        index_file.write(&self.files)?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Like `save`, but skipped if the index was written recently. Used by
+    /// periodic refreshes so an active session does not rewrite it every few seconds.
+    pub fn save_if_due(&mut self) -> Result<()> {
+        if self.index_file.as_ref().is_some_and(IndexFile::save_due) {
+            self.save()?;
+        }
+        Ok(())
+    }
+}
+
+/// Default index location: `<cache dir>/cc-enhanced/usage-index.bin`
+pub fn default_index_path() -> Option<PathBuf> {
+    index_file::default_path("usage-index.bin")
+}
+
+/// Identify a file across renames so a replaced file is re-read from the start
+#[cfg(unix)]
+fn file_id(metadata: &fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.ino()
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &fs::Metadata) -> u64 {
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn temp_file(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ingest_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join(name)
+    }
+
+    fn assistant_line(timestamp: &str, output_tokens: u64) -> String {
+        format!(
+            r#"{{"type":"assistant","timestamp":"{timestamp}","cwd":"/work","message":{{"model":"claude-sonnet-4-20250514","usage":{{"input_tokens":10,"output_tokens":{output_tokens}}}}}}}"#
+        )
+    }
+
+    fn append(path: &Path, content: &str) {
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        write!(file, "{content}").unwrap();
+    }
+
+    #[test]
+    fn test_entry_from_value() {
+        let line = assistant_line("2025-07-01T10:00:00.000Z", 5);
+        let entry = UsageEntry::from_value(&serde_json::from_str(&line).unwrap()).unwrap();
+        let usage = entry.usage.unwrap();
+
+        assert_eq!(entry.time.to_rfc3339(), "2025-07-01T10:00:00+00:00");
+        assert_eq!(usage.timestamp, "2025-07-01T10:00:00.000Z");
+        assert_eq!(usage.model.as_deref(), Some("claude-sonnet-4-20250514"));
+        assert_eq!(usage.cwd.as_deref(), Some("/work"));
+        assert_eq!((usage.input_tokens, usage.output_tokens), (10, 5));
+        assert!(usage.has_tokens());
+
+        let user_line = r#"{"type":"user","timestamp":"2025-07-01T10:00:00Z","costUSD":0.5}"#;
+        let entry = UsageEntry::from_value(&serde_json::from_str(user_line).unwrap()).unwrap();
+        assert!(entry.usage.is_none());
+        assert_eq!(entry.cost_usd, Some(0.5));
+
+        let summary = r#"{"type":"summary","summary":"No timestamp"}"#;
+        assert!(UsageEntry::from_value(&serde_json::from_str(summary).unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_only_appended_lines_are_parsed() {
+        let path = temp_file("append.jsonl");
+        let _ = fs::remove_file(&path);
+        append(
+            &path,
+            &format!("{}\n", assistant_line("2025-07-01T10:00:00Z", 1)),
+        );
+
+        let mut ingestor = SessionIngestor::default();
+        assert_eq!(ingestor.refresh_file(&path).unwrap().entries.len(), 1);
+
+        // A partially written line is held back until its newline arrives
+        let second = assistant_line("2025-07-01T10:01:00Z", 2);
+        let (head, tail) = second.split_at(20);
+        append(&path, head);
+        assert_eq!(ingestor.refresh_file(&path).unwrap().entries.len(), 1);
+
+        append(&path, &format!("{tail}\n"));
+        let entries = &ingestor.refresh_file(&path).unwrap().entries;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].usage.as_ref().unwrap().output_tokens, 2);
+
+        let state = &ingestor.files[&path];
+        assert_eq!(state.offset, fs::metadata(&path).unwrap().len());
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_rewritten_file_is_read_again() {
+        let path = temp_file("rewrite.jsonl");
+        let lines = format!(
+            "{}\n{}\n",
+            assistant_line("2025-07-01T10:00:00Z", 1),
+            assistant_line("2025-07-01T10:01:00Z", 2)
+        );
+        fs::write(&path, &lines).unwrap();
+
+        let mut ingestor = SessionIngestor::default();
+        assert_eq!(ingestor.refresh_file(&path).unwrap().entries.len(), 2);
+
+        // Truncated and rewritten with a single, different line
+        fs::write(
+            &path,
+            format!("{}\n", assistant_line("2025-07-02T09:00:00Z", 7)),
+        )
+        .unwrap();
+        let entries = &ingestor.refresh_file(&path).unwrap().entries;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].usage.as_ref().unwrap().output_tokens, 7);
+
+        fs::remove_file(&path).unwrap();
+        assert!(ingestor.refresh_file(&path).is_err());
+        assert!(ingestor.files.is_empty());
+    }
+
+    #[test]
+    fn test_final_line_without_newline_is_read() {
+        let path = temp_file("no_newline.jsonl");
+        fs::write(&path, assistant_line("2025-07-01T10:00:00Z", 3)).unwrap();
+
+        let mut ingestor = SessionIngestor::default();
+        assert_eq!(ingestor.refresh_file(&path).unwrap().entries.len(), 1);
+        assert_eq!(ingestor.refresh_file(&path).unwrap().entries.len(), 1);
+
+        fs::remove_file(&path).unwrap();
+        ingestor.forget_missing();
+        assert!(ingestor.files.is_empty());
+    }
+
+    #[test]
+    fn test_refresh_files_reads_only_changed_files() {
+        let paths: Vec<PathBuf> = (0..4)
+            .map(|i| temp_file(&format!("parallel_{i}.jsonl")))
+            .collect();
+        for (i, path) in paths.iter().enumerate() {
+            fs::write(
+                path,
+                format!("{}\n", assistant_line("2025-07-01T10:00:00Z", i as u64)),
+            )
+            .unwrap();
+        }
+        let missing = temp_file("parallel_missing.jsonl");
+        let _ = fs::remove_file(&missing);
+
+        let scanned = Mutex::new(Vec::new());
+        let on_progress = |progress: ScanProgress| scanned.lock().unwrap().push(progress);
+
+        let mut ingestor = SessionIngestor::default();
+        let mut all = paths.clone();
+        all.push(missing);
+        ingestor.refresh_files(&all, &on_progress);
+        assert_eq!(ingestor.totals(), (4, 4));
+        assert_eq!(scanned.lock().unwrap().len(), 4);
+
+        // Only the file that grew is read on the next pass
+        append(
+            &paths[2],
+            &format!("{}\n", assistant_line("2025-07-01T11:00:00Z", 9)),
+        );
+        scanned.lock().unwrap().clear();
+        ingestor.refresh_files(&all, &on_progress);
+        assert_eq!(
+            *scanned.lock().unwrap(),
+            vec![ScanProgress {
+                scanned: 1,
+                total: 1
+            }]
+        );
+
+        let file = ingestor.refresh_file(&paths[2]).unwrap();
+        let outputs: Vec<u64> = file
+            .entries
+            .iter()
+            .map(|entry| entry.usage.as_ref().unwrap().output_tokens)
+            .collect();
+        assert_eq!(outputs, vec![2, 9]);
+
+        for path in &paths {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_subagent_lines_are_attributed_to_their_agent() {
+        let record = |line: &str| {
+            UsageRecord::from_value(&serde_json::from_str::<Value>(line).unwrap()).unwrap()
+        };
+        let main = record(r#"{"message":{"usage":{"output_tokens":1}}}"#);
+        let subagent = record(
+            r#"{"isSidechain":true,"agentId":"a7","message":{"usage":{"output_tokens":1}}}"#,
+        );
+        let unnamed = record(r#"{"isSidechain":true,"message":{"usage":{"output_tokens":1}}}"#);
+
+        assert!(!main.is_sidechain);
+        assert_eq!(main.agent("s1"), "s1");
+        assert!(subagent.is_sidechain);
+        assert_eq!(subagent.agent("s1"), "a7");
+        assert_eq!(unnamed.agent("s1"), UNKNOWN_SUBAGENT);
+    }
+
+    #[test]
+    fn test_cache_ttl_and_service_tier_reach_pricing() {
+        let line = r#"{"message":{"usage":{"input_tokens":4,"output_tokens":2,"cache_creation_input_tokens":300,"cache_read_input_tokens":50,"cache_creation":{"ephemeral_5m_input_tokens":100,"ephemeral_1h_input_tokens":200},"service_tier":"batch"}}}"#;
+        let record =
+            UsageRecord::from_value(&serde_json::from_str::<Value>(line).unwrap()).unwrap();
+        let tokens = record.request_tokens();
+
+        assert_eq!(record.cache_write_1h_tokens, 200);
+        assert_eq!((tokens.cache_write_5m, tokens.cache_write_1h), (100, 200));
+        assert_eq!(tokens.prompt_tokens(), 354);
+        assert_eq!(tokens.service_tier, Some("batch"));
+
+        // Without a breakdown every cache write used the 5-minute TTL
+        let plain = r#"{"message":{"usage":{"cache_creation_input_tokens":300}}}"#;
+        let record =
+            UsageRecord::from_value(&serde_json::from_str::<Value>(plain).unwrap()).unwrap();
+        let tokens = record.request_tokens();
+        assert_eq!((tokens.cache_write_5m, tokens.cache_write_1h), (300, 0));
+    }
+
+    #[test]
+    fn test_index_round_trip() {
+        let path = temp_file("indexed.jsonl");
+        let index_path = temp_file("round_trip.bin");
+        let _ = fs::remove_file(&index_path);
+        fs::write(
+            &path,
+            format!("{}\n\n", assistant_line("2025-07-01T10:00:00.250Z", 4)),
+        )
+        .unwrap();
+
+        let mut ingestor = SessionIngestor::open(index_path.clone());
+        ingestor.refresh_file(&path).unwrap();
+        ingestor.save().unwrap();
+
+        let mut reopened = SessionIngestor::open(index_path.clone());
+        assert_eq!(reopened.totals(), (1, 1));
+        assert!(!reopened.dirty);
+
+        // The unchanged file is served from the index without being marked dirty
+        let file = reopened.refresh_file(&path).unwrap();
+        assert_eq!(file.line_count, 1);
+        assert_eq!(file.last_cwd.as_deref(), Some("/work"));
+        assert_eq!(
+            file.entries[0].time.to_rfc3339(),
+            "2025-07-01T10:00:00.250+00:00"
+        );
+        assert_eq!(file.entries[0].usage.as_ref().unwrap().output_tokens, 4);
+        assert!(!reopened.dirty);
+
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&index_path).unwrap();
+    }
+
+    #[test]
+    fn test_index_from_other_schema_is_discarded() {
+        let index_path = temp_file("old_schema.bin");
+
+        let mut bytes = bincode::serialize(&(INDEX_MAGIC, INDEX_SCHEMA_VERSION + 1)).unwrap();
+        bytes.extend(bincode::serialize(&HashMap::<PathBuf, IngestedFile>::new()).unwrap());
+        fs::write(&index_path, bytes).unwrap();
+        assert_eq!(SessionIngestor::open(index_path.clone()).totals(), (0, 0));
+
+        fs::write(&index_path, b"not an index").unwrap();
+        assert_eq!(SessionIngestor::open(index_path.clone()).totals(), (0, 0));
+
+        fs::remove_file(&index_path).unwrap();
+    }
+
+    #[test]
+    fn test_duplicates_dropped_by_message_and_request_id() {
+        let line = |message_id: &str, request_id: &str| {
+            let value: Value = serde_json::from_str(&format!(
+                r#"{{"type":"assistant","timestamp":"2025-07-01T10:00:00Z","requestId":"{request_id}","message":{{"id":"{message_id}","usage":{{"output_tokens":1}}}}}}"#
+            ))
+            .unwrap();
+            UsageEntry::from_value(&value).unwrap()
+        };
+
+        let mut dedup = UsageDeduplicator::default();
+        assert!(dedup.first_seen(line("msg_1", "req_1").dedup_key()));
+        assert!(!dedup.first_seen(line("msg_1", "req_1").dedup_key()));
+        assert!(dedup.first_seen(line("msg_1", "req_2").dedup_key()));
+        assert!(dedup.first_seen(line("msg_2", "req_1").dedup_key()));
+
+        // Without both ids a line cannot be matched, so it is always counted
+        let user_line = r#"{"type":"user","timestamp":"2025-07-01T10:00:00Z"}"#;
+        let entry = UsageEntry::from_value(&serde_json::from_str(user_line).unwrap()).unwrap();
+        assert!(dedup.first_seen(entry.dedup_key()));
+        assert!(dedup.first_seen(entry.dedup_key()));
+
+        assert_eq!(dedup.duplicates(), 1);
+    }
+
+    #[test]
+    fn test_token_counts_beyond_u32_are_kept() {
+        let line = r#"{"type":"assistant","timestamp":"2025-07-01T10:00:00Z","message":{"usage":{"input_tokens":4294967296,"cache_read_input_tokens":5000000000}}}"#;
+        let entry = UsageEntry::from_value(&serde_json::from_str(line).unwrap()).unwrap();
+        let usage = entry.usage.unwrap();
+
+        assert_eq!(usage.input_tokens, 4_294_967_296);
+        assert_eq!(usage.cache_read_tokens, 5_000_000_000);
+    }
+}
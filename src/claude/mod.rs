@@ -4,17 +4,35 @@
 //! usage analytics, pricing, and related functionality. The original large claude.rs
 //! file has been split into focused, maintainable modules.
 
+pub mod alerts;
+pub mod blocks;
+pub mod budget;
+pub mod forecast;
+pub mod index_file;
+pub mod ingest;
+pub mod path_resolver;
+pub mod periods;
 pub mod pricing;
+pub mod pricing_provider;
+pub mod pricing_table;
 pub mod project_scanner;
+pub mod quota;
+pub mod scan;
+pub mod search;
 pub mod session_parser;
+pub mod tools;
+pub mod transcript;
 pub mod usage_calculator;
 // pub mod data_manager;  // Temporarily disabled - needs API alignment
 // pub mod analytics;  // Temporarily disabled for gradual migration
 
 // Re-export only used types
+pub use ingest::{SessionIngestor, SharedIngestor};
 pub use project_scanner::Project;
-pub use session_parser::{MessageContent, Session, SessionMessage, TokenUsage};
-pub use usage_calculator::{ProjectAnalytics, UsageStats};
+pub use quota::QuotaStatus;
+pub use scan::ScanProgress;
+pub use session_parser::{CacheCreation, MessageContent, Session, SessionMessage, TokenUsage};
+pub use usage_calculator::{AgentUsage, ProjectAnalytics, UsageFilter, UsageStats};
 // pub use data_manager::{ClaudeDataManager, EnhancedTodoItem, NotificationStates, SessionMetrics};
 // pub use analytics::{UsageAnalytics, AnalyticsCalculator};  // Temporarily disabled
 
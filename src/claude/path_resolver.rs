@@ -0,0 +1,306 @@
+//! Mapping project data directories back to the directories they were created for
+//!
+//! Claude names each `projects/` entry after the session's working directory with
+//! every non-alphanumeric character replaced by '-', so `/home/me/my-app` and
+//! `/home/me/my/app` both become `-home-me-my-app`. The `cwd` recorded in the
+//! sessions is authoritative; without one, the filesystem is searched for the
+//! split of hyphens into path separators that actually exists.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+
+/// Resolutions, keyed by project data directory
+static RESOLVED: OnceLock<Mutex<HashMap<PathBuf, Resolution>>> = OnceLock::new();
+
+/// Outcome of resolving one project data directory
+#[derive(Debug, Clone)]
+enum Resolution {
+    Found(PathBuf),
+    /// Nothing matched while the newest session file had this modification time
+    /// (None when there was no session file). A new session may record a `cwd`,
+    /// so the directory is tried again once that changes.
+    Missing(Option<SystemTime>),
+}
+
+/// Resolve the working directory a project data directory belongs to.
+/// `recent_cwd` must return the `cwd` of the directory's most recent session, as
+/// `recent_session_cwd` does, so every caller caches the same answer; the filesystem
+/// is searched only when there is none. Resolved paths are cached for the life of the
+/// process, so neither runs again for the same directory. Returns None when nothing
+/// matching the directory name exists; that is cached too, until the directory's
+/// newest session file changes.
+pub fn resolve_project_path(
+    project_dir: &Path,
+    recent_cwd: impl FnOnce() -> Option<String>,
+) -> Option<PathBuf> {
+    let cache = RESOLVED.get_or_init(Default::default);
+    let cached = lock(cache).get(project_dir).cloned();
+    let newest_session = match cached {
+        Some(Resolution::Found(path)) => return Some(path),
+        Some(Resolution::Missing(checked)) => {
+            let newest_session = newest_session_file(project_dir).map(|(_, modified)| modified);
+            if newest_session == checked {
+                return None;
+            }
+            newest_session
+        }
+        None => newest_session_file(project_dir).map(|(_, modified)| modified),
+    };
+
+    let resolved = recent_cwd()
+        .filter(|cwd| !cwd.is_empty() && cwd != "/")
+        .map(PathBuf::from)
+        .or_else(|| {
+            let sanitized = project_dir.file_name()?.to_str()?;
+            search_home_then_root(dirs::home_dir().as_deref(), sanitized)
+        });
+
+    let resolution = match &resolved {
+        Some(path) => Resolution::Found(path.clone()),
+        None => Resolution::Missing(newest_session),
+    };
+    lock(cache).insert(project_dir.to_path_buf(), resolution);
+    resolved
+}
+
+/// Name shown for a project: the last component of its resolved path, or the last
+/// hyphen-separated part of the data directory name when it could not be resolved
+pub fn project_name(sanitized_name: &str, resolved: Option<&Path>) -> String {
+    match resolved.and_then(Path::file_name) {
+        Some(name) => name.to_string_lossy().to_string(),
+        None => sanitized_name
+            .rsplit('-')
+            .find(|part| !part.is_empty())
+            .unwrap_or(sanitized_name)
+            .to_string(),
+    }
+}
+
+/// Claude's directory name for a path: every non-alphanumeric character becomes '-'
+pub fn sanitize_path(path: &Path) -> String {
+    path.to_string_lossy()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
+/// Find an existing directory under `root` whose path sanitizes to `sanitized`.
+/// Components are matched one directory listing at a time, so a hyphen is only
+/// read as a separator where that split exists on disk. Where both readings exist,
+/// the one with fewer separators wins.
+pub fn search_sanitized_path(root: &Path, sanitized: &str) -> Option<PathBuf> {
+    // The leading '-' is the root's own separator
+    let remaining = sanitized.strip_prefix('-')?;
+    search_from(root, remaining)
+}
+
+/// Search the home directory for `sanitized` when the name starts there, which is
+/// where projects almost always live, and only then the whole filesystem
+fn search_home_then_root(home: Option<&Path>, sanitized: &str) -> Option<PathBuf> {
+    home.and_then(|home| match sanitized.strip_prefix(&sanitize_path(home))? {
+        "" => Some(home.to_path_buf()),
+        rest => search_from(home, rest.strip_prefix('-')?),
+    })
+    .or_else(|| search_sanitized_path(Path::new("/"), sanitized))
+}
+
+/// Match `remaining` against the entries of `dir`, descending into each that fits
+fn search_from(dir: &Path, remaining: &str) -> Option<PathBuf> {
+    let mut candidates: Vec<(String, PathBuf)> = fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| (sanitize_path(Path::new(&entry.file_name())), entry.path()))
+        .filter(|(name, path)| {
+            remaining.starts_with(name.as_str())
+                && (remaining.len() == name.len() || remaining[name.len()..].starts_with('-'))
+                && path.is_dir()
+        })
+        .collect();
+    // Longest component first, so `my-app` is preferred over `my/app`
+    candidates.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then_with(|| a.1.cmp(&b.1)));
+
+    candidates.into_iter().find_map(|(name, path)| {
+        if remaining.len() == name.len() {
+            Some(path)
+        } else {
+            search_from(&path, &remaining[name.len() + 1..])
+        }
+    })
+}
+
+/// Most recent `cwd` recorded in the newest session file of a project data directory
+pub fn recent_session_cwd(project_dir: &Path) -> Option<String> {
+    let (newest, _) = newest_session_file(project_dir)?;
+    session_file_cwd(&newest)
+}
+
+/// The most recently modified session file of a project data directory, with its
+/// modification time
+fn newest_session_file(project_dir: &Path) -> Option<(PathBuf, SystemTime)> {
+    fs::read_dir(project_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|s| s.to_str()) == Some("jsonl"))
+        .map(|path| {
+            let modified = fs::metadata(&path)
+                .and_then(|m| m.modified())
+                .unwrap_or(SystemTime::UNIX_EPOCH);
+            (path, modified)
+        })
+        .max_by_key(|(_, modified)| *modified)
+}
+
+/// Last `cwd` recorded in a session file, read line by line
+pub fn session_file_cwd(session_file: &Path) -> Option<String> {
+    let reader = BufReader::new(File::open(session_file).ok()?);
+
+    reader
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| {
+            let value = serde_json::from_str::<serde_json::Value>(&line).ok()?;
+            value.get("cwd")?.as_str().map(|cwd| cwd.to_string())
+        })
+        .last()
+}
+
+/// Lock the cache; a panic while holding it cannot leave a half-written entry
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("resolver_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    #[test]
+    fn test_hyphens_are_split_only_where_directories_exist() {
+        let root = temp_root("hyphens");
+        fs::create_dir_all(root.join("home/me/my-app")).unwrap();
+        fs::create_dir_all(root.join("home/me/.config/cc-enhanced")).unwrap();
+        fs::create_dir_all(root.join("work/a/b")).unwrap();
+
+        assert_eq!(
+            search_sanitized_path(&root, "-home-me-my-app"),
+            Some(root.join("home/me/my-app"))
+        );
+        assert_eq!(
+            search_sanitized_path(&root, "-home-me--config-cc-enhanced"),
+            Some(root.join("home/me/.config/cc-enhanced"))
+        );
+        assert_eq!(
+            search_sanitized_path(&root, "-work-a-b"),
+            Some(root.join("work/a/b"))
+        );
+        assert_eq!(search_sanitized_path(&root, "-home-me-gone"), None);
+        assert_eq!(search_sanitized_path(&root, "home-me"), None);
+
+        // Names under the home directory are searched from there
+        let home = root.join("home/me");
+        let under_home = |path: &str| format!("{}{path}", sanitize_path(&home));
+        assert_eq!(
+            search_home_then_root(Some(&home), &under_home("-my-app")),
+            Some(home.join("my-app"))
+        );
+        assert_eq!(
+            search_home_then_root(Some(&home), &under_home("")),
+            Some(home.clone())
+        );
+        assert_eq!(
+            search_home_then_root(Some(&home), &under_home("-gone")),
+            None
+        );
+
+        // With both readings on disk, the literal hyphen wins
+        fs::create_dir_all(root.join("home/me/my/app")).unwrap();
+        assert_eq!(
+            search_sanitized_path(&root, "-home-me-my-app"),
+            Some(root.join("home/me/my-app"))
+        );
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_session_cwd_is_preferred_and_cached() {
+        let root = temp_root("cwd");
+        let project_dir = root.join("projects").join("-home-me-my-app");
+        fs::create_dir_all(&project_dir).unwrap();
+        fs::write(
+            project_dir.join("s1.jsonl"),
+            "{\"cwd\":\"/home/me/old\"}\nnot json\n{\"cwd\":\"/home/me/my-app\"}\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            recent_session_cwd(&project_dir).as_deref(),
+            Some("/home/me/my-app")
+        );
+        assert_eq!(
+            resolve_project_path(&project_dir, || recent_session_cwd(&project_dir)),
+            Some(PathBuf::from("/home/me/my-app"))
+        );
+        // The cached answer is returned without asking for the cwd again
+        assert_eq!(
+            resolve_project_path(&project_dir, || panic!("cwd read twice")),
+            Some(PathBuf::from("/home/me/my-app"))
+        );
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_failed_lookups_are_cached_until_a_session_changes() {
+        let root = temp_root("missing");
+        let project_dir = root.join("projects").join("-cc-enhanced-no-such-dir");
+        fs::create_dir_all(&project_dir).unwrap();
+
+        assert_eq!(resolve_project_path(&project_dir, || None), None);
+        // Nothing changed, so neither the cwd nor the filesystem is asked again
+        assert_eq!(
+            resolve_project_path(&project_dir, || panic!("looked up twice")),
+            None
+        );
+
+        // A new session records where it ran
+        fs::write(project_dir.join("s1.jsonl"), "{\"cwd\":\"/work/app\"}\n").unwrap();
+        assert_eq!(
+            resolve_project_path(&project_dir, || recent_session_cwd(&project_dir)),
+            Some(PathBuf::from("/work/app"))
+        );
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_project_name() {
+        assert_eq!(
+            project_name("-home-me-my-app", Some(Path::new("/home/me/my-app"))),
+            "my-app"
+        );
+        assert_eq!(project_name("-home-me-my-app", None), "app");
+        assert_eq!(project_name("-home-me-", None), "me");
+    }
+
+    #[test]
+    fn test_sanitize_path() {
+        assert_eq!(
+            sanitize_path(Path::new("/home/me/my_app.v2")),
+            "-home-me-my-app-v2"
+        );
+    }
+}
@@ -0,0 +1,254 @@
+//! Calendar-aligned weeks and billing months (the `periods` config section)
+//!
+//! Weeks start on a configurable weekday (Monday by default, which makes them ISO
+//! weeks). Months run from a configurable billing day to the day before it in the
+//! next month; on day 1 they are calendar months. A billing day past the end of a
+//! short month falls on its last day.
+
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+
+/// How weeks and months are aligned
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeriodSettings {
+    /// First day of the week, e.g. "Mon" or "sunday"
+    #[serde(default = "default_week_start")]
+    pub week_start: Weekday,
+    /// Day of the month a billing cycle starts on (1-31)
+    #[serde(default = "default_billing_day")]
+    pub billing_day: u32,
+}
+
+impl Default for PeriodSettings {
+    fn default() -> Self {
+        Self {
+            week_start: default_week_start(),
+            billing_day: default_billing_day(),
+        }
+    }
+}
+
+fn default_week_start() -> Weekday {
+    Weekday::Mon
+}
+
+fn default_billing_day() -> u32 {
+    1
+}
+
+/// Length of an aggregation period
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PeriodKind {
+    Week,
+    Month,
+}
+
+/// A week or billing month, as local dates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Period {
+    pub start: NaiveDate,
+    /// First day after the period
+    pub end: NaiveDate,
+}
+
+impl Period {
+    /// Days in the period
+    pub fn days(&self) -> i64 {
+        (self.end - self.start).num_days()
+    }
+
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.start <= date && date < self.end
+    }
+}
+
+impl PeriodSettings {
+    /// The week or billing month containing `date`
+    pub fn period(&self, kind: PeriodKind, date: NaiveDate) -> Period {
+        match kind {
+            PeriodKind::Week => {
+                let offset = (7 + date.weekday().num_days_from_monday()
+                    - self.week_start.num_days_from_monday())
+                    % 7;
+                let start = date - Duration::days(offset as i64);
+                Period {
+                    start,
+                    end: start + Duration::days(7),
+                }
+            }
+            PeriodKind::Month => {
+                let this_cycle = self.cycle_start(date.year(), date.month());
+                let start = if date >= this_cycle {
+                    this_cycle
+                } else {
+                    let (year, month) = month_before(date.year(), date.month());
+                    self.cycle_start(year, month)
+                };
+                let (year, month) = month_after(start.year(), start.month());
+                Period {
+                    start,
+                    end: self.cycle_start(year, month),
+                }
+            }
+        }
+    }
+
+    /// The period before `period`
+    pub fn previous(&self, kind: PeriodKind, period: Period) -> Period {
+        self.period(kind, period.start - Duration::days(1))
+    }
+
+    /// Name of a period: the ISO week ("2025-W23") for Monday weeks, the month
+    /// ("2025-06") for calendar months, and otherwise the date it starts on
+    pub fn label(&self, kind: PeriodKind, period: Period) -> String {
+        match kind {
+            PeriodKind::Week if self.week_start == Weekday::Mon => {
+                period.start.format("%G-W%V").to_string()
+            }
+            PeriodKind::Month if period.start.day() == 1 => {
+                period.start.format("%Y-%m").to_string()
+            }
+            _ => period.start.to_string(),
+        }
+    }
+
+    /// Day the billing cycle starts in the given month
+    fn cycle_start(&self, year: i32, month: u32) -> NaiveDate {
+        let (next_year, next_month) = month_after(year, month);
+        let last_day = NaiveDate::from_ymd_opt(next_year, next_month, 1)
+            .and_then(|first| first.pred_opt())
+            .map_or(28, |last| last.day());
+        let day = self.billing_day.clamp(1, last_day);
+        NaiveDate::from_ymd_opt(year, month, day).unwrap_or(NaiveDate::MIN)
+    }
+}
+
+fn month_before(year: i32, month: u32) -> (i32, u32) {
+    if month == 1 {
+        (year - 1, 12)
+    } else {
+        (year, month - 1)
+    }
+}
+
+fn month_after(year: i32, month: u32) -> (i32, u32) {
+    if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    }
+}
+
+/// Spend in the current billing month so far, and where it ends up at the same daily rate
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MonthToDate {
+    pub period: Period,
+    pub spent: f64,
+    pub projected: f64,
+    /// Days of the period up to and including today
+    pub days_elapsed: i64,
+}
+
+impl MonthToDate {
+    /// Sum the cost of `days` (local date and cost, any order) in the billing month
+    /// containing `today`. Today counts as a whole day, so the projection runs high
+    /// early in the morning and settles during the day.
+    pub fn compute<I>(days: I, settings: &PeriodSettings, today: NaiveDate) -> Self
+    where
+        I: IntoIterator<Item = (NaiveDate, f64)>,
+    {
+        let period = settings.period(PeriodKind::Month, today);
+        let spent: f64 = days
+            .into_iter()
+            .filter(|(date, _)| period.contains(*date) && *date <= today)
+            .map(|(_, cost)| cost)
+            .sum();
+        let days_elapsed = (today - period.start).num_days() + 1;
+
+        Self {
+            period,
+            spent,
+            projected: spent / days_elapsed as f64 * period.days() as f64,
+            days_elapsed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_weeks_follow_the_week_start() {
+        // Wednesday 4 June 2025
+        let iso = PeriodSettings::default();
+        let week = iso.period(PeriodKind::Week, date(6, 4));
+        assert_eq!((week.start, week.end), (date(6, 2), date(6, 9)));
+        assert_eq!(iso.label(PeriodKind::Week, week), "2025-W23");
+
+        let sunday = PeriodSettings {
+            week_start: Weekday::Sun,
+            ..Default::default()
+        };
+        let week = sunday.period(PeriodKind::Week, date(6, 4));
+        assert_eq!(week.start, date(6, 1));
+        assert_eq!(sunday.label(PeriodKind::Week, week), "2025-06-01");
+        assert_eq!(sunday.period(PeriodKind::Week, date(6, 1)), week);
+        assert_eq!(sunday.previous(PeriodKind::Week, week).start, date(5, 25));
+
+        let parsed: PeriodSettings = serde_json::from_str(r#"{ "week_start": "sunday" }"#).unwrap();
+        assert_eq!(parsed, sunday);
+    }
+
+    #[test]
+    fn test_billing_months() {
+        let calendar = PeriodSettings::default();
+        let month = calendar.period(PeriodKind::Month, date(6, 4));
+        assert_eq!((month.start, month.end), (date(6, 1), date(7, 1)));
+        assert_eq!(calendar.label(PeriodKind::Month, month), "2025-06");
+
+        let billing = PeriodSettings {
+            billing_day: 15,
+            ..Default::default()
+        };
+        let cycle = billing.period(PeriodKind::Month, date(6, 4));
+        assert_eq!((cycle.start, cycle.end), (date(5, 15), date(6, 15)));
+        assert_eq!(billing.label(PeriodKind::Month, cycle), "2025-05-15");
+        assert_eq!(
+            billing.period(PeriodKind::Month, date(6, 15)).start,
+            date(6, 15)
+        );
+
+        // The 31st falls on the last day of shorter months
+        let end_of_month = PeriodSettings {
+            billing_day: 31,
+            ..Default::default()
+        };
+        let cycle = end_of_month.period(PeriodKind::Month, date(3, 1));
+        assert_eq!((cycle.start, cycle.end), (date(2, 28), date(3, 31)));
+        assert_eq!(
+            end_of_month.previous(PeriodKind::Month, cycle).start,
+            date(1, 31)
+        );
+    }
+
+    #[test]
+    fn test_month_to_date_projection() {
+        let days = [
+            (date(5, 31), 50.0), // last month
+            (date(6, 1), 4.0),
+            (date(6, 5), 2.0),
+            (date(6, 10), 4.0),
+            (date(6, 11), 9.0), // after today
+        ];
+        let mtd = MonthToDate::compute(days, &PeriodSettings::default(), date(6, 10));
+
+        assert_eq!(mtd.days_elapsed, 10);
+        assert!((mtd.spent - 10.0).abs() < 1e-9);
+        assert!((mtd.projected - 30.0).abs() < 1e-9);
+    }
+}
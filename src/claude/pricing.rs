@@ -6,6 +6,10 @@ use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use super::pricing_table::{
+    PricingRates, PricingSource, PricingTable, RequestTokens, ResolvedPricing, DEFAULT_RATES,
+};
+
 /// Model pricing information with input/output costs
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModelPricing {
@@ -15,6 +19,29 @@ pub struct ModelPricing {
     pub cache_read_cost_per_token: f64,
 }
 
+impl ModelPricing {
+    /// Per-token prices from per-million rates
+    fn from_rates(rates: &PricingRates) -> Self {
+        Self {
+            input_cost_per_token: rates.input / 1_000_000.0,
+            output_cost_per_token: rates.output / 1_000_000.0,
+            cache_creation_cost_per_token: rates.cache_write / 1_000_000.0,
+            cache_read_cost_per_token: rates.cache_read / 1_000_000.0,
+        }
+    }
+
+    /// Per-million rates; 1-hour cache writes cost twice the input price
+    fn rates(&self) -> PricingRates {
+        PricingRates {
+            input: self.input_cost_per_token * 1_000_000.0,
+            output: self.output_cost_per_token * 1_000_000.0,
+            cache_write: self.cache_creation_cost_per_token * 1_000_000.0,
+            cache_write_1h: None,
+            cache_read: self.cache_read_cost_per_token * 1_000_000.0,
+        }
+    }
+}
+
 /// OpenRouter model information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OpenRouterModel {
@@ -181,45 +208,39 @@ impl OpenRouterPricing {
 pub struct PricingConstants;
 
 impl PricingConstants {
-    /// Get fallback pricing for Claude models
+    /// Get fallback pricing for Claude models from the bundled pricing table
     pub fn get_claude_fallback_pricing(model: &str) -> ModelPricing {
-        match model {
-            m if m.contains("claude-3-5-sonnet") => ModelPricing {
-                input_cost_per_token: 0.000003,
-                output_cost_per_token: 0.000015,
-                cache_creation_cost_per_token: 0.00000375,
-                cache_read_cost_per_token: 0.0000003,
-            },
-            m if m.contains("claude-3-opus") => ModelPricing {
-                input_cost_per_token: 0.000015,
-                output_cost_per_token: 0.000075,
-                cache_creation_cost_per_token: 0.00001875,
-                cache_read_cost_per_token: 0.0000015,
-            },
-            m if m.contains("claude-3-haiku") => ModelPricing {
-                input_cost_per_token: 0.00000025,
-                output_cost_per_token: 0.00000125,
-                cache_creation_cost_per_token: 0.0000003125,
-                cache_read_cost_per_token: 0.000000025,
-            },
-            _ => ModelPricing {
-                // Default to Claude 3.5 Sonnet pricing
-                input_cost_per_token: 0.000003,
-                output_cost_per_token: 0.000015,
-                cache_creation_cost_per_token: 0.00000375,
-                cache_read_cost_per_token: 0.0000003,
-            },
-        }
+        let rates = PricingTable::bundled()
+            .lookup_at(model, None)
+            .map_or(DEFAULT_RATES, |entry| entry.rates);
+        ModelPricing::from_rates(&rates)
     }
 }
 
 /// Token usage information for cost calculation
 #[derive(Debug, Clone, Default)]
 pub struct TokenUsage {
-    pub input_tokens: u32,
-    pub output_tokens: u32,
-    pub cache_creation_tokens: u32,
-    pub cache_read_tokens: u32,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_tokens: u64,
+    /// Part of `cache_creation_tokens` written with the 1-hour cache TTL
+    pub cache_creation_1h_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub service_tier: Option<String>,
+}
+
+impl TokenUsage {
+    /// Token counts as pricing sees them
+    fn request_tokens(&self) -> RequestTokens<'_> {
+        RequestTokens::new(
+            self.input_tokens,
+            self.output_tokens,
+            self.cache_creation_tokens,
+            self.cache_creation_1h_tokens,
+            self.cache_read_tokens,
+            self.service_tier.as_deref(),
+        )
+    }
 }
 
 /// Pricing manager that combines OpenRouter and fallback pricing
@@ -238,30 +259,47 @@ impl PricingManager {
 
     /// Calculate cost for given token usage and model
     pub async fn calculate_cost(&mut self, usage: &TokenUsage, model: &str) -> f64 {
-        let pricing = if let Ok(Some(pricing)) = self.openrouter.get_model_pricing(model).await {
-            pricing
-        } else {
-            PricingConstants::get_claude_fallback_pricing(model)
-        };
-
-        (usage.input_tokens as f64 * pricing.input_cost_per_token)
-            + (usage.output_tokens as f64 * pricing.output_cost_per_token)
-            + (usage.cache_creation_tokens as f64 * pricing.cache_creation_cost_per_token)
-            + (usage.cache_read_tokens as f64 * pricing.cache_read_cost_per_token)
+        let openrouter = self
+            .openrouter
+            .get_model_pricing(model)
+            .await
+            .ok()
+            .flatten();
+        Self::request_cost(usage, &Self::resolve(model, openrouter))
     }
 
     /// Calculate cost synchronously with fallback
     pub fn calculate_cost_sync(&self, usage: &TokenUsage, model: &str) -> f64 {
-        let pricing = if let Some(pricing) = self.openrouter.get_model_pricing_sync(model) {
-            pricing
-        } else {
-            PricingConstants::get_claude_fallback_pricing(model)
-        };
+        let openrouter = self.openrouter.get_model_pricing_sync(model);
+        Self::request_cost(usage, &Self::resolve(model, openrouter))
+    }
+
+    /// OpenRouter pricing when available, otherwise the bundled table's entry
+    /// (with its long-context rates), otherwise Sonnet rates
+    fn resolve(model: &str, openrouter: Option<ModelPricing>) -> ResolvedPricing {
+        if let Some(pricing) = openrouter {
+            return ResolvedPricing {
+                rates: pricing.rates(),
+                long_context: None,
+                source: PricingSource::OpenRouter,
+                matched: Some(model.to_string()),
+            };
+        }
+
+        PricingTable::bundled()
+            .lookup_at(model, None)
+            .map(|entry| ResolvedPricing::from_entry(entry, PricingSource::Bundled))
+            .unwrap_or_else(ResolvedPricing::default_rates)
+    }
 
-        (usage.input_tokens as f64 * pricing.input_cost_per_token)
-            + (usage.output_tokens as f64 * pricing.output_cost_per_token)
-            + (usage.cache_creation_tokens as f64 * pricing.cache_creation_cost_per_token)
-            + (usage.cache_read_tokens as f64 * pricing.cache_read_cost_per_token)
+    /// Cost of one request at its context-size tier, cache TTLs and service tier
+    fn request_cost(usage: &TokenUsage, pricing: &ResolvedPricing) -> f64 {
+        let tokens = usage.request_tokens();
+        let multiplier = tokens
+            .service_tier
+            .and_then(|tier| PricingTable::bundled().tier_multiplier(tier))
+            .unwrap_or(1.0);
+        pricing.cost(&tokens, multiplier)
     }
 
     /// Get pricing for a specific model
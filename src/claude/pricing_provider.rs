@@ -0,0 +1,1100 @@
+//! Pricing providers and the resolver that picks a model's rates from them
+//!
+//! Prices can come from OpenRouter's model list, a LiteLLM-style
+//! `model_prices.json` on disk, an HTTP endpoint serving either format (an
+//! internal mirror, say) and the bundled table. The providers, their priority,
+//! the request timeout and how long fetched prices are cached are set in the
+//! `pricing` section of the config file:
+//!
+//! ```json
+//! "pricing": {
+//!   "providers": [
+//!     { "type": "http", "url": "http://prices.internal/model_prices.json" },
+//!     { "type": "static" },
+//!     { "type": "openrouter" }
+//!   ],
+//!   "timeout_secs": 10,
+//!   "cache_ttl_hours": 24
+//! }
+//! ```
+//!
+//! Fetched prices are kept in one cache file together with every price each
+//! model has had, so older messages keep the rate that applied when they were sent.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::pricing_table::{
+    LongContextRates, PricingRates, PricingSource, PricingTable, RequestTokens, ResolvedPricing,
+    DEFAULT_LONG_CONTEXT_THRESHOLD,
+};
+use crate::shared::write_atomically;
+
+/// OpenRouter's public model list
+pub const OPENROUTER_MODELS_URL: &str = "https://openrouter.ai/api/v1/models";
+
+/// File name of the fetched-price cache in the primary Claude data directory
+pub const CACHE_FILE_NAME: &str = "pricing_cache.json";
+
+/// Format of a price list served over HTTP
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceFormat {
+    /// LiteLLM's `model_prices.json`: per-token costs keyed by model id
+    #[default]
+    Litellm,
+    /// OpenRouter's `/api/v1/models` response
+    Openrouter,
+}
+
+impl PriceFormat {
+    /// Parse a price list in this format
+    pub fn parse(self, content: &str) -> Result<ProviderPrices> {
+        match self {
+            PriceFormat::Litellm => parse_litellm(content),
+            PriceFormat::Openrouter => parse_openrouter(content),
+        }
+    }
+}
+
+/// A source of model prices
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PricingProvider {
+    /// OpenRouter's model list, or anything serving the same API
+    #[serde(rename = "openrouter")]
+    OpenRouter {
+        #[serde(default = "default_openrouter_url")]
+        url: String,
+    },
+    /// A LiteLLM-style `model_prices.json` on disk, read at startup
+    LitellmFile { path: PathBuf },
+    /// A price list fetched from any HTTP endpoint
+    Http {
+        url: String,
+        #[serde(default)]
+        format: PriceFormat,
+    },
+    /// The table compiled into the binary
+    Static,
+}
+
+fn default_openrouter_url() -> String {
+    OPENROUTER_MODELS_URL.to_string()
+}
+
+impl PricingProvider {
+    /// Key of the provider's prices in the cache file
+    pub fn cache_key(&self) -> String {
+        match self {
+            PricingProvider::OpenRouter { url } => format!("openrouter:{url}"),
+            PricingProvider::LitellmFile { path } => format!("litellm_file:{}", path.display()),
+            PricingProvider::Http { url, .. } => format!("http:{url}"),
+            PricingProvider::Static => "static".to_string(),
+        }
+    }
+
+    /// Where rates resolved from this provider are reported to come from
+    pub fn source(&self) -> PricingSource {
+        match self {
+            PricingProvider::OpenRouter { .. } => PricingSource::OpenRouter,
+            PricingProvider::LitellmFile { .. } => PricingSource::PriceFile,
+            PricingProvider::Http { .. } => PricingSource::Http,
+            PricingProvider::Static => PricingSource::Bundled,
+        }
+    }
+
+    /// Whether prices are fetched over the network and cached
+    pub fn is_remote(&self) -> bool {
+        matches!(
+            self,
+            PricingProvider::OpenRouter { .. } | PricingProvider::Http { .. }
+        )
+    }
+
+    /// Fetch the provider's current prices. The static table has none to fetch.
+    pub async fn fetch(&self, client: &reqwest::Client) -> Result<ProviderPrices> {
+        match self {
+            PricingProvider::OpenRouter { url } => parse_openrouter(&get_text(client, url).await?),
+            PricingProvider::Http { url, format } => format.parse(&get_text(client, url).await?),
+            PricingProvider::LitellmFile { path } => read_price_file(path),
+            PricingProvider::Static => Ok(ProviderPrices::new()),
+        }
+        .with_context(|| format!("Could not load prices from {}", self.cache_key()))
+    }
+}
+
+/// Download a price list
+async fn get_text(client: &reqwest::Client, url: &str) -> Result<String> {
+    let response = client.get(url).send().await?.error_for_status()?;
+    Ok(response.text().await?)
+}
+
+/// Read a LiteLLM-style price file
+fn read_price_file(path: &Path) -> Result<ProviderPrices> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Could not read {}", path.display()))?;
+    parse_litellm(&content)
+}
+
+/// Providers, request timeout and cache lifetime (the `pricing` config section)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PricingSettings {
+    /// Providers in priority order; the first that knows a model prices it
+    #[serde(default = "default_providers")]
+    pub providers: Vec<PricingProvider>,
+    /// Timeout for each price download, in seconds
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// How long fetched prices are used before they are fetched again, in hours
+    #[serde(default = "default_cache_ttl_hours")]
+    pub cache_ttl_hours: u64,
+}
+
+impl Default for PricingSettings {
+    fn default() -> Self {
+        Self {
+            providers: default_providers(),
+            timeout_secs: default_timeout_secs(),
+            cache_ttl_hours: default_cache_ttl_hours(),
+        }
+    }
+}
+
+/// The bundled table first, since it carries long-context and 1-hour cache rates,
+/// then OpenRouter for models released after the build
+fn default_providers() -> Vec<PricingProvider> {
+    vec![
+        PricingProvider::Static,
+        PricingProvider::OpenRouter {
+            url: default_openrouter_url(),
+        },
+    ]
+}
+
+fn default_timeout_secs() -> u64 {
+    10
+}
+
+fn default_cache_ttl_hours() -> u64 {
+    24
+}
+
+impl PricingSettings {
+    /// Timeout for each price download (at least one second)
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs.max(1))
+    }
+
+    /// Lifetime of fetched prices
+    pub fn cache_ttl(&self) -> Duration {
+        Duration::from_secs(self.cache_ttl_hours.saturating_mul(3600))
+    }
+}
+
+/// A model's rates as listed by a provider
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelRates {
+    #[serde(flatten)]
+    pub rates: PricingRates,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub long_context: Option<LongContextRates>,
+}
+
+/// Rates listed by one provider, keyed by its model ids
+pub type ProviderPrices = BTreeMap<String, ModelRates>;
+
+/// Parse OpenRouter's model list. Prices are per token, as strings; cache prices
+/// OpenRouter leaves out default to a 25% markup (writes) and a 90% discount
+/// (reads) on input. Models priced by the request (a negative price) are skipped.
+pub fn parse_openrouter(content: &str) -> Result<ProviderPrices> {
+    let response: Value = serde_json::from_str(content).context("Invalid OpenRouter response")?;
+    let models = response
+        .get("data")
+        .and_then(Value::as_array)
+        .context("OpenRouter response has no model list")?;
+
+    let mut prices = ProviderPrices::new();
+    for model in models {
+        let Some(id) = model.get("id").and_then(Value::as_str) else {
+            continue;
+        };
+        if let Some(rates) = model.get("pricing").and_then(openrouter_rates) {
+            prices.insert(id.to_string(), rates);
+        }
+    }
+    Ok(prices)
+}
+
+/// Rates of one OpenRouter `pricing` object (per token, as strings or numbers)
+fn openrouter_rates(pricing: &Value) -> Option<ModelRates> {
+    let price = |names: &[&str]| {
+        names
+            .iter()
+            .find_map(|name| per_million(pricing.get(*name)))
+    };
+
+    let input = price(&["prompt"])?;
+    let output = price(&["completion"])?;
+    if input < 0.0 || output < 0.0 {
+        return None;
+    }
+
+    Some(ModelRates {
+        rates: PricingRates {
+            input,
+            output,
+            cache_write: price(&["input_cache_write", "cache_creation_input_token_cost"])
+                .unwrap_or(input * 1.25),
+            cache_write_1h: None,
+            cache_read: price(&["input_cache_read", "cache_read_input_token_cost"])
+                .unwrap_or(input * 0.1),
+        },
+        long_context: None,
+    })
+}
+
+/// Parse a LiteLLM-style `model_prices.json`. Prices are per token; entries
+/// without input and output prices (such as `sample_spec`) are skipped, and
+/// `*_above_200k_tokens` prices become long-context rates.
+pub fn parse_litellm(content: &str) -> Result<ProviderPrices> {
+    let models: BTreeMap<String, Value> =
+        serde_json::from_str(content).context("Invalid model_prices.json")?;
+
+    let mut prices = ProviderPrices::new();
+    for (id, model) in models {
+        let price = |name: &str| per_million(model.get(name));
+        let rates = |suffix: &str| -> Option<PricingRates> {
+            let input = price(&format!("input_cost_per_token{suffix}"))?;
+            let output = price(&format!("output_cost_per_token{suffix}"))?;
+            Some(PricingRates {
+                input,
+                output,
+                cache_write: price(&format!("cache_creation_input_token_cost{suffix}"))
+                    .unwrap_or(input * 1.25),
+                cache_write_1h: price(&format!(
+                    "cache_creation_input_token_cost_above_1hr{suffix}"
+                )),
+                cache_read: price(&format!("cache_read_input_token_cost{suffix}"))
+                    .unwrap_or(input * 0.1),
+            })
+        };
+
+        let Some(base) = rates("") else {
+            continue;
+        };
+        let long_context = rates("_above_200k_tokens").map(|rates| LongContextRates {
+            threshold: DEFAULT_LONG_CONTEXT_THRESHOLD,
+            rates,
+        });
+        prices.insert(
+            id,
+            ModelRates {
+                rates: base,
+                long_context,
+            },
+        );
+    }
+    Ok(prices)
+}
+
+/// A per-token price, given as a number or a numeric string, in USD per million tokens
+fn per_million(value: Option<&Value>) -> Option<f64> {
+    let price = match value? {
+        Value::Number(number) => number.as_f64()?,
+        Value::String(text) => text.trim().parse().ok()?,
+        _ => return None,
+    };
+    Some(price * 1_000_000.0)
+}
+
+/// Model id in comparable form: lowercase, without a `vendor/` prefix, with
+/// dots as hyphens (OpenRouter lists `claude-3.5-sonnet`, Claude `claude-3-5-sonnet`)
+fn normalize_id(id: &str) -> String {
+    let name = id.rsplit('/').next().unwrap_or(id);
+    name.to_lowercase().replace('.', "-")
+}
+
+/// Whether a provider id names the requested model: the same id, or the id
+/// followed by a release date (`claude-sonnet-4` for `claude-sonnet-4-20250514`)
+fn names_model(id: &str, model: &str) -> bool {
+    if id.is_empty() {
+        return false;
+    }
+    match model.strip_prefix(id) {
+        Some("") => true,
+        Some(rest) => rest
+            .strip_prefix('-')
+            .is_some_and(|date| date.len() == 8 && date.bytes().all(|b| b.is_ascii_digit())),
+        None => false,
+    }
+}
+
+/// The provider model naming `model`. An id naming it exactly beats one that
+/// leaves out the date; ties go to the first id in sorted order.
+pub fn find_model<'a>(
+    prices: &'a ProviderPrices,
+    model: &str,
+) -> Option<(&'a str, &'a ModelRates)> {
+    let model = normalize_id(model);
+    prices
+        .iter()
+        .filter_map(|(id, rates)| {
+            let normalized = normalize_id(id);
+            names_model(&normalized, &model).then_some((normalized.len(), id, rates))
+        })
+        .fold(None, |best, candidate| match best {
+            Some((len, _, _)) if len >= candidate.0 => best,
+            _ => Some(candidate),
+        })
+        .map(|(_, id, rates)| (id.as_str(), rates))
+}
+
+/// A provider model of the same Claude family (sonnet, opus or haiku);
+/// the first id in sorted order wins
+fn find_family<'a>(prices: &'a ProviderPrices, model: &str) -> Option<&'a str> {
+    let model = model.to_lowercase();
+    let family = ["sonnet", "opus", "haiku"]
+        .into_iter()
+        .find(|family| model.contains("claude") && model.contains(family))?;
+
+    prices
+        .keys()
+        .find(|id| {
+            let id = id.to_lowercase();
+            id.contains("claude") && id.contains(family)
+        })
+        .map(String::as_str)
+}
+
+/// A model's rates and the time they were first seen
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PricedSince {
+    /// Unix timestamp of the refresh that first saw the rates (0 for a model's first rates)
+    pub since: u64,
+    pub rates: ModelRates,
+}
+
+/// Prices fetched from one provider
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProviderCache {
+    /// Unix timestamp of the fetch
+    pub last_updated: u64,
+    pub models: ProviderPrices,
+    /// Every price seen for each model, oldest first, carried over from fetch to fetch
+    #[serde(default)]
+    pub history: BTreeMap<String, Vec<PricedSince>>,
+}
+
+impl ProviderCache {
+    /// Prices fetched at `last_updated`, with no history yet
+    pub fn new(models: ProviderPrices, last_updated: u64) -> Self {
+        Self {
+            last_updated,
+            models,
+            history: BTreeMap::new(),
+        }
+    }
+
+    /// Whether the prices are older than `ttl`
+    fn is_expired(&self, ttl: Duration) -> bool {
+        unix_now().saturating_sub(self.last_updated) > ttl.as_secs()
+    }
+
+    /// Carry over `previous`'s price history and append every price that changed.
+    /// A model's first price is assumed to apply to everything before it.
+    pub fn record_history(&mut self, previous: Option<ProviderCache>) {
+        if let Some(previous) = previous {
+            self.history = previous.history;
+        }
+
+        for (id, rates) in &self.models {
+            let prices = self.history.entry(id.clone()).or_default();
+            if prices.last().map(|p| &p.rates) != Some(rates) {
+                prices.push(PricedSince {
+                    since: if prices.is_empty() {
+                        0
+                    } else {
+                        self.last_updated
+                    },
+                    rates: *rates,
+                });
+            }
+        }
+    }
+
+    /// Rates of a cached model at `at` (the current rates when None)
+    pub fn rates_at(&self, id: &str, at: Option<DateTime<Utc>>) -> Option<&ModelRates> {
+        let current = self.models.get(id);
+        let (Some(at), Some(prices)) = (at, self.history.get(id)) else {
+            return current;
+        };
+
+        let timestamp = u64::try_from(at.timestamp()).unwrap_or(0);
+        prices
+            .iter()
+            .rev()
+            .find(|p| p.since <= timestamp)
+            .or_else(|| prices.first())
+            .map(|p| &p.rates)
+            .or(current)
+    }
+}
+
+/// The cache file: fetched prices of every remote provider, keyed by `cache_key`
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PricingCache {
+    #[serde(default)]
+    providers: BTreeMap<String, ProviderCache>,
+}
+
+impl PricingCache {
+    /// Load the cache file; a missing file is an empty cache. A cache written
+    /// before providers were configurable becomes the default OpenRouter entry.
+    fn load(cache_path: &Path) -> Result<Self> {
+        if !cache_path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content: Value = serde_json::from_str(&fs::read_to_string(cache_path)?)?;
+        if content.get("providers").is_none() && content.get("models").is_some() {
+            return Ok(Self::from_openrouter_only(&content));
+        }
+        Ok(serde_json::from_value(content)?)
+    }
+
+    /// Convert the OpenRouter-only layout (`last_updated`, `models` and `history`,
+    /// prices per token) so its price history keeps old sessions at their old rates
+    fn from_openrouter_only(content: &Value) -> Self {
+        let object = |key: &str| {
+            content
+                .get(key)
+                .and_then(Value::as_object)
+                .cloned()
+                .unwrap_or_default()
+        };
+
+        let models = object("models")
+            .into_iter()
+            .filter_map(|(id, model)| Some((id, openrouter_rates(model.get("pricing")?)?)))
+            .collect();
+        let history = object("history")
+            .into_iter()
+            .map(|(id, prices)| {
+                let prices = prices
+                    .as_array()
+                    .map(Vec::as_slice)
+                    .unwrap_or_default()
+                    .iter()
+                    .filter_map(|price| {
+                        Some(PricedSince {
+                            since: price.get("since")?.as_u64()?,
+                            rates: openrouter_rates(price.get("pricing")?)?,
+                        })
+                    })
+                    .collect();
+                (id, prices)
+            })
+            .collect();
+
+        let provider = PricingProvider::OpenRouter {
+            url: default_openrouter_url(),
+        };
+        let cache = ProviderCache {
+            last_updated: content
+                .get("last_updated")
+                .and_then(Value::as_u64)
+                .unwrap_or(0),
+            models,
+            history,
+        };
+        Self {
+            providers: [(provider.cache_key(), cache)].into(),
+        }
+    }
+
+    /// Write the cache file; several dashboards may share it
+    fn save(&self, cache_path: &Path) -> Result<()> {
+        write_atomically(cache_path, serde_json::to_string_pretty(self)?)
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Model pricing resolver: user overrides, then the configured providers in
+/// priority order, then Sonnet rates
+pub struct PricingResolver {
+    settings: PricingSettings,
+    cache_path: PathBuf,
+    cache: PricingCache,
+    /// Prices read from `litellm_file` providers, keyed by `cache_key`
+    files: BTreeMap<String, ProviderPrices>,
+    /// Pricing table compiled into the binary
+    bundled: &'static PricingTable,
+    /// User's pricing file from the config directory, when present and valid
+    overrides: Option<PricingTable>,
+    /// Why the user's pricing file was ignored, if it was
+    override_error: Option<String>,
+}
+
+impl PricingResolver {
+    /// Resolver for the given providers, caching fetched prices in `claude_dir`
+    pub fn new(claude_dir: &Path, settings: PricingSettings) -> Self {
+        Self {
+            settings,
+            cache_path: claude_dir.join(CACHE_FILE_NAME),
+            cache: PricingCache::default(),
+            files: BTreeMap::new(),
+            bundled: PricingTable::bundled(),
+            overrides: None,
+            override_error: None,
+        }
+    }
+
+    /// Load cached prices, even expired ones, and read the price files. Providers
+    /// that fail to load are skipped; the first error is returned.
+    pub fn init_cache(&mut self) -> Result<()> {
+        let mut first_error = None;
+        match PricingCache::load(&self.cache_path) {
+            Ok(cache) => self.cache = cache,
+            Err(e) => first_error = Some(e),
+        }
+
+        for provider in &self.settings.providers {
+            if let PricingProvider::LitellmFile { path } = provider {
+                match read_price_file(path) {
+                    Ok(prices) => {
+                        self.files.insert(provider.cache_key(), prices);
+                    }
+                    Err(e) => {
+                        first_error.get_or_insert(e);
+                    }
+                }
+            }
+        }
+
+        first_error.map_or(Ok(()), Err)
+    }
+
+    /// Fetch prices from every remote provider whose cached prices are missing or
+    /// older than the cache TTL, and save them with their price history. Every
+    /// provider is tried; the first failure is returned.
+    pub async fn refresh_if_needed(&mut self) -> Result<()> {
+        let ttl = self.settings.cache_ttl();
+        let stale: Vec<PricingProvider> = self
+            .settings
+            .providers
+            .iter()
+            .filter(|p| p.is_remote())
+            .filter(|p| {
+                self.cache
+                    .providers
+                    .get(&p.cache_key())
+                    .map_or(true, |cache| cache.is_expired(ttl))
+            })
+            .cloned()
+            .collect();
+        if stale.is_empty() {
+            return Ok(());
+        }
+
+        let client = reqwest::Client::builder()
+            .timeout(self.settings.timeout())
+            .build()?;
+        let mut first_error = None;
+        let mut fetched = false;
+        for provider in stale {
+            match provider.fetch(&client).await {
+                Ok(models) => {
+                    let key = provider.cache_key();
+                    let mut fresh = ProviderCache::new(models, unix_now());
+                    fresh.record_history(self.cache.providers.remove(&key));
+                    self.cache.providers.insert(key, fresh);
+                    fetched = true;
+                }
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+
+        if fetched {
+            self.cache.save(&self.cache_path)?;
+        }
+        first_error.map_or(Ok(()), Err)
+    }
+
+    /// Load the user's pricing overrides. A missing file clears them; an invalid one
+    /// is ignored and its error kept for `override_error`.
+    pub fn load_overrides(&mut self, path: &Path) -> Result<()> {
+        match PricingTable::load(path) {
+            Ok(table) => {
+                self.overrides = table;
+                self.override_error = None;
+                Ok(())
+            }
+            Err(e) => {
+                self.overrides = None;
+                self.override_error = Some(format!("{e:#}"));
+                Err(e)
+            }
+        }
+    }
+
+    /// Why the user's pricing file was ignored, if it was
+    pub fn override_error(&self) -> Option<&str> {
+        self.override_error.as_deref()
+    }
+
+    /// Resolve a model's current rates
+    pub fn resolve(&self, model_name: Option<&str>) -> ResolvedPricing {
+        self.resolve_at(model_name, None)
+    }
+
+    /// Resolve a model's rates as they were at `at` (now when None). The user's
+    /// overrides come first, then each provider in priority order; a provider model
+    /// of the same family (sonnet/opus/haiku) is used only when no provider knows
+    /// the model itself, and Sonnet rates when nothing matches at all.
+    pub fn resolve_at(
+        &self,
+        model_name: Option<&str>,
+        at: Option<DateTime<Utc>>,
+    ) -> ResolvedPricing {
+        let model = model_name.unwrap_or("");
+
+        if let Some(entry) = self.overrides.as_ref().and_then(|t| t.lookup_at(model, at)) {
+            return ResolvedPricing::from_entry(entry, PricingSource::Override);
+        }
+
+        for provider in &self.settings.providers {
+            let resolved = match provider {
+                PricingProvider::Static => self
+                    .bundled
+                    .lookup_at(model, at)
+                    .map(|entry| ResolvedPricing::from_entry(entry, PricingSource::Bundled)),
+                _ => self
+                    .provider_prices(provider)
+                    .and_then(|prices| find_model(prices, model))
+                    .and_then(|(id, _)| self.provider_rates(provider, id, at)),
+            };
+            if let Some(resolved) = resolved {
+                return resolved;
+            }
+        }
+
+        for provider in &self.settings.providers {
+            if let Some(resolved) = self
+                .provider_prices(provider)
+                .and_then(|prices| find_family(prices, model))
+                .and_then(|id| self.provider_rates(provider, id, at))
+            {
+                return resolved;
+            }
+        }
+
+        ResolvedPricing::default_rates()
+    }
+
+    /// Cost multiplier for a request's service tier: the user's overrides first,
+    /// then the bundled table; tiers neither lists cost the standard rates
+    pub fn service_tier_multiplier(&self, tier: Option<&str>) -> f64 {
+        let Some(tier) = tier else {
+            return 1.0;
+        };
+
+        self.overrides
+            .as_ref()
+            .and_then(|t| t.tier_multiplier(tier))
+            .or_else(|| self.bundled.tier_multiplier(tier))
+            .unwrap_or(1.0)
+    }
+
+    /// Calculate one request's cost from the rates in effect at `at`, applying
+    /// long-context rates, the cache-write TTLs and the service tier. Returns the
+    /// cost in USD and the source its rates came from.
+    pub fn calculate_cost_sync(
+        &self,
+        model_name: Option<&str>,
+        at: Option<DateTime<Utc>>,
+        tokens: &RequestTokens,
+    ) -> (f64, PricingSource) {
+        let resolved = self.resolve_at(model_name, at);
+        let cost = resolved.cost(tokens, self.service_tier_multiplier(tokens.service_tier));
+        (cost, resolved.source)
+    }
+
+    /// Prices a fetched or file provider has, None for the static table
+    fn provider_prices(&self, provider: &PricingProvider) -> Option<&ProviderPrices> {
+        let key = provider.cache_key();
+        match provider {
+            PricingProvider::Static => None,
+            PricingProvider::LitellmFile { .. } => self.files.get(&key),
+            _ => self.cache.providers.get(&key).map(|cache| &cache.models),
+        }
+    }
+
+    /// Rates of a provider's model `id` as priced at `at`
+    fn provider_rates(
+        &self,
+        provider: &PricingProvider,
+        id: &str,
+        at: Option<DateTime<Utc>>,
+    ) -> Option<ResolvedPricing> {
+        let rates = match self.cache.providers.get(&provider.cache_key()) {
+            Some(cache) => cache.rates_at(id, at)?,
+            None => self.provider_prices(provider)?.get(id)?,
+        };
+        Some(ResolvedPricing {
+            rates: rates.rates,
+            long_context: rates.long_context,
+            source: provider.source(),
+            matched: Some(id.to_string()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    const OPENROUTER_RESPONSE: &str = r#"{ "data": [
+        { "id": "anthropic/claude-sonnet-4",
+          "pricing": { "prompt": "0.000003", "completion": "0.000015",
+                       "input_cache_write": "0.00000375", "input_cache_read": "0.0000003" } },
+        { "id": "anthropic/claude-3.5-haiku",
+          "pricing": { "prompt": "0.0000008", "completion": "0.000004" } },
+        { "id": "openrouter/auto", "pricing": { "prompt": "-1", "completion": "-1" } }
+    ] }"#;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("provider_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Serve `body` to each of `requests` connections on a local port; returns its URL
+    fn serve(body: &'static str, requests: usize) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/api/v1/models", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            for stream in listener.incoming().take(requests) {
+                let mut stream = stream.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match stream.read(&mut buf) {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => request.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+        url
+    }
+
+    fn sonnet(input: f64) -> ModelRates {
+        ModelRates {
+            rates: PricingRates {
+                input,
+                output: input * 5.0,
+                cache_write: input * 1.25,
+                cache_write_1h: None,
+                cache_read: input * 0.1,
+            },
+            long_context: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_openrouter() {
+        let prices = parse_openrouter(OPENROUTER_RESPONSE).unwrap();
+
+        assert_eq!(prices.len(), 2, "per-request pricing is skipped");
+        let sonnet = &prices["anthropic/claude-sonnet-4"].rates;
+        assert!((sonnet.input - 3.0).abs() < 1e-9);
+        assert!((sonnet.cache_write - 3.75).abs() < 1e-9);
+        assert!((sonnet.cache_read - 0.3).abs() < 1e-9);
+        // Cache prices that are not listed are derived from the input price
+        let haiku = &prices["anthropic/claude-3.5-haiku"].rates;
+        assert!((haiku.cache_write - 1.0).abs() < 1e-9);
+        assert!((haiku.cache_read - 0.08).abs() < 1e-9);
+
+        assert!(parse_openrouter("{}").is_err());
+    }
+
+    #[test]
+    fn test_parse_litellm() {
+        let prices = parse_litellm(
+            r#"{
+                "sample_spec": { "input_cost_per_token": "set to 0 if free" },
+                "claude-sonnet-4-20250514": {
+                    "input_cost_per_token": 3e-06, "output_cost_per_token": 1.5e-05,
+                    "cache_creation_input_token_cost": 3.75e-06,
+                    "cache_creation_input_token_cost_above_1hr": 6e-06,
+                    "cache_read_input_token_cost": 3e-07,
+                    "input_cost_per_token_above_200k_tokens": 6e-06,
+                    "output_cost_per_token_above_200k_tokens": 2.25e-05
+                },
+                "text-embedding-3-small": { "input_cost_per_token": 2e-08 }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(prices.len(), 1);
+        let sonnet = &prices["claude-sonnet-4-20250514"];
+        assert!((sonnet.rates.cache_write_1h.unwrap() - 6.0).abs() < 1e-9);
+        let long = sonnet.long_context.unwrap();
+        assert_eq!(long.threshold, DEFAULT_LONG_CONTEXT_THRESHOLD);
+        assert!((long.rates.input - 6.0).abs() < 1e-9);
+        assert!((long.rates.output - 22.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_find_model_matches_ids_with_and_without_dates() {
+        let prices: ProviderPrices = [
+            ("anthropic/claude-3.5-haiku".to_string(), sonnet(0.8)),
+            ("anthropic/claude-sonnet-4".to_string(), sonnet(3.0)),
+            ("claude-sonnet-4-20250514".to_string(), sonnet(4.0)),
+        ]
+        .into_iter()
+        .collect();
+
+        let id = |model| find_model(&prices, model).map(|(id, _)| id);
+        // An id naming the exact release beats the undated one
+        assert_eq!(
+            id("claude-sonnet-4-20250514"),
+            Some("claude-sonnet-4-20250514")
+        );
+        assert_eq!(
+            id("claude-sonnet-4-20250601"),
+            Some("anthropic/claude-sonnet-4")
+        );
+        assert_eq!(
+            id("claude-3-5-haiku-20241022"),
+            Some("anthropic/claude-3.5-haiku")
+        );
+        // A newer model is not priced as an older one with a shared prefix
+        assert_eq!(id("claude-sonnet-4-5-20250929"), None);
+        assert_eq!(
+            find_family(&prices, "claude-sonnet-4-5-20250929"),
+            Some("anthropic/claude-sonnet-4")
+        );
+        assert_eq!(find_family(&prices, "gpt-4o"), None);
+    }
+
+    #[test]
+    fn test_settings_from_config() {
+        let settings: PricingSettings = serde_json::from_str(
+            r#"{ "providers": [
+                    { "type": "http", "url": "http://localhost:9000/prices.json" },
+                    { "type": "litellm_file", "path": "/etc/model_prices.json" },
+                    { "type": "openrouter" },
+                    { "type": "static" }
+                 ],
+                 "timeout_secs": 3 }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            settings.providers,
+            vec![
+                PricingProvider::Http {
+                    url: "http://localhost:9000/prices.json".to_string(),
+                    format: PriceFormat::Litellm,
+                },
+                PricingProvider::LitellmFile {
+                    path: PathBuf::from("/etc/model_prices.json"),
+                },
+                PricingProvider::OpenRouter {
+                    url: OPENROUTER_MODELS_URL.to_string(),
+                },
+                PricingProvider::Static,
+            ]
+        );
+        assert_eq!(settings.timeout(), Duration::from_secs(3));
+        assert_eq!(settings.cache_ttl(), Duration::from_secs(24 * 3600));
+
+        let defaults: PricingSettings = serde_json::from_str("{}").unwrap();
+        assert_eq!(defaults, PricingSettings::default());
+    }
+
+    #[test]
+    fn test_providers_are_tried_in_priority_order() {
+        let dir = temp_dir("priority");
+        let price_file = dir.join("model_prices.json");
+        fs::write(
+            &price_file,
+            r#"{ "claude-sonnet-4": { "input_cost_per_token": 1e-06, "output_cost_per_token": 5e-06 },
+                 "claude-opus-9": { "input_cost_per_token": 2e-05, "output_cost_per_token": 1e-04 } }"#,
+        )
+        .unwrap();
+        let file = PricingProvider::LitellmFile { path: price_file };
+
+        let resolver = |providers| {
+            let mut resolver = PricingResolver::new(
+                &dir,
+                PricingSettings {
+                    providers,
+                    ..Default::default()
+                },
+            );
+            resolver.init_cache().unwrap();
+            resolver
+        };
+
+        let file_first = resolver(vec![file.clone(), PricingProvider::Static]);
+        let resolved = file_first.resolve(Some("claude-sonnet-4-20250514"));
+        assert_eq!(resolved.source, PricingSource::PriceFile);
+        assert!((resolved.rates.input - 1.0).abs() < 1e-9);
+
+        let static_first = resolver(vec![PricingProvider::Static, file.clone()]);
+        let resolved = static_first.resolve(Some("claude-sonnet-4-20250514"));
+        assert_eq!(resolved.source, PricingSource::Bundled);
+        // Models the bundled table does not know fall through to the next provider
+        let resolved = static_first.resolve(Some("claude-opus-9-20270101"));
+        assert_eq!(resolved.source, PricingSource::PriceFile);
+        assert_eq!(resolved.matched.as_deref(), Some("claude-opus-9"));
+
+        let file_only = resolver(vec![file]);
+        let resolved = file_only.resolve(Some("claude-opus-4-1-20250805"));
+        assert_eq!(
+            resolved.matched.as_deref(),
+            Some("claude-opus-9"),
+            "same family"
+        );
+        assert_eq!(
+            file_only.resolve(Some("gpt-4o")).source,
+            PricingSource::Default
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_openrouter_only_cache_keeps_its_history() {
+        let dir = temp_dir("legacy_cache");
+        let cache_path = dir.join(CACHE_FILE_NAME);
+        fs::write(
+            &cache_path,
+            r#"{
+                "last_updated": 5000,
+                "models": {
+                    "anthropic/claude-sonnet-4": {
+                        "id": "anthropic/claude-sonnet-4",
+                        "pricing": { "prompt": 0.000002, "completion": 0.00001,
+                                     "cache_creation_input_token_cost": null,
+                                     "cache_read_input_token_cost": 0.0000002 }
+                    }
+                },
+                "history": {
+                    "anthropic/claude-sonnet-4": [
+                        { "since": 0, "pricing": { "prompt": 0.000003, "completion": 0.000015 } },
+                        { "since": 5000, "pricing": { "prompt": 0.000002, "completion": 0.00001 } }
+                    ]
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let cache = PricingCache::load(&cache_path).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+
+        let key = PricingProvider::OpenRouter {
+            url: OPENROUTER_MODELS_URL.to_string(),
+        }
+        .cache_key();
+        let openrouter = &cache.providers[&key];
+        assert_eq!(openrouter.last_updated, 5000);
+
+        let id = "anthropic/claude-sonnet-4";
+        let current = openrouter.rates_at(id, None).unwrap().rates;
+        assert!((current.input - 2.0).abs() < 1e-9);
+        assert!((current.cache_read - 0.2).abs() < 1e-9);
+        let old = openrouter
+            .rates_at(id, DateTime::from_timestamp(100, 0))
+            .unwrap()
+            .rates;
+        assert!((old.input - 3.0).abs() < 1e-9);
+        assert!((old.cache_write - 3.75).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_history_keeps_the_price_of_each_period() {
+        let mut first = ProviderCache::new([("m".to_string(), sonnet(3.0))].into(), 1_000);
+        first.record_history(None);
+        let mut second = ProviderCache::new([("m".to_string(), sonnet(2.0))].into(), 5_000);
+        second.record_history(Some(first));
+
+        let at = |secs| DateTime::from_timestamp(secs, 0);
+        assert_eq!(second.rates_at("m", at(100)), Some(&sonnet(3.0)));
+        assert_eq!(second.rates_at("m", at(4_999)), Some(&sonnet(3.0)));
+        assert_eq!(second.rates_at("m", at(5_000)), Some(&sonnet(2.0)));
+        assert_eq!(second.rates_at("m", None), Some(&sonnet(2.0)));
+    }
+
+    #[tokio::test]
+    async fn test_openrouter_is_fetched_from_the_configured_url_and_cached() {
+        let dir = temp_dir("openrouter");
+        let provider = PricingProvider::OpenRouter {
+            url: serve(OPENROUTER_RESPONSE, 1),
+        };
+        let settings = PricingSettings {
+            providers: vec![provider.clone()],
+            ..Default::default()
+        };
+
+        let mut resolver = PricingResolver::new(&dir, settings.clone());
+        resolver.refresh_if_needed().await.unwrap();
+        let resolved = resolver.resolve(Some("claude-sonnet-4-20250514"));
+        assert_eq!(resolved.source, PricingSource::OpenRouter);
+        assert_eq!(
+            resolved.matched.as_deref(),
+            Some("anthropic/claude-sonnet-4")
+        );
+
+        // A fresh cache is not fetched again (the server has stopped answering)
+        let mut reloaded = PricingResolver::new(&dir, settings);
+        reloaded.init_cache().unwrap();
+        reloaded.refresh_if_needed().await.unwrap();
+        assert_eq!(reloaded.resolve(Some("claude-sonnet-4")), resolved);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_gives_up_after_the_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let provider = PricingProvider::Http {
+            url: format!("http://{}/prices.json", listener.local_addr().unwrap()),
+            format: PriceFormat::Litellm,
+        };
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(200))
+            .build()
+            .unwrap();
+
+        // The listener accepts the connection but never answers
+        assert!(provider.fetch(&client).await.is_err());
+        drop(listener);
+    }
+}
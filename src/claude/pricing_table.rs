@@ -0,0 +1,527 @@
+//! Offline pricing tables mapping model-id patterns to token rates
+//!
+//! A table ships inside the binary (`assets/pricing.json`) so costs stay right on
+//! machines that cannot reach a pricing provider, and `pricing.json` in the
+//! cc-enhanced config directory can override any of its entries. Both files use
+//! the same format, with rates in USD per million tokens:
+//!
+//! ```json
+//! { "models": [
+//!     { "pattern": "claude-sonnet-4", "input": 3.0, "output": 15.0,
+//!       "cache_write": 3.75, "cache_write_1h": 6.0, "cache_read": 0.3,
+//!       "long_context": { "threshold": 200000, "input": 6.0, "output": 22.5,
+//!                         "cache_write": 7.5, "cache_write_1h": 12.0, "cache_read": 0.6 } }
+//!   ],
+//!   "service_tiers": { "batch": 0.5 } }
+//! ```
+//!
+//! An entry may carry an `effective_from` date. When a price changes, the new rate
+//! is added as a dated entry next to the old one instead of replacing it, so messages
+//! keep the price that applied when they were sent.
+//!
+//! Each request is priced on its own: `long_context` rates replace the base rates
+//! when the request's prompt (input plus cache tokens) exceeds the threshold,
+//! cache writes with a 1-hour TTL use `cache_write_1h`, and the total is scaled by
+//! the multiplier of the request's `service_tier`.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Pricing table compiled into the binary
+const BUNDLED_PRICING: &str = include_str!("../../assets/pricing.json");
+
+/// File name of the user's pricing overrides in the config directory
+pub const OVERRIDE_FILE_NAME: &str = "pricing.json";
+
+/// Prompt size above which long-context rates apply, when an entry does not set one
+pub const DEFAULT_LONG_CONTEXT_THRESHOLD: u64 = 200_000;
+
+/// Rates assumed when nothing knows a model: Claude Sonnet, the most common model
+pub const DEFAULT_RATES: PricingRates = PricingRates {
+    input: 3.0,
+    output: 15.0,
+    cache_write: 3.75,
+    cache_write_1h: Some(6.0),
+    cache_read: 0.3,
+};
+
+/// Token rates in USD per million tokens
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PricingRates {
+    pub input: f64,
+    pub output: f64,
+    /// Cache writes with the default 5-minute TTL
+    pub cache_write: f64,
+    /// Cache writes with the 1-hour TTL; twice the input rate when not given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_write_1h: Option<f64>,
+    pub cache_read: f64,
+}
+
+impl PricingRates {
+    /// Rate for cache writes with the 1-hour TTL
+    pub fn cache_write_1h_rate(&self) -> f64 {
+        self.cache_write_1h.unwrap_or(self.input * 2.0)
+    }
+
+    /// Cost in USD of one request's tokens, unrounded
+    pub fn cost(&self, tokens: &RequestTokens) -> f64 {
+        ((tokens.input as f64) * self.input
+            + (tokens.output as f64) * self.output
+            + (tokens.cache_write_5m as f64) * self.cache_write
+            + (tokens.cache_write_1h as f64) * self.cache_write_1h_rate()
+            + (tokens.cache_read as f64) * self.cache_read)
+            / 1_000_000.0
+    }
+}
+
+/// Rates that replace the base rates for requests with a long prompt
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LongContextRates {
+    /// Prompt tokens (input plus cache) above which these rates apply
+    #[serde(default = "default_long_context_threshold")]
+    pub threshold: u64,
+    #[serde(flatten)]
+    pub rates: PricingRates,
+}
+
+fn default_long_context_threshold() -> u64 {
+    DEFAULT_LONG_CONTEXT_THRESHOLD
+}
+
+/// Token counts of one API request, as far as pricing is concerned
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RequestTokens<'a> {
+    pub input: u64,
+    pub output: u64,
+    /// Cache writes with the default 5-minute TTL
+    pub cache_write_5m: u64,
+    /// Cache writes with the 1-hour TTL
+    pub cache_write_1h: u64,
+    pub cache_read: u64,
+    /// `service_tier` the request ran on, when recorded
+    pub service_tier: Option<&'a str>,
+}
+
+impl<'a> RequestTokens<'a> {
+    /// Split a request's cache writes by TTL. Transcripts that do not break them
+    /// down wrote everything with the default 5-minute TTL.
+    pub fn new(
+        input: u64,
+        output: u64,
+        cache_creation: u64,
+        cache_write_1h: u64,
+        cache_read: u64,
+        service_tier: Option<&'a str>,
+    ) -> Self {
+        let cache_write_1h = cache_write_1h.min(cache_creation);
+        Self {
+            input,
+            output,
+            cache_write_5m: cache_creation - cache_write_1h,
+            cache_write_1h,
+            cache_read,
+            service_tier,
+        }
+    }
+
+    /// Size of the prompt: input plus cache writes and reads
+    pub fn prompt_tokens(&self) -> u64 {
+        self.input
+            .saturating_add(self.cache_write_5m)
+            .saturating_add(self.cache_write_1h)
+            .saturating_add(self.cache_read)
+    }
+}
+
+/// One table row: a model-id pattern and its rates
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PricingEntry {
+    /// Case-insensitive pattern; matches model ids containing it, `*` matches any run of characters
+    pub pattern: String,
+    /// First day (UTC) the rates apply; None applies to every date
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effective_from: Option<NaiveDate>,
+    #[serde(flatten)]
+    pub rates: PricingRates,
+    /// Rates for requests whose prompt is above the long-context threshold
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub long_context: Option<LongContextRates>,
+}
+
+impl PricingEntry {
+    /// Whether this entry's pattern matches a model id
+    pub fn matches(&self, model: &str) -> bool {
+        let model = model.to_lowercase();
+        let pattern = self.pattern.to_lowercase();
+
+        // Every `*`-separated part must appear, in order
+        let mut rest = model.as_str();
+        for part in pattern.split('*').filter(|part| !part.is_empty()) {
+            match rest.find(part) {
+                Some(pos) => rest = &rest[pos + part.len()..],
+                None => return false,
+            }
+        }
+        true
+    }
+
+    /// How specific the pattern is: its length without wildcards
+    fn specificity(&self) -> usize {
+        self.pattern.chars().filter(|c| *c != '*').count()
+    }
+
+    /// Whether the rates apply on a given day
+    fn is_effective_on(&self, day: NaiveDate) -> bool {
+        self.effective_from.map_or(true, |from| from <= day)
+    }
+}
+
+/// A list of pricing entries, as read from a pricing file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PricingTable {
+    pub models: Vec<PricingEntry>,
+    /// Cost multiplier per `service_tier` (tiers not listed cost the standard rates)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub service_tiers: BTreeMap<String, f64>,
+}
+
+impl PricingTable {
+    /// The table compiled into the binary
+    pub fn bundled() -> &'static Self {
+        static BUNDLED: OnceLock<PricingTable> = OnceLock::new();
+        BUNDLED
+            .get_or_init(|| Self::parse(BUNDLED_PRICING).expect("bundled pricing table is valid"))
+    }
+
+    /// Parse a pricing file's contents
+    pub fn parse(content: &str) -> Result<Self> {
+        Ok(serde_json::from_str(content)?)
+    }
+
+    /// Load a pricing file; a missing file is `Ok(None)`
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(path)?;
+        Self::parse(&content)
+            .map(Some)
+            .with_context(|| format!("Invalid pricing file {}", path.display()))
+    }
+
+    /// Entry for a model id as priced at `at` (now when None). Only entries already
+    /// effective on that day are considered. The most specific matching pattern wins,
+    /// then the latest `effective_from`, then the entry listed first, so the result
+    /// never depends on anything but the table and the date.
+    pub fn lookup_at(&self, model: &str, at: Option<DateTime<Utc>>) -> Option<&PricingEntry> {
+        let day = at.unwrap_or_else(Utc::now).date_naive();
+
+        self.models
+            .iter()
+            .filter(|entry| entry.is_effective_on(day) && entry.matches(model))
+            .fold(None, |best: Option<&PricingEntry>, entry| match best {
+                Some(best)
+                    if (best.specificity(), best.effective_from)
+                        >= (entry.specificity(), entry.effective_from) =>
+                {
+                    Some(best)
+                }
+                _ => Some(entry),
+            })
+    }
+
+    /// Cost multiplier for a service tier, if this table sets one (case-insensitive)
+    pub fn tier_multiplier(&self, tier: &str) -> Option<f64> {
+        self.service_tiers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(tier))
+            .map(|(_, multiplier)| *multiplier)
+    }
+}
+
+/// Path of the user's pricing overrides (`~/.config/cc-enhanced/pricing.json`)
+pub fn override_path() -> Option<PathBuf> {
+    crate::shared::Config::config_dir()
+        .ok()
+        .map(|dir| dir.join(OVERRIDE_FILE_NAME))
+}
+
+/// Where a model's rates came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PricingSource {
+    /// The user's pricing file in the config directory
+    Override,
+    /// The cached OpenRouter model list
+    OpenRouter,
+    /// A LiteLLM-style `model_prices.json` on disk
+    PriceFile,
+    /// A price list fetched from a configured HTTP endpoint
+    Http,
+    /// The table compiled into the binary
+    Bundled,
+    /// Nothing matched; Sonnet rates are assumed
+    Default,
+}
+
+impl fmt::Display for PricingSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            PricingSource::Override => "user override",
+            PricingSource::OpenRouter => "OpenRouter",
+            PricingSource::PriceFile => "model_prices.json",
+            PricingSource::Http => "HTTP endpoint",
+            PricingSource::Bundled => "bundled table",
+            PricingSource::Default => "default (Sonnet)",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Rates chosen for a model, with where they came from
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedPricing {
+    pub rates: PricingRates,
+    /// Long-context rates, when the source has them
+    pub long_context: Option<LongContextRates>,
+    pub source: PricingSource,
+    /// Table pattern or provider model id that matched (None for the default)
+    pub matched: Option<String>,
+}
+
+impl ResolvedPricing {
+    /// Rates from a table entry
+    pub fn from_entry(entry: &PricingEntry, source: PricingSource) -> Self {
+        Self {
+            rates: entry.rates,
+            long_context: entry.long_context,
+            source,
+            matched: Some(entry.pattern.clone()),
+        }
+    }
+
+    /// The Sonnet rates assumed when nothing matched
+    pub fn default_rates() -> Self {
+        Self {
+            rates: DEFAULT_RATES,
+            long_context: None,
+            source: PricingSource::Default,
+            matched: None,
+        }
+    }
+
+    /// Rates that apply to a request: long-context rates above their threshold
+    pub fn rates_for(&self, tokens: &RequestTokens) -> &PricingRates {
+        match &self.long_context {
+            Some(long) if tokens.prompt_tokens() > long.threshold => &long.rates,
+            _ => &self.rates,
+        }
+    }
+
+    /// Cost in USD of one request, scaled by its service tier's multiplier and
+    /// rounded to 6 decimal places to avoid floating-point artifacts
+    pub fn cost(&self, tokens: &RequestTokens, tier_multiplier: f64) -> f64 {
+        let total = self.rates_for(tokens).cost(tokens) * tier_multiplier;
+        (total * 1_000_000.0).round() / 1_000_000.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(pattern: &str, input: f64) -> PricingEntry {
+        PricingEntry {
+            pattern: pattern.to_string(),
+            effective_from: None,
+            rates: PricingRates {
+                input,
+                output: input * 5.0,
+                cache_write: input * 1.25,
+                cache_write_1h: None,
+                cache_read: input * 0.1,
+            },
+            long_context: None,
+        }
+    }
+
+    #[test]
+    fn test_bundled_table_prices_current_models() {
+        let table = PricingTable::bundled();
+
+        let opus = table.lookup_at("claude-opus-4-20250514", None).unwrap();
+        assert_eq!(opus.pattern, "claude-opus-4");
+        assert_eq!(opus.rates.output, 75.0);
+
+        let opus_4_5 = table.lookup_at("claude-opus-4-5-20251101", None).unwrap();
+        assert_eq!(opus_4_5.pattern, "claude-opus-4-5");
+        assert_eq!(opus_4_5.rates.input, 5.0);
+
+        let sonnet = table.lookup_at("claude-sonnet-4-20250514", None).unwrap();
+        assert_eq!(sonnet.rates.input, 3.0);
+        assert_eq!(sonnet.rates.cache_read, 0.3);
+
+        assert!(table.lookup_at("<synthetic>", None).is_none());
+
+        let long = sonnet.long_context.unwrap();
+        assert_eq!(long.threshold, DEFAULT_LONG_CONTEXT_THRESHOLD);
+        assert_eq!(long.rates.input, 6.0);
+        assert_eq!(table.tier_multiplier("Batch"), Some(0.5));
+        assert_eq!(table.tier_multiplier("standard"), None);
+    }
+
+    #[test]
+    fn test_most_specific_pattern_wins_then_first_listed() {
+        let table = PricingTable {
+            models: vec![
+                entry("claude-*", 1.0),
+                entry("opus", 2.0),
+                entry("claude-opus-4", 3.0),
+                entry("CLAUDE-OPUS-*", 4.0),
+                entry("claude-opus-*", 5.0),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            table
+                .lookup_at("claude-opus-4-1", None)
+                .unwrap()
+                .rates
+                .input,
+            3.0
+        );
+        // "claude-opus-" and "CLAUDE-OPUS-" tie; the earlier entry wins
+        assert_eq!(
+            table.lookup_at("claude-opus-3", None).unwrap().rates.input,
+            4.0
+        );
+        assert_eq!(
+            table.lookup_at("claude-haiku", None).unwrap().rates.input,
+            1.0
+        );
+        assert!(table.lookup_at("gpt-4", None).is_none());
+    }
+
+    #[test]
+    fn test_dated_entries_price_messages_by_their_day() {
+        let dated = |input: f64, from: &str| PricingEntry {
+            effective_from: NaiveDate::parse_from_str(from, "%Y-%m-%d").ok(),
+            ..entry("claude-sonnet-4", input)
+        };
+        let table = PricingTable {
+            models: vec![
+                entry("claude", 9.0),
+                dated(2.0, "2025-09-01"),
+                entry("claude-sonnet-4", 3.0),
+                dated(1.0, "2025-12-01"),
+            ],
+            ..Default::default()
+        };
+        let at = |s: &str| Some(s.parse::<DateTime<Utc>>().unwrap());
+        let input_at = |time| {
+            table
+                .lookup_at("claude-sonnet-4-x", time)
+                .unwrap()
+                .rates
+                .input
+        };
+
+        assert_eq!(input_at(at("2025-06-30T12:00:00Z")), 3.0);
+        assert_eq!(input_at(at("2025-09-01T00:00:00Z")), 2.0);
+        assert_eq!(input_at(at("2025-11-30T23:59:59Z")), 2.0);
+        assert_eq!(input_at(at("2026-01-15T08:00:00Z")), 1.0);
+
+        // A dated entry alone does not price messages from before its date
+        let only_dated = PricingTable {
+            models: vec![entry("claude", 9.0), dated(2.0, "2025-09-01")],
+            ..Default::default()
+        };
+        let early = only_dated.lookup_at("claude-sonnet-4-x", at("2025-01-01T00:00:00Z"));
+        assert_eq!(early.unwrap().pattern, "claude");
+    }
+
+    #[test]
+    fn test_rates_are_per_million_tokens() {
+        let rates = DEFAULT_RATES;
+        let tokens = |input, output, cache_creation, cache_read| {
+            RequestTokens::new(input, output, cache_creation, 0, cache_read, None)
+        };
+
+        assert!((rates.cost(&tokens(1_000_000, 0, 0, 0)) - 3.0).abs() < 1e-9);
+        assert!((rates.cost(&tokens(1000, 1000, 1000, 1000)) - 0.02205).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_each_request_is_priced_at_its_tier() {
+        let sonnet = ResolvedPricing::from_entry(
+            PricingTable::bundled()
+                .lookup_at("claude-sonnet-4-20250514", None)
+                .unwrap(),
+            PricingSource::Bundled,
+        );
+        let cost = |tokens: RequestTokens| sonnet.cost(&tokens, 1.0);
+
+        // Standard request: 5-minute cache writes at the base rates
+        let standard = RequestTokens::new(100_000, 1_000, 50_000, 0, 50_000, None);
+        assert!((cost(standard) - (0.3 + 0.015 + 0.1875 + 0.015)).abs() < 1e-9);
+
+        // 1-hour cache writes cost twice the input rate
+        let one_hour = RequestTokens::new(100_000, 1_000, 50_000, 20_000, 50_000, None);
+        assert_eq!(one_hour.cache_write_5m, 30_000);
+        assert!((cost(one_hour) - (0.3 + 0.015 + 0.1125 + 0.12 + 0.015)).abs() < 1e-9);
+
+        // Exactly at the threshold is still standard; one token more is long context
+        assert_eq!(standard.prompt_tokens(), 200_000);
+        let long = RequestTokens {
+            input: 100_001,
+            ..standard
+        };
+        assert_eq!(sonnet.rates_for(&long).input, 6.0);
+        assert!((cost(long) - (0.600006 + 0.0225 + 0.375 + 0.03)).abs() < 1e-9);
+
+        // Batch requests cost half
+        let batch = RequestTokens {
+            service_tier: Some("batch"),
+            ..standard
+        };
+        let multiplier = PricingTable::bundled()
+            .tier_multiplier(batch.service_tier.unwrap())
+            .unwrap();
+        assert!((sonnet.cost(&batch, multiplier) - cost(standard) / 2.0).abs() < 1e-6);
+
+        // Cache-write breakdowns larger than the total are clamped
+        let clamped = RequestTokens::new(0, 0, 10, 25, 0, None);
+        assert_eq!((clamped.cache_write_5m, clamped.cache_write_1h), (0, 10));
+    }
+
+    #[test]
+    fn test_load_override_file() {
+        let dir = std::env::temp_dir().join(format!("pricing_table_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(OVERRIDE_FILE_NAME);
+
+        assert!(PricingTable::load(&path).unwrap().is_none());
+
+        fs::write(
+            &path,
+            r#"{"models":[{"pattern":"claude-sonnet-4","input":1,"output":2,"cache_write":3,"cache_read":4}]}"#,
+        )
+        .unwrap();
+        let table = PricingTable::load(&path).unwrap().unwrap();
+        assert_eq!(table.models[0].rates.cache_read, 4.0);
+
+        fs::write(&path, r#"{"models":[{"pattern":"claude"}]}"#).unwrap();
+        assert!(PricingTable::load(&path).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
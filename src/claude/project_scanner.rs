@@ -5,6 +5,7 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
+use super::path_resolver;
 use super::session_parser::{Session, SessionParser};
 
 /// Represents a Claude project
@@ -12,10 +13,30 @@ use super::session_parser::{Session, SessionParser};
 pub struct Project {
     pub name: String,
     pub path: PathBuf,
+    /// Directory under `projects/` that holds this project's session files
+    pub data_dir: PathBuf,
+    /// Claude data directory (e.g. `~/.claude`) the project was found in
+    pub root: PathBuf,
     pub sessions: Vec<Session>,
     pub is_active: bool,
 }
 
+impl Project {
+    /// Check whether the project matches a `--project` query (name or path fragment)
+    pub fn matches(&self, query: &str) -> bool {
+        self.name.eq_ignore_ascii_case(query) || self.path.to_string_lossy().contains(query)
+    }
+}
+
+/// Get the Claude data directory that contains a `projects/<name>` directory
+pub fn data_root(project_dir_path: &Path) -> PathBuf {
+    project_dir_path
+        .parent()
+        .and_then(Path::parent)
+        .map(Path::to_path_buf)
+        .unwrap_or_default()
+}
+
 /// Project scanner for Claude directories
 pub struct ProjectScanner {
     claude_dir: PathBuf,
@@ -84,51 +105,18 @@ impl ProjectScanner {
             })?;
 
         // Try to get the real path from the most recent JSONL file's "cwd" field
-        let (original_path, is_orphaned) = match self
+        let resolved = self
             .session_parser
             .get_project_path_from_recent_session(project_dir_path)
-        {
-            Ok(cwd_path) => {
-                if PathBuf::from(&cwd_path).exists() {
-                    (PathBuf::from(cwd_path), false)
-                } else {
-                    // CWD exists in JSONL but directory doesn't exist anymore
-                    (PathBuf::from(format!("Orphaned: {cwd_path}")), true)
-                }
-            }
-            Err(_) => {
-                // Fallback to old reconstruction method
-                match self.reconstruct_path_from_sanitized_name(sanitized_name) {
-                    Ok(p) => {
-                        if p.exists() {
-                            (p, false)
-                        } else {
-                            (PathBuf::from(format!("Orphaned: {sanitized_name}")), true)
-                        }
-                    }
-                    Err(_) => (PathBuf::from(format!("Unknown: {sanitized_name}")), true),
-                }
-            }
-        };
-
-        let project_name = if is_orphaned {
-            // For orphaned/unknown projects, use a cleaner name
-            if let Some(last_part) = sanitized_name.split('-').next_back() {
-                if last_part.is_empty() {
-                    sanitized_name.to_string()
-                } else {
-                    last_part.to_string()
-                }
-            } else {
-                sanitized_name.to_string()
-            }
-        } else {
-            original_path
-                .file_name()
-                .and_then(|name| name.to_str())
-                .unwrap_or(sanitized_name)
-                .to_string()
+            .ok()
+            .map(PathBuf::from);
+        let original_path = match &resolved {
+            Some(path) if path.exists() => path.clone(),
+            // CWD exists in JSONL but directory doesn't exist anymore
+            Some(path) => PathBuf::from(format!("Orphaned: {}", path.display())),
+            None => PathBuf::from(format!("Orphaned: {sanitized_name}")),
         };
+        let project_name = path_resolver::project_name(sanitized_name, resolved.as_deref());
 
         // Scan for sessions in the project directory
         let mut sessions = Vec::new();
@@ -161,59 +149,13 @@ impl ProjectScanner {
         Ok(Some(Project {
             name: project_name,
             path: original_path,
+            data_dir: project_dir_path.to_path_buf(),
+            root: data_root(project_dir_path),
             sessions,
             is_active,
         }))
     }
 
-    /// Reconstruct original project path from sanitized directory name
-    fn reconstruct_path_from_sanitized_name(&self, sanitized_name: &str) -> Result<PathBuf> {
-        // Claude sanitizes directory names by replacing special characters
-        // This is a reverse engineering attempt
-
-        // Common patterns:
-        // 1. Spaces become "-"
-        // 2. "/" becomes "--"
-        // 3. Special characters get removed or replaced
-
-        let reconstructed = sanitized_name.replace("--", "/").replace("-", " ");
-
-        // Try various common project locations
-        let possible_paths = vec![
-            PathBuf::from(&reconstructed),
-            dirs::home_dir().unwrap_or_default().join(&reconstructed),
-            dirs::home_dir()
-                .unwrap_or_default()
-                .join("Desktop")
-                .join(&reconstructed),
-            dirs::home_dir()
-                .unwrap_or_default()
-                .join("Documents")
-                .join(&reconstructed),
-            dirs::home_dir()
-                .unwrap_or_default()
-                .join("workspace")
-                .join(&reconstructed),
-            dirs::home_dir()
-                .unwrap_or_default()
-                .join("dev")
-                .join(&reconstructed),
-            dirs::home_dir()
-                .unwrap_or_default()
-                .join("projects")
-                .join(&reconstructed),
-        ];
-
-        for path in possible_paths {
-            if path.exists() {
-                return Ok(path);
-            }
-        }
-
-        // If nothing found, return the best guess
-        Ok(PathBuf::from(reconstructed))
-    }
-
     /// Get project by name
     pub fn get_project_by_name(&self, name: &str) -> Result<Option<Project>> {
         let projects = self.scan_projects()?;
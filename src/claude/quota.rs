@@ -0,0 +1,463 @@
+//! Plan limits and how much of them the active block and the past week have used
+//!
+//! Claude plans limit usage per 5-hour block and per week. Anthropic publishes
+//! these limits as rough message counts and hours of use rather than tokens, so
+//! the built-in token limits are estimates; `block_tokens` and `weekly_tokens` in
+//! the `quota` config section replace them, and are the only limits of a custom plan.
+//!
+//! For plans whose limits are unknown, the block limit is inferred from history:
+//! the 90th percentile (or the maximum) of the token totals of the blocks completed
+//! in the last 30 days. An inferred limit can be pinned, which saves it as `block_tokens`.
+
+use chrono::{DateTime, Duration, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use super::blocks::{self, BlockEntry, UsageBlock};
+use super::budget::BudgetSpend;
+use super::forecast::BurnRate;
+use super::periods::PeriodSettings;
+
+/// Days of completed blocks the block limit is inferred from
+pub const INFERENCE_DAYS: i64 = 30;
+
+/// Subscription plan the quota is measured against
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Plan {
+    #[default]
+    Pro,
+    #[serde(rename = "max5x")]
+    Max5x,
+    #[serde(rename = "max20x")]
+    Max20x,
+    /// Pay-as-you-go API usage, which has no block or weekly limit
+    Api,
+    /// Limits taken only from the config
+    Custom,
+}
+
+impl Plan {
+    /// Estimated input plus output tokens per 5-hour block
+    pub fn block_token_limit(self) -> Option<u64> {
+        match self {
+            Plan::Pro => Some(19_000),
+            Plan::Max5x => Some(88_000),
+            Plan::Max20x => Some(220_000),
+            Plan::Api | Plan::Custom => None,
+        }
+    }
+
+    /// Estimated input plus output tokens per week: the low end of the weekly
+    /// hours Anthropic lists for the plan, counted as full blocks
+    pub fn weekly_token_limit(self) -> Option<u64> {
+        let weekly_hours = match self {
+            Plan::Pro => 40,
+            Plan::Max5x => 140,
+            Plan::Max20x => 240,
+            Plan::Api | Plan::Custom => return None,
+        };
+        self.block_token_limit()
+            .map(|limit| limit * weekly_hours / blocks::BLOCK_HOURS as u64)
+    }
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Plan::Pro => "Pro",
+            Plan::Max5x => "Max 5x",
+            Plan::Max20x => "Max 20x",
+            Plan::Api => "API",
+            Plan::Custom => "Custom",
+        };
+        f.write_str(name)
+    }
+}
+
+/// The plan and any limits that replace its estimates (the `quota` config section)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QuotaSettings {
+    #[serde(default)]
+    pub plan: Plan,
+    /// Input plus output tokens allowed per 5-hour block
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_tokens: Option<u64>,
+    /// Input plus output tokens allowed per rolling week
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weekly_tokens: Option<u64>,
+    /// How the block limit is inferred from past blocks
+    #[serde(default)]
+    pub inference: InferenceMethod,
+}
+
+impl QuotaSettings {
+    /// Token limit of a 5-hour block, if the plan has one
+    pub fn block_limit(&self) -> Option<u64> {
+        self.block_tokens.or(self.plan.block_token_limit())
+    }
+
+    /// Token limit of a rolling week, if the plan has one
+    pub fn weekly_limit(&self) -> Option<u64> {
+        self.weekly_tokens.or(self.plan.weekly_token_limit())
+    }
+}
+
+/// Statistic of past blocks' token totals taken as the inferred block limit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InferenceMethod {
+    /// 90th percentile, which ignores a few unusually heavy blocks
+    #[default]
+    P90,
+    /// The heaviest block
+    Max,
+}
+
+impl fmt::Display for InferenceMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            InferenceMethod::P90 => "P90",
+            InferenceMethod::Max => "max",
+        })
+    }
+}
+
+/// How far an inferred limit can be trusted, from the number of blocks behind it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Confidence {
+    Low,
+    Medium,
+    High,
+}
+
+impl Confidence {
+    /// Confidence of a limit inferred from `samples` blocks
+    pub fn from_samples(samples: usize) -> Self {
+        match samples {
+            0..=9 => Confidence::Low,
+            10..=29 => Confidence::Medium,
+            _ => Confidence::High,
+        }
+    }
+}
+
+impl fmt::Display for Confidence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Confidence::Low => "low",
+            Confidence::Medium => "medium",
+            Confidence::High => "high",
+        })
+    }
+}
+
+/// A block limit inferred from completed blocks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InferredLimit {
+    pub tokens: u64,
+    pub method: InferenceMethod,
+    /// Completed blocks with usage the limit was taken from
+    pub samples: usize,
+    pub confidence: Confidence,
+}
+
+/// Infer the block limit from the blocks completed by `now` that used any tokens.
+/// None when there are no such blocks.
+pub fn infer_block_limit(
+    blocks: &[UsageBlock],
+    now: DateTime<Utc>,
+    method: InferenceMethod,
+) -> Option<InferredLimit> {
+    let mut totals: Vec<u64> = blocks
+        .iter()
+        .filter(|block| !block.is_active_at(now) && block.tokens > 0)
+        .map(|block| block.tokens)
+        .collect();
+    if totals.is_empty() {
+        return None;
+    }
+    totals.sort_unstable();
+
+    let tokens = match method {
+        // Nearest-rank percentile
+        InferenceMethod::P90 => totals[(totals.len() * 9).div_ceil(10) - 1],
+        InferenceMethod::Max => totals[totals.len() - 1],
+    };
+    Some(InferredLimit {
+        tokens,
+        method,
+        samples: totals.len(),
+        confidence: Confidence::from_samples(totals.len()),
+    })
+}
+
+/// Where the block limit being measured against came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitSource {
+    /// `block_tokens` in the config, set by hand or by pinning an inferred limit
+    Configured,
+    /// The plan's estimate
+    Plan,
+    /// Inferred from past blocks, since neither the config nor the plan has one
+    Inferred,
+    /// No limit is known
+    None,
+}
+
+/// Tokens used in a time window, against its limit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WindowUsage {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Input plus output tokens used in the window
+    pub tokens: u64,
+    pub limit: Option<u64>,
+}
+
+impl WindowUsage {
+    /// Share of the limit used (above 1.0 once it is exceeded)
+    pub fn ratio(&self) -> Option<f64> {
+        self.limit
+            .filter(|limit| *limit > 0)
+            .map(|limit| self.tokens as f64 / limit as f64)
+    }
+
+    /// Tokens left before the limit
+    pub fn remaining(&self) -> Option<u64> {
+        self.limit.map(|limit| limit.saturating_sub(self.tokens))
+    }
+}
+
+/// Usage of the active block and the past week against the plan's limits
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuotaStatus {
+    pub plan: Plan,
+    /// The active 5-hour block; None between blocks
+    pub block: Option<WindowUsage>,
+    /// The seven days up to now
+    pub week: WindowUsage,
+    /// Block limit inferred from the blocks of the last `INFERENCE_DAYS`
+    pub inferred: Option<InferredLimit>,
+    /// Where the active block's limit came from
+    pub block_limit_source: LimitSource,
+    /// Burn rate of the active block and today's spend, for forecasts
+    pub burn: BurnRate,
+    /// Cost spent in the current day, week and month, for budgets
+    pub spend: BudgetSpend,
+}
+
+impl QuotaStatus {
+    /// Measure `entries` (any order, covering at least the past `INFERENCE_DAYS` and
+    /// the current billing month) at `now`, with budget periods aligned by `periods`
+    pub fn compute(
+        entries: &[BlockEntry],
+        settings: &QuotaSettings,
+        periods: &PeriodSettings,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let week_start = now - Duration::days(7);
+        let week_tokens = entries
+            .iter()
+            .filter(|entry| entry.time > week_start && entry.time <= now)
+            .fold(0u64, |sum, entry| sum.saturating_add(entry.tokens));
+
+        let recent = entries
+            .iter()
+            .filter(|entry| entry.time <= now)
+            .copied()
+            .collect();
+        let blocks = blocks::segment_blocks(recent);
+        let block = blocks::active_block(&blocks, now).map(|block| WindowUsage {
+            start: block.start,
+            end: block.end,
+            tokens: block.tokens,
+            limit: None,
+        });
+
+        let (day_start, day_end) = local_day_bounds(now);
+        let burn = BurnRate::compute(
+            entries,
+            block.as_ref().map(|block| block.start),
+            day_start,
+            day_end,
+            now,
+        );
+
+        let history_start = now - Duration::days(INFERENCE_DAYS);
+        let history: Vec<UsageBlock> = blocks
+            .into_iter()
+            .filter(|block| block.start >= history_start)
+            .collect();
+
+        Self {
+            plan: settings.plan,
+            block,
+            week: WindowUsage {
+                start: week_start,
+                end: now,
+                tokens: week_tokens,
+                limit: None,
+            },
+            inferred: infer_block_limit(&history, now, settings.inference),
+            block_limit_source: LimitSource::None,
+            burn,
+            spend: BudgetSpend::compute(entries, periods, now),
+        }
+        .with_settings(settings)
+    }
+
+    /// The same usage measured against the limits of `settings`. The inferred limit
+    /// applies only when neither `block_tokens` nor the plan sets a block limit.
+    pub fn with_settings(mut self, settings: &QuotaSettings) -> Self {
+        let (limit, source) = match (settings.block_limit(), self.inferred) {
+            (Some(tokens), _) if settings.block_tokens.is_some() => {
+                (Some(tokens), LimitSource::Configured)
+            }
+            (Some(tokens), _) => (Some(tokens), LimitSource::Plan),
+            (None, Some(inferred)) => (Some(inferred.tokens), LimitSource::Inferred),
+            (None, None) => (None, LimitSource::None),
+        };
+
+        self.plan = settings.plan;
+        self.block_limit_source = source;
+        if let Some(block) = &mut self.block {
+            block.limit = limit;
+        }
+        self.week.limit = settings.weekly_limit();
+        self
+    }
+}
+
+/// Start and end of the local day containing `now`
+fn local_day_bounds(now: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+    let today = now.with_timezone(&Local).date_naive();
+    let local_midnight = |date: chrono::NaiveDate| {
+        date.and_hms_opt(0, 0, 0)
+            .and_then(|midnight| midnight.and_local_timezone(Local).earliest())
+            .map(|midnight| midnight.with_timezone(&Utc))
+    };
+    let start = local_midnight(today).unwrap_or(now);
+    let end = today
+        .succ_opt()
+        .and_then(local_midnight)
+        .unwrap_or(start + Duration::days(1));
+    (start, end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 6, day, hour, 30, 0).unwrap()
+    }
+
+    fn entry(day: u32, hour: u32, tokens: u64) -> BlockEntry {
+        BlockEntry {
+            time: at(day, hour),
+            tokens,
+            cost: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_plan_limits_and_overrides() {
+        let pro = QuotaSettings::default();
+        assert_eq!(pro.block_limit(), Some(19_000));
+        assert_eq!(pro.weekly_limit(), Some(152_000));
+
+        let api = QuotaSettings {
+            plan: Plan::Api,
+            ..Default::default()
+        };
+        assert_eq!((api.block_limit(), api.weekly_limit()), (None, None));
+
+        let custom: QuotaSettings =
+            serde_json::from_str(r#"{ "plan": "custom", "block_tokens": 50000 }"#).unwrap();
+        assert_eq!(custom.block_limit(), Some(50_000));
+        assert_eq!(custom.weekly_limit(), None);
+
+        let max: QuotaSettings =
+            serde_json::from_str(r#"{ "plan": "max20x", "weekly_tokens": 1 }"#).unwrap();
+        assert_eq!(max.block_limit(), Some(220_000));
+        assert_eq!(max.weekly_limit(), Some(1));
+    }
+
+    #[test]
+    fn test_active_block_and_rolling_week() {
+        let entries = [
+            entry(1, 12, 1_000_000), // more than a week before `now`
+            entry(3, 9, 4_000),
+            entry(8, 10, 2_000),
+            entry(8, 11, 3_000),
+        ];
+        let status = QuotaStatus::compute(
+            &entries,
+            &QuotaSettings::default(),
+            &PeriodSettings::default(),
+            at(8, 12),
+        );
+
+        let block = status.block.unwrap();
+        assert_eq!(block.tokens, 5_000);
+        assert_eq!(
+            block.end,
+            Utc.with_ymd_and_hms(2025, 6, 8, 15, 0, 0).unwrap()
+        );
+        assert!((block.ratio().unwrap() - 5_000.0 / 19_000.0).abs() < 1e-9);
+        assert_eq!(block.remaining(), Some(14_000));
+        assert_eq!(status.week.tokens, 9_000);
+
+        // Between blocks only the week is measured
+        let idle = QuotaStatus::compute(
+            &entries,
+            &QuotaSettings::default(),
+            &PeriodSettings::default(),
+            at(8, 20),
+        );
+        assert_eq!(idle.block, None);
+        assert_eq!(idle.week.tokens, 9_000);
+    }
+
+    #[test]
+    fn test_block_limit_is_inferred_from_completed_blocks() {
+        // Ten completed blocks of 1K..10K tokens, an idle one and the active one
+        let mut entries: Vec<BlockEntry> =
+            (1..=10).map(|i| entry(i, 6, i as u64 * 1_000)).collect();
+        entries.push(entry(11, 6, 0));
+        entries.push(entry(12, 10, 50_000));
+        let now = at(12, 11);
+
+        let blocks = blocks::segment_blocks(entries.clone());
+        let p90 = infer_block_limit(&blocks, now, InferenceMethod::P90).unwrap();
+        assert_eq!(p90.tokens, 9_000);
+        assert_eq!(p90.samples, 10);
+        assert_eq!(p90.confidence, Confidence::Medium);
+        let max = infer_block_limit(&blocks, now, InferenceMethod::Max).unwrap();
+        assert_eq!(max.tokens, 10_000);
+        assert_eq!(
+            infer_block_limit(&blocks[..0], now, InferenceMethod::Max),
+            None
+        );
+
+        // A custom plan without `block_tokens` measures against the inferred limit
+        let custom = QuotaSettings {
+            plan: Plan::Custom,
+            ..Default::default()
+        };
+        let status = QuotaStatus::compute(&entries, &custom, &PeriodSettings::default(), now);
+        assert_eq!(status.block_limit_source, LimitSource::Inferred);
+        assert_eq!(status.block.as_ref().unwrap().limit, Some(9_000));
+
+        // Pinning saves it as `block_tokens`
+        let pinned = QuotaSettings {
+            block_tokens: Some(p90.tokens),
+            ..custom
+        };
+        let status = status.with_settings(&pinned);
+        assert_eq!(status.block_limit_source, LimitSource::Configured);
+        assert_eq!(status.block.unwrap().limit, Some(9_000));
+    }
+}
@@ -0,0 +1,130 @@
+//! Parallel scanning of session files and project directories
+//!
+//! Parsing JSONL is CPU-bound and every file is independent, so scans hand
+//! files (or whole project directories) to a small pool of worker threads
+//! and report how far they got after each one.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// How far a scan has progressed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScanProgress {
+    /// Items finished so far
+    pub scanned: usize,
+    /// Items the scan started with
+    pub total: usize,
+}
+
+impl ScanProgress {
+    /// Whether every item has been scanned
+    pub fn is_done(&self) -> bool {
+        self.scanned >= self.total
+    }
+}
+
+/// Progress callback that ignores every update
+pub fn no_progress(_: ScanProgress) {}
+
+/// Run `f` over every item on a pool of worker threads, one per available core,
+/// and return the results in input order. `on_progress` is called from the
+/// worker that finished an item, so updates can arrive out of order.
+pub fn map_parallel<T, R, F>(
+    items: Vec<T>,
+    on_progress: &(dyn Fn(ScanProgress) + Sync),
+    f: F,
+) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+    F: Fn(T) -> R + Sync,
+{
+    let total = items.len();
+    let workers = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(total);
+
+    let queue = Mutex::new(items.into_iter().enumerate());
+    let results = Mutex::new(Vec::with_capacity(total));
+    let scanned = AtomicUsize::new(0);
+
+    let work = || loop {
+        // Take the next item without holding the queue while it is processed
+        let next = queue
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .next();
+        let Some((index, item)) = next else {
+            break;
+        };
+
+        let result = f(item);
+        results
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push((index, result));
+
+        on_progress(ScanProgress {
+            scanned: scanned.fetch_add(1, Ordering::Relaxed) + 1,
+            total,
+        });
+    };
+
+    if workers <= 1 {
+        work();
+    } else {
+        thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(work);
+            }
+        });
+    }
+
+    let mut results = results
+        .into_inner()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    results.sort_unstable_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_results_keep_input_order() {
+        let items: Vec<u64> = (0..200).collect();
+        let squares = map_parallel(items, &no_progress, |n| n * n);
+
+        assert_eq!(squares.len(), 200);
+        assert!(squares
+            .iter()
+            .enumerate()
+            .all(|(i, sq)| *sq == (i * i) as u64));
+    }
+
+    #[test]
+    fn test_progress_reaches_total() {
+        let updates = Mutex::new(Vec::new());
+        let on_progress = |progress: ScanProgress| updates.lock().unwrap().push(progress);
+
+        map_parallel(vec!["a", "b", "c"], &on_progress, |s| s.len());
+
+        let mut updates = updates.into_inner().unwrap();
+        updates.sort_by_key(|p| p.scanned);
+        assert_eq!(
+            updates.iter().map(|p| p.scanned).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        assert!(updates.iter().all(|p| p.total == 3));
+        assert!(updates.last().unwrap().is_done());
+    }
+
+    #[test]
+    fn test_empty_input() {
+        let results: Vec<usize> = map_parallel(Vec::<&str>::new(), &no_progress, |s| s.len());
+        assert!(results.is_empty());
+    }
+}
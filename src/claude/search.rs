@@ -0,0 +1,916 @@
+//! Full-text search over session transcripts
+//!
+//! Every prompt and reply is a document: the text blocks of one user or assistant
+//! message, without its thinking, tool calls or tool output. An inverted index maps
+//! each lower-cased word to the messages that contain it. Like the usage index,
+//! session files are tracked by (inode, size, mtime, offset) so later refreshes read
+//! only appended lines. A file that was rewritten or deleted has its postings
+//! retired, and retired postings are compacted away once they outnumber live ones.
+//!
+//! Word queries are answered from the index and ranked with BM25, newer messages
+//! first on ties. Regex queries cannot use the index, so they read every session
+//! of the matching projects.
+//!
+//! The index is persisted as `<cache dir>/cc-enhanced/search-index.bin`.
+
+use anyhow::Result;
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use super::index_file::{self, IndexFile};
+use super::ingest::{self, FileStamp};
+use super::project_scanner::Project;
+use super::scan::{self, ScanProgress};
+use super::transcript::{self, Role, TranscriptMessage};
+
+/// Bumped whenever the persisted index changes shape
+pub const SEARCH_INDEX_SCHEMA_VERSION: u32 = 1;
+
+/// Marks a file as a cc-enhanced search index
+const INDEX_MAGIC: [u8; 8] = *b"CCESERCH";
+
+/// Longest word that is indexed; longer runs are hashes and encoded data
+const MAX_WORD_CHARS: usize = 64;
+
+/// BM25 term-frequency saturation and length normalisation
+const BM25_K1: f64 = 1.2;
+const BM25_B: f64 = 0.75;
+
+/// Characters of context on each side of the match in a snippet
+const SNIPPET_CONTEXT: usize = 60;
+
+/// Hits returned when no limit is given
+pub const DEFAULT_LIMIT: usize = 20;
+
+/// Search index shared between the dashboard and its background search task
+pub type SharedSearchIndex = Arc<Mutex<SearchIndex>>;
+
+/// Lower-cased words of `text`: runs of letters, digits and underscores.
+/// Single ASCII characters and over-long runs are left out.
+pub fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|ch: char| !(ch.is_alphanumeric() || ch == '_'))
+        .filter(|word| {
+            let chars = word.chars().count();
+            chars <= MAX_WORD_CHARS && !(chars <= 1 && word.is_ascii())
+        })
+        .map(str::to_lowercase)
+}
+
+/// What to look for
+#[derive(Debug, Clone)]
+pub enum SearchPattern {
+    /// Messages containing every word
+    Words(Vec<String>),
+    /// Messages matching a case-insensitive regular expression
+    Regex(Regex),
+}
+
+/// A search and its filters
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+    pub pattern: SearchPattern,
+    /// Only search projects matching this name or path fragment
+    pub project: Option<String>,
+    /// First local date to include (inclusive)
+    pub since: Option<NaiveDate>,
+    /// Last local date to include (inclusive)
+    pub until: Option<NaiveDate>,
+    /// Most hits to return
+    pub limit: usize,
+}
+
+impl SearchQuery {
+    /// A word query, or a regex query when `regex` is set
+    pub fn new(text: &str, regex: bool) -> Result<Self> {
+        let pattern = if regex {
+            let regex = RegexBuilder::new(text)
+                .case_insensitive(true)
+                .build()
+                .map_err(|e| anyhow::anyhow!("Invalid regex: {e}"))?;
+            SearchPattern::Regex(regex)
+        } else {
+            let words: Vec<String> = words(text).collect();
+            if words.is_empty() {
+                return Err(anyhow::anyhow!("Nothing to search for"));
+            }
+            SearchPattern::Words(words)
+        };
+
+        Ok(Self {
+            pattern,
+            project: None,
+            since: None,
+            until: None,
+            limit: DEFAULT_LIMIT,
+        })
+    }
+
+    /// Parse the dashboard's search box: words, with `project:`, `since:` and `until:`
+    /// filters anywhere, or `re:` before a regular expression
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut text = Vec::new();
+        let (mut project, mut since, mut until) = (None, None, None);
+        for token in input.split_whitespace() {
+            if let Some(value) = token.strip_prefix("project:") {
+                project = Some(value.to_string());
+            } else if let Some(value) = token.strip_prefix("since:") {
+                since = Some(parse_date(value)?);
+            } else if let Some(value) = token.strip_prefix("until:") {
+                until = Some(parse_date(value)?);
+            } else {
+                text.push(token);
+            }
+        }
+
+        let text = text.join(" ");
+        let mut query = match text.strip_prefix("re:") {
+            Some(pattern) => Self::new(pattern, true)?,
+            None => Self::new(&text, false)?,
+        };
+        query.project = project;
+        query.since = since;
+        query.until = until;
+        Ok(query)
+    }
+
+    /// Check whether a message sent at `time` falls inside the date range.
+    /// Messages without a timestamp only pass when no range is set.
+    fn includes_time(&self, time: Option<DateTime<Utc>>) -> bool {
+        if self.since.is_none() && self.until.is_none() {
+            return true;
+        }
+        time.map(|time| time.with_timezone(&Local).date_naive())
+            .is_some_and(|date| {
+                self.since.map_or(true, |since| date >= since)
+                    && self.until.map_or(true, |until| date <= until)
+            })
+    }
+}
+
+/// Parse a `YYYY-MM-DD` date
+fn parse_date(value: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| anyhow::anyhow!("Invalid date '{value}', expected YYYY-MM-DD"))
+}
+
+/// A message that matched a search
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    pub project_name: String,
+    pub project_path: String,
+    pub session_id: String,
+    pub session_path: PathBuf,
+    /// Position of the message in the session transcript
+    pub message: usize,
+    pub role: Role,
+    pub time: Option<DateTime<Utc>>,
+    pub score: f64,
+    /// The message text around the first match, on one line
+    pub snippet: String,
+}
+
+/// One indexed message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Doc {
+    /// Position of the message in the session transcript
+    message: u32,
+    role: Role,
+    time: Option<DateTime<Utc>>,
+    /// Indexed words in the message, for length normalisation
+    words: u32,
+}
+
+/// Occurrences of a word in one message of one file version
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct Posting {
+    file: u32,
+    message: u32,
+    count: u32,
+}
+
+/// Read position and indexed messages of one session file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct IndexedFile {
+    /// Id of this version of the file in the postings
+    id: u32,
+    stamp: Option<FileStamp>,
+    /// Byte offset just past the last line that was indexed
+    offset: u64,
+    /// Transcript messages read so far
+    messages: u32,
+    /// Message id of the last message, to merge the next lines of a streamed response
+    last_message_id: Option<String>,
+    /// Messages with any text, in transcript order
+    docs: Vec<Doc>,
+    /// Words in `docs`
+    words: u64,
+    /// Postings added for this file
+    postings: u64,
+}
+
+/// Word counts of the messages a refresh read, by message position
+type AddedWords = Vec<(u32, HashMap<String, u32>)>;
+
+impl IndexedFile {
+    /// Index the lines appended since the last update
+    fn update(&mut self, path: &Path, stamp: FileStamp) -> io::Result<AddedWords> {
+        let mut added: AddedWords = Vec::new();
+        let mut offset = self.offset;
+
+        ingest::read_appended_lines(path, &mut offset, |_, value| {
+            let Some(message) = value.as_ref().and_then(TranscriptMessage::from_value) else {
+                return;
+            };
+            // Numbered exactly as `read_transcript` numbers the messages it merges
+            let continued = message.message_id().is_some()
+                && message.message_id() == self.last_message_id.as_deref();
+            if !continued {
+                self.messages += 1;
+                self.last_message_id = message.message_id().map(str::to_string);
+            }
+            let position = self.messages - 1;
+
+            let mut counts: HashMap<String, u32> = HashMap::new();
+            for word in words(&message.text()) {
+                *counts.entry(word).or_default() += 1;
+            }
+            let total: u32 = counts.values().sum();
+            if total == 0 {
+                return;
+            }
+
+            self.words += total as u64;
+            match self.docs.last_mut() {
+                Some(doc) if doc.message == position => doc.words += total,
+                _ => self.docs.push(Doc {
+                    message: position,
+                    role: message.role,
+                    time: message.time,
+                    words: total,
+                }),
+            }
+            match added.last_mut() {
+                Some((last, last_counts)) if *last == position => {
+                    for (word, count) in counts {
+                        *last_counts.entry(word).or_default() += count;
+                    }
+                }
+                _ => added.push((position, counts)),
+            }
+        })?;
+
+        self.offset = offset;
+        self.stamp = Some(stamp);
+        Ok(added)
+    }
+
+    fn doc(&self, message: u32) -> Option<&Doc> {
+        self.docs
+            .binary_search_by_key(&message, |doc| doc.message)
+            .ok()
+            .map(|i| &self.docs[i])
+    }
+}
+
+/// The persisted part of the index
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct IndexData {
+    files: HashMap<PathBuf, IndexedFile>,
+    /// Word to the messages containing it
+    postings: HashMap<String, Vec<Posting>>,
+    next_id: u32,
+    /// Postings of file versions that were rewritten or deleted
+    retired: u64,
+}
+
+/// A message matched before snippets are taken
+#[derive(Debug, Clone)]
+struct Match {
+    path: PathBuf,
+    message: usize,
+    role: Role,
+    time: Option<DateTime<Utc>>,
+    score: f64,
+}
+
+/// Inverted index over every session file it has seen, updated incrementally
+#[derive(Debug, Default)]
+pub struct SearchIndex {
+    data: IndexData,
+    /// Path of each live file id
+    paths: HashMap<u32, PathBuf>,
+    /// Where the index is persisted (None keeps everything in memory)
+    index_file: Option<IndexFile>,
+    /// Whether the persisted index has been read; it is read on first use
+    loaded: bool,
+    /// Whether `data` changed since the index was last written
+    dirty: bool,
+}
+
+impl SearchIndex {
+    /// Create an empty in-memory index that can be shared across threads
+    pub fn shared() -> SharedSearchIndex {
+        Arc::new(Mutex::new(Self::open_default()))
+    }
+
+    /// Open the search index at its default location, falling back to memory only
+    pub fn open_default() -> Self {
+        match default_index_path() {
+            Some(path) => Self::open(path),
+            None => Self::default(),
+        }
+    }
+
+    /// Open a search index. Nothing is read until the first refresh; a missing,
+    /// unreadable or outdated index then starts empty and is rewritten on the next save.
+    pub fn open(index_path: PathBuf) -> Self {
+        Self {
+            index_file: Some(IndexFile::new(
+                index_path,
+                INDEX_MAGIC,
+                SEARCH_INDEX_SCHEMA_VERSION,
+            )),
+            ..Default::default()
+        }
+    }
+
+    fn load(&mut self) {
+        if self.loaded {
+            return;
+        }
+        self.loaded = true;
+        if let Some(data) = self
+            .index_file
+            .as_ref()
+            .and_then(IndexFile::read::<IndexData>)
+        {
+            self.paths = data
+                .files
+                .iter()
+                .map(|(path, file)| (file.id, path.clone()))
+                .collect();
+            self.data = data;
+        }
+    }
+
+    /// Bring the index up to date with `paths`, the complete list of session files.
+    /// Files missing from the list are dropped. `on_progress` counts only the files
+    /// that had to be read.
+    pub fn refresh_files(
+        &mut self,
+        paths: &[PathBuf],
+        on_progress: &(dyn Fn(ScanProgress) + Sync),
+    ) {
+        self.load();
+
+        let listed: HashSet<&PathBuf> = paths.iter().collect();
+        let gone: Vec<PathBuf> = self
+            .data
+            .files
+            .keys()
+            .filter(|path| !listed.contains(path))
+            .cloned()
+            .collect();
+        for path in &gone {
+            self.retire(path);
+        }
+
+        let mut stale = Vec::new();
+        for path in paths {
+            let Ok(metadata) = fs::metadata(path) else {
+                self.retire(path);
+                continue;
+            };
+            let stamp = FileStamp::of(&metadata);
+            let previous = self
+                .data
+                .files
+                .get(path)
+                .and_then(|file| file.stamp.as_ref());
+            if previous == Some(&stamp) {
+                continue;
+            }
+
+            // Anything other than growth means the file was rewritten from the start
+            let file = match previous {
+                Some(previous) if stamp.grew_from(previous) => self.data.files.remove(path),
+                _ => None,
+            };
+            let file = file.unwrap_or_else(|| {
+                self.retire(path);
+                let id = self.data.next_id;
+                self.data.next_id = self.data.next_id.wrapping_add(1);
+                IndexedFile {
+                    id,
+                    ..Default::default()
+                }
+            });
+            stale.push((path.clone(), stamp, file));
+        }
+
+        if stale.is_empty() {
+            return;
+        }
+        self.dirty = true;
+
+        let updated = scan::map_parallel(stale, on_progress, |(path, stamp, mut file)| {
+            let result = file.update(&path, stamp);
+            (path, file, result)
+        });
+        for (path, mut file, result) in updated {
+            let Ok(added) = result else {
+                // Unreadable files are dropped and read from the start next time
+                self.paths.remove(&file.id);
+                self.data.retired += file.postings;
+                continue;
+            };
+            for (message, counts) in added {
+                for (word, count) in counts {
+                    self.data.postings.entry(word).or_default().push(Posting {
+                        file: file.id,
+                        message,
+                        count,
+                    });
+                    file.postings += 1;
+                }
+            }
+            self.paths.insert(file.id, path.clone());
+            self.data.files.insert(path, file);
+        }
+
+        self.compact_if_needed();
+    }
+
+    /// Drop a file; its postings stay until the next compaction
+    fn retire(&mut self, path: &Path) {
+        if let Some(file) = self.data.files.remove(path) {
+            self.paths.remove(&file.id);
+            self.data.retired += file.postings;
+            self.dirty = true;
+        }
+    }
+
+    /// Remove retired postings once they outnumber live ones
+    fn compact_if_needed(&mut self) {
+        let live: u64 = self.data.files.values().map(|file| file.postings).sum();
+        if self.data.retired <= live {
+            return;
+        }
+
+        let paths = &self.paths;
+        self.data.postings.retain(|_, postings| {
+            postings.retain(|posting| paths.contains_key(&posting.file));
+            !postings.is_empty()
+        });
+        self.data.retired = 0;
+    }
+
+    /// Forget everything indexed so far; the next refresh re-reads every file
+    pub fn clear(&mut self) {
+        self.data = IndexData::default();
+        self.paths.clear();
+        self.loaded = true;
+        self.dirty = true;
+    }
+
+    /// Number of indexed files and messages
+    pub fn totals(&self) -> (usize, usize) {
+        let docs = self.data.files.values().map(|file| file.docs.len()).sum();
+        (self.data.files.len(), docs)
+    }
+
+    /// Messages containing every word, in files `include` accepts, best match first
+    fn search_words(
+        &self,
+        words: &[String],
+        include: &dyn Fn(&Path) -> bool,
+        query: &SearchQuery,
+    ) -> Vec<Match> {
+        let doc_count: usize = self.data.files.values().map(|file| file.docs.len()).sum();
+        let total_words: u64 = self.data.files.values().map(|file| file.words).sum();
+        let average_words = total_words as f64 / doc_count.max(1) as f64;
+
+        // Occurrences of each word per live message, rarest word first
+        let mut per_word: Vec<HashMap<(u32, u32), u32>> = Vec::new();
+        for word in words.iter().collect::<HashSet<_>>() {
+            let mut counts: HashMap<(u32, u32), u32> = HashMap::new();
+            for posting in self.data.postings.get(word).into_iter().flatten() {
+                if self.paths.contains_key(&posting.file) {
+                    *counts.entry((posting.file, posting.message)).or_default() += posting.count;
+                }
+            }
+            if counts.is_empty() {
+                return Vec::new();
+            }
+            per_word.push(counts);
+        }
+        per_word.sort_by_key(HashMap::len);
+        let Some((rarest, others)) = per_word.split_first() else {
+            return Vec::new();
+        };
+
+        let idf = |counts: &HashMap<(u32, u32), u32>| {
+            let df = counts.len() as f64;
+            ((doc_count as f64 - df + 0.5) / (df + 0.5) + 1.0).ln()
+        };
+        let idfs: Vec<f64> = per_word.iter().map(idf).collect();
+
+        let mut matches = Vec::new();
+        for &(file_id, message) in rarest.keys() {
+            if !others
+                .iter()
+                .all(|counts| counts.contains_key(&(file_id, message)))
+            {
+                continue;
+            }
+            let Some(path) = self.paths.get(&file_id) else {
+                continue;
+            };
+            let Some(doc) = self.data.files.get(path).and_then(|file| file.doc(message)) else {
+                continue;
+            };
+            if !include(path) || !query.includes_time(doc.time) {
+                continue;
+            }
+
+            let length = 1.0 - BM25_B + BM25_B * doc.words as f64 / average_words.max(1.0);
+            let score = per_word
+                .iter()
+                .zip(&idfs)
+                .map(|(counts, idf)| {
+                    let tf = counts[&(file_id, message)] as f64;
+                    idf * tf * (BM25_K1 + 1.0) / (tf + BM25_K1 * length)
+                })
+                .sum();
+            matches.push(Match {
+                path: path.clone(),
+                message: message as usize,
+                role: doc.role,
+                time: doc.time,
+                score,
+            });
+        }
+        matches
+    }
+
+    /// Write the index if anything changed since it was last written
+    pub fn save(&mut self) -> Result<()> {
+        let Some(index_file) = &mut self.index_file else {
+            return Ok(());
+        };
+        if !self.dirty {
+            return Ok(());
+        }
+
+        index_file.write(&self.data)?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Like `save`, but skipped if the index was written recently, so searching
+    /// while a session is active does not rewrite it every time
+    pub fn save_if_due(&mut self) -> Result<()> {
+        if self.index_file.as_ref().is_some_and(IndexFile::save_due) {
+            self.save()?;
+        }
+        Ok(())
+    }
+}
+
+/// Default index location: `<cache dir>/cc-enhanced/search-index.bin`
+pub fn default_index_path() -> Option<PathBuf> {
+    index_file::default_path("search-index.bin")
+}
+
+/// Messages of `paths` matching `regex`, scored by their number of matches
+fn search_regex(paths: Vec<PathBuf>, regex: &Regex, query: &SearchQuery) -> Vec<Match> {
+    scan::map_parallel(paths, &scan::no_progress, |path| {
+        // Unreadable sessions are skipped, as in the index
+        let messages = transcript::read_transcript(&path).unwrap_or_default();
+        messages
+            .iter()
+            .enumerate()
+            .filter(|(_, message)| query.includes_time(message.time))
+            .filter_map(|(position, message)| {
+                let found = regex.find_iter(&message.text()).count();
+                (found > 0).then(|| Match {
+                    path: path.clone(),
+                    message: position,
+                    role: message.role,
+                    time: message.time,
+                    score: found as f64,
+                })
+            })
+            .collect::<Vec<_>>()
+    })
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Search `session_files` (every session file of `projects`), best match first.
+/// Word queries refresh the index first, reporting the files read to `on_progress`.
+pub fn search(
+    index: &mut SearchIndex,
+    session_files: &[PathBuf],
+    projects: &[Project],
+    query: &SearchQuery,
+    on_progress: &(dyn Fn(ScanProgress) + Sync),
+) -> Result<Vec<SearchHit>> {
+    let project_dirs: HashMap<&Path, &Project> = projects
+        .iter()
+        .map(|project| (project.data_dir.as_path(), project))
+        .collect();
+    let project_of = |path: &Path| path.parent().and_then(|dir| project_dirs.get(dir).copied());
+
+    if let Some(name) = &query.project {
+        if !projects.iter().any(|project| project.matches(name)) {
+            return Err(anyhow::anyhow!("No project matches '{name}'"));
+        }
+    }
+    let include = |path: &Path| match &query.project {
+        Some(name) => project_of(path).is_some_and(|project| project.matches(name)),
+        None => true,
+    };
+
+    let mut matches = match &query.pattern {
+        SearchPattern::Words(words) => {
+            index.refresh_files(session_files, on_progress);
+            index.search_words(words, &include, query)
+        }
+        SearchPattern::Regex(regex) => {
+            let paths = session_files
+                .iter()
+                .filter(|path| include(path))
+                .cloned()
+                .collect();
+            search_regex(paths, regex, query)
+        }
+    };
+    matches.sort_by(|a, b| b.score.total_cmp(&a.score).then(b.time.cmp(&a.time)));
+    matches.truncate(query.limit);
+
+    // Snippets come from the transcripts themselves, read once per session
+    let highlight = match &query.pattern {
+        SearchPattern::Regex(regex) => Some(regex.clone()),
+        SearchPattern::Words(words) => {
+            let alternatives: Vec<String> = words.iter().map(|word| regex::escape(word)).collect();
+            RegexBuilder::new(&alternatives.join("|"))
+                .case_insensitive(true)
+                .build()
+                .ok()
+        }
+    };
+    let mut transcripts: BTreeMap<&Path, Vec<TranscriptMessage>> = BTreeMap::new();
+    for found in &matches {
+        if !transcripts.contains_key(found.path.as_path()) {
+            let messages = transcript::read_transcript(&found.path).unwrap_or_default();
+            transcripts.insert(&found.path, messages);
+        }
+    }
+
+    Ok(matches
+        .iter()
+        .map(|found| {
+            let project = project_of(&found.path);
+            let text = transcripts
+                .get(found.path.as_path())
+                .and_then(|messages| messages.get(found.message))
+                .map(TranscriptMessage::text)
+                .unwrap_or_default();
+            SearchHit {
+                project_name: project.map_or_else(
+                    || {
+                        found
+                            .path
+                            .parent()
+                            .and_then(Path::file_name)
+                            .map(|name| name.to_string_lossy().to_string())
+                            .unwrap_or_default()
+                    },
+                    |project| project.name.clone(),
+                ),
+                project_path: project
+                    .map(|project| project.path.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                session_id: found
+                    .path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                session_path: found.path.clone(),
+                message: found.message,
+                role: found.role,
+                time: found.time,
+                score: found.score,
+                snippet: snippet(&text, highlight.as_ref()),
+            }
+        })
+        .collect())
+}
+
+/// `text` on one line, cut to the context around the first match of `highlight`
+fn snippet(text: &str, highlight: Option<&Regex>) -> String {
+    let flat = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let (start, end) = highlight
+        .and_then(|regex| regex.find(&flat))
+        .map_or((0, 0), |found| (found.start(), found.end()));
+
+    let from = flat[..start]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_CONTEXT - 1)
+        .map_or(0, |(i, _)| i);
+    let to = flat[end..]
+        .char_indices()
+        .nth(SNIPPET_CONTEXT)
+        .map_or(flat.len(), |(i, _)| end + i);
+
+    format!(
+        "{}{}{}",
+        if from > 0 { "…" } else { "" },
+        &flat[from..to],
+        if to < flat.len() { "…" } else { "" }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn user_line(text: &str) -> String {
+        format!(
+            r#"{{"type":"user","timestamp":"2025-06-01T10:00:00Z","message":{{"role":"user","content":"{text}"}}}}"#
+        )
+    }
+
+    fn assistant_line(id: &str, text: &str) -> String {
+        format!(
+            r#"{{"type":"assistant","timestamp":"2025-06-02T10:00:00Z","message":{{"id":"{id}","content":[{{"type":"text","text":"{text}"}}]}}}}"#
+        )
+    }
+
+    fn append(path: &Path, lines: &[String]) {
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        for line in lines {
+            writeln!(file, "{line}").unwrap();
+        }
+    }
+
+    fn found(index: &mut SearchIndex, paths: &[PathBuf], text: &str) -> Vec<(String, usize)> {
+        search(
+            index,
+            paths,
+            &[],
+            &SearchQuery::parse(text).unwrap(),
+            &scan::no_progress,
+        )
+        .unwrap()
+        .into_iter()
+        .map(|hit| (hit.session_id, hit.message))
+        .collect()
+    }
+
+    #[test]
+    fn test_words() {
+        let words: Vec<String> =
+            words("Fix the DB_migration bug, a 2nd time: 마이그레이션").collect();
+        assert_eq!(
+            words,
+            vec![
+                "fix",
+                "the",
+                "db_migration",
+                "bug",
+                "2nd",
+                "time",
+                "마이그레이션"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_index_follows_appended_and_rewritten_files() {
+        let dir = std::env::temp_dir().join(format!("cc-enhanced-search-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let first = dir.join("first.jsonl");
+        let second = dir.join("second.jsonl");
+        append(
+            &first,
+            &[
+                user_line("Why does the migration fail?"),
+                assistant_line("m1", "The migration"),
+            ],
+        );
+        append(&second, &[user_line("Add a migration for users")]);
+        let paths = vec![first.clone(), second.clone()];
+
+        let mut index = SearchIndex::default();
+        assert_eq!(
+            found(&mut index, &paths, "migration fail"),
+            vec![("first".to_string(), 0)]
+        );
+
+        // The rest of a streamed reply extends the same message
+        append(
+            &first,
+            &[
+                assistant_line("m1", "bug is a missing index on users"),
+                user_line("thanks"),
+            ],
+        );
+        let mut users = found(&mut index, &paths, "users");
+        users.sort();
+        assert_eq!(
+            users,
+            vec![("first".to_string(), 1), ("second".to_string(), 0)]
+        );
+        assert_eq!(
+            transcript::read_transcript(&first).unwrap()[1].text(),
+            "The migration\nbug is a missing index on users"
+        );
+        assert_eq!(
+            found(&mut index, &paths, "thanks"),
+            vec![("first".to_string(), 2)]
+        );
+
+        // A rewritten file is indexed again from the start, and a deleted one is dropped
+        fs::write(&first, format!("{}\n", user_line("something else"))).unwrap();
+        assert!(found(&mut index, &paths, "migration fail").is_empty());
+        assert_eq!(
+            found(&mut index, &paths, "something"),
+            vec![("first".to_string(), 0)]
+        );
+        assert!(found(&mut index, &paths[..1], "users").is_empty());
+
+        // Regex queries read the sessions and take the same filters
+        let hits = search(
+            &mut index,
+            &paths,
+            &[],
+            &SearchQuery::parse("re:some.*else since:2025-06-01").unwrap(),
+            &scan::no_progress,
+        )
+        .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].snippet, "something else");
+        assert!(search(
+            &mut index,
+            &paths,
+            &[],
+            &SearchQuery::parse("re:some.*else until:2025-05-31").unwrap(),
+            &scan::no_progress,
+        )
+        .unwrap()
+        .is_empty());
+
+        // The index survives a save and load
+        let index_path = dir.join("search-index.bin");
+        index.index_file = Some(IndexFile::new(
+            index_path.clone(),
+            INDEX_MAGIC,
+            SEARCH_INDEX_SCHEMA_VERSION,
+        ));
+        index.save().unwrap();
+        let mut reopened = SearchIndex::open(index_path);
+        reopened.load();
+        assert_eq!(reopened.totals(), index.totals());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_query_syntax_and_snippets() {
+        let query = SearchQuery::parse("project:api re:fix(ed)? bug since:2025-06-01").unwrap();
+        assert_eq!(query.project.as_deref(), Some("api"));
+        assert_eq!(query.since, NaiveDate::from_ymd_opt(2025, 6, 1));
+        match &query.pattern {
+            SearchPattern::Regex(regex) => assert!(regex.is_match("Fixed bug")),
+            SearchPattern::Words(_) => panic!("expected a regex query"),
+        }
+        assert!(SearchQuery::parse("since:yesterday bug").is_err());
+        assert!(SearchQuery::parse("project:api").is_err());
+
+        let long = format!("{} needle {}", "a ".repeat(100), "b ".repeat(100));
+        let regex = Regex::new("needle").unwrap();
+        let text = snippet(&long, Some(&regex));
+        assert!(text.starts_with('…') && text.ends_with('…'));
+        assert!(text.contains("needle"));
+        assert_eq!(
+            text.chars().count(),
+            2 * SNIPPET_CONTEXT + "needle".len() + 2
+        );
+    }
+}
@@ -1,11 +1,18 @@
 #![allow(dead_code)] // Allow unused code during migration
 
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::fs;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
+use super::ingest;
+use super::path_resolver;
+use super::pricing_table::RequestTokens;
+use super::scan::{self, ScanProgress};
+
 /// Advanced session message data from .jsonl files
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionMessage {
@@ -15,6 +22,32 @@ pub struct SessionMessage {
     pub message_type: String,
     pub message: MessageContent,
     pub cwd: Option<String>,
+    /// Written by a Task-tool subagent rather than the main thread
+    #[serde(rename = "isSidechain", default)]
+    pub is_sidechain: bool,
+    /// Subagent that wrote the message, when Claude Code records one
+    #[serde(rename = "agentId", default)]
+    pub agent_id: Option<String>,
+}
+
+impl SessionMessage {
+    /// Agent that produced the message: the session id for the main thread,
+    /// otherwise the subagent's id (as used in todo file names)
+    pub fn agent(&self) -> &str {
+        ingest::agent_name(
+            &self.session_id,
+            self.is_sidechain,
+            self.agent_id.as_deref(),
+        )
+    }
+
+    /// When the message was sent, if its timestamp parses
+    pub fn time(&self) -> Option<DateTime<Utc>> {
+        let timestamp = self.message.timestamp.as_deref()?;
+        DateTime::parse_from_rfc3339(timestamp)
+            .ok()
+            .map(|dt| dt.with_timezone(&Utc))
+    }
 }
 
 /// Message content with usage and model information
@@ -29,13 +62,25 @@ pub struct MessageContent {
 /// Token usage information from Claude sessions
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenUsage {
-    pub input_tokens: Option<u32>,
-    pub output_tokens: Option<u32>,
-    pub cache_creation_input_tokens: Option<u32>,
-    pub cache_read_input_tokens: Option<u32>,
+    pub input_tokens: Option<u64>,
+    pub output_tokens: Option<u64>,
+    pub cache_creation_input_tokens: Option<u64>,
+    pub cache_read_input_tokens: Option<u64>,
+    /// Cache writes broken down by TTL, when the transcript records it
+    #[serde(default)]
+    pub cache_creation: Option<CacheCreation>,
     pub service_tier: Option<String>,
 }
 
+/// `usage.cache_creation`: cache write tokens by TTL
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CacheCreation {
+    #[serde(default)]
+    pub ephemeral_5m_input_tokens: u64,
+    #[serde(default)]
+    pub ephemeral_1h_input_tokens: u64,
+}
+
 /// Represents a Claude session
 #[derive(Debug, Clone)]
 pub struct Session {
@@ -61,11 +106,12 @@ impl SessionParser {
         let metadata = fs::metadata(session_path)?;
         let last_modified = metadata.modified()?;
 
-        let content = fs::read_to_string(session_path)?;
-        let message_count = content
-            .lines()
-            .filter(|line| !line.trim().is_empty())
-            .count();
+        let mut message_count = 0;
+        for line in BufReader::new(File::open(session_path)?).lines() {
+            if !line?.trim().is_empty() {
+                message_count += 1;
+            }
+        }
 
         if message_count == 0 {
             return Ok(None);
@@ -85,182 +131,165 @@ impl SessionParser {
         }))
     }
 
-    /// Parse all session messages from .jsonl files - Real token usage analytics
+    /// Parse all session messages from .jsonl files - Real token usage analytics.
+    /// Message `content` is dropped; use `scan_session_messages` to keep it.
     pub fn parse_all_session_messages(&self) -> Result<Vec<SessionMessage>> {
+        self.scan_session_messages(false, &scan::no_progress)
+    }
+
+    /// Parse the messages with token usage from every project directory, scanning
+    /// directories in parallel. Each file is streamed line by line, and message
+    /// `content` is only kept when `include_content` is set.
+    /// `on_progress` is called as each project directory finishes.
+    pub fn scan_session_messages(
+        &self,
+        include_content: bool,
+        on_progress: &(dyn Fn(ScanProgress) + Sync),
+    ) -> Result<Vec<SessionMessage>> {
         let projects_dir = self.claude_dir.join("projects");
         if !projects_dir.exists() {
             return Ok(Vec::new());
         }
 
-        let mut all_messages = Vec::new();
-
+        let mut project_dirs = Vec::new();
         for entry in fs::read_dir(&projects_dir)? {
-            let entry = entry?;
-            let project_path = entry.path();
-
+            let project_path = entry?.path();
             if project_path.is_dir() {
-                for file_entry in fs::read_dir(&project_path)? {
-                    let file_entry = file_entry?;
-                    let file_path = file_entry.path();
-
-                    if file_path.extension().and_then(|s| s.to_str()) == Some("jsonl") {
-                        if let Ok(content) = fs::read_to_string(&file_path) {
-                            let session_id = file_path
-                                .file_stem()
-                                .and_then(|s| s.to_str())
-                                .unwrap_or("")
-                                .to_string();
-
-                            for line in content.lines() {
-                                if line.trim().is_empty() {
-                                    continue;
-                                }
-
-                                // Parse as generic JSON first to check structure
-                                if let Ok(json_value) =
-                                    serde_json::from_str::<serde_json::Value>(line)
-                                {
-                                    // Get timestamp from root level
-                                    if let Some(_timestamp) = json_value.get("timestamp") {
-                                        // Check if this entry has usage data in message.usage
-                                        if let Some(message) = json_value.get("message") {
-                                            if let Some(usage) = message.get("usage") {
-                                                // Only include entries with actual token usage (non-zero)
-                                                let input_tokens = usage
-                                                    .get("input_tokens")
-                                                    .and_then(|v| v.as_u64())
-                                                    .unwrap_or(0);
-                                                let output_tokens = usage
-                                                    .get("output_tokens")
-                                                    .and_then(|v| v.as_u64())
-                                                    .unwrap_or(0);
-                                                let cache_creation = usage
-                                                    .get("cache_creation_input_tokens")
-                                                    .and_then(|v| v.as_u64())
-                                                    .unwrap_or(0);
-                                                let cache_read = usage
-                                                    .get("cache_read_input_tokens")
-                                                    .and_then(|v| v.as_u64())
-                                                    .unwrap_or(0);
-
-                                                if input_tokens > 0
-                                                    || output_tokens > 0
-                                                    || cache_creation > 0
-                                                    || cache_read > 0
-                                                {
-                                                    // Create SessionMessage from the parsed data
-                                                    let session_message = SessionMessage {
-                                                        session_id: session_id.clone(),
-                                                        message_type: json_value
-                                                            .get("type")
-                                                            .and_then(|v| v.as_str())
-                                                            .unwrap_or("unknown")
-                                                            .to_string(),
-                                                        message: MessageContent {
-                                                            usage: Some(TokenUsage {
-                                                                input_tokens: Some(
-                                                                    input_tokens as u32,
-                                                                ),
-                                                                output_tokens: Some(
-                                                                    output_tokens as u32,
-                                                                ),
-                                                                cache_creation_input_tokens: Some(
-                                                                    cache_creation as u32,
-                                                                ),
-                                                                cache_read_input_tokens: Some(
-                                                                    cache_read as u32,
-                                                                ),
-                                                                service_tier: usage
-                                                                    .get("service_tier")
-                                                                    .and_then(|v| v.as_str())
-                                                                    .map(|s| s.to_string()),
-                                                            }),
-                                                            model: message
-                                                                .get("model")
-                                                                .and_then(|v| v.as_str())
-                                                                .map(|s| s.to_string()),
-                                                            timestamp: json_value
-                                                                .get("timestamp")
-                                                                .and_then(|v| v.as_str())
-                                                                .map(|s| s.to_string()),
-                                                            content: message
-                                                                .get("content")
-                                                                .cloned(),
-                                                        },
-                                                        cwd: json_value
-                                                            .get("cwd")
-                                                            .and_then(|v| v.as_str())
-                                                            .map(|s| s.to_string()),
-                                                    };
-
-                                                    all_messages.push(session_message);
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
+                project_dirs.push(project_path);
             }
         }
 
+        let per_project = scan::map_parallel(project_dirs, on_progress, |project_path| {
+            Self::read_project_messages(&project_path, include_content)
+        });
+
+        let mut all_messages = Vec::new();
+        for messages in per_project {
+            all_messages.extend(messages?);
+        }
         Ok(all_messages)
     }
 
-    /// Get the real project path from the most recent JSONL session file's "cwd" field
-    pub fn get_project_path_from_recent_session(&self, project_dir_path: &Path) -> Result<String> {
-        // Find the most recent JSONL file in the project directory
-        let entries = std::fs::read_dir(project_dir_path)?;
-        let mut jsonl_files: Vec<(PathBuf, SystemTime)> = Vec::new();
-
-        for entry in entries {
-            let entry = entry?;
-            let path = entry.path();
-
-            if path.extension().and_then(|s| s.to_str()) == Some("jsonl") {
-                if let Ok(metadata) = std::fs::metadata(&path) {
-                    if let Ok(modified) = metadata.modified() {
-                        jsonl_files.push((path, modified));
-                    }
+    /// Parse the messages with token usage from every session file in one project directory
+    fn read_project_messages(
+        project_path: &Path,
+        include_content: bool,
+    ) -> Result<Vec<SessionMessage>> {
+        let mut messages = Vec::new();
+
+        for file_entry in fs::read_dir(project_path)? {
+            let file_path = file_entry?.path();
+            if file_path.extension().and_then(|s| s.to_str()) == Some("jsonl") {
+                // Unreadable files are skipped rather than failing the whole scan
+                if let Ok(file_messages) = Self::read_session_messages(&file_path, include_content)
+                {
+                    messages.extend(file_messages);
                 }
             }
         }
 
-        // Sort by modification time (most recent first)
-        jsonl_files.sort_by(|a, b| b.1.cmp(&a.1));
+        Ok(messages)
+    }
 
-        // Try to find the most recent JSONL file with a valid cwd field
-        for (jsonl_path, _) in jsonl_files {
-            if let Ok(content) = std::fs::read_to_string(&jsonl_path) {
-                // Parse the first few lines to find a cwd field
-                for line in content.lines().take(10) {
-                    if line.trim().is_empty() {
-                        continue;
-                    }
+    /// Stream one session file and keep the messages that carry token usage
+    pub fn read_session_messages(
+        session_path: &Path,
+        include_content: bool,
+    ) -> Result<Vec<SessionMessage>> {
+        let session_id = session_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("")
+            .to_string();
+        let reader = BufReader::new(File::open(session_path)?);
 
-                    if let Ok(json_value) = serde_json::from_str::<serde_json::Value>(line) {
-                        if let Some(cwd) = json_value.get("cwd").and_then(|v| v.as_str()) {
-                            if !cwd.is_empty() && cwd != "/" {
-                                return Ok(cwd.to_string());
-                            }
-                        }
-                    }
+        let mut messages = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            // Parse as generic JSON first to check structure
+            if let Ok(json_value) = serde_json::from_str::<serde_json::Value>(&line) {
+                if let Some(message) =
+                    Self::message_from_value(&json_value, &session_id, include_content)
+                {
+                    messages.push(message);
                 }
             }
         }
 
-        // Fallback: reconstruct from directory name
-        let dir_name = project_dir_path
-            .file_name()
-            .and_then(|s| s.to_str())
-            .unwrap_or("unknown");
+        Ok(messages)
+    }
 
-        // Basic path reconstruction from sanitized directory names
-        let reconstructed = dir_name.replace("__", "/").replace("_", " ");
+    /// Build a `SessionMessage` from a transcript line with a timestamp and non-zero usage
+    fn message_from_value(
+        json_value: &serde_json::Value,
+        session_id: &str,
+        include_content: bool,
+    ) -> Option<SessionMessage> {
+        // Get timestamp from root level
+        json_value.get("timestamp")?;
+        // Check if this entry has usage data in message.usage
+        let message = json_value.get("message")?;
+        let usage = message.get("usage")?;
+
+        let token_count = |key: &str| usage.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+        let input_tokens = token_count("input_tokens");
+        let output_tokens = token_count("output_tokens");
+        let cache_creation = token_count("cache_creation_input_tokens");
+        let cache_read = token_count("cache_read_input_tokens");
+
+        // Only include entries with actual token usage (non-zero)
+        if input_tokens == 0 && output_tokens == 0 && cache_creation == 0 && cache_read == 0 {
+            return None;
+        }
+
+        let as_string = |value: Option<&serde_json::Value>| {
+            value.and_then(|v| v.as_str()).map(|s| s.to_string())
+        };
 
-        Ok(reconstructed)
+        Some(SessionMessage {
+            session_id: session_id.to_string(),
+            message_type: as_string(json_value.get("type"))
+                .unwrap_or_else(|| "unknown".to_string()),
+            message: MessageContent {
+                usage: Some(TokenUsage {
+                    input_tokens: Some(input_tokens),
+                    output_tokens: Some(output_tokens),
+                    cache_creation_input_tokens: Some(cache_creation),
+                    cache_read_input_tokens: Some(cache_read),
+                    cache_creation: usage
+                        .get("cache_creation")
+                        .and_then(|c| serde_json::from_value(c.clone()).ok()),
+                    service_tier: as_string(usage.get("service_tier")),
+                }),
+                model: as_string(message.get("model")),
+                timestamp: as_string(json_value.get("timestamp")),
+                content: if include_content {
+                    message.get("content").cloned()
+                } else {
+                    None
+                },
+            },
+            cwd: as_string(json_value.get("cwd")),
+            is_sidechain: json_value
+                .get("isSidechain")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+            agent_id: as_string(json_value.get("agentId")),
+        })
+    }
+
+    /// Get the real project path from the most recent JSONL session file's "cwd" field,
+    /// falling back to a filesystem search for the directory name
+    pub fn get_project_path_from_recent_session(&self, project_dir_path: &Path) -> Result<String> {
+        path_resolver::resolve_project_path(project_dir_path, || {
+            path_resolver::recent_session_cwd(project_dir_path)
+        })
+        .map(|path| path.to_string_lossy().to_string())
+        .ok_or_else(|| anyhow::anyhow!("Cannot resolve project path for {:?}", project_dir_path))
     }
 
     /// Extract session ID from JSONL file path
@@ -315,13 +344,44 @@ impl SessionParser {
 
 impl TokenUsage {
     /// Get total input tokens including cache creation
-    pub fn total_input_tokens(&self) -> u32 {
-        self.input_tokens.unwrap_or(0) + self.cache_creation_input_tokens.unwrap_or(0)
+    pub fn total_input_tokens(&self) -> u64 {
+        self.input_tokens
+            .unwrap_or(0)
+            .saturating_add(self.cache_creation_input_tokens.unwrap_or(0))
     }
 
     /// Get total cache tokens
-    pub fn total_cache_tokens(&self) -> u32 {
-        self.cache_creation_input_tokens.unwrap_or(0) + self.cache_read_input_tokens.unwrap_or(0)
+    pub fn total_cache_tokens(&self) -> u64 {
+        self.cache_creation_input_tokens
+            .unwrap_or(0)
+            .saturating_add(self.cache_read_input_tokens.unwrap_or(0))
+    }
+
+    /// Get input plus output tokens, excluding cache traffic
+    pub fn input_output_tokens(&self) -> u64 {
+        self.input_tokens
+            .unwrap_or(0)
+            .saturating_add(self.output_tokens.unwrap_or(0))
+    }
+
+    /// Get all tokens of every kind
+    pub fn total_tokens(&self) -> u64 {
+        self.input_output_tokens()
+            .saturating_add(self.total_cache_tokens())
+    }
+
+    /// Token counts as pricing sees them
+    pub fn request_tokens(&self) -> RequestTokens<'_> {
+        RequestTokens::new(
+            self.input_tokens.unwrap_or(0),
+            self.output_tokens.unwrap_or(0),
+            self.cache_creation_input_tokens.unwrap_or(0),
+            self.cache_creation
+                .as_ref()
+                .map_or(0, |c| c.ephemeral_1h_input_tokens),
+            self.cache_read_input_tokens.unwrap_or(0),
+            self.service_tier.as_deref(),
+        )
     }
 
     /// Check if this usage has any non-zero tokens
@@ -486,6 +546,51 @@ mod tests {
         let _ = fs::remove_dir_all(&temp_dir);
     }
 
+    #[test]
+    fn test_scan_drops_content_unless_requested() {
+        let root = std::env::temp_dir().join(format!("claude_scan_test_{}", std::process::id()));
+        let line = |tokens: u64| {
+            format!(
+                r#"{{"type":"assistant","timestamp":"2025-07-01T10:00:00Z","cwd":"/work","message":{{"model":"claude-sonnet-4-20250514","content":[{{"type":"text","text":"hello"}}],"usage":{{"input_tokens":{tokens},"output_tokens":1}}}}}}"#
+            )
+        };
+        for (project, tokens) in [("-work-a", 10), ("-work-b", 20)] {
+            let dir = root.join("projects").join(project);
+            fs::create_dir_all(&dir).unwrap();
+            let user_line =
+                r#"{"type":"user","timestamp":"2025-07-01T09:59:00Z","message":{"content":"hi"}}"#;
+            fs::write(
+                dir.join("s.jsonl"),
+                format!("{user_line}\n\n{}\n", line(tokens)),
+            )
+            .unwrap();
+        }
+        let parser = SessionParser::new(root.clone());
+
+        let mut messages = parser.parse_all_session_messages().unwrap();
+        messages.sort_by_key(|m| m.message.usage.as_ref().unwrap().total_input_tokens());
+        assert_eq!(messages.len(), 2);
+        assert_eq!(
+            messages[0].message.usage.as_ref().unwrap().input_tokens,
+            Some(10)
+        );
+        assert_eq!(
+            messages[1].message.usage.as_ref().unwrap().input_tokens,
+            Some(20)
+        );
+        assert!(messages.iter().all(|m| m.message.content.is_none()));
+        assert_eq!(messages[0].cwd.as_deref(), Some("/work"));
+
+        let scanned = std::sync::Mutex::new(0);
+        let with_content = parser
+            .scan_session_messages(true, &|_| *scanned.lock().unwrap() += 1)
+            .unwrap();
+        assert_eq!(*scanned.lock().unwrap(), 2);
+        assert!(with_content.iter().all(|m| m.message.content.is_some()));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
     #[test]
     fn test_token_usage_parsing() {
         let usage_json = r#"{
@@ -493,6 +598,7 @@ mod tests {
             "output_tokens": 75,
             "cache_creation_input_tokens": 10,
             "cache_read_input_tokens": 5,
+            "cache_creation": {"ephemeral_5m_input_tokens": 4, "ephemeral_1h_input_tokens": 6},
             "service_tier": "premium"
         }"#;
 
@@ -502,6 +608,10 @@ mod tests {
         assert_eq!(usage.cache_creation_input_tokens, Some(10));
         assert_eq!(usage.cache_read_input_tokens, Some(5));
         assert_eq!(usage.service_tier, Some("premium".to_string()));
+
+        let tokens = usage.request_tokens();
+        assert_eq!((tokens.cache_write_5m, tokens.cache_write_1h), (4, 6));
+        assert_eq!(tokens.service_tier, Some("premium"));
     }
 
     #[test]
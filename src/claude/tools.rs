@@ -0,0 +1,257 @@
+//! Tool-usage analytics
+//!
+//! Assistant lines carry `tool_use` blocks naming the tool Claude invoked (Bash,
+//! Edit, Read, Grep, `mcp__<server>__<tool>` for MCP tools), and the user line that
+//! follows carries the matching `tool_result`, flagged with `is_error` when the
+//! tool failed. The usage index keeps both, reduced to ids, names and sizes, so
+//! calls can be counted per session, per project and across all projects.
+//!
+//! Transcripts do not record how many tokens a tool result took up, so it is
+//! estimated from the length of its text at four characters per token.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use super::transcript;
+
+/// Characters per token used to estimate the size of tool results
+const CHARS_PER_TOKEN: u64 = 4;
+
+/// A `tool_use` or `tool_result` content block, reduced to what the analytics need
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ToolBlock {
+    /// A tool call made by Claude
+    Use { id: String, name: String },
+    /// What a tool returned to the call with the same id
+    Result {
+        id: String,
+        is_error: bool,
+        /// Estimated tokens of the result text
+        tokens: u64,
+    },
+}
+
+/// Tool blocks in the `message.content` of a transcript line
+pub fn tool_blocks(value: &Value) -> Vec<ToolBlock> {
+    let Some(content) = value
+        .get("message")
+        .and_then(|message| message.get("content"))
+        .and_then(Value::as_array)
+    else {
+        return Vec::new();
+    };
+
+    let string = |block: &Value, key: &str| {
+        block
+            .get(key)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+    content
+        .iter()
+        .filter_map(|block| match block.get("type")?.as_str()? {
+            "tool_use" => Some(ToolBlock::Use {
+                id: string(block, "id"),
+                name: string(block, "name"),
+            }),
+            "tool_result" => Some(ToolBlock::Result {
+                id: string(block, "tool_use_id"),
+                is_error: block
+                    .get("is_error")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false),
+                tokens: block
+                    .get("content")
+                    .map(|content| estimate_tokens(&transcript::tool_output(content)))
+                    .unwrap_or(0),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Rough token count of `text`
+fn estimate_tokens(text: &str) -> u64 {
+    (text.chars().count() as u64).div_ceil(CHARS_PER_TOKEN)
+}
+
+/// Name to show for a tool; MCP tools (`mcp__<server>__<tool>`) become `<server>:<tool>`
+pub fn display_name(name: &str) -> String {
+    match name
+        .strip_prefix("mcp__")
+        .and_then(|rest| rest.split_once("__"))
+    {
+        Some((server, tool)) => format!("{server}:{tool}"),
+        None => name.to_string(),
+    }
+}
+
+/// Calls and results of one tool
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ToolStats {
+    pub calls: u64,
+    /// Calls whose result came back
+    pub results: u64,
+    /// Results flagged with `is_error`
+    pub errors: u64,
+    /// Estimated tokens of all results, which are sent back to the model as input
+    pub result_tokens: u64,
+}
+
+impl ToolStats {
+    /// Share of results that were errors, as a percentage
+    pub fn error_rate(&self) -> f64 {
+        if self.results == 0 {
+            0.0
+        } else {
+            self.errors as f64 / self.results as f64 * 100.0
+        }
+    }
+
+    fn merge(&mut self, other: &ToolStats) {
+        self.calls = self.calls.saturating_add(other.calls);
+        self.results = self.results.saturating_add(other.results);
+        self.errors = self.errors.saturating_add(other.errors);
+        self.result_tokens = self.result_tokens.saturating_add(other.result_tokens);
+    }
+}
+
+/// Statistics of every tool used, keyed by tool name
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct ToolUsage {
+    tools: BTreeMap<String, ToolStats>,
+}
+
+impl ToolUsage {
+    /// Add another set of statistics to this one
+    pub fn merge(&mut self, other: &ToolUsage) {
+        for (name, stats) in &other.tools {
+            self.tools.entry(name.clone()).or_default().merge(stats);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tools.is_empty()
+    }
+
+    /// Tools with their statistics, most called first
+    pub fn by_calls(&self) -> Vec<(&str, &ToolStats)> {
+        let mut tools: Vec<(&str, &ToolStats)> = self
+            .tools
+            .iter()
+            .map(|(name, stats)| (name.as_str(), stats))
+            .collect();
+        tools.sort_by(|a, b| b.1.calls.cmp(&a.1.calls).then(a.0.cmp(b.0)));
+        tools
+    }
+
+    /// Statistics of all tools together
+    pub fn total(&self) -> ToolStats {
+        let mut total = ToolStats::default();
+        for stats in self.tools.values() {
+            total.merge(stats);
+        }
+        total
+    }
+}
+
+/// Matches tool results to the calls that produced them, and counts each call and
+/// result once however many session files repeat it. Resumed and forked sessions
+/// copy earlier messages, tool ids included.
+#[derive(Debug, Default)]
+pub struct ToolTracker {
+    /// Tool name of every call seen, by `tool_use` id
+    calls: HashMap<String, String>,
+    /// Ids of the results already counted
+    results: HashSet<String>,
+}
+
+impl ToolTracker {
+    /// Add the tool blocks of one transcript line to `usage`. Results of calls that
+    /// were never seen cannot be attributed to a tool and are skipped.
+    pub fn record(&mut self, blocks: &[ToolBlock], usage: &mut ToolUsage) {
+        for block in blocks {
+            match block {
+                ToolBlock::Use { id, name } => {
+                    if self.calls.insert(id.clone(), name.clone()).is_some() {
+                        continue;
+                    }
+                    let stats = usage.tools.entry(name.clone()).or_default();
+                    stats.calls = stats.calls.saturating_add(1);
+                }
+                ToolBlock::Result {
+                    id,
+                    is_error,
+                    tokens,
+                } => {
+                    let Some(name) = self.calls.get(id) else {
+                        continue;
+                    };
+                    if !self.results.insert(id.clone()) {
+                        continue;
+                    }
+                    let stats = usage.tools.entry(name.clone()).or_default();
+                    stats.results = stats.results.saturating_add(1);
+                    stats.errors = stats.errors.saturating_add(u64::from(*is_error));
+                    stats.result_tokens = stats.result_tokens.saturating_add(*tokens);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_blocks_and_tracking() {
+        let call: Value = serde_json::from_str(
+            r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Running"},{"type":"tool_use","id":"t1","name":"Bash","input":{}},{"type":"tool_use","id":"t2","name":"mcp__github__create_issue","input":{}}]}}"#,
+        )
+        .unwrap();
+        let results: Value = serde_json::from_str(
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t1","content":"12345678","is_error":true},{"type":"tool_result","tool_use_id":"t2","content":[{"type":"text","text":"ok"}]},{"type":"tool_result","tool_use_id":"unknown","content":"lost"}]}}"#,
+        )
+        .unwrap();
+
+        let call_blocks = tool_blocks(&call);
+        assert_eq!(
+            call_blocks[0],
+            ToolBlock::Use {
+                id: "t1".to_string(),
+                name: "Bash".to_string(),
+            }
+        );
+        let result_blocks = tool_blocks(&results);
+        assert_eq!(
+            result_blocks[0],
+            ToolBlock::Result {
+                id: "t1".to_string(),
+                is_error: true,
+                tokens: 2,
+            }
+        );
+
+        // A resumed session repeats both lines; they are only counted once
+        let mut tracker = ToolTracker::default();
+        let mut usage = ToolUsage::default();
+        for _ in 0..2 {
+            tracker.record(&call_blocks, &mut usage);
+            tracker.record(&result_blocks, &mut usage);
+        }
+
+        let tools = usage.by_calls();
+        assert_eq!(tools.len(), 2);
+        let bash = &usage.tools["Bash"];
+        assert_eq!((bash.calls, bash.results, bash.errors), (1, 1, 1));
+        assert_eq!(bash.error_rate(), 100.0);
+        assert_eq!(usage.total().calls, 2);
+        assert_eq!(usage.total().result_tokens, 3);
+        assert_eq!(display_name(tools[1].0), "github:create_issue");
+        assert_eq!(display_name("Read"), "Read");
+    }
+}
@@ -0,0 +1,295 @@
+//! Readable transcript of one session, for the transcript viewer
+//!
+//! Each line of a session file holds a user or assistant message whose
+//! `message.content` is either plain text or a list of blocks: text, thinking,
+//! tool_use calls and tool_result outputs. Claude Code writes a streamed response
+//! as several lines that share a message id, one per content block; these are
+//! merged back into a single message.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use super::ingest::UsageEntry;
+
+/// Who wrote a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    User,
+    Assistant,
+}
+
+/// One content block of a message
+#[derive(Debug, Clone, PartialEq)]
+pub enum TranscriptBlock {
+    Text(String),
+    Thinking(String),
+    /// A tool call, with its input as compact JSON
+    ToolUse {
+        name: String,
+        input: String,
+    },
+    /// What a tool returned
+    ToolResult {
+        output: String,
+        is_error: bool,
+    },
+}
+
+/// A user or assistant message of the transcript
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranscriptMessage {
+    pub role: Role,
+    pub time: Option<DateTime<Utc>>,
+    pub model: Option<String>,
+    /// Written by a Task-tool subagent rather than the main thread
+    pub is_sidechain: bool,
+    pub blocks: Vec<TranscriptBlock>,
+    /// Usage and cost fields of the response's first line, for pricing
+    pub usage: Option<UsageEntry>,
+    /// Cost of the response; filled in by the data manager
+    pub cost: f64,
+    message_id: Option<String>,
+}
+
+impl TranscriptMessage {
+    /// Whether the message starts a turn: a prompt typed by the user, rather than
+    /// tool results sent back on the user's behalf
+    pub fn is_prompt(&self) -> bool {
+        self.role == Role::User
+            && !self.is_sidechain
+            && self
+                .blocks
+                .iter()
+                .any(|block| matches!(block, TranscriptBlock::Text(_)))
+    }
+
+    /// Build a message from a transcript line; None for other line types, meta
+    /// lines and lines without content
+    pub fn from_value(value: &Value) -> Option<Self> {
+        let role = match value.get("type")?.as_str()? {
+            "user" => Role::User,
+            "assistant" => Role::Assistant,
+            _ => return None,
+        };
+        if value.get("isMeta").and_then(Value::as_bool) == Some(true) {
+            return None;
+        }
+
+        let message = value.get("message")?;
+        let blocks = message
+            .get("content")
+            .map(content_blocks)
+            .unwrap_or_default();
+        if blocks.is_empty() {
+            return None;
+        }
+
+        let string = |v: Option<&Value>| v.and_then(Value::as_str).map(|s| s.to_string());
+        Some(Self {
+            role,
+            time: string(value.get("timestamp"))
+                .and_then(|timestamp| DateTime::parse_from_rfc3339(&timestamp).ok())
+                .map(|time| time.with_timezone(&Utc)),
+            model: string(message.get("model")),
+            is_sidechain: value
+                .get("isSidechain")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+            blocks,
+            usage: UsageEntry::from_value(value).filter(|entry| entry.usage.is_some()),
+            cost: 0.0,
+            message_id: string(message.get("id")),
+        })
+    }
+
+    /// `message.id` of the API response, shared by the lines of a streamed response
+    pub fn message_id(&self) -> Option<&str> {
+        self.message_id.as_deref()
+    }
+
+    /// The prompt or reply text, without thinking, tool calls or tool output
+    pub fn text(&self) -> String {
+        self.blocks
+            .iter()
+            .filter_map(|block| match block {
+                TranscriptBlock::Text(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Read every user and assistant message of a session file, oldest first.
+/// Lines that are not valid JSON are skipped.
+pub fn read_transcript(path: &Path) -> Result<Vec<TranscriptMessage>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut messages: Vec<TranscriptMessage> = Vec::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Ok(value) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+        let Some(message) = TranscriptMessage::from_value(&value) else {
+            continue;
+        };
+
+        // Later lines of a streamed response add their blocks to the first one
+        match messages.last_mut() {
+            Some(previous)
+                if message.message_id.is_some() && previous.message_id == message.message_id =>
+            {
+                previous.blocks.extend(message.blocks);
+            }
+            _ => messages.push(message),
+        }
+    }
+
+    Ok(messages)
+}
+
+/// Content blocks of `message.content`, which is a string or a list of blocks
+fn content_blocks(content: &Value) -> Vec<TranscriptBlock> {
+    if let Some(text) = content.as_str() {
+        return non_empty_text(text).into_iter().collect();
+    }
+
+    let string = |block: &Value, key: &str| {
+        block
+            .get(key)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+    content
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|block| match block.get("type")?.as_str()? {
+            "text" => non_empty_text(block.get("text")?.as_str()?),
+            "thinking" => Some(TranscriptBlock::Thinking(string(block, "thinking"))),
+            "tool_use" => Some(TranscriptBlock::ToolUse {
+                name: string(block, "name"),
+                input: block.get("input").map(Value::to_string).unwrap_or_default(),
+            }),
+            "tool_result" => Some(TranscriptBlock::ToolResult {
+                output: block.get("content").map(tool_output).unwrap_or_default(),
+                is_error: block
+                    .get("is_error")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+fn non_empty_text(text: &str) -> Option<TranscriptBlock> {
+    let text = text.trim();
+    (!text.is_empty()).then(|| TranscriptBlock::Text(text.to_string()))
+}
+
+/// Text of a tool result, which is a string or a list of text and image blocks
+pub fn tool_output(content: &Value) -> String {
+    if let Some(text) = content.as_str() {
+        return text.to_string();
+    }
+    content
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .map(|block| match block.get("type").and_then(Value::as_str) {
+            Some("text") => block
+                .get("text")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            Some(other) => format!("[{other}]"),
+            None => String::new(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_transcript_blocks_and_streamed_responses() {
+        let lines = [
+            r#"{"type":"summary","summary":"Fix the build"}"#,
+            r#"{"type":"user","isMeta":true,"message":{"role":"user","content":"<caveat>"}}"#,
+            r#"{"type":"user","timestamp":"2025-06-01T10:00:00Z","message":{"role":"user","content":"Run the tests"}}"#,
+            r#"{"type":"assistant","timestamp":"2025-06-01T10:00:05Z","requestId":"r1","message":{"id":"m1","model":"claude-sonnet-4","content":[{"type":"thinking","thinking":"Use cargo"}],"usage":{"input_tokens":10,"output_tokens":5}}}"#,
+            r#"{"type":"assistant","timestamp":"2025-06-01T10:00:06Z","requestId":"r1","message":{"id":"m1","model":"claude-sonnet-4","content":[{"type":"tool_use","id":"t1","name":"Bash","input":{"command":"cargo test"}}],"usage":{"input_tokens":10,"output_tokens":5}}}"#,
+            r#"{"type":"user","timestamp":"2025-06-01T10:00:30Z","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"t1","content":[{"type":"text","text":"ok"}],"is_error":true}]}}"#,
+            "not json",
+        ];
+        let path = std::env::temp_dir().join(format!(
+            "cc-enhanced-transcript-{}.jsonl",
+            std::process::id()
+        ));
+        let mut file = File::create(&path).unwrap();
+        for line in lines {
+            writeln!(file, "{line}").unwrap();
+        }
+
+        let messages = read_transcript(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(messages.len(), 3);
+        assert!(messages[0].is_prompt());
+        assert_eq!(
+            messages[0].blocks,
+            vec![TranscriptBlock::Text("Run the tests".to_string())]
+        );
+
+        let response = &messages[1];
+        assert_eq!(response.role, Role::Assistant);
+        assert_eq!(response.model.as_deref(), Some("claude-sonnet-4"));
+        assert_eq!(
+            response.blocks,
+            vec![
+                TranscriptBlock::Thinking("Use cargo".to_string()),
+                TranscriptBlock::ToolUse {
+                    name: "Bash".to_string(),
+                    input: r#"{"command":"cargo test"}"#.to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            response
+                .usage
+                .as_ref()
+                .unwrap()
+                .usage
+                .as_ref()
+                .unwrap()
+                .output_tokens,
+            5
+        );
+
+        assert!(!messages[2].is_prompt());
+        assert_eq!(
+            messages[2].blocks,
+            vec![TranscriptBlock::ToolResult {
+                output: "ok".to_string(),
+                is_error: true,
+            }]
+        );
+    }
+}
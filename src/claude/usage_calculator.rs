@@ -1,49 +1,96 @@
 #![allow(dead_code)] // Allow unused code during migration
 
 use anyhow::Result;
-use chrono::{DateTime, Local, NaiveDate};
-use serde_json::Value;
+use chrono::{DateTime, Local, NaiveDate, SecondsFormat, Utc};
+use serde::{Serialize, Serializer};
 use std::collections::{BTreeMap, HashMap, HashSet};
-use std::fs;
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
 use super::pricing::TokenUsage as PricingTokenUsage;
 use super::session_parser::SessionMessage;
+use super::tools::ToolUsage;
+
+/// Serialize a `SystemTime` as an RFC 3339 UTC timestamp
+fn serialize_time<S>(time: &SystemTime, serializer: S) -> std::result::Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer
+        .serialize_str(&DateTime::<Utc>::from(*time).to_rfc3339_opts(SecondsFormat::Secs, true))
+}
+
+/// Serialize an optional `SystemTime` as an RFC 3339 UTC timestamp or null
+fn serialize_optional_time<S>(
+    time: &Option<SystemTime>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match time {
+        Some(time) => serialize_time(time, serializer),
+        None => serializer.serialize_none(),
+    }
+}
 
 /// Usage statistics for a time period
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct UsageStats {
-    pub input_tokens: u32,
-    pub output_tokens: u32,
-    pub cache_creation_tokens: u32,
-    pub cache_read_tokens: u32,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub cache_read_tokens: u64,
     pub total_cost: f64,
     pub message_count: u32,
+    #[serde(serialize_with = "serialize_optional_time")]
     pub reset_time: Option<SystemTime>,
     pub is_subscription_user: bool, // true if using fallback pricing (subscription)
 }
 
 /// Session block statistics (5-hour periods)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SessionBlock {
+    #[serde(serialize_with = "serialize_time")]
     pub start_time: SystemTime,
+    #[serde(serialize_with = "serialize_time")]
     pub end_time: SystemTime,
     pub usage_stats: UsageStats,
     pub is_active: bool,
 }
 
 /// Project analytics data
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct ProjectAnalytics {
     pub total_sessions: usize,
     pub total_messages: usize,
-    pub total_tokens: u32,
+    pub total_tokens: u64,
     pub estimated_cost: f64,
+    /// Input and output tokens of Task-tool subagents, included in `total_tokens`
+    pub subagent_tokens: u64,
+    /// Cost of subagent traffic, included in `estimated_cost`
+    pub subagent_cost: f64,
+    #[serde(serialize_with = "serialize_optional_time")]
     pub first_session: Option<SystemTime>,
+    #[serde(serialize_with = "serialize_optional_time")]
     pub last_session: Option<SystemTime>,
     pub cache_efficiency: f64, // Percentage of cache usage
     pub session_blocks: Vec<SessionBlock>,
+    /// Usage of each session split by agent, keyed by session id
+    pub session_agents: BTreeMap<String, Vec<AgentUsage>>,
+    /// Tools called across the project's sessions
+    pub tool_usage: ToolUsage,
+    /// Tools called in each session, keyed by session id
+    pub session_tools: BTreeMap<String, ToolUsage>,
+}
+
+/// Usage of one agent within a session: the main thread or a Task-tool subagent
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentUsage {
+    /// Agent name as used in todo file names; the session id for the main thread
+    pub agent_id: String,
+    pub is_sidechain: bool,
+    pub usage_stats: UsageStats,
 }
 
 /// Daily usage breakdown
@@ -64,10 +111,10 @@ pub struct ModelUsage {
 #[derive(Debug, Clone)]
 pub struct DailyUsageDetail {
     pub date: String,
-    pub input_tokens: u32,
-    pub output_tokens: u32,
-    pub cache_creation_tokens: u32,
-    pub cache_read_tokens: u32,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub cache_read_tokens: u64,
     pub total_cost: f64,
     pub message_count: u32,
     pub session_count: u32,
@@ -80,7 +127,7 @@ pub struct DailyUsageDetail {
 #[derive(Debug, Clone)]
 pub struct ProjectUsageStats {
     pub project_name: String,
-    pub total_tokens: u32,
+    pub total_tokens: u64,
     pub total_cost: f64,
     pub session_count: u32,
     pub message_count: u32,
@@ -92,299 +139,62 @@ pub struct ProjectUsageStats {
     pub avg_session_length: f64, // Average session duration in minutes
 }
 
-/// Usage calculator for Claude session data
-pub struct UsageCalculator {
-    claude_dir: PathBuf,
+/// Date and project restrictions applied when aggregating usage
+#[derive(Debug, Clone, Default)]
+pub struct UsageFilter {
+    /// First local date to include (inclusive)
+    pub since: Option<NaiveDate>,
+    /// Last local date to include (inclusive)
+    pub until: Option<NaiveDate>,
+    /// Project data directories to include; `None` means every project
+    pub project_dirs: Option<Vec<PathBuf>>,
 }
 
-impl UsageCalculator {
-    /// Create new usage calculator
-    pub fn new(claude_dir: PathBuf) -> Self {
-        Self { claude_dir }
-    }
-
-    /// Calculate today's usage statistics
-    pub fn calculate_today_usage(&self) -> Result<UsageStats> {
-        let mut stats = UsageStats::default();
-        let today = Local::now().date_naive();
-
-        let projects_dir = self.claude_dir.join("projects");
-        if !projects_dir.exists() {
-            return Ok(stats);
-        }
-
-        // Scan all JSONL files for today's data
-        for entry in fs::read_dir(&projects_dir)? {
-            let entry = entry?;
-            let project_path = entry.path();
-
-            if project_path.is_dir() {
-                for file_entry in fs::read_dir(&project_path)? {
-                    let file_entry = file_entry?;
-                    let file_path = file_entry.path();
-
-                    if file_path.extension().and_then(|s| s.to_str()) == Some("jsonl") {
-                        self.process_jsonl_for_usage(&file_path, &mut stats, today)?;
-                    }
-                }
-            }
-        }
-
-        Ok(stats)
-    }
-
-    /// Process a JSONL file for usage statistics
-    fn process_jsonl_for_usage(
-        &self,
-        file_path: &Path,
-        stats: &mut UsageStats,
-        target_date: NaiveDate,
-    ) -> Result<()> {
-        let content = fs::read_to_string(file_path)?;
-
-        for line in content.lines() {
-            let line = line.trim();
-            if line.is_empty() {
-                continue;
-            }
-
-            if let Ok(entry) = serde_json::from_str::<Value>(line) {
-                // Check if this entry is from today
-                if let Some(timestamp_str) = entry.get("timestamp").and_then(|v| v.as_str()) {
-                    if let Ok(datetime) = DateTime::parse_from_rfc3339(timestamp_str) {
-                        let entry_date = datetime.with_timezone(&Local).date_naive();
-
-                        if entry_date == target_date {
-                            self.extract_usage_from_entry(&entry, stats)?;
-                        }
-                    }
-                }
-            }
-        }
-
-        Ok(())
-    }
-
-    /// Extract usage data from a single JSONL entry
-    fn extract_usage_from_entry(&self, entry: &Value, stats: &mut UsageStats) -> Result<()> {
-        let mut current_input_tokens = 0u32;
-        let mut current_output_tokens = 0u32;
-        let mut current_cache_creation_tokens = 0u32;
-        let mut current_cache_read_tokens = 0u32;
-
-        // Check for usage data in message.usage
-        if let Some(message) = entry.get("message") {
-            if let Some(usage) = message.get("usage") {
-                if let Some(input) = usage.get("input_tokens").and_then(|v| v.as_u64()) {
-                    current_input_tokens = input as u32;
-                }
-                if let Some(output) = usage.get("output_tokens").and_then(|v| v.as_u64()) {
-                    current_output_tokens = output as u32;
-                }
-                if let Some(cache_creation) = usage
-                    .get("cache_creation_input_tokens")
-                    .and_then(|v| v.as_u64())
-                {
-                    current_cache_creation_tokens = cache_creation as u32;
-                }
-                if let Some(cache_read) = usage
-                    .get("cache_read_input_tokens")
-                    .and_then(|v| v.as_u64())
-                {
-                    current_cache_read_tokens = cache_read as u32;
-                }
-            }
-        }
-
-        // Only add if there's actual usage
-        if current_input_tokens > 0
-            || current_output_tokens > 0
-            || current_cache_creation_tokens > 0
-            || current_cache_read_tokens > 0
-        {
-            stats.input_tokens += current_input_tokens;
-            stats.output_tokens += current_output_tokens;
-            stats.cache_creation_tokens += current_cache_creation_tokens;
-            stats.cache_read_tokens += current_cache_read_tokens;
-            stats.message_count += 1;
-
-            // Calculate cost using model information if available
-            if let Some(message) = entry.get("message") {
-                if let Some(model) = message.get("model").and_then(|v| v.as_str()) {
-                    let usage = PricingTokenUsage {
-                        input_tokens: current_input_tokens,
-                        output_tokens: current_output_tokens,
-                        cache_creation_tokens: current_cache_creation_tokens,
-                        cache_read_tokens: current_cache_read_tokens,
-                    };
-
-                    // Use basic calculation with fallback pricing
-                    // This would be replaced with actual pricing manager in integration
-                    let cost = self.calculate_basic_cost(&usage, model);
-                    stats.total_cost += cost;
-                }
-            }
-        }
-
-        Ok(())
-    }
-
-    /// Calculate usage statistics for a single session
-    pub fn calculate_session_usage(&self, session_path: &Path) -> Result<UsageStats> {
-        let mut stats = UsageStats::default();
-        let content = fs::read_to_string(session_path)?;
-
-        for line in content.lines() {
-            let line = line.trim();
-            if line.is_empty() {
-                continue;
-            }
-
-            if let Ok(entry) = serde_json::from_str::<Value>(line) {
-                self.extract_usage_from_entry(&entry, &mut stats)?;
-            }
-        }
-
-        Ok(stats)
+impl UsageFilter {
+    /// Check whether a local date falls inside the filter range
+    pub fn includes_date(&self, date: NaiveDate) -> bool {
+        self.since.map_or(true, |since| date >= since)
+            && self.until.map_or(true, |until| date <= until)
     }
 
-    /// Calculate daily usage breakdown for the last N days (optimized single-pass)
-    pub fn calculate_daily_usage(&self, days: u32) -> Result<Vec<DailyUsage>> {
-        let today = Local::now().date_naive();
-        let mut daily_stats: HashMap<NaiveDate, UsageStats> = HashMap::new();
-
-        // Initialize empty stats for each day
-        for i in 0..days {
-            let date = today - chrono::Duration::days(i as i64);
-            daily_stats.insert(date, UsageStats::default());
-        }
-
-        let projects_dir = self.claude_dir.join("projects");
-        if !projects_dir.exists() {
-            return Ok(daily_stats
-                .into_iter()
-                .map(|(date, usage_stats)| DailyUsage { date, usage_stats })
-                .collect());
-        }
-
-        // Single pass through all JSONL files
-        for entry in fs::read_dir(&projects_dir)? {
-            let entry = entry?;
-            let project_path = entry.path();
-
-            if project_path.is_dir() {
-                for file_entry in fs::read_dir(&project_path)? {
-                    let file_entry = file_entry?;
-                    let file_path = file_entry.path();
-
-                    if file_path.extension().and_then(|s| s.to_str()) == Some("jsonl") {
-                        self.process_jsonl_for_daily_usage(&file_path, &mut daily_stats, days)?;
-                    }
-                }
-            }
+    /// Check whether an RFC 3339 timestamp falls inside the filter range.
+    /// Entries without a parseable timestamp only pass when no range is set.
+    pub fn includes_timestamp(&self, timestamp: Option<&str>) -> bool {
+        if self.since.is_none() && self.until.is_none() {
+            return true;
         }
 
-        Ok(daily_stats
-            .into_iter()
-            .map(|(date, usage_stats)| DailyUsage { date, usage_stats })
-            .collect())
+        timestamp
+            .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+            .map(|dt| self.includes_date(dt.with_timezone(&Local).date_naive()))
+            .unwrap_or(false)
     }
 
-    /// Process JSONL file for daily usage statistics
-    fn process_jsonl_for_daily_usage(
-        &self,
-        file_path: &Path,
-        daily_stats: &mut HashMap<NaiveDate, UsageStats>,
-        days: u32,
-    ) -> Result<()> {
-        let content = fs::read_to_string(file_path)?;
-        let today = Local::now().date_naive();
-        let earliest_date = today - chrono::Duration::days(days as i64);
-
-        for line in content.lines() {
-            let line = line.trim();
-            if line.is_empty() {
-                continue;
-            }
-
-            if let Ok(entry) = serde_json::from_str::<Value>(line) {
-                if let Some(timestamp_str) = entry.get("timestamp").and_then(|v| v.as_str()) {
-                    if let Ok(datetime) = DateTime::parse_from_rfc3339(timestamp_str) {
-                        let entry_date = datetime.with_timezone(&Local).date_naive();
-
-                        // Only process if within our date range
-                        if entry_date >= earliest_date && entry_date <= today {
-                            if let Some(stats) = daily_stats.get_mut(&entry_date) {
-                                self.extract_usage_from_entry(&entry, stats)?;
-                            }
-                        }
-                    }
-                }
-            }
-        }
-
-        Ok(())
+    /// Check whether a point in time falls inside the filter range
+    pub fn includes_time(&self, time: SystemTime) -> bool {
+        let date = DateTime::<Local>::from(time).date_naive();
+        self.includes_date(date)
     }
 
-    /// Calculate model usage breakdown
-    pub fn calculate_model_usage(&self) -> Result<Vec<ModelUsage>> {
-        let mut model_stats: HashMap<String, UsageStats> = HashMap::new();
-
-        let projects_dir = self.claude_dir.join("projects");
-        if !projects_dir.exists() {
-            return Ok(Vec::new());
-        }
-
-        for entry in fs::read_dir(&projects_dir)? {
-            let entry = entry?;
-            let project_path = entry.path();
-
-            if project_path.is_dir() {
-                for file_entry in fs::read_dir(&project_path)? {
-                    let file_entry = file_entry?;
-                    let file_path = file_entry.path();
-
-                    if file_path.extension().and_then(|s| s.to_str()) == Some("jsonl") {
-                        self.process_jsonl_for_model_usage(&file_path, &mut model_stats)?;
-                    }
-                }
-            }
-        }
-
-        Ok(model_stats
-            .into_iter()
-            .map(|(model_name, usage_stats)| ModelUsage {
-                model_name,
-                usage_stats,
-            })
-            .collect())
+    /// Check whether a project data directory should be scanned
+    pub fn includes_dir(&self, dir: &Path) -> bool {
+        self.project_dirs
+            .as_ref()
+            .map_or(true, |dirs| dirs.iter().any(|d| d == dir))
     }
+}
 
-    /// Process JSONL file for model-specific usage statistics
-    fn process_jsonl_for_model_usage(
-        &self,
-        file_path: &Path,
-        model_stats: &mut HashMap<String, UsageStats>,
-    ) -> Result<()> {
-        let content = fs::read_to_string(file_path)?;
-
-        for line in content.lines() {
-            let line = line.trim();
-            if line.is_empty() {
-                continue;
-            }
-
-            if let Ok(entry) = serde_json::from_str::<Value>(line) {
-                if let Some(message) = entry.get("message") {
-                    if let Some(model_name) = message.get("model").and_then(|v| v.as_str()) {
-                        let stats = model_stats.entry(model_name.to_string()).or_default();
-                        self.extract_usage_from_entry(&entry, stats)?;
-                    }
-                }
-            }
-        }
+/// Usage calculator for Claude session data. Totals over the session files
+/// themselves come from `ClaudeDataManager`, which reads them through the
+/// session ingestor; this works on messages that were already parsed.
+pub struct UsageCalculator {
+    claude_dir: PathBuf,
+}
 
-        Ok(())
+impl UsageCalculator {
+    /// Create new usage calculator
+    pub fn new(claude_dir: PathBuf) -> Self {
+        Self { claude_dir }
     }
 
     /// Analyze daily usage with full detail
@@ -419,11 +229,18 @@ impl UsageCalculator {
                             });
 
                     if let Some(usage) = &message.message.usage {
-                        detail.input_tokens += usage.input_tokens.unwrap_or(0);
-                        detail.output_tokens += usage.output_tokens.unwrap_or(0);
-                        detail.cache_creation_tokens +=
-                            usage.cache_creation_input_tokens.unwrap_or(0);
-                        detail.cache_read_tokens += usage.cache_read_input_tokens.unwrap_or(0);
+                        detail.input_tokens = detail
+                            .input_tokens
+                            .saturating_add(usage.input_tokens.unwrap_or(0));
+                        detail.output_tokens = detail
+                            .output_tokens
+                            .saturating_add(usage.output_tokens.unwrap_or(0));
+                        detail.cache_creation_tokens = detail
+                            .cache_creation_tokens
+                            .saturating_add(usage.cache_creation_input_tokens.unwrap_or(0));
+                        detail.cache_read_tokens = detail
+                            .cache_read_tokens
+                            .saturating_add(usage.cache_read_input_tokens.unwrap_or(0));
                         detail.message_count += 1;
 
                         if let Some(model) = &message.message.model {
@@ -445,8 +262,13 @@ impl UsageCalculator {
 
         // Calculate efficiency scores and costs
         for detail in daily_stats.values_mut() {
-            let total_cache = detail.cache_creation_tokens + detail.cache_read_tokens;
-            let total_tokens = detail.input_tokens + detail.output_tokens + total_cache;
+            let total_cache = detail
+                .cache_creation_tokens
+                .saturating_add(detail.cache_read_tokens);
+            let total_tokens = detail
+                .input_tokens
+                .saturating_add(detail.output_tokens)
+                .saturating_add(total_cache);
 
             if total_tokens > 0 {
                 detail.efficiency_score = (total_cache as f64 / total_tokens as f64) * 100.0;
@@ -502,10 +324,7 @@ impl UsageCalculator {
                     });
 
                 if let Some(usage) = &message.message.usage {
-                    stats.total_tokens += usage.input_tokens.unwrap_or(0)
-                        + usage.output_tokens.unwrap_or(0)
-                        + usage.cache_creation_input_tokens.unwrap_or(0)
-                        + usage.cache_read_input_tokens.unwrap_or(0);
+                    stats.total_tokens = stats.total_tokens.saturating_add(usage.total_tokens());
 
                     stats.message_count += 1;
 
@@ -562,16 +381,17 @@ impl UsageCalculator {
     /// Calculate basic cost from individual token counts
     fn calculate_basic_cost_from_tokens(
         &self,
-        input_tokens: u32,
-        output_tokens: u32,
-        cache_creation_tokens: u32,
-        cache_read_tokens: u32,
+        input_tokens: u64,
+        output_tokens: u64,
+        cache_creation_tokens: u64,
+        cache_read_tokens: u64,
     ) -> f64 {
         let usage = PricingTokenUsage {
             input_tokens,
             output_tokens,
             cache_creation_tokens,
             cache_read_tokens,
+            ..Default::default()
         };
         self.calculate_basic_cost(&usage, "claude-3-5-sonnet") // default model
     }
@@ -579,13 +399,52 @@ impl UsageCalculator {
 
 impl UsageStats {
     /// Get total tokens
-    pub fn total_tokens(&self) -> u32 {
-        self.input_tokens + self.output_tokens + self.cache_creation_tokens + self.cache_read_tokens
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens
+            .saturating_add(self.output_tokens)
+            .saturating_add(self.cache_creation_tokens)
+            .saturating_add(self.cache_read_tokens)
+    }
+
+    /// Add one message's tokens. Sums saturate rather than wrap, so a corrupt
+    /// or enormous line cannot turn totals into garbage.
+    pub fn add_tokens(
+        &mut self,
+        input_tokens: u64,
+        output_tokens: u64,
+        cache_creation_tokens: u64,
+        cache_read_tokens: u64,
+    ) {
+        self.input_tokens = self.input_tokens.saturating_add(input_tokens);
+        self.output_tokens = self.output_tokens.saturating_add(output_tokens);
+        self.cache_creation_tokens = self
+            .cache_creation_tokens
+            .saturating_add(cache_creation_tokens);
+        self.cache_read_tokens = self.cache_read_tokens.saturating_add(cache_read_tokens);
+    }
+
+    /// Add another set of statistics onto this one
+    pub fn merge(&mut self, other: &UsageStats) {
+        self.add_tokens(
+            other.input_tokens,
+            other.output_tokens,
+            other.cache_creation_tokens,
+            other.cache_read_tokens,
+        );
+        self.total_cost += other.total_cost;
+        self.message_count = self.message_count.saturating_add(other.message_count);
+    }
+
+    /// Get input plus output tokens, excluding cache traffic
+    pub fn input_output_tokens(&self) -> u64 {
+        self.input_tokens.saturating_add(self.output_tokens)
     }
 
     /// Get cache efficiency percentage
     pub fn cache_efficiency(&self) -> f64 {
-        let total_cache = self.cache_creation_tokens + self.cache_read_tokens;
+        let total_cache = self
+            .cache_creation_tokens
+            .saturating_add(self.cache_read_tokens);
         let total = self.total_tokens();
 
         if total > 0 {
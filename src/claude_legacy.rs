@@ -1,16 +1,31 @@
 use anyhow::Result;
-use chrono::Timelike;
+use chrono::{DateTime, Timelike, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
 
 // Import types from the new modular system
+use crate::claude::blocks::{self, BlockEntry, BlockSummary, TimelineEntry};
+use crate::claude::ingest::{
+    self, IngestedFile, SessionIngestor, SharedIngestor, UsageDeduplicator, UsageEntry, UsageRecord,
+};
+use crate::claude::path_resolver;
+use crate::claude::periods::PeriodSettings;
+use crate::claude::pricing_provider::PricingResolver;
+use crate::claude::pricing_table::{self, PricingSource, ResolvedPricing};
+use crate::claude::quota::{self, QuotaSettings, QuotaStatus};
+use crate::claude::scan::{self, ScanProgress};
+use crate::claude::search::{self, SearchHit, SearchIndex, SearchQuery};
+use crate::claude::tools::{ToolTracker, ToolUsage};
+use crate::claude::transcript::{self, TranscriptMessage};
 use crate::claude::usage_calculator::SessionBlock;
 use crate::claude::{
-    MessageContent, Project, ProjectAnalytics, Session, SessionMessage, TokenUsage, UsageStats,
+    AgentUsage, CacheCreation, MessageContent, Project, ProjectAnalytics, Session, SessionMessage,
+    TokenUsage, UsageFilter, UsageStats,
 };
 
 /// Enhanced todo item from ~/.claude/todos/ directory
@@ -24,21 +39,6 @@ pub struct EnhancedTodoItem {
     pub project_name: Option<String>, // Inferred project name
 }
 
-/// Cost warning states from ~/.claude/config/notification_states.json
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct NotificationStates {
-    pub switch_to_custom: NotificationState,
-    pub exceed_max_limit: NotificationState,
-    pub tokens_will_run_out: NotificationState,
-    pub cost_will_exceed: NotificationState,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct NotificationState {
-    pub triggered: bool,
-    pub timestamp: Option<String>,
-}
-
 /// Session intelligence metrics
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
@@ -64,15 +64,17 @@ pub struct UsageAnalytics {
     pub cost_breakdown: CostBreakdown,
     pub project_usage: HashMap<String, ProjectUsageStats>,
     pub session_analytics: Vec<SessionAnalytics>,
+    /// Tools called across all sessions
+    pub tool_usage: ToolUsage,
 }
 
 #[derive(Debug, Clone)]
 pub struct DailyUsageDetail {
     pub date: String,
-    pub total_input_tokens: u32,
-    pub total_output_tokens: u32,
-    pub total_cache_creation_tokens: u32,
-    pub total_cache_read_tokens: u32,
+    pub total_input_tokens: u64,
+    pub total_output_tokens: u64,
+    pub total_cache_creation_tokens: u64,
+    pub total_cache_read_tokens: u64,
     pub total_cost: f64,
     #[allow(dead_code)]
     pub session_count: usize,
@@ -83,8 +85,8 @@ pub struct DailyUsageDetail {
 #[derive(Debug, Clone)]
 pub struct ModelUsageStats {
     pub model_name: String,
-    pub total_input_tokens: u32,
-    pub total_output_tokens: u32,
+    pub total_input_tokens: u64,
+    pub total_output_tokens: u64,
     pub total_cost: f64,
     pub usage_count: usize,
     pub first_used: String,
@@ -96,15 +98,15 @@ pub struct ModelUsageStats {
 pub struct HourlyUsage {
     #[allow(dead_code)]
     pub hour: u8,
-    pub total_tokens: u32,
+    pub total_tokens: u64,
     pub total_cost: f64,
     pub message_count: usize,
 }
 
 #[derive(Debug, Clone)]
 pub struct CacheEfficiencyStats {
-    pub total_cache_creation_tokens: u32,
-    pub total_cache_read_tokens: u32,
+    pub total_cache_creation_tokens: u64,
+    pub total_cache_read_tokens: u64,
     pub cache_hit_rate: f64,
     pub cache_cost_savings: f64,
 }
@@ -130,7 +132,7 @@ pub struct CostBreakdown {
 #[derive(Debug, Clone)]
 pub struct ProjectUsageStats {
     pub project_name: String,
-    pub total_tokens: u32,
+    pub total_tokens: u64,
     pub total_cost: f64,
     #[allow(dead_code)]
     pub session_count: usize,
@@ -139,7 +141,7 @@ pub struct ProjectUsageStats {
     pub avg_session_length: f64,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SessionAnalytics {
     pub session_id: String,
     #[allow(dead_code)]
@@ -150,537 +152,269 @@ pub struct SessionAnalytics {
     pub end_time: String,
     pub duration_minutes: f64,
     #[allow(dead_code)]
-    pub total_tokens: u32,
+    pub total_tokens: u64,
     pub total_cost: f64,
+    /// Part of `total_tokens` produced by Task-tool subagents
+    #[allow(dead_code)]
+    pub subagent_tokens: u64,
+    /// Part of `total_cost` spent by Task-tool subagents
+    pub subagent_cost: f64,
     #[allow(dead_code)]
     pub message_count: usize,
     #[allow(dead_code)]
     pub models_used: Vec<String>,
     #[allow(dead_code)]
     pub efficiency_score: f64,
+    /// Tools called in the session
+    pub tools: ToolUsage,
 }
 
-/// OpenRouter model pricing data
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct OpenRouterModel {
-    pub id: String,
-    pub pricing: ModelPricing,
-}
+// Project, Session, and UsageStats types moved to respective modules
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ModelPricing {
-    #[serde(deserialize_with = "string_to_f64")]
-    pub prompt: f64, // Price per token for input
-    #[serde(deserialize_with = "string_to_f64")]
-    pub completion: f64, // Price per token for output
-    #[serde(default, deserialize_with = "optional_string_to_f64")]
-    pub cache_creation_input_token_cost: Option<f64>,
-    #[serde(default, deserialize_with = "optional_string_to_f64")]
-    pub cache_read_input_token_cost: Option<f64>,
-}
+// SessionBlock type moved to usage_calculator.rs module
 
-/// Custom deserializer for string to f64 conversion
-fn string_to_f64<'de, D>(deserializer: D) -> Result<f64, D::Error>
-where
-    D: serde::Deserializer<'de>,
-{
-    use serde::de::Error;
-    use serde::Deserialize;
-
-    match serde_json::Value::deserialize(deserializer)? {
-        serde_json::Value::String(s) => s.parse().map_err(D::Error::custom),
-        serde_json::Value::Number(num) => num
-            .as_f64()
-            .ok_or_else(|| D::Error::custom("invalid number")),
-        _ => Err(D::Error::custom("expected string or number")),
-    }
-}
+// ProjectAnalytics moved to usage_calculator.rs module
 
-/// Custom deserializer for optional string to f64 conversion
-fn optional_string_to_f64<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
-where
-    D: serde::Deserializer<'de>,
-{
-    use serde::de::Error;
-    use serde::Deserialize;
-
-    match Option::<serde_json::Value>::deserialize(deserializer)? {
-        None => Ok(None),
-        Some(serde_json::Value::Null) => Ok(None),
-        Some(serde_json::Value::String(s)) => {
-            if s.is_empty() {
-                Ok(None)
-            } else {
-                s.parse().map(Some).map_err(D::Error::custom)
-            }
-        }
-        Some(serde_json::Value::Number(num)) => Ok(num.as_f64()),
-        Some(_) => Err(D::Error::custom("expected string, number, or null")),
-    }
+/// Daily usage breakdown
+#[derive(Debug, Clone, Serialize)]
+pub struct DailyUsage {
+    #[allow(dead_code)]
+    pub date: chrono::NaiveDate,
+    #[allow(dead_code)]
+    pub usage_stats: UsageStats,
 }
 
-/// OpenRouter API response structure
-#[derive(Debug, Deserialize)]
-struct OpenRouterResponse {
-    data: Vec<OpenRouterModel>,
+/// Model usage statistics
+#[derive(Debug, Clone, Serialize)]
+#[allow(dead_code)]
+pub struct ModelUsage {
+    pub model_name: String,
+    pub usage_stats: UsageStats,
 }
 
-/// Cached pricing data with expiration
-#[derive(Debug, Serialize, Deserialize)]
-struct PricingCache {
-    last_updated: u64, // Unix timestamp
-    models: HashMap<String, OpenRouterModel>,
+/// Claude data manager
+pub struct ClaudeDataManager {
+    /// Claude data directories, primary first
+    claude_dirs: Vec<PathBuf>,
+    pricing: PricingResolver,
+    /// Parsed session entries, updated incrementally as files grow
+    ingestor: SharedIngestor,
+    /// Duplicate message copies dropped by the most recent usage aggregate
+    duplicates_dropped: AtomicUsize,
+    /// Models whose cost was calculated from token counts, with the pricing source used
+    priced_models: Mutex<BTreeMap<String, PricingSource>>,
+    /// Plan whose limits `quota_status` measures against
+    quota_settings: QuotaSettings,
+    /// Alignment of the budget weeks and months in `quota_status`
+    period_settings: PeriodSettings,
 }
 
-impl PricingCache {
-    const CACHE_DURATION_HOURS: u64 = 24;
-
-    /// Check if cache is expired (older than 24 hours)
-    fn is_expired(&self) -> bool {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-
-        now - self.last_updated > Self::CACHE_DURATION_HOURS * 3600
-    }
-
-    /// Load cache from file
-    fn load_from_file(cache_path: &Path) -> Result<Option<Self>> {
-        if !cache_path.exists() {
-            return Ok(None);
-        }
-
-        let content = fs::read_to_string(cache_path)?;
-        let cache: PricingCache = serde_json::from_str(&content)?;
-
-        if cache.is_expired() {
-            Ok(None)
-        } else {
-            Ok(Some(cache))
-        }
-    }
-
-    /// Save cache to file
-    fn save_to_file(&self, cache_path: &Path) -> Result<()> {
-        if let Some(parent) = cache_path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-
-        let content = serde_json::to_string_pretty(self)?;
-        fs::write(cache_path, content)?;
-        Ok(())
+impl ClaudeDataManager {
+    /// Create a new Claude data manager reading from the given data directories.
+    /// The first directory is the primary one and holds the pricing cache.
+    /// Parsed sessions are loaded from the on-disk usage index when it is current.
+    pub fn new(claude_dirs: &[PathBuf]) -> Result<Self> {
+        let ingestor = Arc::new(Mutex::new(SessionIngestor::open_default()));
+        Self::with_ingestor(claude_dirs, ingestor)
     }
 
-    /// Fetch fresh data from OpenRouter API
-    async fn fetch_from_openrouter() -> Result<Self> {
-        let client = reqwest::Client::new();
-        let response = client
-            .get("https://openrouter.ai/api/v1/models")
-            .send()
-            .await?;
+    /// Create a data manager that reuses another manager's parsed session entries
+    pub fn with_ingestor(claude_dirs: &[PathBuf], ingestor: SharedIngestor) -> Result<Self> {
+        let primary = claude_dirs
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("No Claude data directory configured"))?;
+        let config = crate::shared::Config::load().unwrap_or_default();
+        let mut pricing = PricingResolver::new(primary, config.pricing);
 
-        let api_response: OpenRouterResponse = response.json().await?;
-
-        let mut models = HashMap::new();
-        for model in api_response.data {
-            // Only cache Claude models
-            if model.id.contains("claude") {
-                models.insert(model.id.clone(), model);
-            }
+        // Initialize cache from file (ignore errors, fallback will be used)
+        let _ = pricing.init_cache();
+        // An invalid pricing file is reported by `override_error` and otherwise ignored
+        if let Some(path) = pricing_table::override_path() {
+            let _ = pricing.load_overrides(&path);
         }
 
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-
-        Ok(PricingCache {
-            last_updated: now,
-            models,
+        Ok(Self {
+            claude_dirs: claude_dirs.to_vec(),
+            pricing,
+            ingestor,
+            duplicates_dropped: AtomicUsize::new(0),
+            priced_models: Mutex::new(BTreeMap::new()),
+            quota_settings: config.quota,
+            period_settings: config.periods,
         })
     }
-}
-
-/// OpenRouter pricing manager with daily caching
-pub struct OpenRouterPricing {
-    cache_path: PathBuf,
-    cache: Option<PricingCache>,
-}
 
-impl Default for OpenRouterPricing {
-    fn default() -> Self {
-        let home_dir = dirs::home_dir().unwrap_or_else(|| std::path::PathBuf::from("/tmp"));
-        let cache_path = home_dir.join(".claude").join("pricing_cache.json");
-        Self {
-            cache_path,
-            cache: None,
-        }
+    /// How weeks and billing months are aligned
+    pub fn period_settings(&self) -> &PeriodSettings {
+        &self.period_settings
     }
-}
 
-impl OpenRouterPricing {
-    pub fn new(claude_dir: &Path) -> Self {
-        let cache_path = claude_dir.join("pricing_cache.json");
-        Self {
-            cache_path,
-            cache: None,
-        }
+    /// Number of duplicate message copies the most recent usage aggregate left out
+    pub fn duplicates_dropped(&self) -> usize {
+        self.duplicates_dropped.load(Ordering::Relaxed)
     }
 
-    /// Get pricing for a model, loading from cache or fetching if needed
-    #[allow(dead_code)]
-    pub async fn get_model_pricing(&mut self, model_name: &str) -> Result<Option<ModelPricing>> {
-        // Load cache if not already loaded
-        if self.cache.is_none() {
-            self.cache = PricingCache::load_from_file(&self.cache_path)?;
-        }
-
-        // If cache is empty or expired, fetch fresh data
-        if self.cache.is_none() {
-            match PricingCache::fetch_from_openrouter().await {
-                Ok(fresh_cache) => {
-                    fresh_cache.save_to_file(&self.cache_path)?;
-                    self.cache = Some(fresh_cache);
-                }
-                Err(_) => {
-                    // Fallback to hardcoded values if API fails
-                    return Ok(None);
-                }
-            }
-        }
-
-        if let Some(cache) = &self.cache {
-            // Try exact match first
-            if let Some(model) = cache.models.get(model_name) {
-                return Ok(Some(model.pricing.clone()));
-            }
-
-            // Try pattern matching for Claude models
-            for (key, model) in &cache.models {
-                if self.matches_claude_model(model_name, key) {
-                    return Ok(Some(model.pricing.clone()));
-                }
-            }
-        }
-
-        Ok(None)
+    /// Models priced from token counts so far, with the rates and source each resolved to
+    pub fn priced_models(&self) -> Vec<(String, ResolvedPricing)> {
+        let priced = self
+            .priced_models
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        priced
+            .keys()
+            .map(|model| (model.clone(), self.pricing.resolve(Some(model))))
+            .collect()
     }
 
-    /// Initialize cache by loading from file (synchronous)
-    pub fn init_cache(&mut self) -> Result<()> {
-        self.cache = PricingCache::load_from_file(&self.cache_path)?;
-        Ok(())
+    /// Why the user's pricing file was ignored, if it was
+    pub fn pricing_override_error(&self) -> Option<&str> {
+        self.pricing.override_error()
     }
 
-    /// Get pricing for a model (synchronous, uses existing cache)
-    pub fn get_model_pricing_sync(&self, model_name: &str) -> Option<ModelPricing> {
-        if let Some(cache) = &self.cache {
-            // Try exact match first
-            if let Some(model) = cache.models.get(model_name) {
-                return Some(model.pricing.clone());
-            }
+    /// Remember how many duplicates an aggregate dropped, for debug output
+    fn record_duplicates(&self, dedup: &UsageDeduplicator) {
+        self.duplicates_dropped
+            .store(dedup.duplicates(), Ordering::Relaxed);
+    }
 
-            // Try pattern matching for Claude models
-            for (key, model) in &cache.models {
-                if self.matches_claude_model(model_name, key) {
-                    return Some(model.pricing.clone());
-                }
-            }
-        }
+    /// Handle to the session ingestor, for managers created on other tasks
+    pub fn ingestor(&self) -> SharedIngestor {
+        Arc::clone(&self.ingestor)
+    }
 
-        None
+    /// Lock the session ingestor. A panic elsewhere cannot leave it half-updated,
+    /// so poisoning is ignored.
+    fn lock_ingestor(&self) -> MutexGuard<'_, SessionIngestor> {
+        self.ingestor
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
     }
 
-    /// Calculate cost using OpenRouter pricing or fallback to hardcoded (synchronous)
-    pub fn calculate_cost_sync(
+    /// Run `f` over a session file's ingested contents, parsing only lines appended since the last call
+    fn with_session_file<T>(
         &self,
-        model_name: Option<&str>,
-        input_tokens: u32,
-        output_tokens: u32,
-        cache_creation_tokens: u32,
-        cache_read_tokens: u32,
-    ) -> f64 {
-        if let Some(model) = model_name {
-            if let Some(pricing) = self.get_model_pricing_sync(model) {
-                // OpenRouter provides price per token, not per million tokens
-                let input_cost = (input_tokens as f64) * pricing.prompt;
-                let output_cost = (output_tokens as f64) * pricing.completion;
-
-                let cache_creation_cost =
-                    if let Some(cache_cost) = pricing.cache_creation_input_token_cost {
-                        (cache_creation_tokens as f64) * cache_cost
-                    } else {
-                        // Default: 25% markup over input cost
-                        (cache_creation_tokens as f64) * pricing.prompt * 1.25
-                    };
-
-                let cache_read_cost = if let Some(read_cost) = pricing.cache_read_input_token_cost {
-                    (cache_read_tokens as f64) * read_cost
-                } else {
-                    // Default: 90% discount from input cost
-                    (cache_read_tokens as f64) * pricing.prompt * 0.1
-                };
-
-                return input_cost + output_cost + cache_creation_cost + cache_read_cost;
-            }
-        }
-
-        // Fallback to hardcoded pricing
-        PricingConstants::calculate_cost(
-            model_name,
-            input_tokens,
-            output_tokens,
-            cache_creation_tokens,
-            cache_read_tokens,
-        )
+        file_path: &Path,
+        f: impl FnOnce(&IngestedFile) -> T,
+    ) -> Result<T> {
+        let mut ingestor = self.lock_ingestor();
+        let file = ingestor.refresh_file(file_path)?;
+        Ok(f(file))
     }
 
-    /// Calculate cost using OpenRouter pricing or fallback to hardcoded
-    #[allow(dead_code)]
-    pub async fn calculate_cost(
-        &mut self,
-        model_name: Option<&str>,
-        input_tokens: u32,
-        output_tokens: u32,
-        cache_creation_tokens: u32,
-        cache_read_tokens: u32,
-    ) -> f64 {
-        if let Some(model) = model_name {
-            if let Ok(Some(pricing)) = self.get_model_pricing(model).await {
-                let input_cost = (input_tokens as f64) * pricing.prompt;
-                let output_cost = (output_tokens as f64) * pricing.completion;
-
-                let cache_creation_cost =
-                    if let Some(cache_cost) = pricing.cache_creation_input_token_cost {
-                        (cache_creation_tokens as f64) * cache_cost
-                    } else {
-                        // Default: 25% markup over input cost
-                        (cache_creation_tokens as f64) * pricing.prompt * 1.25
-                    };
-
-                let cache_read_cost = if let Some(read_cost) = pricing.cache_read_input_token_cost {
-                    (cache_read_tokens as f64) * read_cost
-                } else {
-                    // Default: 90% discount from input cost
-                    (cache_read_tokens as f64) * pricing.prompt * 0.1
-                };
+    /// Run `f` over a session file's entries, parsing only lines appended since the last call
+    fn with_session_entries<T>(
+        &self,
+        file_path: &Path,
+        f: impl FnOnce(&[UsageEntry]) -> T,
+    ) -> Result<T> {
+        self.with_session_file(file_path, |file| f(&file.entries))
+    }
 
-                return input_cost + output_cost + cache_creation_cost + cache_read_cost;
-            }
-        }
+    /// Write the usage index if it changed
+    pub fn save_index(&self) -> Result<()> {
+        self.lock_ingestor().save()
+    }
 
-        // Fallback to hardcoded pricing
-        PricingConstants::calculate_cost(
-            model_name,
-            input_tokens,
-            output_tokens,
-            cache_creation_tokens,
-            cache_read_tokens,
-        )
+    /// Write the usage index if it changed and was not written recently
+    pub fn save_index_if_due(&self) -> Result<()> {
+        self.lock_ingestor().save_if_due()
     }
 
-    /// Check if a model name matches a cached Claude model
-    fn matches_claude_model(&self, requested: &str, cached: &str) -> bool {
-        let requested_lower = requested.to_lowercase();
-        let cached_lower = cached.to_lowercase();
+    /// Discard the usage index and re-parse every session file in every data directory.
+    /// Returns the number of files and entries indexed.
+    pub fn rebuild_index(&self) -> Result<(usize, usize)> {
+        let session_files = self.session_files()?;
 
-        // Direct match
-        if requested_lower == cached_lower {
-            return true;
-        }
+        let mut ingestor = self.lock_ingestor();
+        ingestor.clear();
+        // Unreadable files are skipped here just as they are during normal scans
+        ingestor.refresh_files(&session_files, &scan::no_progress);
+        ingestor.save()?;
 
-        // Check for Claude model variants
-        if requested_lower.contains("claude") && cached_lower.contains("claude") {
-            // Extract model type (sonnet, opus, haiku)
-            let requested_type = if requested_lower.contains("sonnet") {
-                "sonnet"
-            } else if requested_lower.contains("opus") {
-                "opus"
-            } else if requested_lower.contains("haiku") {
-                "haiku"
-            } else {
-                return false;
-            };
-
-            cached_lower.contains(requested_type)
-        } else {
-            false
-        }
+        Ok(ingestor.totals())
     }
-}
-
-/// Claude API pricing constants (per individual token)
-/// Used as fallback when OpenRouter API is unavailable
-/// Pricing based on official API documentation and market analysis
-/// and OpenRouter API data as of January 2025
-pub struct PricingConstants;
-
-impl PricingConstants {
-    // Claude 3.5 Sonnet pricing - per individual token
-    // Based on $3.00 input / $15.00 output per 1M tokens (from API documentation)
-    pub const SONNET_INPUT_COST: f64 = 0.000003; // $3.00 / 1M tokens
-    pub const SONNET_OUTPUT_COST: f64 = 0.000015; // $15.00 / 1M tokens
-    pub const SONNET_CACHE_CREATION_COST: f64 = 0.00000375; // 25% markup on input
-    pub const SONNET_CACHE_READ_COST: f64 = 0.0000003; // 90% discount on input
-
-    // Claude 3 Opus pricing - per individual token
-    // Based on $15.00 input / $75.00 output per 1M tokens (from API documentation)
-    pub const OPUS_INPUT_COST: f64 = 0.000015; // $15.00 / 1M tokens
-    pub const OPUS_OUTPUT_COST: f64 = 0.000075; // $75.00 / 1M tokens
-    pub const OPUS_CACHE_CREATION_COST: f64 = 0.00001875; // 25% markup on input
-    pub const OPUS_CACHE_READ_COST: f64 = 0.0000015; // 90% discount on input
-
-    // Claude 3 Haiku pricing - per individual token
-    // Based on $0.25 input / $1.25 output per 1M tokens (from API documentation)
-    pub const HAIKU_INPUT_COST: f64 = 0.00000025; // $0.25 / 1M tokens
-    pub const HAIKU_OUTPUT_COST: f64 = 0.00000125; // $1.25 / 1M tokens
-    pub const HAIKU_CACHE_CREATION_COST: f64 = 0.0000003125; // 25% markup on input
-    pub const HAIKU_CACHE_READ_COST: f64 = 0.000000025; // 90% discount on input
-
-    /// Calculate cost based on model name and token usage
-    /// Returns cost in USD with improved accuracy based on API specifications
-    pub fn calculate_cost(
-        model_name: Option<&str>,
-        input_tokens: u32,
-        output_tokens: u32,
-        cache_creation_tokens: u32,
-        cache_read_tokens: u32,
-    ) -> f64 {
-        let (input_cost, output_cost, cache_creation_cost, cache_read_cost) =
-            Self::get_pricing_for_model(model_name);
 
-        let input_cost_usd = (input_tokens as f64) * input_cost;
-        let output_cost_usd = (output_tokens as f64) * output_cost;
-        let cache_creation_cost_usd = (cache_creation_tokens as f64) * cache_creation_cost;
-        let cache_read_cost_usd = (cache_read_tokens as f64) * cache_read_cost;
+    /// Discard the search index and re-read every session file into it.
+    /// Returns the number of files and messages indexed.
+    pub fn rebuild_search_index(&self, index: &mut SearchIndex) -> Result<(usize, usize)> {
+        let session_files = self.session_files()?;
 
-        let total =
-            input_cost_usd + output_cost_usd + cache_creation_cost_usd + cache_read_cost_usd;
+        index.clear();
+        index.refresh_files(&session_files, &scan::no_progress);
+        index.save()?;
 
-        // Round to reasonable precision (6 decimal places) to avoid floating-point artifacts
-        // Use 6 decimal places precision to avoid floating-point artifacts
-        (total * 1_000_000.0).round() / 1_000_000.0
+        Ok(index.totals())
     }
 
-    /// Get pricing constants for a specific model
-    /// Returns (input_cost, output_cost, cache_creation_cost, cache_read_cost) per million tokens
-    fn get_pricing_for_model(model_name: Option<&str>) -> (f64, f64, f64, f64) {
-        let model = model_name.unwrap_or("").to_lowercase();
+    /// Parse every session file that changed since the last refresh, in parallel.
+    /// `on_progress` is told how many of the changed files have been read.
+    pub fn refresh_sessions(&self, on_progress: &(dyn Fn(ScanProgress) + Sync)) -> Result<()> {
+        let session_files = self.session_files()?;
+        let mut ingestor = self.lock_ingestor();
+        ingestor.refresh_files(&session_files, on_progress);
+        // Files deleted since the last full scan no longer need their entries
+        ingestor.forget_missing();
+        Ok(())
+    }
 
-        if model.contains("opus") || model.contains("claude-4-opus") {
-            (
-                Self::OPUS_INPUT_COST,
-                Self::OPUS_OUTPUT_COST,
-                Self::OPUS_CACHE_CREATION_COST,
-                Self::OPUS_CACHE_READ_COST,
-            )
-        } else if model.contains("haiku") || model.contains("claude-3-haiku") {
-            (
-                Self::HAIKU_INPUT_COST,
-                Self::HAIKU_OUTPUT_COST,
-                Self::HAIKU_CACHE_CREATION_COST,
-                Self::HAIKU_CACHE_READ_COST,
-            )
-        } else {
-            // Default to Sonnet (most common model)
-            (
-                Self::SONNET_INPUT_COST,
-                Self::SONNET_OUTPUT_COST,
-                Self::SONNET_CACHE_CREATION_COST,
-                Self::SONNET_CACHE_READ_COST,
-            )
+    /// Every session file in every project directory
+    fn session_files(&self) -> Result<Vec<PathBuf>> {
+        let mut session_files = Vec::new();
+        for project_path in self.project_entries()? {
+            if !project_path.is_dir() {
+                continue;
+            }
+            for entry in fs::read_dir(&project_path)? {
+                let path = entry?.path();
+                if path.extension().and_then(|s| s.to_str()) == Some("jsonl") {
+                    session_files.push(path);
+                }
+            }
         }
+        Ok(session_files)
     }
-}
-
-// Project, Session, and UsageStats types moved to respective modules
-
-// SessionBlock type moved to usage_calculator.rs module
-
-// ProjectAnalytics moved to usage_calculator.rs module
-
-/// Daily usage breakdown
-#[derive(Debug, Clone)]
-pub struct DailyUsage {
-    #[allow(dead_code)]
-    pub date: chrono::NaiveDate,
-    #[allow(dead_code)]
-    pub usage_stats: UsageStats,
-}
-
-/// Model usage statistics
-#[derive(Debug, Clone)]
-#[allow(dead_code)]
-pub struct ModelUsage {
-    pub model_name: String,
-    pub usage_stats: UsageStats,
-}
 
-/// Claude data manager
-pub struct ClaudeDataManager {
-    claude_dir: PathBuf,
-    openrouter_pricing: OpenRouterPricing,
-}
+    /// List the entries under every data directory's `projects/` folder.
+    /// Missing roots are skipped so a stale config entry does not hide the others.
+    fn project_entries(&self) -> Result<Vec<PathBuf>> {
+        let mut entries = Vec::new();
 
-impl ClaudeDataManager {
-    /// Create a new Claude data manager
-    pub fn new() -> Result<Self> {
-        let home_dir =
-            dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?;
-        let claude_dir = home_dir.join(".claude");
-        let mut openrouter_pricing = OpenRouterPricing::new(&claude_dir);
-
-        // Initialize cache from file (ignore errors, fallback will be used)
-        let _ = openrouter_pricing.init_cache();
-
-        Ok(Self {
-            claude_dir,
-            openrouter_pricing,
-        })
-    }
-
-    /// Update OpenRouter pricing cache in background (if needed)
-    pub async fn update_pricing_cache_if_needed(&mut self) -> Result<()> {
-        // Check if cache needs update
-        if let Ok(Some(cache)) = PricingCache::load_from_file(&self.openrouter_pricing.cache_path) {
-            if !cache.is_expired() {
-                // Cache is still valid
-                return Ok(());
+        for root in &self.claude_dirs {
+            let projects_dir = root.join("projects");
+            if !projects_dir.exists() {
+                continue;
             }
-        }
 
-        // Cache is expired or missing, fetch new data
-        match PricingCache::fetch_from_openrouter().await {
-            Ok(fresh_cache) => {
-                fresh_cache.save_to_file(&self.openrouter_pricing.cache_path)?;
-                self.openrouter_pricing.cache = Some(fresh_cache);
-                println!("Updated OpenRouter pricing cache");
-            }
-            Err(e) => {
-                eprintln!("Failed to update OpenRouter pricing cache: {e}");
-                // Continue with existing cache or fallback pricing
+            for entry in fs::read_dir(&projects_dir)? {
+                entries.push(entry?.path());
             }
         }
 
-        Ok(())
+        Ok(entries)
+    }
+
+    /// Fetch prices from the configured providers whose cached prices have expired
+    pub async fn update_pricing_cache_if_needed(&mut self) -> Result<()> {
+        self.pricing.refresh_if_needed().await
     }
 
-    /// Scan for all projects in the Claude directory
+    /// Scan for all projects in every Claude data directory
     pub fn scan_projects(&self) -> Result<Vec<Project>> {
-        let projects_dir = self.claude_dir.join("projects");
+        self.scan_projects_with_progress(&scan::no_progress)
+    }
 
-        if !projects_dir.exists() {
-            return Ok(Vec::new());
-        }
+    /// Scan for all projects, reporting progress while changed session files are parsed
+    pub fn scan_projects_with_progress(
+        &self,
+        on_progress: &(dyn Fn(ScanProgress) + Sync),
+    ) -> Result<Vec<Project>> {
+        // Parse changed files up front on the worker pool; the per-project pass
+        // below then only reads already ingested state
+        self.refresh_sessions(on_progress)?;
 
         let mut projects = Vec::new();
-        let entries = fs::read_dir(&projects_dir)?;
-
-        for entry in entries {
-            let entry = entry?;
-            let path = entry.path();
 
+        for path in self.project_entries()? {
             if path.is_dir() {
                 if let Some(project) = self.parse_project(&path)? {
                     projects.push(project);
@@ -688,26 +422,25 @@ impl ClaudeDataManager {
             }
         }
 
-        // Sort by most recently active
-        projects.sort_by(|a, b| {
-            let a_last = a
-                .sessions
-                .iter()
-                .map(|s| s.last_modified)
-                .max()
-                .unwrap_or(std::time::UNIX_EPOCH);
-            let b_last = b
-                .sessions
-                .iter()
-                .map(|s| s.last_modified)
-                .max()
-                .unwrap_or(std::time::UNIX_EPOCH);
-            b_last.cmp(&a_last)
-        });
+        Self::sort_projects_by_activity(&mut projects);
 
         Ok(projects)
     }
 
+    /// Sort projects by most recently active session, newest first
+    pub fn sort_projects_by_activity(projects: &mut [Project]) {
+        projects.sort_by_key(|project| {
+            std::cmp::Reverse(
+                project
+                    .sessions
+                    .iter()
+                    .map(|s| s.last_modified)
+                    .max()
+                    .unwrap_or(std::time::UNIX_EPOCH),
+            )
+        });
+    }
+
     /// Parse a project directory
     fn parse_project(&self, project_dir_path: &Path) -> Result<Option<Project>> {
         let sanitized_name = project_dir_path
@@ -717,51 +450,16 @@ impl ClaudeDataManager {
                 anyhow::anyhow!("Invalid project directory path: {:?}", project_dir_path)
             })?;
 
-        // Try to get the real path from the most recent JSONL file's "cwd" field
-        let (original_path, is_orphaned) =
-            match self.get_project_path_from_recent_session(project_dir_path) {
-                Ok(cwd_path) => {
-                    if PathBuf::from(&cwd_path).exists() {
-                        (PathBuf::from(cwd_path), false)
-                    } else {
-                        // CWD exists in JSONL but directory doesn't exist anymore
-                        (PathBuf::from(format!("Orphaned: {cwd_path}")), true)
-                    }
-                }
-                Err(_) => {
-                    // Fallback to old reconstruction method
-                    match self.reconstruct_path_from_sanitized_name(sanitized_name) {
-                        Ok(p) => {
-                            if p.exists() {
-                                (p, false)
-                            } else {
-                                (PathBuf::from(format!("Orphaned: {sanitized_name}")), true)
-                            }
-                        }
-                        Err(_) => (PathBuf::from(format!("Unknown: {sanitized_name}")), true),
-                    }
-                }
-            };
-
-        let project_name = if is_orphaned {
-            // For orphaned/unknown projects, use a cleaner name
-            if let Some(last_part) = sanitized_name.split('-').next_back() {
-                if last_part.is_empty() {
-                    sanitized_name.replace('-', "/")
-                } else {
-                    last_part.to_string()
-                }
-            } else {
-                sanitized_name.to_string()
-            }
-        } else {
-            original_path
-                .file_name()
-                .and_then(|s| s.to_str())
-                .unwrap_or("unknown")
-                .to_string()
+        let resolved = self.resolve_project_path(project_dir_path);
+        let (original_path, is_orphaned) = match &resolved {
+            Some(path) if path.exists() => (path.clone(), false),
+            // The recorded directory has since been deleted or moved
+            Some(path) => (PathBuf::from(format!("Orphaned: {}", path.display())), true),
+            None => (PathBuf::from(format!("Orphaned: {sanitized_name}")), true),
         };
 
+        let project_name = path_resolver::project_name(sanitized_name, resolved.as_deref());
+
         let mut sessions = Vec::new();
         let mut session_files = Vec::new();
 
@@ -781,7 +479,7 @@ impl ClaudeDataManager {
         }
 
         // Sort by modification time (newest first) and take only the most recent ones
-        session_files.sort_by(|a, b| b.1.cmp(&a.1));
+        session_files.sort_by_key(|f| std::cmp::Reverse(f.1));
 
         // Only process the 3 most recent sessions for better performance
         for (path, _) in session_files.into_iter().take(3) {
@@ -809,6 +507,8 @@ impl ClaudeDataManager {
         Ok(Some(Project {
             name: project_name,
             path: original_path,
+            data_dir: project_dir_path.to_path_buf(),
+            root: crate::claude::project_scanner::data_root(project_dir_path),
             sessions,
             is_active,
         }))
@@ -819,11 +519,7 @@ impl ClaudeDataManager {
         let metadata = fs::metadata(session_path)?;
         let last_modified = metadata.modified()?;
 
-        let content = fs::read_to_string(session_path)?;
-        let message_count = content
-            .lines()
-            .filter(|line| !line.trim().is_empty())
-            .count();
+        let message_count = self.with_session_file(session_path, |file| file.line_count)?;
 
         if message_count == 0 {
             return Ok(None);
@@ -867,110 +563,121 @@ impl ClaudeDataManager {
         }
 
         // Sort by modification time (newest first)
-        jsonl_files.sort_by(|a, b| b.1.cmp(&a.1));
+        jsonl_files.sort_by_key(|f| std::cmp::Reverse(f.1));
 
-        // Read the most recent JSONL file and find the last line with "cwd"
+        // The ingestor remembers the last "cwd" seen in the most recent JSONL file
         let most_recent_file = &jsonl_files[0].0;
-        let content = std::fs::read_to_string(most_recent_file)?;
-
-        // Read lines from the end to find the most recent "cwd"
-        for line in content.lines().rev() {
-            if let Ok(json_value) = serde_json::from_str::<serde_json::Value>(line) {
-                if let Some(cwd) = json_value.get("cwd") {
-                    if let Some(cwd_str) = cwd.as_str() {
-                        return Ok(cwd_str.to_string());
-                    }
-                }
-            }
-        }
-
-        Err(anyhow::anyhow!("No 'cwd' field found in JSONL file"))
+        self.with_session_file(most_recent_file, |file| file.last_cwd.clone())?
+            .ok_or_else(|| anyhow::anyhow!("No 'cwd' field found in JSONL file"))
     }
 
-    /// Reconstruct the original filesystem path from Claude's sanitized directory name.
-    /// e.g., "-Users-user-dev-project" -> "/Users/user/dev/project"
-    fn reconstruct_path_from_sanitized_name(&self, sanitized_name: &str) -> Result<PathBuf> {
-        if !sanitized_name.starts_with('-') {
-            return Err(anyhow::anyhow!(
-                "Cannot reconstruct path from non-standard name"
-            ));
-        }
+    /// Resolve the directory a project data directory was created for: the most recent
+    /// session's "cwd", otherwise whichever reading of the hyphens in its name exists
+    fn resolve_project_path(&self, project_dir_path: &Path) -> Option<PathBuf> {
+        path_resolver::resolve_project_path(project_dir_path, || {
+            self.get_project_path_from_recent_session(project_dir_path)
+                .ok()
+        })
+    }
 
-        // Drop the leading '-' and replace remaining '-' with '/'
-        let path_str: String = sanitized_name
-            .chars()
-            .skip(1)
-            .map(|c| if c == '-' { '/' } else { c })
-            .collect();
+    /// Find the project data directory for a working directory (or one of its parents).
+    /// Claude names these directories by replacing every non-alphanumeric character with '-'.
+    pub fn project_dir_for_path(&self, path: &Path) -> Option<PathBuf> {
+        path.ancestors().find_map(|ancestor| {
+            let sanitized = path_resolver::sanitize_path(ancestor);
+            self.claude_dirs
+                .iter()
+                .map(|root| root.join("projects").join(&sanitized))
+                .find(|candidate| candidate.is_dir())
+        })
+    }
 
-        // Create absolute path
-        let reconstructed_path = PathBuf::from(format!("/{path_str}"));
+    /// Load a single project from its data directory
+    pub fn load_project(&self, project_dir_path: &Path) -> Result<Option<Project>> {
+        self.parse_project(project_dir_path)
+    }
 
-        // Debug: Check if path exists with proper error handling
-        if reconstructed_path.exists() {
-            Ok(reconstructed_path)
-        } else {
-            // Try alternative reconstruction - sometimes Claude uses different encodings
-            // Handle special case where the path might have different casing or symlinks
-            let canonical_path = if let Ok(canonical) = reconstructed_path.canonicalize() {
-                canonical
-            } else if reconstructed_path
-                .parent()
-                .map(|p| p.exists())
-                .unwrap_or(false)
-            {
-                // Parent exists, but this specific directory doesn't - could be deleted
-                reconstructed_path
-            } else {
-                // Try resolving symlinks in the path
-                let resolved = std::fs::read_link(&reconstructed_path).or_else(|_| {
-                    // If not a symlink, check if it's a case sensitivity issue on macOS
-                    if cfg!(target_os = "macos") {
-                        // On macOS, try lowercase version
-                        let lowercase_path = PathBuf::from(format!("/{}", path_str.to_lowercase()));
-                        if lowercase_path.exists() {
-                            Ok(lowercase_path)
-                        } else {
-                            Err(anyhow::anyhow!("Path does not exist"))
-                        }
-                    } else {
-                        Err(anyhow::anyhow!("Path does not exist"))
-                    }
-                })?;
-                resolved
-            };
+    /// Get the model used by the most recent assistant message in a session file,
+    /// from the ingested entries so only newly appended lines are read
+    pub fn last_model_used(&self, session_path: &Path) -> Option<String> {
+        self.with_session_entries(session_path, |entries| {
+            entries.iter().rev().find_map(|entry| {
+                entry
+                    .usage
+                    .as_ref()?
+                    .model
+                    .clone()
+                    .filter(|model| !model.starts_with('<'))
+            })
+        })
+        .ok()
+        .flatten()
+    }
 
-            Ok(canonical_path)
+    /// Read a session's transcript, with the cost of each assistant response
+    pub fn session_transcript(&self, session_path: &Path) -> Result<Vec<TranscriptMessage>> {
+        let mut messages = transcript::read_transcript(session_path)?;
+        for message in &mut messages {
+            if let Some(entry) = &message.usage {
+                let mut stats = UsageStats::default();
+                self.add_entry_usage(
+                    entry.usage.as_ref(),
+                    Some(entry.time),
+                    entry.cost_usd,
+                    &mut stats,
+                );
+                message.cost = stats.total_cost;
+            }
         }
+        Ok(messages)
+    }
+
+    /// Search the prompts and replies of every session file. `projects` names the
+    /// sessions and resolves the query's project filter.
+    pub fn search_sessions(
+        &self,
+        index: &mut SearchIndex,
+        projects: &[Project],
+        query: &SearchQuery,
+        on_progress: &(dyn Fn(ScanProgress) + Sync),
+    ) -> Result<Vec<SearchHit>> {
+        search::search(index, &self.session_files()?, projects, query, on_progress)
     }
 
     /// Calculate today's usage statistics
     pub fn calculate_today_usage(&self) -> Result<UsageStats> {
         let mut stats = UsageStats::default();
+        let mut dedup = UsageDeduplicator::default();
         let today = chrono::Local::now().date_naive();
 
-        let projects_dir = self.claude_dir.join("projects");
-        if !projects_dir.exists() {
-            return Ok(stats);
-        }
+        // Files untouched since local midnight cannot contain today's entries
+        let start_of_today = today
+            .and_hms_opt(0, 0, 0)
+            .and_then(|midnight| midnight.and_local_timezone(chrono::Local).earliest())
+            .map(std::time::SystemTime::from);
 
         // Scan all JSONL files for today's data
-        for entry in std::fs::read_dir(&projects_dir)? {
-            let entry = entry?;
-            let project_path = entry.path();
-
+        for project_path in self.project_entries()? {
             if project_path.is_dir() {
                 for file_entry in std::fs::read_dir(&project_path)? {
                     let file_entry = file_entry?;
                     let file_path = file_entry.path();
 
                     if file_path.extension().and_then(|s| s.to_str()) == Some("jsonl") {
-                        self.process_jsonl_for_usage(&file_path, &mut stats, today)?;
+                        let modified = file_entry.metadata().and_then(|m| m.modified()).ok();
+                        if let (Some(modified), Some(start)) = (modified, start_of_today) {
+                            if modified < start {
+                                continue;
+                            }
+                        }
+
+                        self.process_jsonl_for_usage(&file_path, &mut stats, today, &mut dedup)?;
                     }
                 }
             }
         }
 
+        self.record_duplicates(&dedup);
         Ok(stats)
     }
 
@@ -980,30 +687,15 @@ impl ClaudeDataManager {
         file_path: &Path,
         stats: &mut UsageStats,
         target_date: chrono::NaiveDate,
+        dedup: &mut UsageDeduplicator,
     ) -> Result<()> {
-        let content = std::fs::read_to_string(file_path)?;
-
-        for line in content.lines() {
-            let line = line.trim();
-            if line.is_empty() {
-                continue;
-            }
-
-            if let Ok(entry) = serde_json::from_str::<Value>(line) {
-                // Check if this entry is from today
-                if let Some(timestamp_str) = entry.get("timestamp").and_then(|v| v.as_str()) {
-                    if let Ok(datetime) = chrono::DateTime::parse_from_rfc3339(timestamp_str) {
-                        let entry_date = datetime.with_timezone(&chrono::Local).date_naive();
-
-                        if entry_date == target_date {
-                            self.extract_usage_from_entry(&entry, stats)?;
-                        }
-                    }
+        self.with_session_entries(file_path, |entries| {
+            for entry in entries {
+                if entry.local_date() == target_date {
+                    self.add_unique_entry_usage(entry, dedup, stats);
                 }
             }
-        }
-
-        Ok(())
+        })
     }
 
     /// Process a JSONL file for usage statistics for multiple dates in single pass (optimized)
@@ -1013,6 +705,7 @@ impl ClaudeDataManager {
         file_path: &Path,
         daily_stats: &mut std::collections::HashMap<chrono::NaiveDate, UsageStats>,
         target_dates: &[chrono::NaiveDate],
+        dedup: &mut UsageDeduplicator,
     ) -> Result<()> {
         // Early return if no target dates
         if target_dates.is_empty() {
@@ -1023,113 +716,91 @@ impl ClaudeDataManager {
         let target_set: std::collections::HashSet<chrono::NaiveDate> =
             target_dates.iter().cloned().collect();
 
-        let content = std::fs::read_to_string(file_path)?;
-
-        for line in content.lines() {
-            let line = line.trim();
-            if line.is_empty() {
-                continue;
-            }
+        self.with_session_entries(file_path, |entries| {
+            for entry in entries {
+                let entry_date = entry.local_date();
 
-            if let Ok(entry) = serde_json::from_str::<Value>(line) {
-                // Check if this entry is from any of our target dates
-                if let Some(timestamp_str) = entry.get("timestamp").and_then(|v| v.as_str()) {
-                    if let Ok(datetime) = chrono::DateTime::parse_from_rfc3339(timestamp_str) {
-                        let entry_date = datetime.with_timezone(&chrono::Local).date_naive();
-
-                        // Only process if this date is in our target set
-                        if target_set.contains(&entry_date) {
-                            if let Some(stats) = daily_stats.get_mut(&entry_date) {
-                                self.extract_usage_from_entry(&entry, stats)?;
-                            }
-                        }
+                // Only process if this date is in our target set
+                if target_set.contains(&entry_date) {
+                    if let Some(stats) = daily_stats.get_mut(&entry_date) {
+                        self.add_unique_entry_usage(entry, dedup, stats);
                     }
                 }
             }
-        }
-
-        Ok(())
+        })
     }
 
     /// Extract usage data from a single JSONL entry
+    #[allow(dead_code)]
     fn extract_usage_from_entry(&self, entry: &Value, stats: &mut UsageStats) -> Result<()> {
-        let mut current_input_tokens = 0u32;
-        let mut current_output_tokens = 0u32;
-        let mut current_cache_creation_tokens = 0u32;
-        let mut current_cache_read_tokens = 0u32;
-
-        // Extract usage data from message field
-        if let Some(message) = entry.get("message") {
-            if let Some(usage) = message.get("usage") {
-                if let Some(input_tokens) = usage.get("input_tokens").and_then(|v| v.as_u64()) {
-                    current_input_tokens = input_tokens as u32;
-                    stats.input_tokens += current_input_tokens;
-                }
-                if let Some(output_tokens) = usage.get("output_tokens").and_then(|v| v.as_u64()) {
-                    current_output_tokens = output_tokens as u32;
-                    stats.output_tokens += current_output_tokens;
-                }
-                if let Some(cache_creation) = usage
-                    .get("cache_creation_input_tokens")
-                    .and_then(|v| v.as_u64())
-                {
-                    current_cache_creation_tokens = cache_creation as u32;
-                    stats.cache_creation_tokens += current_cache_creation_tokens;
-                }
-                if let Some(cache_read) = usage
-                    .get("cache_read_input_tokens")
-                    .and_then(|v| v.as_u64())
-                {
-                    current_cache_read_tokens = cache_read as u32;
-                    stats.cache_read_tokens += current_cache_read_tokens;
-                }
+        let usage = UsageRecord::from_value(entry);
+        let time = usage
+            .as_ref()
+            .and_then(|u| DateTime::parse_from_rfc3339(&u.timestamp).ok())
+            .map(|dt| dt.with_timezone(&Utc));
+        self.add_entry_usage(usage.as_ref(), time, ingest::cost_usd(entry), stats);
+        Ok(())
+    }
 
-                stats.message_count += 1;
-            }
+    /// Add an entry's usage to `stats` unless a copy of it was already counted
+    fn add_unique_entry_usage(
+        &self,
+        entry: &UsageEntry,
+        dedup: &mut UsageDeduplicator,
+        stats: &mut UsageStats,
+    ) {
+        if dedup.first_seen(entry.dedup_key()) {
+            self.add_entry_usage(
+                entry.usage.as_ref(),
+                Some(entry.time),
+                entry.cost_usd,
+                stats,
+            );
+        }
+    }
+
+    /// Add one entry's tokens and cost to `stats`, pricing tokens at the entry's `time`
+    fn add_entry_usage(
+        &self,
+        usage: Option<&UsageRecord>,
+        time: Option<DateTime<Utc>>,
+        cost_usd: Option<f64>,
+        stats: &mut UsageStats,
+    ) {
+        if let Some(usage) = usage {
+            stats.add_tokens(
+                usage.input_tokens,
+                usage.output_tokens,
+                usage.cache_creation_tokens,
+                usage.cache_read_tokens,
+            );
+            stats.message_count += 1;
         }
 
-        // Extract cost data if available, otherwise calculate from tokens
-        if let Some(cost_usd) = entry.get("costUSD").and_then(|v| v.as_f64()) {
-            if cost_usd > 0.0 {
-                // Use actual cost from API users
+        // Use costUSD when present (API users), otherwise calculate from tokens (subscription users)
+        match cost_usd {
+            Some(cost_usd) if cost_usd > 0.0 => {
                 stats.total_cost += cost_usd;
                 stats.is_subscription_user = false;
-            } else {
-                // Fallback calculation for subscription users
-                let model_name = entry
-                    .get("message")
-                    .and_then(|m| m.get("model"))
-                    .and_then(|v| v.as_str());
-
-                let calculated_cost = self.openrouter_pricing.calculate_cost_sync(
-                    model_name,
-                    current_input_tokens,
-                    current_output_tokens,
-                    current_cache_creation_tokens,
-                    current_cache_read_tokens,
-                );
+            }
+            _ => {
+                let model_name = usage.and_then(|u| u.model.as_deref());
+                let tokens = usage.map(UsageRecord::request_tokens).unwrap_or_default();
+                let (calculated_cost, source) =
+                    self.pricing.calculate_cost_sync(model_name, time, &tokens);
+                if let Some(model) = model_name {
+                    let mut priced = self
+                        .priced_models
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner());
+                    if !priced.contains_key(model) {
+                        priced.insert(model.to_string(), source);
+                    }
+                }
                 stats.total_cost += calculated_cost;
                 stats.is_subscription_user = true;
             }
-        } else {
-            // No costUSD field - calculate from tokens (subscription users)
-            let model_name = entry
-                .get("message")
-                .and_then(|m| m.get("model"))
-                .and_then(|v| v.as_str());
-
-            let calculated_cost = self.openrouter_pricing.calculate_cost_sync(
-                model_name,
-                current_input_tokens,
-                current_output_tokens,
-                current_cache_creation_tokens,
-                current_cache_read_tokens,
-            );
-            stats.total_cost += calculated_cost;
-            stats.is_subscription_user = true;
         }
-
-        Ok(())
     }
 
     /// Round timestamp to the nearest hour for block calculations
@@ -1160,103 +831,153 @@ impl ClaudeDataManager {
     /// Find the end time of the currently active 5-hour block
     /// Implementation approach: create time blocks from session entries, then find active ones
     pub fn find_active_block_end_time(&self) -> Option<std::time::SystemTime> {
-        let projects_dir = self.claude_dir.join("projects");
-        if !projects_dir.exists() {
-            return None;
-        }
+        self.find_active_block_end_time_within(None)
+    }
 
-        let now = std::time::SystemTime::now();
-        let mut all_entries = Vec::new();
+    /// Find the end time of the active block, only looking at entries from the last `lookback`.
+    /// Skipping older files keeps this cheap enough for frequently polled callers.
+    pub fn find_active_block_end_time_within(
+        &self,
+        lookback: Option<std::time::Duration>,
+    ) -> Option<std::time::SystemTime> {
+        let entries = self.block_entries_within(lookback)?;
+        let blocks = blocks::segment_blocks(entries);
+        blocks::active_block(&blocks, Utc::now()).map(|block| block.end.into())
+    }
 
-        // Collect all entries with timestamps
-        for entry in std::fs::read_dir(&projects_dir).ok()? {
-            let entry = entry.ok()?;
-            let project_path = entry.path();
+    /// Usage of the active block and the past week against the configured plan,
+    /// with the block limit inferred from recent history and the spend against budgets
+    pub fn quota_status(&self) -> QuotaStatus {
+        // Enough history for the inferred limit and for the billing month's budget
+        let days = quota::INFERENCE_DAYS.max(31) as u64;
+        let history = std::time::Duration::from_secs(days * 24 * 3600);
+        let entries = self.block_entries_within(Some(history)).unwrap_or_default();
+        QuotaStatus::compute(
+            &entries,
+            &self.quota_settings,
+            &self.period_settings,
+            Utc::now(),
+        )
+    }
 
-            if project_path.is_dir() {
-                for file_entry in std::fs::read_dir(&project_path).ok()? {
-                    let file_entry = file_entry.ok()?;
-                    let file_path = file_entry.path();
+    /// Every 5-hour block in the session history, oldest first, with its cost,
+    /// models and projects; the active block carries a projection
+    pub fn usage_blocks(&self) -> Result<Vec<BlockSummary>> {
+        let mut dedup = UsageDeduplicator::default();
+        let mut timeline = Vec::new();
 
-                    if file_path.extension().and_then(|s| s.to_str()) == Some("jsonl") {
-                        if let Ok(content) = std::fs::read_to_string(&file_path) {
-                            for line in content.lines() {
-                                if let Ok(json_value) =
-                                    serde_json::from_str::<serde_json::Value>(line)
-                                {
-                                    if let Some(timestamp_str) =
-                                        json_value.get("timestamp").and_then(|v| v.as_str())
-                                    {
-                                        if let Ok(dt) =
-                                            chrono::DateTime::parse_from_rfc3339(timestamp_str)
-                                        {
-                                            let entry_time = std::time::SystemTime::UNIX_EPOCH
-                                                + std::time::Duration::from_secs(
-                                                    dt.timestamp() as u64
-                                                );
-                                            all_entries.push(entry_time);
-                                        }
-                                    }
-                                }
-                            }
+        for project_path in self.project_entries()? {
+            if !project_path.is_dir() {
+                continue;
+            }
+            let project = project_path
+                .file_name()
+                .and_then(|s| s.to_str())
+                .map(|sanitized| {
+                    path_resolver::project_name(
+                        sanitized,
+                        self.resolve_project_path(&project_path).as_deref(),
+                    )
+                })
+                .unwrap_or_default();
+
+            for file_entry in fs::read_dir(&project_path)? {
+                let file_path = file_entry?.path();
+                if file_path.extension().and_then(|s| s.to_str()) != Some("jsonl") {
+                    continue;
+                }
+
+                self.with_session_entries(&file_path, |entries| {
+                    for entry in entries {
+                        let mut stats = UsageStats::default();
+                        let usage = entry
+                            .usage
+                            .as_ref()
+                            .filter(|_| dedup.first_seen(entry.dedup_key()));
+                        if let Some(usage) = usage {
+                            self.add_entry_usage(
+                                Some(usage),
+                                Some(entry.time),
+                                entry.cost_usd,
+                                &mut stats,
+                            );
                         }
+                        timeline.push(TimelineEntry {
+                            time: entry.time,
+                            tokens: stats.input_output_tokens(),
+                            cost: stats.total_cost,
+                            model: usage.and_then(|u| u.model.clone()),
+                            project: project.clone(),
+                        });
                     }
-                }
+                })?;
             }
         }
 
-        if all_entries.is_empty() {
-            return None;
-        }
-
-        // Sort entries by time for chronological processing
-        all_entries.sort();
+        self.record_duplicates(&dedup);
+        Ok(blocks::summarize_blocks(timeline, Utc::now()))
+    }
 
-        // Transform entries into 5-hour time blocks
-        let mut blocks: Vec<(std::time::SystemTime, std::time::SystemTime)> = Vec::new(); // (start, end)
-        let mut current_block_start: Option<std::time::SystemTime> = None;
-        let mut current_block_end: Option<std::time::SystemTime> = None;
-
-        for entry_time in all_entries.iter() {
-            let entry_block_start = self.round_to_hour(*entry_time);
-            let entry_block_end = entry_block_start + std::time::Duration::from_secs(5 * 3600);
-
-            // Check if we need a new block based on time boundaries
-            let need_new_block = match (current_block_start, current_block_end) {
-                (None, None) => true, // First entry
-                (Some(_start), Some(end)) => {
-                    // New block needed if entry would be outside current block's end time
-                    *entry_time >= end
-                }
-                _ => true, // Safety fallback
-            };
+    /// Every entry from the last `lookback` (all of them when None) with the input and
+    /// output tokens it counts toward its block and its cost; duplicate copies count nothing
+    fn block_entries_within(
+        &self,
+        lookback: Option<std::time::Duration>,
+    ) -> Option<Vec<BlockEntry>> {
+        let now = std::time::SystemTime::now();
+        let cutoff = lookback.and_then(|lookback| now.checked_sub(lookback));
+        let mut dedup = UsageDeduplicator::default();
+        let mut block_entries = Vec::new();
 
-            if need_new_block {
-                // Save current block if it exists
-                if let (Some(start), Some(end)) = (current_block_start, current_block_end) {
-                    blocks.push((start, end));
-                }
-                // Start new block
-                current_block_start = Some(entry_block_start);
-                current_block_end = Some(entry_block_end);
-            }
-        }
+        for project_path in self.project_entries().ok()? {
+            if project_path.is_dir() {
+                // An unreadable directory or entry only loses its own sessions
+                let Ok(file_entries) = std::fs::read_dir(&project_path) else {
+                    continue;
+                };
+                for file_entry in file_entries {
+                    let Ok(file_entry) = file_entry else {
+                        continue;
+                    };
+                    let file_path = file_entry.path();
 
-        // Add the last block
-        if let (Some(start), Some(end)) = (current_block_start, current_block_end) {
-            blocks.push((start, end));
-        }
+                    if file_path.extension().and_then(|s| s.to_str()) == Some("jsonl") {
+                        if let Some(cutoff) = cutoff {
+                            let modified = file_entry.metadata().and_then(|m| m.modified());
+                            if modified.map(|m| m < cutoff).unwrap_or(false) {
+                                continue;
+                            }
+                        }
 
-        // Find active block by checking current time against block boundaries
-        // A block is active if block.end_time > current_time
-        for (_block_start, block_end) in blocks.iter() {
-            // Active condition: block end time is in the future
-            if *block_end > now {
-                return Some(*block_end);
+                        let _ = self.with_session_entries(&file_path, |entries| {
+                            for entry in entries {
+                                if cutoff.is_some_and(|c| entry.system_time() < c) {
+                                    continue;
+                                }
+                                let mut stats = UsageStats::default();
+                                if let Some(usage) = &entry.usage {
+                                    if dedup.first_seen(entry.dedup_key()) {
+                                        self.add_entry_usage(
+                                            Some(usage),
+                                            Some(entry.time),
+                                            entry.cost_usd,
+                                            &mut stats,
+                                        );
+                                    }
+                                }
+                                block_entries.push(BlockEntry {
+                                    time: entry.time,
+                                    tokens: stats.input_output_tokens(),
+                                    cost: stats.total_cost,
+                                });
+                            }
+                        });
+                    }
+                }
             }
         }
 
-        None
+        Some(block_entries)
     }
 
     /// Calculate time until next reset in a human-readable format
@@ -1283,26 +1004,40 @@ impl ClaudeDataManager {
     /// Calculate comprehensive analytics for a specific project
     pub fn calculate_project_analytics(&self, project: &Project) -> Result<ProjectAnalytics> {
         let mut total_messages = 0;
-        let mut total_tokens = 0;
-        let mut estimated_cost = 0.0;
+        let mut usage = UsageStats::default();
         let mut first_session: Option<std::time::SystemTime> = None;
         let mut last_session: Option<std::time::SystemTime> = None;
-        let mut cache_read_tokens = 0;
-        let mut cache_creation_tokens = 0;
-        let mut total_input_tokens = 0;
+        let mut subagent_usage = UsageStats::default();
+        let mut session_agents = BTreeMap::new();
+        let mut tool_usage = ToolUsage::default();
+        let mut session_tools = BTreeMap::new();
+        // Resumed and forked sessions repeat messages from earlier session files
+        let mut dedup = UsageDeduplicator::default();
+        let mut tool_tracker = ToolTracker::default();
 
         for session in &project.sessions {
             total_messages += session.message_count;
 
-            // Parse session file for detailed statistics
-            if let Ok(session_stats) = self.calculate_session_usage(&session.path) {
-                total_tokens += session_stats.input_tokens + session_stats.output_tokens;
-                estimated_cost += session_stats.total_cost;
-                cache_read_tokens += session_stats.cache_read_tokens;
-                cache_creation_tokens += session_stats.cache_creation_tokens;
-                total_input_tokens += session_stats.input_tokens;
+            // Parse session file for detailed statistics, split by agent
+            if let Ok(agents) = self.calculate_session_agent_usage(session, &mut dedup) {
+                for agent in &agents {
+                    usage.merge(&agent.usage_stats);
+                    if agent.is_sidechain {
+                        subagent_usage.merge(&agent.usage_stats);
+                    }
+                }
+                session_agents.insert(session.id.clone(), agents);
             }
 
+            let mut tools = ToolUsage::default();
+            let _ = self.with_session_entries(&session.path, |entries| {
+                for entry in entries {
+                    tool_tracker.record(&entry.tools, &mut tools);
+                }
+            });
+            tool_usage.merge(&tools);
+            session_tools.insert(session.id.clone(), tools);
+
             // Track first and last session times
             if first_session.is_none() || session.last_modified < first_session.unwrap() {
                 first_session = Some(session.last_modified);
@@ -1314,44 +1049,126 @@ impl ClaudeDataManager {
 
         // Calculate cache efficiency (cache read tokens / total tokens including cache creation)
         // Cache efficiency = how much we're reusing vs creating new cache
-        let total_cacheable_tokens = total_input_tokens + cache_creation_tokens;
+        let total_cacheable_tokens = usage
+            .input_tokens
+            .saturating_add(usage.cache_creation_tokens);
         let cache_efficiency = if total_cacheable_tokens > 0 {
-            (cache_read_tokens as f64 / total_cacheable_tokens as f64) * 100.0
+            (usage.cache_read_tokens as f64 / total_cacheable_tokens as f64) * 100.0
         } else {
             0.0
         };
 
+        self.record_duplicates(&dedup);
+
         // Generate session blocks (placeholder for now)
         let session_blocks = self.calculate_session_blocks(project)?;
 
         Ok(ProjectAnalytics {
             total_sessions: project.sessions.len(),
             total_messages,
-            total_tokens,
-            estimated_cost,
+            total_tokens: usage.input_output_tokens(),
+            estimated_cost: usage.total_cost,
+            subagent_tokens: subagent_usage.input_output_tokens(),
+            subagent_cost: subagent_usage.total_cost,
             first_session,
             last_session,
             cache_efficiency,
             session_blocks,
+            session_agents,
+            tool_usage,
+            session_tools,
         })
     }
 
-    /// Calculate usage statistics for a single session
-    fn calculate_session_usage(&self, session_path: &Path) -> Result<UsageStats> {
-        let mut stats = UsageStats::default();
-        let content = std::fs::read_to_string(session_path)?;
-
-        for line in content.lines() {
-            let line = line.trim();
-            if line.is_empty() {
+    /// Tools called in each session, keyed by session id, counting only calls and
+    /// results inside the filter's date range and projects
+    pub fn calculate_tool_usage(
+        &self,
+        filter: &UsageFilter,
+    ) -> Result<BTreeMap<String, ToolUsage>> {
+        self.refresh_sessions(&scan::no_progress)?;
+
+        let mut session_tools: BTreeMap<String, ToolUsage> = BTreeMap::new();
+        let mut tracker = ToolTracker::default();
+        for path in self.session_files()? {
+            if !path.parent().is_some_and(|dir| filter.includes_dir(dir)) {
                 continue;
             }
+            let Some(session_id) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
 
-            if let Ok(entry) = serde_json::from_str::<Value>(line) {
-                self.extract_usage_from_entry(&entry, &mut stats)?;
+            let mut tools = ToolUsage::default();
+            let _ = self.with_session_entries(&path, |entries| {
+                for entry in entries {
+                    if filter.includes_date(entry.local_date()) {
+                        tracker.record(&entry.tools, &mut tools);
+                    }
+                }
+            });
+            if !tools.is_empty() {
+                // The same session id can appear in several data directories
+                session_tools
+                    .entry(session_id.to_string())
+                    .or_default()
+                    .merge(&tools);
             }
         }
 
+        Ok(session_tools)
+    }
+
+    /// Calculate a session's usage per agent, skipping messages `dedup` has seen.
+    /// The main thread comes first, then subagents in the order they first appear.
+    fn calculate_session_agent_usage(
+        &self,
+        session: &Session,
+        dedup: &mut UsageDeduplicator,
+    ) -> Result<Vec<AgentUsage>> {
+        let mut agents = vec![AgentUsage {
+            agent_id: session.id.clone(),
+            is_sidechain: false,
+            usage_stats: UsageStats::default(),
+        }];
+
+        self.with_session_entries(&session.path, |entries| {
+            for entry in entries {
+                let (agent_id, is_sidechain) = match &entry.usage {
+                    Some(usage) => (usage.agent(&session.id), usage.is_sidechain),
+                    None => (session.id.as_str(), false),
+                };
+                let index = match agents.iter().position(|a| a.agent_id == agent_id) {
+                    Some(index) => index,
+                    None => {
+                        agents.push(AgentUsage {
+                            agent_id: agent_id.to_string(),
+                            is_sidechain,
+                            usage_stats: UsageStats::default(),
+                        });
+                        agents.len() - 1
+                    }
+                };
+                self.add_unique_entry_usage(entry, dedup, &mut agents[index].usage_stats);
+            }
+        })?;
+
+        Ok(agents)
+    }
+
+    /// Calculate usage statistics for a single session, skipping messages `dedup` has seen
+    fn calculate_session_usage(
+        &self,
+        session_path: &Path,
+        dedup: &mut UsageDeduplicator,
+    ) -> Result<UsageStats> {
+        let mut stats = UsageStats::default();
+
+        self.with_session_entries(session_path, |entries| {
+            for entry in entries {
+                self.add_unique_entry_usage(entry, dedup, &mut stats);
+            }
+        })?;
+
         Ok(stats)
     }
 
@@ -1366,6 +1183,7 @@ impl ClaudeDataManager {
         // Group sessions into 5-hour blocks
         let mut current_block_start: Option<std::time::SystemTime> = None;
         let mut current_block_stats = UsageStats::default();
+        let mut dedup = UsageDeduplicator::default();
 
         for session in &project.sessions {
             // If this is the first session or more than 5 hours from current block
@@ -1394,13 +1212,8 @@ impl ClaudeDataManager {
             }
 
             // Add session stats to current block
-            if let Ok(session_stats) = self.calculate_session_usage(&session.path) {
-                current_block_stats.input_tokens += session_stats.input_tokens;
-                current_block_stats.output_tokens += session_stats.output_tokens;
-                current_block_stats.cache_creation_tokens += session_stats.cache_creation_tokens;
-                current_block_stats.cache_read_tokens += session_stats.cache_read_tokens;
-                current_block_stats.total_cost += session_stats.total_cost;
-                current_block_stats.message_count += session_stats.message_count;
+            if let Ok(session_stats) = self.calculate_session_usage(&session.path, &mut dedup) {
+                current_block_stats.merge(&session_stats);
             }
         }
 
@@ -1427,49 +1240,55 @@ impl ClaudeDataManager {
     /// Calculate daily usage breakdown for the last N days (optimized single-pass)
     #[allow(dead_code)]
     pub fn calculate_daily_usage(&self, days: u32) -> Result<Vec<DailyUsage>> {
+        self.calculate_daily_usage_filtered(days, &UsageFilter::default())
+    }
+
+    /// Calculate daily usage for the last N days, restricted by date range and project
+    pub fn calculate_daily_usage_filtered(
+        &self,
+        days: u32,
+        filter: &UsageFilter,
+    ) -> Result<Vec<DailyUsage>> {
         let today = chrono::Local::now().date_naive();
         let mut daily_stats: std::collections::HashMap<chrono::NaiveDate, UsageStats> =
             std::collections::HashMap::new();
+        let mut dedup = UsageDeduplicator::default();
 
         // Initialize all target dates
         let target_dates: Vec<chrono::NaiveDate> = (0..days)
             .map(|i| today - chrono::Duration::days(i as i64))
+            .filter(|date| filter.includes_date(*date))
             .collect();
 
         for date in &target_dates {
             daily_stats.insert(*date, UsageStats::default());
         }
 
-        let projects_dir = self.claude_dir.join("projects");
-        if projects_dir.exists() {
-            for entry in std::fs::read_dir(&projects_dir)? {
-                let entry = entry?;
-                let project_path = entry.path();
-
-                if project_path.is_dir() {
-                    for file_entry in std::fs::read_dir(&project_path)? {
-                        let file_entry = file_entry?;
-                        let file_path = file_entry.path();
-
-                        if file_path.extension().and_then(|s| s.to_str()) == Some("jsonl") {
-                            // Skip files that are too old to contain relevant data
-                            if let Ok(metadata) = std::fs::metadata(&file_path) {
-                                if let Ok(modified) = metadata.modified() {
-                                    let file_age = modified.elapsed().unwrap_or_default();
-                                    // Skip files older than 8 days (1 day buffer)
-                                    if file_age.as_secs() > (days + 1) as u64 * 24 * 3600 {
-                                        continue;
-                                    }
+        for project_path in self.project_entries()? {
+            if project_path.is_dir() && filter.includes_dir(&project_path) {
+                for file_entry in std::fs::read_dir(&project_path)? {
+                    let file_entry = file_entry?;
+                    let file_path = file_entry.path();
+
+                    if file_path.extension().and_then(|s| s.to_str()) == Some("jsonl") {
+                        // Skip files that are too old to contain relevant data
+                        if let Ok(metadata) = std::fs::metadata(&file_path) {
+                            if let Ok(modified) = metadata.modified() {
+                                let file_age = modified.elapsed().unwrap_or_default();
+                                // Skip files older than 8 days (1 day buffer)
+                                if file_age.as_secs() > (days + 1) as u64 * 24 * 3600 {
+                                    continue;
                                 }
                             }
-
-                            // Single-pass processing: read file once and extract data for all dates
-                            self.process_jsonl_for_all_dates(
-                                &file_path,
-                                &mut daily_stats,
-                                &target_dates,
-                            )?;
                         }
+
+                        // Single-pass processing: read file once and extract data for all dates
+                        self.process_jsonl_for_all_dates(
+                            &file_path,
+                            &mut daily_stats,
+                            &target_dates,
+                            &mut dedup,
+                        )?;
                     }
                 }
             }
@@ -1484,32 +1303,36 @@ impl ClaudeDataManager {
             })
             .collect();
 
-        daily_usage.sort_by(|a, b| b.date.cmp(&a.date));
+        daily_usage.sort_by_key(|d| std::cmp::Reverse(d.date));
+        self.record_duplicates(&dedup);
         Ok(daily_usage)
     }
 
     /// Calculate model usage breakdown
     #[allow(dead_code)]
     pub fn calculate_model_usage(&self) -> Result<Vec<ModelUsage>> {
+        self.calculate_model_usage_filtered(&UsageFilter::default())
+    }
+
+    /// Calculate model usage breakdown restricted by date range and project
+    pub fn calculate_model_usage_filtered(&self, filter: &UsageFilter) -> Result<Vec<ModelUsage>> {
         let mut model_stats: std::collections::HashMap<String, UsageStats> =
             std::collections::HashMap::new();
+        let mut dedup = UsageDeduplicator::default();
 
-        let projects_dir = self.claude_dir.join("projects");
-        if !projects_dir.exists() {
-            return Ok(Vec::new());
-        }
-
-        for entry in std::fs::read_dir(&projects_dir)? {
-            let entry = entry?;
-            let project_path = entry.path();
-
-            if project_path.is_dir() {
+        for project_path in self.project_entries()? {
+            if project_path.is_dir() && filter.includes_dir(&project_path) {
                 for file_entry in std::fs::read_dir(&project_path)? {
                     let file_entry = file_entry?;
                     let file_path = file_entry.path();
 
                     if file_path.extension().and_then(|s| s.to_str()) == Some("jsonl") {
-                        self.process_jsonl_for_model_usage(&file_path, &mut model_stats)?;
+                        self.process_jsonl_for_model_usage(
+                            &file_path,
+                            &mut model_stats,
+                            filter,
+                            &mut dedup,
+                        )?;
                     }
                 }
             }
@@ -1525,11 +1348,12 @@ impl ClaudeDataManager {
 
         // Sort by total tokens (descending)
         model_usage.sort_by(|a, b| {
-            let a_total = a.usage_stats.input_tokens + a.usage_stats.output_tokens;
-            let b_total = b.usage_stats.input_tokens + b.usage_stats.output_tokens;
-            b_total.cmp(&a_total)
+            b.usage_stats
+                .input_output_tokens()
+                .cmp(&a.usage_stats.input_output_tokens())
         });
 
+        self.record_duplicates(&dedup);
         Ok(model_usage)
     }
 
@@ -1539,33 +1363,27 @@ impl ClaudeDataManager {
         &self,
         file_path: &Path,
         model_stats: &mut std::collections::HashMap<String, UsageStats>,
+        filter: &UsageFilter,
+        dedup: &mut UsageDeduplicator,
     ) -> Result<()> {
-        let content = std::fs::read_to_string(file_path)?;
-
-        for line in content.lines() {
-            let line = line.trim();
-            if line.is_empty() {
-                continue;
-            }
-
-            if let Ok(entry) = serde_json::from_str::<Value>(line) {
-                // Extract model name
-                let model_name = entry
-                    .get("message")
-                    .and_then(|m| m.get("model"))
-                    .and_then(|m| m.as_str())
-                    .unwrap_or("unknown")
-                    .to_string();
+        self.with_session_entries(file_path, |entries| {
+            for entry in entries {
+                // Only messages with usage belong to a model
+                let Some(usage) = &entry.usage else {
+                    continue;
+                };
+                if !filter.includes_timestamp(Some(&usage.timestamp)) {
+                    continue;
+                }
+                if !dedup.first_seen(usage.dedup_key()) {
+                    continue;
+                }
 
-                // Get or create stats for this model
+                let model_name = usage.model.as_deref().unwrap_or("unknown").to_string();
                 let stats = model_stats.entry(model_name).or_default();
-
-                // Extract usage data
-                self.extract_usage_from_entry(&entry, stats)?;
+                self.add_entry_usage(Some(usage), Some(entry.time), entry.cost_usd, stats);
             }
-        }
-
-        Ok(())
+        })
     }
 
     /// Calculate burn rate (tokens per minute) for active sessions
@@ -1584,8 +1402,9 @@ impl ClaudeDataManager {
             .max_by_key(|s| s.last_modified);
 
         if let Some(session) = recent_session {
-            if let Ok(session_stats) = self.calculate_session_usage(&session.path) {
-                let total_tokens = session_stats.input_tokens + session_stats.output_tokens;
+            let mut dedup = UsageDeduplicator::default();
+            if let Ok(session_stats) = self.calculate_session_usage(&session.path, &mut dedup) {
+                let total_tokens = session_stats.input_output_tokens();
                 let session_duration = session
                     .last_modified
                     .elapsed()
@@ -1604,39 +1423,40 @@ impl ClaudeDataManager {
 
     // 🎯 INNOVATIVE FEATURES BASED ON ~/.claude DEEP ANALYSIS
 
-    /// Load all todos from ~/.claude/todos/ directory - Cross-session todo intelligence
+    /// Load all todos from every data directory's todos/ folder - Cross-session todo intelligence
     #[allow(dead_code)]
     pub fn load_enhanced_todos(&self) -> Result<Vec<EnhancedTodoItem>> {
-        let todos_dir = self.claude_dir.join("todos");
-        if !todos_dir.exists() {
-            return Ok(Vec::new());
-        }
-
         let mut enhanced_todos = Vec::new();
 
-        for entry in fs::read_dir(&todos_dir)? {
-            let entry = entry?;
-            let file_path = entry.path();
+        for todos_dir in self.claude_dirs.iter().map(|root| root.join("todos")) {
+            if !todos_dir.exists() {
+                continue;
+            }
 
-            if file_path.extension().and_then(|s| s.to_str()) == Some("json") {
-                let content = fs::read_to_string(&file_path)?;
-                let file_name = file_path
-                    .file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or("")
-                    .to_string();
+            for entry in fs::read_dir(&todos_dir)? {
+                let entry = entry?;
+                let file_path = entry.path();
 
-                // Extract session ID from filename (format: {session-id}-agent-{agent-id}.json)
-                let session_id = file_name.split('-').next().map(|s| s.to_string());
-
-                // Try to parse as todo array
-                if let Ok(todos) = serde_json::from_str::<Vec<EnhancedTodoItem>>(&content) {
-                    for mut todo in todos {
-                        todo.session_id = session_id.clone();
-                        // Infer project name from session if possible
-                        todo.project_name =
-                            self.infer_project_from_session(session_id.as_deref().unwrap_or(""));
-                        enhanced_todos.push(todo);
+                if file_path.extension().and_then(|s| s.to_str()) == Some("json") {
+                    let content = fs::read_to_string(&file_path)?;
+                    let file_name = file_path
+                        .file_name()
+                        .and_then(|n| n.to_str())
+                        .unwrap_or("")
+                        .to_string();
+
+                    // Extract session ID from filename (format: {session-id}-agent-{agent-id}.json)
+                    let session_id = file_name.split('-').next().map(|s| s.to_string());
+
+                    // Try to parse as todo array
+                    if let Ok(todos) = serde_json::from_str::<Vec<EnhancedTodoItem>>(&content) {
+                        for mut todo in todos {
+                            todo.session_id = session_id.clone();
+                            // Infer project name from session if possible
+                            todo.project_name = self
+                                .infer_project_from_session(session_id.as_deref().unwrap_or(""));
+                            enhanced_todos.push(todo);
+                        }
                     }
                 }
             }
@@ -1645,65 +1465,22 @@ impl ClaudeDataManager {
         Ok(enhanced_todos)
     }
 
-    /// Load cost notification states - Smart cost warning system
-    #[allow(dead_code)]
-    pub fn load_notification_states(&self) -> Result<NotificationStates> {
-        let config_path = self
-            .claude_dir
-            .join("config")
-            .join("notification_states.json");
-
-        if config_path.exists() {
-            let content = fs::read_to_string(&config_path)?;
-            let states: NotificationStates = serde_json::from_str(&content)?;
-            Ok(states)
-        } else {
-            // Return default states if file doesn't exist
-            Ok(NotificationStates {
-                switch_to_custom: NotificationState {
-                    triggered: false,
-                    timestamp: None,
-                },
-                exceed_max_limit: NotificationState {
-                    triggered: false,
-                    timestamp: None,
-                },
-                tokens_will_run_out: NotificationState {
-                    triggered: false,
-                    timestamp: None,
-                },
-                cost_will_exceed: NotificationState {
-                    triggered: false,
-                    timestamp: None,
-                },
-            })
-        }
-    }
-
     /// Analyze session intelligence - Most active sessions, productivity patterns
     #[allow(dead_code)]
     pub fn analyze_session_intelligence(&self) -> Result<Vec<SessionMetrics>> {
-        let projects_dir = self.claude_dir.join("projects");
-        if !projects_dir.exists() {
-            return Ok(Vec::new());
-        }
-
         let mut session_metrics = Vec::new();
         let mut max_lines = 0usize;
 
         // First pass: find sessions and count lines
-        for entry in fs::read_dir(&projects_dir)? {
-            let entry = entry?;
-            let project_path = entry.path();
-
+        for project_path in self.project_entries()? {
             if project_path.is_dir() {
                 let sanitized_name = project_path
                     .file_name()
                     .and_then(|s| s.to_str())
                     .unwrap_or("");
                 let project_name = self
-                    .reconstruct_path_from_sanitized_name(sanitized_name)
-                    .unwrap_or_else(|_| PathBuf::from(sanitized_name))
+                    .resolve_project_path(&project_path)
+                    .unwrap_or_else(|| PathBuf::from(sanitized_name))
                     .to_string_lossy()
                     .to_string();
 
@@ -1719,8 +1496,8 @@ impl ClaudeDataManager {
                             .to_string();
 
                         // Count lines in session file
-                        let line_count = fs::read_to_string(&file_path)
-                            .map(|content| content.lines().count())
+                        let line_count = self
+                            .with_session_file(&file_path, |file| file.line_count)
                             .unwrap_or(0);
 
                         max_lines = max_lines.max(line_count);
@@ -1744,7 +1521,7 @@ impl ClaudeDataManager {
         }
 
         // Sort by activity level (most active first)
-        session_metrics.sort_by(|a, b| b.line_count.cmp(&a.line_count));
+        session_metrics.sort_by_key(|m| std::cmp::Reverse(m.line_count));
 
         Ok(session_metrics)
     }
@@ -1786,12 +1563,7 @@ impl ClaudeDataManager {
     /// Infer project name from session ID (helper method)
     #[allow(dead_code)]
     fn infer_project_from_session(&self, session_id: &str) -> Option<String> {
-        let projects_dir = self.claude_dir.join("projects");
-
-        for entry in fs::read_dir(&projects_dir).ok()? {
-            let entry = entry.ok()?;
-            let project_path = entry.path();
-
+        for project_path in self.project_entries().ok()? {
             if project_path.is_dir() {
                 for file_entry in fs::read_dir(&project_path).ok()? {
                     let file_entry = file_entry.ok()?;
@@ -1804,8 +1576,8 @@ impl ClaudeDataManager {
                                 .and_then(|s| s.to_str())
                                 .unwrap_or("");
                             return Some(
-                                self.reconstruct_path_from_sanitized_name(sanitized_name)
-                                    .unwrap_or_else(|_| PathBuf::from(sanitized_name))
+                                self.resolve_project_path(&project_path)
+                                    .unwrap_or_else(|| PathBuf::from(sanitized_name))
                                     .to_string_lossy()
                                     .to_string(),
                             );
@@ -1820,111 +1592,73 @@ impl ClaudeDataManager {
 
     // 🚀 COMPREHENSIVE USAGE ANALYTICS - 최고 수준의 사용량 분석
 
-    /// Parse all session messages from .jsonl files - Real token usage analytics
+    /// Parse all session messages from .jsonl files - Real token usage analytics.
+    /// Messages come from the session ingestor, so their `content` is not loaded.
     pub fn parse_all_session_messages(&self) -> Result<Vec<SessionMessage>> {
-        let projects_dir = self.claude_dir.join("projects");
-        if !projects_dir.exists() {
-            return Ok(Vec::new());
-        }
+        self.refresh_sessions(&scan::no_progress)?;
 
         let mut all_messages = Vec::new();
+        let mut dedup = UsageDeduplicator::default();
 
-        for entry in fs::read_dir(&projects_dir)? {
-            let entry = entry?;
-            let project_path = entry.path();
-
+        for project_path in self.project_entries()? {
             if project_path.is_dir() {
                 for file_entry in fs::read_dir(&project_path)? {
                     let file_entry = file_entry?;
                     let file_path = file_entry.path();
 
                     if file_path.extension().and_then(|s| s.to_str()) == Some("jsonl") {
-                        if let Ok(content) = fs::read_to_string(&file_path) {
-                            let session_id = file_path
-                                .file_stem()
-                                .and_then(|s| s.to_str())
-                                .unwrap_or("")
-                                .to_string();
-
-                            for line in content.lines() {
-                                if line.trim().is_empty() {
-                                    continue;
-                                }
+                        let session_id = file_path
+                            .file_stem()
+                            .and_then(|s| s.to_str())
+                            .unwrap_or("")
+                            .to_string();
 
-                                // Parse as generic JSON first to check structure
-                                if let Ok(json_value) =
-                                    serde_json::from_str::<serde_json::Value>(line)
-                                {
-                                    // Get timestamp from root level
-                                    if let Some(timestamp) = json_value.get("timestamp") {
-                                        // Check if this entry has usage data in message.usage
-                                        if let Some(message) = json_value.get("message") {
-                                            if let Some(usage) = message.get("usage") {
-                                                // Only include entries with actual token usage (non-zero)
-                                                let input_tokens = usage
-                                                    .get("input_tokens")
-                                                    .and_then(|v| v.as_u64())
-                                                    .unwrap_or(0);
-                                                let output_tokens = usage
-                                                    .get("output_tokens")
-                                                    .and_then(|v| v.as_u64())
-                                                    .unwrap_or(0);
-                                                let cache_creation = usage
-                                                    .get("cache_creation_input_tokens")
-                                                    .and_then(|v| v.as_u64())
-                                                    .unwrap_or(0);
-                                                let cache_read = usage
-                                                    .get("cache_read_input_tokens")
-                                                    .and_then(|v| v.as_u64())
-                                                    .unwrap_or(0);
-
-                                                if input_tokens > 0
-                                                    || output_tokens > 0
-                                                    || cache_creation > 0
-                                                    || cache_read > 0
-                                                {
-                                                    // Create SessionMessage from the parsed data
-                                                    let session_message = SessionMessage {
-                                                        session_id: session_id.clone(),
-                                                        message_type: json_value
-                                                            .get("type")
-                                                            .and_then(|v| v.as_str())
-                                                            .unwrap_or("unknown")
-                                                            .to_string(),
-                                                        message: MessageContent {
-                                                            usage: serde_json::from_value(
-                                                                usage.clone(),
-                                                            )
-                                                            .ok(),
-                                                            model: message
-                                                                .get("model")
-                                                                .and_then(|v| v.as_str())
-                                                                .map(|s| s.to_string()),
-                                                            timestamp: timestamp
-                                                                .as_str()
-                                                                .map(|s| s.to_string()),
-                                                            content: message
-                                                                .get("content")
-                                                                .cloned(),
-                                                        },
-                                                        cwd: json_value
-                                                            .get("cwd")
-                                                            .and_then(|v| v.as_str())
-                                                            .map(|s| s.to_string()),
-                                                    };
-                                                    all_messages.push(session_message);
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
+                        let _ = self.with_session_entries(&file_path, |entries| {
+                            // Only include entries with actual token usage (non-zero)
+                            let usage_records = entries
+                                .iter()
+                                .filter_map(|entry| entry.usage.as_ref())
+                                .filter(|usage| usage.has_tokens())
+                                .filter(|usage| dedup.first_seen(usage.dedup_key()));
+
+                            for usage in usage_records {
+                                all_messages.push(SessionMessage {
+                                    session_id: session_id.clone(),
+                                    message_type: usage.message_type.clone(),
+                                    message: MessageContent {
+                                        usage: Some(TokenUsage {
+                                            input_tokens: Some(usage.input_tokens),
+                                            output_tokens: Some(usage.output_tokens),
+                                            cache_creation_input_tokens: Some(
+                                                usage.cache_creation_tokens,
+                                            ),
+                                            cache_read_input_tokens: Some(usage.cache_read_tokens),
+                                            cache_creation: Some(CacheCreation {
+                                                ephemeral_5m_input_tokens: usage
+                                                    .cache_creation_tokens
+                                                    .saturating_sub(usage.cache_write_1h_tokens),
+                                                ephemeral_1h_input_tokens: usage
+                                                    .cache_write_1h_tokens,
+                                            }),
+                                            service_tier: usage.service_tier.clone(),
+                                        }),
+                                        model: usage.model.clone(),
+                                        timestamp: Some(usage.timestamp.clone()),
+                                        content: None,
+                                    },
+                                    cwd: usage.cwd.clone(),
+                                    is_sidechain: usage.is_sidechain,
+                                    agent_id: usage.agent_id.clone(),
+                                });
                             }
-                        }
+                        });
                     }
                 }
             }
         }
 
+        self.record_duplicates(&dedup);
+
         // Sort by timestamp for chronological analysis
         all_messages.sort_by(|a, b| {
             let ts_a = a.message.timestamp.as_deref().unwrap_or("");
@@ -1938,7 +1672,7 @@ impl ClaudeDataManager {
     /// Generate comprehensive usage analytics - 매력적인 모든 통계
     pub fn generate_comprehensive_analytics(&self) -> Result<UsageAnalytics> {
         let messages = self.parse_all_session_messages()?;
-        let pricing = &self.openrouter_pricing;
+        let pricing = &self.pricing;
 
         // Daily usage analysis
         let daily_usage = self.analyze_daily_usage_detailed(&messages, pricing)?;
@@ -1958,8 +1692,16 @@ impl ClaudeDataManager {
         // Project usage
         let project_usage = self.analyze_project_usage(&messages, pricing)?;
 
+        // Tool usage, overall and per session
+        let mut session_tools = self.calculate_tool_usage(&UsageFilter::default())?;
+        let mut tool_usage = ToolUsage::default();
+        for tools in session_tools.values() {
+            tool_usage.merge(tools);
+        }
+
         // Session analytics
-        let session_analytics = self.analyze_session_details(&messages, pricing)?;
+        let session_analytics =
+            self.analyze_session_details(&messages, pricing, &mut session_tools)?;
 
         Ok(UsageAnalytics {
             daily_usage,
@@ -1969,14 +1711,36 @@ impl ClaudeDataManager {
             cost_breakdown,
             project_usage,
             session_analytics,
+            tool_usage,
         })
     }
 
+    /// Calculate per-session analytics restricted by date range and project
+    pub fn calculate_session_analytics(
+        &self,
+        filter: &UsageFilter,
+    ) -> Result<Vec<SessionAnalytics>> {
+        let session_projects = self.map_sessions_to_projects()?;
+        let mut messages = self.parse_all_session_messages()?;
+
+        messages.retain(|message| {
+            filter.includes_timestamp(message.message.timestamp.as_deref())
+                && session_projects
+                    .get(&message.session_id)
+                    .map_or(filter.project_dirs.is_none(), |(dir, _)| {
+                        filter.includes_dir(dir)
+                    })
+        });
+
+        let mut session_tools = self.calculate_tool_usage(filter)?;
+        self.analyze_session_details(&messages, &self.pricing, &mut session_tools)
+    }
+
     /// Analyze daily usage with full detail - 일별 상세 사용량
     fn analyze_daily_usage_detailed(
         &self,
         messages: &[SessionMessage],
-        pricing: &OpenRouterPricing,
+        pricing: &PricingResolver,
     ) -> Result<Vec<DailyUsageDetail>> {
         use std::collections::BTreeMap;
         let mut daily_stats: BTreeMap<String, DailyUsageDetail> = BTreeMap::new();
@@ -2017,11 +1781,18 @@ impl ClaudeDataManager {
                 }
 
                 if let Some(usage) = &message.message.usage {
-                    entry.total_input_tokens += usage.input_tokens.unwrap_or(0);
-                    entry.total_output_tokens += usage.output_tokens.unwrap_or(0);
-                    entry.total_cache_creation_tokens +=
-                        usage.cache_creation_input_tokens.unwrap_or(0);
-                    entry.total_cache_read_tokens += usage.cache_read_input_tokens.unwrap_or(0);
+                    entry.total_input_tokens = entry
+                        .total_input_tokens
+                        .saturating_add(usage.input_tokens.unwrap_or(0));
+                    entry.total_output_tokens = entry
+                        .total_output_tokens
+                        .saturating_add(usage.output_tokens.unwrap_or(0));
+                    entry.total_cache_creation_tokens = entry
+                        .total_cache_creation_tokens
+                        .saturating_add(usage.cache_creation_input_tokens.unwrap_or(0));
+                    entry.total_cache_read_tokens = entry
+                        .total_cache_read_tokens
+                        .saturating_add(usage.cache_read_input_tokens.unwrap_or(0));
 
                     // Calculate cost for this message
                     if let Some(model) = &message.message.model {
@@ -2029,7 +1800,8 @@ impl ClaudeDataManager {
                             entry.models_used.push(model.clone());
                         }
 
-                        let cost = self.calculate_message_cost(usage, model, pricing);
+                        let cost =
+                            self.calculate_message_cost(usage, model, message.time(), pricing);
                         entry.total_cost += cost;
                     }
                 }
@@ -2043,7 +1815,7 @@ impl ClaudeDataManager {
     fn analyze_model_distribution(
         &self,
         messages: &[SessionMessage],
-        pricing: &OpenRouterPricing,
+        pricing: &PricingResolver,
     ) -> Result<HashMap<String, ModelUsageStats>> {
         let mut model_stats: HashMap<String, ModelUsageStats> = HashMap::new();
 
@@ -2070,11 +1842,15 @@ impl ClaudeDataManager {
                     avg_cost_per_message: 0.0,
                 });
 
-                entry.total_input_tokens += usage.input_tokens.unwrap_or(0);
-                entry.total_output_tokens += usage.output_tokens.unwrap_or(0);
+                entry.total_input_tokens = entry
+                    .total_input_tokens
+                    .saturating_add(usage.input_tokens.unwrap_or(0));
+                entry.total_output_tokens = entry
+                    .total_output_tokens
+                    .saturating_add(usage.output_tokens.unwrap_or(0));
                 entry.usage_count += 1;
 
-                let cost = self.calculate_message_cost(usage, model, pricing);
+                let cost = self.calculate_message_cost(usage, model, message.time(), pricing);
                 entry.total_cost += cost;
 
                 if let Some(timestamp) = &message.message.timestamp {
@@ -2127,13 +1903,14 @@ impl ClaudeDataManager {
                     hourly_stats[hour].message_count += 1;
 
                     if let Some(usage) = &message.message.usage {
-                        let total_tokens =
-                            usage.input_tokens.unwrap_or(0) + usage.output_tokens.unwrap_or(0);
-                        hourly_stats[hour].total_tokens += total_tokens;
+                        hourly_stats[hour].total_tokens = hourly_stats[hour]
+                            .total_tokens
+                            .saturating_add(usage.input_output_tokens());
 
                         if let Some(model) = &message.message.model {
-                            let pricing = &self.openrouter_pricing;
-                            let cost = self.calculate_message_cost(usage, model, pricing);
+                            let pricing = &self.pricing;
+                            let cost =
+                                self.calculate_message_cost(usage, model, message.time(), pricing);
                             hourly_stats[hour].total_cost += cost;
                         }
                     }
@@ -2149,39 +1926,35 @@ impl ClaudeDataManager {
         &self,
         messages: &[SessionMessage],
     ) -> Result<CacheEfficiencyStats> {
-        let mut total_cache_creation = 0u32;
-        let mut total_cache_read = 0u32;
+        let mut total_cache_creation = 0u64;
+        let mut total_cache_read = 0u64;
         let mut total_cache_cost_saved = 0.0;
 
         for message in messages {
             if let Some(usage) = &message.message.usage {
-                total_cache_creation += usage.cache_creation_input_tokens.unwrap_or(0);
-                total_cache_read += usage.cache_read_input_tokens.unwrap_or(0);
+                total_cache_creation = total_cache_creation
+                    .saturating_add(usage.cache_creation_input_tokens.unwrap_or(0));
+                total_cache_read =
+                    total_cache_read.saturating_add(usage.cache_read_input_tokens.unwrap_or(0));
 
                 // Calculate actual cache savings using model-specific pricing
                 if let Some(model) = &message.message.model {
-                    let input_cost = self
-                        .openrouter_pricing
-                        .get_model_pricing_sync(model)
-                        .map(|p| p.prompt)
-                        .unwrap_or(PricingConstants::SONNET_INPUT_COST);
-
-                    let cache_read_cost = self
-                        .openrouter_pricing
-                        .get_model_pricing_sync(model)
-                        .and_then(|p| p.cache_read_input_token_cost)
-                        .unwrap_or(PricingConstants::SONNET_CACHE_READ_COST);
+                    let pricing = &self.pricing;
+                    let tokens = usage.request_tokens();
+                    let resolved = pricing.resolve_at(Some(model), message.time());
+                    let rates = resolved.rates_for(&tokens);
+                    let scale = pricing.service_tier_multiplier(tokens.service_tier) / 1_000_000.0;
 
                     // Savings = (regular_cost - cache_cost) * cache_read_tokens
-                    let savings_per_token = input_cost - cache_read_cost;
-                    total_cache_cost_saved +=
-                        (usage.cache_read_input_tokens.unwrap_or(0) as f64) * savings_per_token;
+                    let savings_per_token = (rates.input - rates.cache_read) * scale;
+                    total_cache_cost_saved += (tokens.cache_read as f64) * savings_per_token;
                 }
             }
         }
 
-        let cache_hit_rate = if total_cache_creation + total_cache_read > 0 {
-            (total_cache_read as f64) / ((total_cache_creation + total_cache_read) as f64) * 100.0
+        let total_cache = total_cache_creation.saturating_add(total_cache_read);
+        let cache_hit_rate = if total_cache > 0 {
+            (total_cache_read as f64) / (total_cache as f64) * 100.0
         } else {
             0.0
         };
@@ -2194,28 +1967,25 @@ impl ClaudeDataManager {
         })
     }
 
-    /// Calculate message cost - 메시지별 정확한 비용 계산
+    /// Calculate message cost at the prices in effect when it was sent - 메시지별 정확한 비용 계산
     fn calculate_message_cost(
         &self,
         usage: &TokenUsage,
         model: &str,
-        pricing: &OpenRouterPricing,
+        at: Option<DateTime<Utc>>,
+        pricing: &PricingResolver,
     ) -> f64 {
         // Use the existing pricing calculation logic
-        pricing.calculate_cost_sync(
-            Some(model),
-            usage.input_tokens.unwrap_or(0),
-            usage.output_tokens.unwrap_or(0),
-            usage.cache_creation_input_tokens.unwrap_or(0),
-            usage.cache_read_input_tokens.unwrap_or(0),
-        )
+        pricing
+            .calculate_cost_sync(Some(model), at, &usage.request_tokens())
+            .0
     }
 
     /// Analyze cost breakdown - 비용 세부 분석
     fn analyze_cost_breakdown(
         &self,
         messages: &[SessionMessage],
-        pricing: &OpenRouterPricing,
+        pricing: &PricingResolver,
     ) -> Result<CostBreakdown> {
         let mut total_cost = 0.0;
         let mut input_cost = 0.0;
@@ -2225,31 +1995,23 @@ impl ClaudeDataManager {
 
         for message in messages {
             if let (Some(usage), Some(model)) = (&message.message.usage, &message.message.model) {
-                // Calculate individual costs using the OpenRouter pricing
-                let msg_input_cost = (usage.input_tokens.unwrap_or(0) as f64)
-                    * pricing
-                        .get_model_pricing_sync(model)
-                        .map(|p| p.prompt)
-                        .unwrap_or(PricingConstants::SONNET_INPUT_COST);
-
-                let msg_output_cost = (usage.output_tokens.unwrap_or(0) as f64)
-                    * pricing
-                        .get_model_pricing_sync(model)
-                        .map(|p| p.completion)
-                        .unwrap_or(PricingConstants::SONNET_OUTPUT_COST);
-
-                let msg_cache_creation_cost = (usage.cache_creation_input_tokens.unwrap_or(0)
-                    as f64)
-                    * pricing
-                        .get_model_pricing_sync(model)
-                        .and_then(|p| p.cache_creation_input_token_cost)
-                        .unwrap_or(PricingConstants::SONNET_CACHE_CREATION_COST);
-
-                let msg_cache_read_cost = (usage.cache_read_input_tokens.unwrap_or(0) as f64)
-                    * pricing
-                        .get_model_pricing_sync(model)
-                        .and_then(|p| p.cache_read_input_token_cost)
-                        .unwrap_or(PricingConstants::SONNET_CACHE_READ_COST);
+                // Calculate individual costs from the rates in effect when the message was
+                // sent, at the request's context-size tier and service tier
+                let tokens = usage.request_tokens();
+                let resolved = pricing.resolve_at(Some(model), message.time());
+                let rates = resolved.rates_for(&tokens);
+                // Rates are per 1M tokens
+                let scale = pricing.service_tier_multiplier(tokens.service_tier) / 1_000_000.0;
+
+                let msg_input_cost = (tokens.input as f64) * rates.input * scale;
+
+                let msg_output_cost = (tokens.output as f64) * rates.output * scale;
+
+                let msg_cache_creation_cost = ((tokens.cache_write_5m as f64) * rates.cache_write
+                    + (tokens.cache_write_1h as f64) * rates.cache_write_1h_rate())
+                    * scale;
+
+                let msg_cache_read_cost = (tokens.cache_read as f64) * rates.cache_read * scale;
 
                 input_cost += msg_input_cost;
                 output_cost += msg_output_cost;
@@ -2301,83 +2063,18 @@ impl ClaudeDataManager {
     fn analyze_project_usage(
         &self,
         messages: &[SessionMessage],
-        pricing: &OpenRouterPricing,
+        pricing: &PricingResolver,
     ) -> Result<HashMap<String, ProjectUsageStats>> {
         let mut project_stats: HashMap<String, ProjectUsageStats> = HashMap::new();
-        let mut session_projects: HashMap<String, String> = HashMap::new();
 
         // First pass: map sessions to projects
-        let projects_dir = self.claude_dir.join("projects");
-        if projects_dir.exists() {
-            for entry in fs::read_dir(&projects_dir)? {
-                let entry = entry?;
-                let project_path = entry.path();
-
-                if project_path.is_dir() {
-                    let sanitized_name = project_path
-                        .file_name()
-                        .and_then(|s| s.to_str())
-                        .unwrap_or("");
-                    // Use the same project name logic as project_scanner.rs
-                    let project_name =
-                        match self.reconstruct_path_from_sanitized_name(sanitized_name) {
-                            Ok(original_path) => {
-                                if original_path.exists() {
-                                    // Extract just the directory name (e.g., "cc-enhanced" from full path)
-                                    original_path
-                                        .file_name()
-                                        .and_then(|name| name.to_str())
-                                        .unwrap_or(sanitized_name)
-                                        .to_string()
-                                } else {
-                                    // For orphaned projects, use cleaner name logic
-                                    if let Some(last_part) = sanitized_name.split('-').next_back() {
-                                        if last_part.is_empty() {
-                                            sanitized_name.to_string()
-                                        } else {
-                                            last_part.to_string()
-                                        }
-                                    } else {
-                                        sanitized_name.to_string()
-                                    }
-                                }
-                            }
-                            Err(_) => {
-                                // Fallback: use cleaner name from sanitized_name
-                                if let Some(last_part) = sanitized_name.split('-').next_back() {
-                                    if last_part.is_empty() {
-                                        sanitized_name.to_string()
-                                    } else {
-                                        last_part.to_string()
-                                    }
-                                } else {
-                                    sanitized_name.to_string()
-                                }
-                            }
-                        };
-
-                    for file_entry in fs::read_dir(&project_path)? {
-                        let file_entry = file_entry?;
-                        let file_path = file_entry.path();
-
-                        if file_path.extension().and_then(|s| s.to_str()) == Some("jsonl") {
-                            let session_id = file_path
-                                .file_stem()
-                                .and_then(|s| s.to_str())
-                                .unwrap_or("")
-                                .to_string();
-                            session_projects.insert(session_id, project_name.clone());
-                        }
-                    }
-                }
-            }
-        }
+        let session_projects = self.map_sessions_to_projects()?;
 
         // Second pass: analyze messages
         for message in messages {
             let project_name = session_projects
                 .get(&message.session_id)
-                .cloned()
+                .map(|(_, name)| name.clone())
                 .unwrap_or_else(|| "Unknown Project".to_string());
 
             let entry = project_stats
@@ -2392,11 +2089,12 @@ impl ClaudeDataManager {
                 });
 
             if let Some(usage) = &message.message.usage {
-                entry.total_tokens +=
-                    usage.input_tokens.unwrap_or(0) + usage.output_tokens.unwrap_or(0);
+                entry.total_tokens = entry
+                    .total_tokens
+                    .saturating_add(usage.input_output_tokens());
 
                 if let Some(model) = &message.message.model {
-                    let cost = self.calculate_message_cost(usage, model, pricing);
+                    let cost = self.calculate_message_cost(usage, model, message.time(), pricing);
                     entry.total_cost += cost;
                     entry.most_used_model = model.clone(); // Simplified - could track frequency
                 }
@@ -2406,14 +2104,51 @@ impl ClaudeDataManager {
         Ok(project_stats)
     }
 
-    /// Analyze session details - 세션별 상세 분석
+    /// Map session ids to their project data directory and display name
+    fn map_sessions_to_projects(&self) -> Result<HashMap<String, (PathBuf, String)>> {
+        let mut session_projects: HashMap<String, (PathBuf, String)> = HashMap::new();
+
+        for project_path in self.project_entries()? {
+            if project_path.is_dir() {
+                let sanitized_name = project_path
+                    .file_name()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or("");
+                // Use the same project name logic as the project list
+                let resolved = self.resolve_project_path(&project_path);
+                let project_name = path_resolver::project_name(sanitized_name, resolved.as_deref());
+
+                for file_entry in fs::read_dir(&project_path)? {
+                    let file_entry = file_entry?;
+                    let file_path = file_entry.path();
+
+                    if file_path.extension().and_then(|s| s.to_str()) == Some("jsonl") {
+                        let session_id = file_path
+                            .file_stem()
+                            .and_then(|s| s.to_str())
+                            .unwrap_or("")
+                            .to_string();
+                        session_projects
+                            .insert(session_id, (project_path.clone(), project_name.clone()));
+                    }
+                }
+            }
+        }
+
+        Ok(session_projects)
+    }
+
+    /// Analyze session details - 세션별 상세 분석.
+    /// Each session takes its tool usage out of `session_tools`.
     fn analyze_session_details(
         &self,
         messages: &[SessionMessage],
-        pricing: &OpenRouterPricing,
+        pricing: &PricingResolver,
+        session_tools: &mut BTreeMap<String, ToolUsage>,
     ) -> Result<Vec<SessionAnalytics>> {
         use std::collections::HashMap;
         let mut session_map: HashMap<String, Vec<&SessionMessage>> = HashMap::new();
+        let session_projects = self.map_sessions_to_projects()?;
 
         // Group messages by session
         for message in messages {
@@ -2443,20 +2178,30 @@ impl ClaudeDataManager {
 
             let duration_minutes = self.calculate_session_duration(&start_time, &end_time);
 
-            let mut total_tokens = 0u32;
+            let mut total_tokens = 0u64;
             let mut total_cost = 0.0;
+            let mut subagent_tokens = 0u64;
+            let mut subagent_cost = 0.0;
             let mut models_used = Vec::new();
 
             for message in &session_messages {
                 if let Some(usage) = &message.message.usage {
-                    total_tokens +=
-                        usage.input_tokens.unwrap_or(0) + usage.output_tokens.unwrap_or(0);
+                    let tokens = usage.input_output_tokens();
+                    total_tokens = total_tokens.saturating_add(tokens);
+                    if message.is_sidechain {
+                        subagent_tokens = subagent_tokens.saturating_add(tokens);
+                    }
 
                     if let Some(model) = &message.message.model {
                         if !models_used.contains(model) {
                             models_used.push(model.clone());
                         }
-                        total_cost += self.calculate_message_cost(usage, model, pricing);
+                        let cost =
+                            self.calculate_message_cost(usage, model, message.time(), pricing);
+                        total_cost += cost;
+                        if message.is_sidechain {
+                            subagent_cost += cost;
+                        }
                     }
                 }
             }
@@ -2469,15 +2214,21 @@ impl ClaudeDataManager {
 
             session_analytics.push(SessionAnalytics {
                 session_id: session_id.clone(),
-                project_name: "Unknown".to_string(), // Could be enhanced with project mapping
+                project_name: session_projects
+                    .get(&session_id)
+                    .map(|(_, name)| name.clone())
+                    .unwrap_or_else(|| "Unknown".to_string()),
                 start_time,
                 end_time,
                 duration_minutes,
                 total_tokens,
                 total_cost,
+                subagent_tokens,
+                subagent_cost,
                 message_count: session_messages.len(),
                 models_used,
                 efficiency_score,
+                tools: session_tools.remove(&session_id).unwrap_or_default(),
             });
         }
 
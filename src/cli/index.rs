@@ -0,0 +1,58 @@
+//! Maintenance of the on-disk usage and search indexes
+
+use anyhow::Result;
+use std::path::PathBuf;
+
+use crate::claude::search::{self, SearchIndex};
+use crate::claude::{ingest, ClaudeDataManager};
+
+/// What to do with the indexes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IndexAction {
+    /// Discard both indexes and re-read every session file
+    Rebuild,
+}
+
+impl IndexAction {
+    /// Parse the arguments that follow `index`
+    pub fn parse<I>(args: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut args = args.into_iter();
+
+        let action = match args.next().as_deref() {
+            Some("rebuild") => Self::Rebuild,
+            Some(other) => return Err(anyhow::anyhow!("Unknown index action '{other}'")),
+            None => return Err(anyhow::anyhow!("Missing index action (rebuild)")),
+        };
+
+        if let Some(extra) = args.next() {
+            return Err(anyhow::anyhow!("Unexpected index argument '{extra}'"));
+        }
+        Ok(action)
+    }
+}
+
+/// Run an index action and report what was done
+pub fn run(action: IndexAction, claude_dirs: &[PathBuf]) -> Result<()> {
+    match action {
+        IndexAction::Rebuild => {
+            let manager = ClaudeDataManager::new(claude_dirs)?;
+            let (files, entries) = manager.rebuild_index()?;
+
+            let location = ingest::default_index_path()
+                .map(|path| path.display().to_string())
+                .unwrap_or_else(|| "memory (no cache directory)".to_string());
+            println!("Indexed {entries} entries from {files} session files into {location}");
+
+            let mut index = SearchIndex::open_default();
+            let (files, messages) = manager.rebuild_search_index(&mut index)?;
+            let location = search::default_index_path()
+                .map(|path| path.display().to_string())
+                .unwrap_or_else(|| "memory (no cache directory)".to_string());
+            println!("Indexed {messages} messages from {files} session files into {location}");
+            Ok(())
+        }
+    }
+}
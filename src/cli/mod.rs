@@ -0,0 +1,297 @@
+//! Command-line module - headless entry points that run without the TUI
+//!
+//! This module provides:
+//! - Argument parsing for the `cc-enhanced` binary
+//! - `report`: usage tables for cron jobs and CI boxes
+//! - `statusline`: one short line for shell prompts, tmux and the Claude Code hook
+//! - `index`: maintenance of the on-disk usage and search indexes
+//! - `search`: full-text search of session transcripts
+
+pub mod index;
+pub mod report;
+pub mod search;
+pub mod statusline;
+
+use anyhow::Result;
+use std::path::PathBuf;
+
+pub use index::IndexAction;
+pub use report::ReportArgs;
+pub use search::SearchArgs;
+pub use statusline::StatuslineArgs;
+
+/// What the binary should do, as decided by the command line
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Start the interactive dashboard (no arguments)
+    Tui,
+    /// Print a usage report to stdout
+    Report(ReportArgs),
+    /// Print a one-line status summary
+    Statusline(StatuslineArgs),
+    /// Maintain the usage and search indexes
+    Index(IndexAction),
+    /// Search session transcripts
+    Search(SearchArgs),
+    /// Print usage information
+    Help,
+}
+
+/// Parsed command line: global options plus the command to run
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cli {
+    /// Claude data directories given with `--claude-dir` (empty when not given)
+    pub claude_dirs: Vec<PathBuf>,
+    pub command: Command,
+}
+
+/// Usage text printed by `--help`
+pub const USAGE: &str = "\
+Usage: cc-enhanced [--claude-dir <PATH>]... [COMMAND]
+
+Commands:
+  (none)                                   Start the interactive dashboard
+  report <daily|weekly|monthly|session|project|blocks>
+                                           Print a usage report without the TUI
+  statusline                               Print a one-line status summary
+  index rebuild                            Re-read all sessions into the usage and search indexes
+  search <QUERY>...                        Find prompts and replies across all sessions
+
+Global options:
+  --claude-dir <PATH>    Claude data directory to read; repeat to merge several.
+                         Defaults to $CLAUDE_CONFIG_DIR (comma-separated), the
+                         claude_dirs config entry, then ~/.claude
+
+Report options:
+  --since <YYYY-MM-DD>   First date to include
+  --until <YYYY-MM-DD>   Last date to include
+  --project <NAME|PATH>  Only include matching projects
+  --format <FORMAT>      table (default), json or csv
+  --debug                Print dropped duplicates and where each model's
+                         pricing came from to stderr
+
+Search options:
+  --regex                Treat the query as a case-insensitive regular expression
+  --project <NAME|PATH>  Only search matching projects
+  --since, --until       Only include messages from these dates
+  --limit <N>            Most hits to print (default 20)
+  --format <FORMAT>      table (default) or json
+
+Statusline options:
+  --format <TEMPLATE>    Placeholders: {cost_today} {block_remaining} {block_end}
+                         {burn_rate} {project} {model}
+  --cache-ttl <SECS>     Reuse cached values for this long (default 10, 0 disables)
+
+  -h, --help             Show this help";
+
+/// Parse command-line arguments (without the program name).
+/// `--claude-dir` is accepted anywhere on the line.
+pub fn parse_args<I>(args: I) -> Result<Cli>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut claude_dirs = Vec::new();
+    let mut rest = Vec::new();
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--claude-dir" {
+            claude_dirs.push(PathBuf::from(flag_value(&arg, &mut args)?));
+        } else {
+            rest.push(arg);
+        }
+    }
+
+    Ok(Cli {
+        claude_dirs,
+        command: parse_command(rest)?,
+    })
+}
+
+/// Parse the command and its own options
+fn parse_command(args: Vec<String>) -> Result<Command> {
+    let mut args = args.into_iter();
+
+    match args.next().as_deref() {
+        None => Ok(Command::Tui),
+        Some("-h") | Some("--help") | Some("help") => Ok(Command::Help),
+        Some("report") => Ok(Command::Report(ReportArgs::parse(args)?)),
+        Some("statusline") => Ok(Command::Statusline(StatuslineArgs::parse(args)?)),
+        Some("index") => Ok(Command::Index(IndexAction::parse(args)?)),
+        Some("search") => Ok(Command::Search(SearchArgs::parse(args)?)),
+        Some(other) => Err(anyhow::anyhow!(
+            "Unknown command '{other}'. Run 'cc-enhanced --help' for usage."
+        )),
+    }
+}
+
+/// Run a headless command against the given Claude data directories
+pub fn run(command: Command, claude_dirs: &[PathBuf]) -> Result<()> {
+    match command {
+        Command::Tui => Ok(()),
+        Command::Help => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        Command::Report(args) => report::run(&args, claude_dirs),
+        Command::Statusline(args) => statusline::run(&args, claude_dirs),
+        Command::Index(action) => index::run(action, claude_dirs),
+        Command::Search(args) => search::run(&args, claude_dirs),
+    }
+}
+
+/// Take the value that follows a `--flag`
+fn flag_value<I>(flag: &str, args: &mut I) -> Result<String>
+where
+    I: Iterator<Item = String>,
+{
+    args.next()
+        .ok_or_else(|| anyhow::anyhow!("Missing value for {flag}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::report::ReportKind;
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn parse(args: &[&str]) -> Result<Command> {
+        parse_args(args.iter().map(|s| s.to_string())).map(|cli| cli.command)
+    }
+
+    #[test]
+    fn test_no_arguments_starts_tui() {
+        assert_eq!(parse(&[]).unwrap(), Command::Tui);
+    }
+
+    #[test]
+    fn test_help_flags() {
+        assert_eq!(parse(&["--help"]).unwrap(), Command::Help);
+        assert_eq!(parse(&["-h"]).unwrap(), Command::Help);
+    }
+
+    #[test]
+    fn test_unknown_command_is_rejected() {
+        assert!(parse(&["frobnicate"]).is_err());
+    }
+
+    #[test]
+    fn test_report_with_filters() {
+        let command = parse(&[
+            "report",
+            "daily",
+            "--since",
+            "2025-01-01",
+            "--until",
+            "2025-01-31",
+            "--project",
+            "cc-enhanced",
+            "--debug",
+        ])
+        .unwrap();
+
+        match command {
+            Command::Report(args) => {
+                assert_eq!(args.kind, ReportKind::Daily);
+                assert_eq!(args.since, NaiveDate::from_ymd_opt(2025, 1, 1));
+                assert_eq!(args.until, NaiveDate::from_ymd_opt(2025, 1, 31));
+                assert_eq!(args.project.as_deref(), Some("cc-enhanced"));
+                assert!(args.debug);
+            }
+            other => panic!("expected report command, got {other:?}"),
+        }
+
+        assert!(matches!(
+            parse(&["report", "blocks"]).unwrap(),
+            Command::Report(args) if args.kind == ReportKind::Blocks
+        ));
+    }
+
+    #[test]
+    fn test_statusline_command() {
+        match parse(&["statusline", "--format", "{model}"]).unwrap() {
+            Command::Statusline(args) => assert_eq!(args.template.as_deref(), Some("{model}")),
+            other => panic!("expected statusline command, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_index_command() {
+        assert_eq!(
+            parse(&["index", "rebuild"]).unwrap(),
+            Command::Index(IndexAction::Rebuild)
+        );
+        assert!(parse(&["index"]).is_err());
+        assert!(parse(&["index", "drop"]).is_err());
+        assert!(parse(&["index", "rebuild", "now"]).is_err());
+    }
+
+    #[test]
+    fn test_search_command() {
+        match parse(&[
+            "search",
+            "migration",
+            "bug",
+            "--project",
+            "api",
+            "--limit",
+            "5",
+        ])
+        .unwrap()
+        {
+            Command::Search(args) => {
+                assert_eq!(args.text, "migration bug");
+                assert_eq!(args.project.as_deref(), Some("api"));
+                assert_eq!(args.limit, 5);
+                assert!(!args.regex);
+            }
+            other => panic!("expected search command, got {other:?}"),
+        }
+        assert!(parse(&["search"]).is_err());
+        assert!(parse(&["search", "bug", "--limit", "0"]).is_err());
+        assert!(parse(&["search", "bug", "--fuzzy"]).is_err());
+    }
+
+    #[test]
+    fn test_claude_dir_is_global_and_repeatable() {
+        let cli = parse_args(
+            [
+                "--claude-dir",
+                "/a",
+                "report",
+                "daily",
+                "--claude-dir",
+                "/b",
+            ]
+            .iter()
+            .map(|s| s.to_string()),
+        )
+        .unwrap();
+
+        assert_eq!(
+            cli.claude_dirs,
+            vec![PathBuf::from("/a"), PathBuf::from("/b")]
+        );
+        assert!(matches!(cli.command, Command::Report(_)));
+
+        assert!(parse(&["--claude-dir"]).is_err());
+        assert_eq!(parse(&["--claude-dir", "/a"]).unwrap(), Command::Tui);
+    }
+
+    #[test]
+    fn test_report_requires_valid_kind_and_dates() {
+        assert!(parse(&["report"]).is_err());
+        assert!(parse(&["report", "yearly"]).is_err());
+        assert!(parse(&["report", "daily", "--since", "yesterday"]).is_err());
+        assert!(parse(&["report", "daily", "--since"]).is_err());
+        assert!(parse(&[
+            "report",
+            "daily",
+            "--until",
+            "2025-01-01",
+            "--since",
+            "2025-02-01"
+        ])
+        .is_err());
+    }
+}
@@ -0,0 +1,1309 @@
+//! Headless usage reports - prints tables, JSON or CSV to stdout
+
+use anyhow::Result;
+use chrono::{Duration, Local, NaiveDate};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use unicode_width::UnicodeWidthStr;
+
+use super::flag_value;
+use crate::claude::blocks::{self, BlockGap, BlockSummary};
+use crate::claude::periods::{MonthToDate, Period, PeriodKind, PeriodSettings};
+use crate::claude::{
+    ClaudeDataManager, DailyUsage, ModelUsage, ProjectAnalytics, SessionAnalytics, UsageFilter,
+    UsageStats,
+};
+use crate::ui::{format_cost, format_duration};
+
+/// Version of the JSON report layout.
+/// Bump whenever a field is renamed or removed so consumers can detect the change.
+pub const REPORT_SCHEMA_VERSION: u32 = 1;
+
+/// Which report to print
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReportKind {
+    Daily,
+    Weekly,
+    Monthly,
+    Session,
+    Project,
+    Blocks,
+}
+
+impl ReportKind {
+    /// Parse a report name from the command line
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "daily" => Some(Self::Daily),
+            "weekly" => Some(Self::Weekly),
+            "monthly" => Some(Self::Monthly),
+            "session" | "sessions" => Some(Self::Session),
+            "project" | "projects" => Some(Self::Project),
+            "block" | "blocks" => Some(Self::Blocks),
+            _ => None,
+        }
+    }
+
+    /// Canonical report name, used in JSON output
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
+            Self::Monthly => "monthly",
+            Self::Session => "session",
+            Self::Project => "project",
+            Self::Blocks => "blocks",
+        }
+    }
+}
+
+/// How a report is written to stdout
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OutputFormat {
+    /// Aligned plain-text tables for humans
+    #[default]
+    Table,
+    /// One JSON document carrying `schema_version`
+    Json,
+    /// The report's main table as CSV with a header row
+    Csv,
+}
+
+impl OutputFormat {
+    /// Parse a format name from the command line
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "table" => Some(Self::Table),
+            "json" => Some(Self::Json),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+}
+
+/// Arguments for `cc-enhanced report`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportArgs {
+    pub kind: ReportKind,
+    pub since: Option<NaiveDate>,
+    pub until: Option<NaiveDate>,
+    pub project: Option<String>,
+    pub format: OutputFormat,
+    /// Print diagnostics (such as dropped duplicate messages) to stderr
+    pub debug: bool,
+}
+
+impl ReportArgs {
+    /// Parse the arguments that follow `report`
+    pub fn parse<I>(args: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut args = args.into_iter();
+
+        let kind_name = args.next().ok_or_else(|| {
+            anyhow::anyhow!(
+                "Missing report type (daily, weekly, monthly, session, project or blocks)"
+            )
+        })?;
+        let kind = ReportKind::from_name(&kind_name)
+            .ok_or_else(|| anyhow::anyhow!("Unknown report type '{kind_name}'"))?;
+
+        let mut report_args = Self {
+            kind,
+            since: None,
+            until: None,
+            project: None,
+            format: OutputFormat::default(),
+            debug: false,
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--since" => report_args.since = Some(parse_date(&flag_value(&arg, &mut args)?)?),
+                "--until" => report_args.until = Some(parse_date(&flag_value(&arg, &mut args)?)?),
+                "--project" => report_args.project = Some(flag_value(&arg, &mut args)?),
+                "--debug" => report_args.debug = true,
+                "--format" => {
+                    let name = flag_value(&arg, &mut args)?;
+                    report_args.format = OutputFormat::from_name(&name).ok_or_else(|| {
+                        anyhow::anyhow!("Unknown format '{name}' (expected table, json or csv)")
+                    })?;
+                }
+                other => return Err(anyhow::anyhow!("Unknown report option '{other}'")),
+            }
+        }
+
+        if let (Some(since), Some(until)) = (report_args.since, report_args.until) {
+            if since > until {
+                return Err(anyhow::anyhow!("--since must not be after --until"));
+            }
+        }
+
+        Ok(report_args)
+    }
+}
+
+/// Parse a `YYYY-MM-DD` date
+pub(super) fn parse_date(value: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| anyhow::anyhow!("Invalid date '{value}', expected YYYY-MM-DD"))
+}
+
+/// A complete report, independent of the output format
+#[derive(Debug, Serialize)]
+struct Report {
+    schema_version: u32,
+    report: &'static str,
+    since: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+    project: Option<String>,
+    #[serde(flatten)]
+    data: ReportData,
+}
+
+/// Report body; each variant serializes its fields at the top level of the JSON document
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum ReportData {
+    Daily {
+        days: Vec<DailyUsage>,
+        models: Vec<ModelUsage>,
+    },
+    Weekly {
+        weeks: Vec<WeeklyUsage>,
+        models: Vec<ModelUsage>,
+    },
+    Monthly {
+        months: Vec<MonthlyUsage>,
+        models: Vec<ModelUsage>,
+        /// The current billing month so far and its projection, when the range covers it
+        #[serde(skip_serializing_if = "Option::is_none")]
+        month_to_date: Option<MonthToDate>,
+    },
+    Session {
+        sessions: Vec<SessionAnalytics>,
+    },
+    Project {
+        projects: Vec<ProjectReport>,
+    },
+    Blocks {
+        blocks: Vec<BlockSummary>,
+        /// Idle periods between blocks in the range, across all projects
+        gaps: Vec<BlockGap>,
+    },
+}
+
+/// Usage summed over one week
+#[derive(Debug, Clone, Serialize)]
+struct WeeklyUsage {
+    /// ISO week ("2025-W23") for weeks starting on Monday, otherwise the first day
+    week: String,
+    start: NaiveDate,
+    usage_stats: UsageStats,
+}
+
+/// Usage summed over one billing month
+#[derive(Debug, Clone, Serialize)]
+struct MonthlyUsage {
+    /// Calendar month ("2025-06") when billing starts on the 1st, otherwise the first day
+    month: String,
+    start: NaiveDate,
+    usage_stats: UsageStats,
+}
+
+/// Analytics for one project in the project report
+#[derive(Debug, Clone, Serialize)]
+struct ProjectReport {
+    project_name: String,
+    project_path: String,
+    /// Claude data directory the project was read from
+    data_root: String,
+    analytics: ProjectAnalytics,
+}
+
+/// Run a report and print it to stdout
+pub fn run(args: &ReportArgs, claude_dirs: &[PathBuf]) -> Result<()> {
+    let manager = ClaudeDataManager::new(claude_dirs)?;
+    let today = Local::now().date_naive();
+    let report = build_report(&manager, args, today)?;
+
+    let output = match args.format {
+        OutputFormat::Table => render_text(&report, today),
+        OutputFormat::Json => format!("{}\n", serde_json::to_string_pretty(&report)?),
+        OutputFormat::Csv => render_csv(&report),
+    };
+
+    print!("{output}");
+
+    if args.debug {
+        eprintln!(
+            "debug: dropped {} duplicate message copies",
+            manager.duplicates_dropped()
+        );
+        if let Some(error) = manager.pricing_override_error() {
+            eprintln!("debug: ignored pricing overrides: {error}");
+        }
+        for (model, pricing) in manager.priced_models() {
+            match pricing.matched {
+                Some(matched) => {
+                    eprintln!("debug: priced {model} from {} ({matched})", pricing.source)
+                }
+                None => eprintln!("debug: priced {model} from {}", pricing.source),
+            }
+        }
+    }
+
+    // The report is already printed, so a cache directory that cannot be written is not an error
+    let _ = manager.save_index();
+    Ok(())
+}
+
+/// Collect the data for a report
+fn build_report(
+    manager: &ClaudeDataManager,
+    args: &ReportArgs,
+    today: NaiveDate,
+) -> Result<Report> {
+    let mut filter = build_filter(manager, args)?;
+    let mut since = args.since;
+    let periods = manager.period_settings();
+
+    let data = match args.kind {
+        ReportKind::Daily => {
+            let start = args.since.unwrap_or(today - Duration::days(6));
+            since = Some(start);
+            filter.since = since;
+
+            let mut days =
+                manager.calculate_daily_usage_filtered(days_since(start, today), &filter)?;
+            days.sort_by_key(|d| d.date);
+
+            ReportData::Daily {
+                days,
+                models: model_usage(manager, &filter)?,
+            }
+        }
+        ReportKind::Weekly => {
+            // Default to the current week and the three before it
+            let start = args
+                .since
+                .unwrap_or_else(|| periods_back(periods, PeriodKind::Week, today, 3).start);
+            since = Some(start);
+            filter.since = since;
+
+            let days = manager.calculate_daily_usage_filtered(days_since(start, today), &filter)?;
+
+            ReportData::Weekly {
+                weeks: aggregate_by_week(&days, periods),
+                models: model_usage(manager, &filter)?,
+            }
+        }
+        ReportKind::Monthly => {
+            // Default to the current billing month and the two before it
+            let start = args
+                .since
+                .unwrap_or_else(|| periods_back(periods, PeriodKind::Month, today, 2).start);
+            since = Some(start);
+            filter.since = since;
+
+            let days = manager.calculate_daily_usage_filtered(days_since(start, today), &filter)?;
+
+            // Only a range that holds the whole billing month so far gives its spend
+            let current = periods.period(PeriodKind::Month, today);
+            let covers_current =
+                start <= current.start && args.until.map_or(true, |until| until >= today);
+            let month_to_date = covers_current.then(|| {
+                MonthToDate::compute(
+                    days.iter()
+                        .map(|day| (day.date, day.usage_stats.total_cost)),
+                    periods,
+                    today,
+                )
+            });
+
+            ReportData::Monthly {
+                months: aggregate_by_month(&days, periods),
+                models: model_usage(manager, &filter)?,
+                month_to_date,
+            }
+        }
+        ReportKind::Session => ReportData::Session {
+            sessions: manager.calculate_session_analytics(&filter)?,
+        },
+        ReportKind::Project => ReportData::Project {
+            projects: project_analytics(manager, &filter)?,
+        },
+        ReportKind::Blocks => block_timeline(manager, args, &filter)?,
+    };
+
+    Ok(Report {
+        schema_version: REPORT_SCHEMA_VERSION,
+        report: args.kind.name(),
+        since,
+        until: args.until,
+        project: args.project.clone(),
+        data,
+    })
+}
+
+/// Build the usage filter for a report, resolving `--project` to data directories
+fn build_filter(manager: &ClaudeDataManager, args: &ReportArgs) -> Result<UsageFilter> {
+    let project_dirs = match &args.project {
+        Some(query) => {
+            let dirs: Vec<PathBuf> = manager
+                .scan_projects()?
+                .into_iter()
+                .filter(|p| p.matches(query))
+                .map(|p| p.data_dir)
+                .collect();
+
+            if dirs.is_empty() {
+                return Err(anyhow::anyhow!("No project matches '{query}'"));
+            }
+            Some(dirs)
+        }
+        None => None,
+    };
+
+    Ok(UsageFilter {
+        since: args.since,
+        until: args.until,
+        project_dirs,
+    })
+}
+
+/// Number of days from `since` up to and including `today`
+fn days_since(since: NaiveDate, today: NaiveDate) -> u32 {
+    ((today - since).num_days() + 1).max(0) as u32
+}
+
+/// Models with any usage in the filtered range
+fn model_usage(manager: &ClaudeDataManager, filter: &UsageFilter) -> Result<Vec<ModelUsage>> {
+    Ok(manager
+        .calculate_model_usage_filtered(filter)?
+        .into_iter()
+        .filter(|m| m.usage_stats.has_usage())
+        .collect())
+}
+
+/// Analytics for every project active in the requested range
+fn project_analytics(
+    manager: &ClaudeDataManager,
+    filter: &UsageFilter,
+) -> Result<Vec<ProjectReport>> {
+    let mut projects = Vec::new();
+
+    for project in manager.scan_projects()? {
+        if !filter.includes_dir(&project.data_dir) {
+            continue;
+        }
+
+        let analytics = manager.calculate_project_analytics(&project)?;
+        let in_range = match analytics.last_session {
+            Some(time) => filter.includes_time(time),
+            None => filter.since.is_none() && filter.until.is_none(),
+        };
+
+        if in_range {
+            projects.push(ProjectReport {
+                project_name: project.name.clone(),
+                project_path: project.path.to_string_lossy().to_string(),
+                data_root: project.root.to_string_lossy().to_string(),
+                analytics,
+            });
+        }
+    }
+
+    Ok(projects)
+}
+
+/// Blocks that started in the requested range, restricted to blocks the matching
+/// projects used, with the gaps between them
+fn block_timeline(
+    manager: &ClaudeDataManager,
+    args: &ReportArgs,
+    filter: &UsageFilter,
+) -> Result<ReportData> {
+    let all_blocks = manager.usage_blocks()?;
+    let gaps = blocks::block_gaps(&all_blocks)
+        .into_iter()
+        .filter(|gap| filter.includes_time(gap.end.into()))
+        .collect();
+
+    let project_names: Option<Vec<String>> = match &args.project {
+        Some(query) => Some(
+            manager
+                .scan_projects()?
+                .into_iter()
+                .filter(|p| p.matches(query))
+                .map(|p| p.name)
+                .collect(),
+        ),
+        None => None,
+    };
+
+    let blocks = all_blocks
+        .into_iter()
+        .filter(|block| filter.includes_time(block.start.into()))
+        .filter(|block| {
+            project_names.as_ref().map_or(true, |names| {
+                block.projects.iter().any(|p| names.contains(&p.name))
+            })
+        })
+        .collect();
+
+    Ok(ReportData::Blocks { blocks, gaps })
+}
+
+/// The period `count` periods before the one containing `today`
+fn periods_back(
+    periods: &PeriodSettings,
+    kind: PeriodKind,
+    today: NaiveDate,
+    count: usize,
+) -> Period {
+    let mut period = periods.period(kind, today);
+    for _ in 0..count {
+        period = periods.previous(kind, period);
+    }
+    period
+}
+
+/// Sum daily usage into weeks or billing months, oldest first
+fn aggregate_by_period(
+    daily: &[DailyUsage],
+    kind: PeriodKind,
+    periods: &PeriodSettings,
+) -> Vec<(String, Period, UsageStats)> {
+    let mut totals: BTreeMap<Period, UsageStats> = BTreeMap::new();
+
+    for day in daily {
+        let period = totals.entry(periods.period(kind, day.date)).or_default();
+        period.merge(&day.usage_stats);
+    }
+
+    totals
+        .into_iter()
+        .map(|(period, usage_stats)| (periods.label(kind, period), period, usage_stats))
+        .collect()
+}
+
+/// Sum daily usage into weeks, oldest first
+fn aggregate_by_week(daily: &[DailyUsage], periods: &PeriodSettings) -> Vec<WeeklyUsage> {
+    aggregate_by_period(daily, PeriodKind::Week, periods)
+        .into_iter()
+        .map(|(week, period, usage_stats)| WeeklyUsage {
+            week,
+            start: period.start,
+            usage_stats,
+        })
+        .collect()
+}
+
+/// Sum daily usage into billing months, oldest first
+fn aggregate_by_month(daily: &[DailyUsage], periods: &PeriodSettings) -> Vec<MonthlyUsage> {
+    aggregate_by_period(daily, PeriodKind::Month, periods)
+        .into_iter()
+        .map(|(month, period, usage_stats)| MonthlyUsage {
+            month,
+            start: period.start,
+            usage_stats,
+        })
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Table output
+// ---------------------------------------------------------------------------
+
+const USAGE_HEADERS: [&str; 7] = [
+    "Messages",
+    "Input",
+    "Output",
+    "Cache Write",
+    "Cache Read",
+    "Total Tokens",
+    "Cost",
+];
+const SESSION_HEADERS: [&str; 7] = [
+    "Session", "Project", "Started", "Duration", "Messages", "Tokens", "Cost",
+];
+const BLOCK_HEADERS: [&str; 7] = [
+    "Start", "End", "Projects", "Models", "Messages", "Tokens", "Cost",
+];
+const PROJECT_HEADERS: [&str; 7] = [
+    "Project",
+    "Sessions",
+    "Messages",
+    "Tokens",
+    "Cost",
+    "Cache",
+    "Last Active",
+];
+
+/// Render a report as human-readable tables
+fn render_text(report: &Report, today: NaiveDate) -> String {
+    let range = report
+        .since
+        .map(|since| describe_range(since, report.until, today))
+        .unwrap_or_default();
+
+    match &report.data {
+        ReportData::Daily { days, models } => {
+            let rows: Vec<(String, &UsageStats)> = days
+                .iter()
+                .map(|d| (d.date.to_string(), &d.usage_stats))
+                .collect();
+            format!(
+                "Daily usage ({range})\n\n{}\n{}",
+                usage_table("Date", &rows),
+                model_table(models)
+            )
+        }
+        ReportData::Weekly { weeks, models } => {
+            let rows: Vec<(String, &UsageStats)> = weeks
+                .iter()
+                .map(|w| (w.week.clone(), &w.usage_stats))
+                .collect();
+            format!(
+                "Weekly usage ({range})\n\n{}\n{}",
+                usage_table("Week", &rows),
+                model_table(models)
+            )
+        }
+        ReportData::Monthly {
+            months,
+            models,
+            month_to_date,
+        } => {
+            let rows: Vec<(String, &UsageStats)> = months
+                .iter()
+                .map(|m| (m.month.clone(), &m.usage_stats))
+                .collect();
+            let month_to_date = month_to_date
+                .as_ref()
+                .map(|mtd| format!("{}\n", describe_month_to_date(mtd)))
+                .unwrap_or_default();
+            format!(
+                "Monthly usage ({range})\n\n{}{month_to_date}\n{}",
+                usage_table("Month", &rows),
+                model_table(models)
+            )
+        }
+        ReportData::Session { sessions } => {
+            if sessions.is_empty() {
+                return "No sessions found.\n".to_string();
+            }
+
+            let rows: Vec<Vec<String>> = sessions
+                .iter()
+                .map(|s| {
+                    vec![
+                        s.session_id.chars().take(8).collect(),
+                        s.project_name.clone(),
+                        format_timestamp(&s.start_time),
+                        format!("{:.0}m", s.duration_minutes),
+                        s.message_count.to_string(),
+                        format_count(s.total_tokens),
+                        format_cost(s.total_cost),
+                    ]
+                })
+                .collect();
+            render_table(&SESSION_HEADERS, &rows, 3)
+        }
+        ReportData::Project { projects } => {
+            if projects.is_empty() {
+                return "No projects found.\n".to_string();
+            }
+
+            let rows: Vec<Vec<String>> = projects
+                .iter()
+                .map(|p| {
+                    vec![
+                        p.project_name.clone(),
+                        p.analytics.total_sessions.to_string(),
+                        p.analytics.total_messages.to_string(),
+                        format_count(p.analytics.total_tokens),
+                        format_cost(p.analytics.estimated_cost),
+                        format!("{:.1}%", p.analytics.cache_efficiency),
+                        p.analytics
+                            .last_session
+                            .map(|time| {
+                                chrono::DateTime::<Local>::from(time)
+                                    .format("%Y-%m-%d %H:%M")
+                                    .to_string()
+                            })
+                            .unwrap_or_else(|| "-".to_string()),
+                    ]
+                })
+                .collect();
+            render_table(&PROJECT_HEADERS, &rows, 1)
+        }
+        ReportData::Blocks { blocks, gaps } => render_blocks(blocks, gaps),
+    }
+}
+
+/// One-line summary of the billing month so far and where it is heading
+fn describe_month_to_date(mtd: &MonthToDate) -> String {
+    let last_day = mtd.period.end - Duration::days(1);
+    format!(
+        "\nMonth to date: {} over {} of {} days, projected {} by {last_day}",
+        format_cost(mtd.spent),
+        mtd.days_elapsed,
+        mtd.period.days(),
+        format_cost(mtd.projected),
+    )
+}
+
+/// Blocks oldest first, with a row for each idle gap and the active block's projection
+fn render_blocks(blocks: &[BlockSummary], gaps: &[BlockGap]) -> String {
+    if blocks.is_empty() {
+        return "No blocks found.\n".to_string();
+    }
+
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    for (i, block) in blocks.iter().enumerate() {
+        let gap_before = gaps.iter().find(|gap| gap.end == block.start);
+        if let Some(gap) = gap_before.filter(|_| i > 0) {
+            rows.push(vec![format!("  idle {}", format_duration(gap.duration()))]);
+        }
+
+        let end = chrono::DateTime::<Local>::from(block.end).format("%H:%M");
+        rows.push(vec![
+            chrono::DateTime::<Local>::from(block.start)
+                .format("%Y-%m-%d %H:%M")
+                .to_string(),
+            if block.is_active {
+                format!("{end} (active)")
+            } else {
+                end.to_string()
+            },
+            block
+                .projects
+                .iter()
+                .map(|p| p.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+            block.models.join(", "),
+            block.messages.to_string(),
+            format_count(block.tokens),
+            format_cost(block.cost),
+        ]);
+    }
+
+    let mut output = render_table(&BLOCK_HEADERS, &rows, 4);
+    if let Some((block, projection)) = blocks
+        .iter()
+        .find_map(|block| block.projection.map(|projection| (block, projection)))
+    {
+        output.push_str(&format!(
+            "\nActive block projected to reach {} tokens and {} by {}\n",
+            format_count(projection.tokens),
+            format_cost(projection.cost),
+            chrono::DateTime::<Local>::from(block.end).format("%H:%M")
+        ));
+    }
+    output
+}
+
+/// Per-model breakdown appended to the daily and monthly tables
+fn model_table(models: &[ModelUsage]) -> String {
+    if models.is_empty() {
+        return String::new();
+    }
+
+    let rows: Vec<(String, &UsageStats)> = models
+        .iter()
+        .map(|m| (m.model_name.clone(), &m.usage_stats))
+        .collect();
+    format!("By model\n\n{}", usage_table("Model", &rows))
+}
+
+/// Token/cost table with a trailing total row
+fn usage_table(label: &str, rows: &[(String, &UsageStats)]) -> String {
+    let mut total = UsageStats::default();
+    let mut table_rows: Vec<Vec<String>> = rows
+        .iter()
+        .map(|(key, stats)| {
+            total.merge(stats);
+            usage_cells(key.clone(), stats, false)
+        })
+        .collect();
+
+    if rows.len() > 1 {
+        table_rows.push(usage_cells("Total".to_string(), &total, false));
+    }
+
+    let mut headers = vec![label];
+    headers.extend(USAGE_HEADERS);
+    render_table(&headers, &table_rows, 1)
+}
+
+/// Format one usage row; `raw` keeps plain numbers for machine-readable output
+fn usage_cells(key: String, stats: &UsageStats, raw: bool) -> Vec<String> {
+    let count = |value: u64| {
+        if raw {
+            value.to_string()
+        } else {
+            format_count(value)
+        }
+    };
+
+    vec![
+        key,
+        stats.message_count.to_string(),
+        count(stats.input_tokens),
+        count(stats.output_tokens),
+        count(stats.cache_creation_tokens),
+        count(stats.cache_read_tokens),
+        count(stats.total_tokens()),
+        if raw {
+            format!("{:.6}", stats.total_cost)
+        } else {
+            format_cost(stats.total_cost)
+        },
+    ]
+}
+
+/// Render rows as an aligned plain-text table.
+/// The first `left_columns` columns are left-aligned, the rest are right-aligned.
+pub(super) fn render_table(headers: &[&str], rows: &[Vec<String>], left_columns: usize) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.width()).collect();
+    for row in rows {
+        for (i, cell) in row.iter().enumerate() {
+            if let Some(width) = widths.get_mut(i) {
+                *width = (*width).max(cell.width());
+            }
+        }
+    }
+
+    let format_row = |cells: &mut dyn Iterator<Item = &str>| -> String {
+        let line: Vec<String> = cells
+            .enumerate()
+            .map(|(i, cell)| {
+                let padding = " ".repeat(widths[i].saturating_sub(cell.width()));
+                if i < left_columns {
+                    format!("{cell}{padding}")
+                } else {
+                    format!("{padding}{cell}")
+                }
+            })
+            .collect();
+        line.join("  ").trim_end().to_string()
+    };
+
+    let mut output = format_row(&mut headers.iter().copied());
+    output.push('\n');
+    output.push_str(
+        &widths
+            .iter()
+            .map(|w| "-".repeat(*w))
+            .collect::<Vec<_>>()
+            .join("  "),
+    );
+    output.push('\n');
+    for row in rows {
+        output.push_str(&format_row(&mut row.iter().map(|s| s.as_str())));
+        output.push('\n');
+    }
+
+    output
+}
+
+// ---------------------------------------------------------------------------
+// CSV output
+// ---------------------------------------------------------------------------
+
+const CSV_USAGE_COLUMNS: [&str; 7] = [
+    "messages",
+    "input_tokens",
+    "output_tokens",
+    "cache_creation_tokens",
+    "cache_read_tokens",
+    "total_tokens",
+    "cost_usd",
+];
+
+/// Render a report's main table as CSV (the per-model breakdown and block gaps are JSON/table only)
+fn render_csv(report: &Report) -> String {
+    let (headers, rows): (Vec<&str>, Vec<Vec<String>>) = match &report.data {
+        ReportData::Daily { days, .. } => {
+            let mut headers = vec!["date"];
+            headers.extend(CSV_USAGE_COLUMNS);
+            let rows = days
+                .iter()
+                .map(|d| usage_cells(d.date.to_string(), &d.usage_stats, true))
+                .collect();
+            (headers, rows)
+        }
+        ReportData::Weekly { weeks, .. } => {
+            let mut headers = vec!["week"];
+            headers.extend(CSV_USAGE_COLUMNS);
+            let rows = weeks
+                .iter()
+                .map(|w| usage_cells(w.week.clone(), &w.usage_stats, true))
+                .collect();
+            (headers, rows)
+        }
+        ReportData::Monthly { months, .. } => {
+            let mut headers = vec!["month"];
+            headers.extend(CSV_USAGE_COLUMNS);
+            let rows = months
+                .iter()
+                .map(|m| usage_cells(m.month.clone(), &m.usage_stats, true))
+                .collect();
+            (headers, rows)
+        }
+        ReportData::Session { sessions } => {
+            let headers = vec![
+                "session_id",
+                "project_name",
+                "start_time",
+                "end_time",
+                "duration_minutes",
+                "messages",
+                "total_tokens",
+                "cost_usd",
+            ];
+            let rows = sessions
+                .iter()
+                .map(|s| {
+                    vec![
+                        s.session_id.clone(),
+                        s.project_name.clone(),
+                        s.start_time.clone(),
+                        s.end_time.clone(),
+                        format!("{:.0}", s.duration_minutes),
+                        s.message_count.to_string(),
+                        s.total_tokens.to_string(),
+                        format!("{:.6}", s.total_cost),
+                    ]
+                })
+                .collect();
+            (headers, rows)
+        }
+        ReportData::Project { projects } => {
+            let headers = vec![
+                "project_name",
+                "project_path",
+                "sessions",
+                "messages",
+                "total_tokens",
+                "cost_usd",
+                "cache_efficiency",
+                "last_session",
+            ];
+            let rows = projects
+                .iter()
+                .map(|p| {
+                    vec![
+                        p.project_name.clone(),
+                        p.project_path.clone(),
+                        p.analytics.total_sessions.to_string(),
+                        p.analytics.total_messages.to_string(),
+                        p.analytics.total_tokens.to_string(),
+                        format!("{:.6}", p.analytics.estimated_cost),
+                        format!("{:.2}", p.analytics.cache_efficiency),
+                        p.analytics
+                            .last_session
+                            .map(|time| chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339())
+                            .unwrap_or_default(),
+                    ]
+                })
+                .collect();
+            (headers, rows)
+        }
+        ReportData::Blocks { blocks, .. } => {
+            let headers = vec![
+                "start",
+                "end",
+                "active",
+                "messages",
+                "total_tokens",
+                "cost_usd",
+                "models",
+                "projects",
+                "projected_tokens",
+                "projected_cost_usd",
+            ];
+            let rows = blocks
+                .iter()
+                .map(|b| {
+                    vec![
+                        b.start.to_rfc3339(),
+                        b.end.to_rfc3339(),
+                        b.is_active.to_string(),
+                        b.messages.to_string(),
+                        b.tokens.to_string(),
+                        format!("{:.6}", b.cost),
+                        b.models.join(";"),
+                        b.projects
+                            .iter()
+                            .map(|p| p.name.as_str())
+                            .collect::<Vec<_>>()
+                            .join(";"),
+                        b.projection
+                            .map(|p| p.tokens.to_string())
+                            .unwrap_or_default(),
+                        b.projection
+                            .map(|p| format!("{:.6}", p.cost))
+                            .unwrap_or_default(),
+                    ]
+                })
+                .collect();
+            (headers, rows)
+        }
+    };
+
+    let mut output = csv_line(headers.iter().copied());
+    for row in &rows {
+        output.push_str(&csv_line(row.iter().map(|s| s.as_str())));
+    }
+    output
+}
+
+/// Join fields into one CSV line, quoting where RFC 4180 requires it
+fn csv_line<'a>(fields: impl Iterator<Item = &'a str>) -> String {
+    let fields: Vec<String> = fields
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect();
+    format!("{}\n", fields.join(","))
+}
+
+// ---------------------------------------------------------------------------
+// Formatting helpers
+// ---------------------------------------------------------------------------
+
+/// Format a count with thousands separators
+fn format_count(value: u64) -> String {
+    let digits = value.to_string();
+    let mut formatted = String::with_capacity(digits.len() + digits.len() / 3);
+
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            formatted.push(',');
+        }
+        formatted.push(c);
+    }
+
+    formatted
+}
+
+/// Format an RFC 3339 timestamp as local `YYYY-MM-DD HH:MM`
+fn format_timestamp(timestamp: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .map(|dt| {
+            dt.with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        })
+        .unwrap_or_else(|_| "-".to_string())
+}
+
+/// Describe the effective date range of a report
+fn describe_range(since: NaiveDate, until: Option<NaiveDate>, today: NaiveDate) -> String {
+    let end = until.map_or(today, |until| until.min(today));
+    format!("{since} to {end}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(input: u64, output: u64, cost: f64) -> UsageStats {
+        UsageStats {
+            input_tokens: input,
+            output_tokens: output,
+            total_cost: cost,
+            message_count: 1,
+            ..Default::default()
+        }
+    }
+
+    fn daily_report() -> Report {
+        Report {
+            schema_version: REPORT_SCHEMA_VERSION,
+            report: "daily",
+            since: NaiveDate::from_ymd_opt(2025, 1, 1),
+            until: None,
+            project: None,
+            data: ReportData::Daily {
+                days: vec![DailyUsage {
+                    date: NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+                    usage_stats: stats(100, 10, 0.5),
+                }],
+                models: vec![ModelUsage {
+                    model_name: "claude-sonnet-4".to_string(),
+                    usage_stats: stats(100, 10, 0.5),
+                }],
+            },
+        }
+    }
+
+    #[test]
+    fn test_format_count() {
+        assert_eq!(format_count(0), "0");
+        assert_eq!(format_count(999), "999");
+        assert_eq!(format_count(1_000), "1,000");
+        assert_eq!(format_count(12_345_678), "12,345,678");
+    }
+
+    #[test]
+    fn test_days_since() {
+        let today = NaiveDate::from_ymd_opt(2025, 3, 10).unwrap();
+        assert_eq!(days_since(today, today), 1);
+        assert_eq!(
+            days_since(NaiveDate::from_ymd_opt(2025, 3, 4).unwrap(), today),
+            7
+        );
+        assert_eq!(
+            days_since(NaiveDate::from_ymd_opt(2025, 3, 12).unwrap(), today),
+            0
+        );
+    }
+
+    #[test]
+    fn test_aggregate_by_month() {
+        let daily = vec![
+            DailyUsage {
+                date: NaiveDate::from_ymd_opt(2025, 1, 30).unwrap(),
+                usage_stats: stats(100, 10, 0.5),
+            },
+            DailyUsage {
+                date: NaiveDate::from_ymd_opt(2025, 1, 31).unwrap(),
+                usage_stats: stats(200, 20, 0.25),
+            },
+            DailyUsage {
+                date: NaiveDate::from_ymd_opt(2025, 2, 1).unwrap(),
+                usage_stats: stats(50, 5, 1.0),
+            },
+        ];
+
+        let months = aggregate_by_month(&daily, &PeriodSettings::default());
+        assert_eq!(months.len(), 2);
+
+        let january = &months[0];
+        assert_eq!(january.month, "2025-01");
+        assert_eq!(january.usage_stats.input_tokens, 300);
+        assert_eq!(january.usage_stats.output_tokens, 30);
+        assert_eq!(january.usage_stats.message_count, 2);
+        assert!((january.usage_stats.total_cost - 0.75).abs() < f64::EPSILON);
+
+        assert_eq!(months[1].month, "2025-02");
+        assert_eq!(months[1].usage_stats.input_tokens, 50);
+    }
+
+    #[test]
+    fn test_weekly_and_billing_month_aggregation() {
+        let day = |month: u32, day: u32, cost: f64| DailyUsage {
+            date: NaiveDate::from_ymd_opt(2025, month, day).unwrap(),
+            usage_stats: stats(100, 10, cost),
+        };
+        // Sunday 1 June, Monday 2 June, Sunday 8 June and Monday 16 June
+        let daily = vec![
+            day(6, 1, 1.0),
+            day(6, 2, 2.0),
+            day(6, 8, 4.0),
+            day(6, 16, 8.0),
+        ];
+
+        let weeks = aggregate_by_week(&daily, &PeriodSettings::default());
+        let labels: Vec<&str> = weeks.iter().map(|w| w.week.as_str()).collect();
+        assert_eq!(labels, vec!["2025-W22", "2025-W23", "2025-W25"]);
+        assert!((weeks[1].usage_stats.total_cost - 6.0).abs() < 1e-9);
+
+        let billing = PeriodSettings {
+            billing_day: 15,
+            ..Default::default()
+        };
+        let months = aggregate_by_month(&daily, &billing);
+        let labels: Vec<&str> = months.iter().map(|m| m.month.as_str()).collect();
+        assert_eq!(labels, vec!["2025-05-15", "2025-06-15"]);
+        assert!((months[0].usage_stats.total_cost - 7.0).abs() < 1e-9);
+
+        let today = NaiveDate::from_ymd_opt(2025, 6, 17).unwrap();
+        let mtd = MonthToDate::compute(
+            daily
+                .iter()
+                .map(|day| (day.date, day.usage_stats.total_cost)),
+            &billing,
+            today,
+        );
+        assert_eq!(
+            describe_month_to_date(&mtd),
+            "\nMonth to date: $8.00 over 3 of 30 days, projected $80.00 by 2025-07-14"
+        );
+    }
+
+    #[test]
+    fn test_totals_beyond_u32_range() {
+        // A heavy month of cache reads is well past u32::MAX (about 4.29 billion)
+        let day = |day: u32| DailyUsage {
+            date: NaiveDate::from_ymd_opt(2025, 3, day).unwrap(),
+            usage_stats: UsageStats {
+                cache_read_tokens: 3_000_000_000,
+                message_count: 1,
+                ..Default::default()
+            },
+        };
+
+        let months = aggregate_by_month(&[day(1), day(2)], &PeriodSettings::default());
+        let march = &months[0].usage_stats;
+        assert_eq!(march.cache_read_tokens, 6_000_000_000);
+        assert_eq!(march.total_tokens(), 6_000_000_000);
+
+        let rows = vec![("2025-03".to_string(), march)];
+        let table = usage_table("Month", &rows);
+        assert!(table.contains("6,000,000,000"));
+
+        // Sums saturate instead of wrapping around
+        let mut huge = UsageStats {
+            cache_read_tokens: u64::MAX - 1,
+            ..Default::default()
+        };
+        huge.merge(march);
+        assert_eq!(huge.cache_read_tokens, u64::MAX);
+        assert_eq!(huge.total_tokens(), u64::MAX);
+    }
+
+    #[test]
+    fn test_render_table_alignment() {
+        let table = render_table(
+            &["Name", "Tokens"],
+            &[
+                vec!["alpha".to_string(), "1,000".to_string()],
+                vec!["b".to_string(), "5".to_string()],
+            ],
+            1,
+        );
+
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[0], "Name   Tokens");
+        assert_eq!(lines[1], "-----  ------");
+        assert_eq!(lines[2], "alpha   1,000");
+        assert_eq!(lines[3], "b           5");
+    }
+
+    #[test]
+    fn test_usage_table_adds_total_row() {
+        let first = stats(100, 10, 0.5);
+        let second = stats(200, 20, 1.5);
+        let rows = vec![
+            ("2025-01-01".to_string(), &first),
+            ("2025-01-02".to_string(), &second),
+        ];
+
+        let table = usage_table("Date", &rows);
+        let total_line = table.lines().last().unwrap();
+        assert!(total_line.starts_with("Total"));
+        assert!(total_line.contains("330"));
+        assert!(total_line.ends_with("$2.00"));
+    }
+
+    #[test]
+    fn test_parse_format_flag() {
+        let args =
+            ReportArgs::parse(["daily", "--format", "json"].iter().map(|s| s.to_string())).unwrap();
+        assert_eq!(args.format, OutputFormat::Json);
+
+        let args = ReportArgs::parse(["daily"].iter().map(|s| s.to_string())).unwrap();
+        assert_eq!(args.format, OutputFormat::Table);
+
+        assert!(
+            ReportArgs::parse(["daily", "--format", "xml"].iter().map(|s| s.to_string())).is_err()
+        );
+    }
+
+    #[test]
+    fn test_json_schema_is_versioned_and_flat() {
+        let value = serde_json::to_value(daily_report()).unwrap();
+
+        assert_eq!(value["schema_version"], REPORT_SCHEMA_VERSION);
+        assert_eq!(value["report"], "daily");
+        assert_eq!(value["since"], "2025-01-01");
+        assert!(value["until"].is_null());
+        assert_eq!(value["days"][0]["date"], "2025-01-01");
+        assert_eq!(value["days"][0]["usage_stats"]["input_tokens"], 100);
+        assert_eq!(value["models"][0]["model_name"], "claude-sonnet-4");
+        assert!(value["days"][0]["usage_stats"]["reset_time"].is_null());
+    }
+
+    #[test]
+    fn test_csv_output() {
+        let csv = render_csv(&daily_report());
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(
+            lines[0],
+            "date,messages,input_tokens,output_tokens,cache_creation_tokens,cache_read_tokens,total_tokens,cost_usd"
+        );
+        assert_eq!(lines[1], "2025-01-01,1,100,10,0,0,110,0.500000");
+        assert_eq!(lines.len(), 2);
+    }
+
+    #[test]
+    fn test_blocks_table_shows_gaps_and_projection() {
+        use crate::claude::blocks::{BlockProject, BlockProjection};
+        use chrono::TimeZone;
+
+        let at = |day: u32, hour: u32| {
+            chrono::Utc
+                .with_ymd_and_hms(2025, 6, day, hour, 0, 0)
+                .unwrap()
+        };
+        let block = |start: chrono::DateTime<chrono::Utc>, projection: Option<BlockProjection>| {
+            BlockSummary {
+                start,
+                end: start + blocks::block_duration(),
+                last_activity: start,
+                tokens: 1_500,
+                cost: 0.25,
+                messages: 3,
+                models: vec!["claude-sonnet-4".to_string()],
+                projects: vec![BlockProject {
+                    name: "web".to_string(),
+                    tokens: 1_500,
+                    cost: 0.25,
+                }],
+                is_active: projection.is_some(),
+                projection,
+            }
+        };
+        let blocks = vec![
+            block(at(1, 9), None),
+            block(
+                at(2, 1),
+                Some(BlockProjection {
+                    tokens: 6_000,
+                    cost: 1.0,
+                }),
+            ),
+        ];
+        let gaps = blocks::block_gaps(&blocks);
+
+        let table = render_blocks(&blocks, &gaps);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[0].split_whitespace().next(), Some("Start"));
+        assert!(lines[2].contains("web") && lines[2].ends_with("1,500  $0.25"));
+        assert_eq!(lines[3].trim(), "idle 11h 0m");
+        assert!(lines[4].contains("(active)"));
+        assert!(table.contains("projected to reach 6,000 tokens and $1.00"));
+    }
+
+    #[test]
+    fn test_csv_line_quoting() {
+        assert_eq!(csv_line(["a", "b"].into_iter()), "a,b\n");
+        assert_eq!(
+            csv_line(["my, project", "say \"hi\""].into_iter()),
+            "\"my, project\",\"say \"\"hi\"\"\"\n"
+        );
+    }
+}
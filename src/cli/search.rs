@@ -0,0 +1,174 @@
+//! Full-text search of session transcripts from the command line
+
+use anyhow::Result;
+use chrono::{Local, NaiveDate};
+use serde::Serialize;
+use std::path::PathBuf;
+
+use super::flag_value;
+use super::report::{parse_date, render_table};
+use crate::claude::scan;
+use crate::claude::search::{self, SearchHit, SearchIndex, SearchQuery};
+use crate::claude::ClaudeDataManager;
+
+/// Version of the JSON search output layout
+pub const SEARCH_SCHEMA_VERSION: u32 = 1;
+
+/// How hits are printed
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SearchFormat {
+    /// An aligned plain-text table
+    #[default]
+    Table,
+    /// One JSON document carrying `schema_version`
+    Json,
+}
+
+/// Arguments for `cc-enhanced search`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchArgs {
+    /// The words to find, or the pattern with `--regex`
+    pub text: String,
+    pub regex: bool,
+    pub project: Option<String>,
+    pub since: Option<NaiveDate>,
+    pub until: Option<NaiveDate>,
+    pub limit: usize,
+    pub format: SearchFormat,
+}
+
+impl SearchArgs {
+    /// Parse the arguments that follow `search`
+    pub fn parse<I>(args: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut args = args.into_iter();
+        let mut words = Vec::new();
+        let mut search_args = Self {
+            text: String::new(),
+            regex: false,
+            project: None,
+            since: None,
+            until: None,
+            limit: search::DEFAULT_LIMIT,
+            format: SearchFormat::default(),
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--regex" => search_args.regex = true,
+                "--project" => search_args.project = Some(flag_value(&arg, &mut args)?),
+                "--since" => search_args.since = Some(parse_date(&flag_value(&arg, &mut args)?)?),
+                "--until" => search_args.until = Some(parse_date(&flag_value(&arg, &mut args)?)?),
+                "--limit" => {
+                    let value = flag_value(&arg, &mut args)?;
+                    search_args.limit = value
+                        .parse()
+                        .ok()
+                        .filter(|limit| *limit > 0)
+                        .ok_or_else(|| anyhow::anyhow!("Invalid limit '{value}'"))?;
+                }
+                "--format" => {
+                    search_args.format = match flag_value(&arg, &mut args)?.as_str() {
+                        "table" => SearchFormat::Table,
+                        "json" => SearchFormat::Json,
+                        other => {
+                            return Err(anyhow::anyhow!(
+                                "Unknown format '{other}' (expected table or json)"
+                            ))
+                        }
+                    };
+                }
+                other if other.starts_with("--") => {
+                    return Err(anyhow::anyhow!("Unknown search option '{other}'"))
+                }
+                _ => words.push(arg),
+            }
+        }
+
+        if words.is_empty() {
+            return Err(anyhow::anyhow!("Missing search query"));
+        }
+        search_args.text = words.join(" ");
+        Ok(search_args)
+    }
+
+    /// The query these arguments describe
+    fn query(&self) -> Result<SearchQuery> {
+        let mut query = SearchQuery::new(&self.text, self.regex)?;
+        query.project = self.project.clone();
+        query.since = self.since;
+        query.until = self.until;
+        query.limit = self.limit;
+        Ok(query)
+    }
+}
+
+/// Search output as JSON
+#[derive(Serialize)]
+struct SearchOutput<'a> {
+    schema_version: u32,
+    query: &'a str,
+    regex: bool,
+    hits: &'a [SearchHit],
+}
+
+/// Run a search and print the hits, best match first
+pub fn run(args: &SearchArgs, claude_dirs: &[PathBuf]) -> Result<()> {
+    let query = args.query()?;
+    let manager = ClaudeDataManager::new(claude_dirs)?;
+    let projects = manager.scan_projects()?;
+
+    let mut index = SearchIndex::open_default();
+    let hits = manager.search_sessions(&mut index, &projects, &query, &scan::no_progress)?;
+    // A failed save only costs re-reading the sessions next time
+    let _ = index.save();
+
+    match args.format {
+        SearchFormat::Json => {
+            let output = SearchOutput {
+                schema_version: SEARCH_SCHEMA_VERSION,
+                query: &args.text,
+                regex: args.regex,
+                hits: &hits,
+            };
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        }
+        SearchFormat::Table if hits.is_empty() => println!("No matches for '{}'", args.text),
+        SearchFormat::Table => print!("{}", render_hits(&hits)),
+    }
+    Ok(())
+}
+
+/// Hits as a table, numbering the messages of each session from 1
+fn render_hits(hits: &[SearchHit]) -> String {
+    let rows: Vec<Vec<String>> = hits
+        .iter()
+        .map(|hit| {
+            vec![
+                hit.time
+                    .map(|time| {
+                        time.with_timezone(&Local)
+                            .format("%Y-%m-%d %H:%M")
+                            .to_string()
+                    })
+                    .unwrap_or_else(|| "-".to_string()),
+                hit.project_name.clone(),
+                hit.session_id.clone(),
+                (hit.message + 1).to_string(),
+                match hit.role {
+                    crate::claude::transcript::Role::User => "you".to_string(),
+                    crate::claude::transcript::Role::Assistant => "claude".to_string(),
+                },
+                hit.snippet.clone(),
+            ]
+        })
+        .collect();
+
+    render_table(
+        &["Date", "Project", "Session", "Message", "From", "Match"],
+        &rows,
+        6,
+    )
+}
@@ -0,0 +1,418 @@
+//! Compact one-line status for shell prompts, tmux and the Claude Code statusline hook
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{IsTerminal, Read};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::flag_value;
+use crate::claude::{ClaudeDataManager, SessionIngestor};
+use crate::shared::{write_atomically, Config};
+use crate::ui::format_cost;
+
+/// Template used when neither `--format` nor the config sets one
+pub const DEFAULT_TEMPLATE: &str = "{cost_today} | {block_remaining} | {burn_rate} | {project}";
+
+/// Seconds a cached snapshot stays valid unless `--cache-ttl` says otherwise
+const DEFAULT_CACHE_TTL_SECS: u64 = 10;
+
+/// How far back to look for entries when locating the active 5-hour block
+const BLOCK_LOOKBACK_HOURS: u64 = 24;
+
+/// Cached snapshots older than this are dropped from the cache file
+const CACHE_PRUNE_SECS: u64 = 3600;
+
+/// Arguments for `cc-enhanced statusline`
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatuslineArgs {
+    /// Output template; falls back to the config entry, then `DEFAULT_TEMPLATE`
+    pub template: Option<String>,
+    /// How long a cached snapshot may be reused (0 disables the cache)
+    pub cache_ttl_secs: u64,
+}
+
+impl StatuslineArgs {
+    /// Parse the arguments that follow `statusline`
+    pub fn parse<I>(args: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut args = args.into_iter();
+        let mut statusline_args = Self {
+            template: None,
+            cache_ttl_secs: DEFAULT_CACHE_TTL_SECS,
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--format" => statusline_args.template = Some(flag_value(&arg, &mut args)?),
+                "--cache-ttl" => {
+                    let value = flag_value(&arg, &mut args)?;
+                    statusline_args.cache_ttl_secs = value.parse().map_err(|_| {
+                        anyhow::anyhow!("Invalid --cache-ttl '{value}', expected seconds")
+                    })?;
+                }
+                other => return Err(anyhow::anyhow!("Unknown statusline option '{other}'")),
+            }
+        }
+
+        Ok(statusline_args)
+    }
+}
+
+/// Values shown in the statusline, cached between invocations
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct StatusSnapshot {
+    /// Unix timestamp (seconds) when the snapshot was computed
+    generated_at: u64,
+    cost_today: f64,
+    /// Unix timestamp (seconds) when the active 5-hour block ends
+    block_end: Option<u64>,
+    /// Tokens per minute in the project's active session
+    burn_rate: f64,
+    project: Option<String>,
+    model: Option<String>,
+}
+
+/// On-disk cache of snapshots, keyed by working directory
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StatuslineCache {
+    entries: HashMap<String, StatusSnapshot>,
+}
+
+impl StatuslineCache {
+    /// Get the cache file path under the user cache directory
+    fn path() -> Option<PathBuf> {
+        dirs::cache_dir().map(|dir| dir.join("cc-enhanced").join("statusline.json"))
+    }
+
+    /// Load the cache, treating a missing or unreadable file as empty
+    fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save the cache; concurrent invocations share it
+    fn save(&self, path: &Path) -> Result<()> {
+        write_atomically(path, serde_json::to_string(self)?)
+    }
+
+    /// Get a snapshot if it is younger than `ttl_secs`
+    fn fresh(&self, key: &str, now: u64, ttl_secs: u64) -> Option<&StatusSnapshot> {
+        self.entries
+            .get(key)
+            .filter(|snapshot| now.saturating_sub(snapshot.generated_at) < ttl_secs)
+    }
+
+    /// Drop snapshots nobody has asked for in a while
+    fn prune(&mut self, now: u64) {
+        self.entries
+            .retain(|_, snapshot| now.saturating_sub(snapshot.generated_at) < CACHE_PRUNE_SECS);
+    }
+}
+
+/// Context the Claude Code statusline hook passes as JSON on stdin
+#[derive(Debug, Default)]
+struct HookInput {
+    cwd: Option<PathBuf>,
+    model: Option<String>,
+}
+
+impl HookInput {
+    /// Read hook JSON from stdin when it is piped; interactive shells are left alone
+    fn read() -> Self {
+        let stdin = std::io::stdin();
+        if stdin.is_terminal() {
+            return Self::default();
+        }
+
+        let mut input = String::new();
+        if stdin.lock().read_to_string(&mut input).is_err() {
+            return Self::default();
+        }
+
+        Self::parse(&input)
+    }
+
+    /// Extract the working directory and model from hook JSON
+    fn parse(input: &str) -> Self {
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(input) else {
+            return Self::default();
+        };
+
+        let cwd = value
+            .get("workspace")
+            .and_then(|w| w.get("current_dir"))
+            .or_else(|| value.get("cwd"))
+            .and_then(|v| v.as_str())
+            .map(PathBuf::from);
+        let model = value
+            .get("model")
+            .and_then(|m| m.get("display_name").or_else(|| m.get("id")))
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+
+        Self { cwd, model }
+    }
+}
+
+/// Print the statusline to stdout
+pub fn run(args: &StatuslineArgs, claude_dirs: &[PathBuf]) -> Result<()> {
+    let config = Config::load().unwrap_or_default();
+    let template = args
+        .template
+        .clone()
+        .or(config.statusline_format)
+        .unwrap_or_else(|| DEFAULT_TEMPLATE.to_string());
+
+    let hook = HookInput::read();
+    let cwd = hook.cwd.or_else(|| std::env::current_dir().ok());
+    let key = cwd
+        .as_ref()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let now = unix_secs(SystemTime::now());
+    let cache_path = StatuslineCache::path();
+    let mut cache = cache_path
+        .as_deref()
+        .map(StatuslineCache::load)
+        .unwrap_or_default();
+
+    let snapshot = match cache.fresh(&key, now, args.cache_ttl_secs) {
+        Some(snapshot) => snapshot.clone(),
+        None => {
+            let snapshot = collect_snapshot(claude_dirs, cwd.as_deref(), now)?;
+            if let Some(path) = &cache_path {
+                cache.prune(now);
+                cache.entries.insert(key, snapshot.clone());
+                // A read-only cache dir should not break the statusline
+                let _ = cache.save(path);
+            }
+            snapshot
+        }
+    };
+
+    let model = hook.model.or_else(|| snapshot.model.clone());
+    println!("{}", render(&template, &snapshot, model.as_deref(), now));
+    Ok(())
+}
+
+/// Compute a fresh snapshot, touching only recently modified session files
+fn collect_snapshot(
+    claude_dirs: &[PathBuf],
+    cwd: Option<&Path>,
+    now: u64,
+) -> Result<StatusSnapshot> {
+    // Loading the full usage index would cost more than the few recent files read here
+    let manager = ClaudeDataManager::with_ingestor(claude_dirs, SessionIngestor::shared())?;
+
+    let cost_today = manager.calculate_today_usage()?.total_cost;
+    let block_end = manager
+        .find_active_block_end_time_within(Some(Duration::from_secs(BLOCK_LOOKBACK_HOURS * 3600)))
+        .map(unix_secs);
+
+    let project = cwd
+        .and_then(|cwd| manager.project_dir_for_path(cwd))
+        .and_then(|dir| manager.load_project(&dir).ok().flatten());
+
+    let burn_rate = project
+        .as_ref()
+        .map(|p| manager.calculate_burn_rate(p))
+        .unwrap_or(0.0);
+    let model = project
+        .as_ref()
+        .and_then(|p| p.most_recent_session())
+        .and_then(|session| manager.last_model_used(&session.path));
+
+    Ok(StatusSnapshot {
+        generated_at: now,
+        cost_today,
+        block_end,
+        burn_rate,
+        project: project.map(|p| p.name),
+        model,
+    })
+}
+
+/// Fill in a template. Unknown placeholders are left untouched.
+fn render(template: &str, snapshot: &StatusSnapshot, model: Option<&str>, now: u64) -> String {
+    let block_end = snapshot.block_end.map(|end| {
+        chrono::DateTime::<chrono::Local>::from(UNIX_EPOCH + Duration::from_secs(end))
+            .format("%H:%M")
+            .to_string()
+    });
+
+    template
+        .replace("{cost_today}", &format_cost(snapshot.cost_today))
+        .replace(
+            "{block_remaining}",
+            &format_remaining(snapshot.block_end, now),
+        )
+        .replace("{block_end}", block_end.as_deref().unwrap_or("-"))
+        .replace("{burn_rate}", &format_burn_rate(snapshot.burn_rate))
+        .replace("{project}", snapshot.project.as_deref().unwrap_or("-"))
+        .replace("{model}", model.unwrap_or("-"))
+}
+
+/// Format the time left in the active block, e.g. "2h 13m left"
+fn format_remaining(block_end: Option<u64>, now: u64) -> String {
+    match block_end {
+        Some(end) if end > now => {
+            let remaining = end - now;
+            let hours = remaining / 3600;
+            let minutes = (remaining % 3600) / 60;
+
+            if hours > 0 {
+                format!("{hours}h {minutes}m left")
+            } else {
+                format!("{minutes}m left")
+            }
+        }
+        _ => "no active block".to_string(),
+    }
+}
+
+/// Format a burn rate in tokens per minute
+fn format_burn_rate(tokens_per_minute: f64) -> String {
+    if tokens_per_minute <= 0.0 {
+        "idle".to_string()
+    } else if tokens_per_minute >= 1000.0 {
+        format!("{:.1}K tok/min", tokens_per_minute / 1000.0)
+    } else {
+        format!("{tokens_per_minute:.0} tok/min")
+    }
+}
+
+/// Seconds since the Unix epoch
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(generated_at: u64) -> StatusSnapshot {
+        StatusSnapshot {
+            generated_at,
+            cost_today: 12.5,
+            block_end: Some(generated_at + 2 * 3600 + 13 * 60),
+            burn_rate: 1500.0,
+            project: Some("cc-enhanced".to_string()),
+            model: Some("claude-sonnet-4".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let args = StatuslineArgs::parse(Vec::<String>::new()).unwrap();
+        assert_eq!(args.template, None);
+        assert_eq!(args.cache_ttl_secs, DEFAULT_CACHE_TTL_SECS);
+
+        let args = StatuslineArgs::parse(
+            ["--format", "{model}", "--cache-ttl", "0"]
+                .iter()
+                .map(|s| s.to_string()),
+        )
+        .unwrap();
+        assert_eq!(args.template.as_deref(), Some("{model}"));
+        assert_eq!(args.cache_ttl_secs, 0);
+
+        assert!(
+            StatuslineArgs::parse(["--cache-ttl", "soon"].iter().map(|s| s.to_string())).is_err()
+        );
+        assert!(StatuslineArgs::parse(["--verbose"].iter().map(|s| s.to_string())).is_err());
+    }
+
+    #[test]
+    fn test_render_template() {
+        let now = 1_700_000_000;
+        let line = render(
+            "{cost_today} | {block_remaining} | {burn_rate} | {project} | {model} | {unknown}",
+            &snapshot(now),
+            Some("Opus"),
+            now,
+        );
+
+        assert_eq!(
+            line,
+            "$12.50 | 2h 13m left | 1.5K tok/min | cc-enhanced | Opus | {unknown}"
+        );
+    }
+
+    #[test]
+    fn test_render_missing_values() {
+        let now = 1_700_000_000;
+        let empty = StatusSnapshot {
+            generated_at: now,
+            cost_today: 0.0,
+            block_end: None,
+            burn_rate: 0.0,
+            project: None,
+            model: None,
+        };
+
+        assert_eq!(
+            render(DEFAULT_TEMPLATE, &empty, None, now),
+            "$0.00 | no active block | idle | -"
+        );
+    }
+
+    #[test]
+    fn test_format_remaining() {
+        assert_eq!(format_remaining(Some(100 + 45 * 60), 100), "45m left");
+        assert_eq!(format_remaining(Some(100), 100), "no active block");
+        assert_eq!(format_remaining(None, 100), "no active block");
+    }
+
+    #[test]
+    fn test_cache_freshness_and_pruning() {
+        let mut cache = StatuslineCache::default();
+        cache.entries.insert("/work".to_string(), snapshot(1_000));
+
+        assert!(cache.fresh("/work", 1_005, 10).is_some());
+        assert!(cache.fresh("/work", 1_010, 10).is_none());
+        assert!(cache.fresh("/work", 1_005, 0).is_none());
+        assert!(cache.fresh("/other", 1_005, 10).is_none());
+
+        cache.prune(1_000 + CACHE_PRUNE_SECS);
+        assert!(cache.entries.is_empty());
+    }
+
+    #[test]
+    fn test_cache_round_trip() {
+        let path = std::env::temp_dir()
+            .join(format!("statusline_test_{}", std::process::id()))
+            .join("statusline.json");
+
+        let mut cache = StatuslineCache::default();
+        cache.entries.insert("/work".to_string(), snapshot(1_000));
+        cache.save(&path).unwrap();
+
+        let loaded = StatuslineCache::load(&path);
+        assert_eq!(loaded.entries.get("/work"), Some(&snapshot(1_000)));
+
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_hook_input_parsing() {
+        let hook = HookInput::parse(
+            r#"{"session_id":"abc","cwd":"/fallback","model":{"id":"claude-opus-4-1","display_name":"Opus"},"workspace":{"current_dir":"/work/app"}}"#,
+        );
+        assert_eq!(hook.cwd, Some(PathBuf::from("/work/app")));
+        assert_eq!(hook.model.as_deref(), Some("Opus"));
+
+        let hook = HookInput::parse("not json");
+        assert_eq!(hook.cwd, None);
+        assert_eq!(hook.model, None);
+    }
+}
@@ -4,6 +4,8 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use crate::claude::path_resolver;
+
 /// Todo item status
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
@@ -55,13 +57,23 @@ pub struct TodoItem {
 #[derive(Debug, Clone)]
 pub struct SessionTodos {
     pub session_id: String,
-    #[allow(dead_code)]
+    /// Agent that wrote the todos; equal to `session_id` for the main thread
     pub agent_id: String,
     pub project_path: String,
     pub todos: Vec<TodoItem>,
     pub last_modified: std::time::SystemTime,
 }
 
+impl SessionTodos {
+    /// Number of todos marked completed
+    pub fn completed_count(&self) -> usize {
+        self.todos
+            .iter()
+            .filter(|todo| todo.status == TodoStatus::Completed)
+            .count()
+    }
+}
+
 /// Project-level todo statistics
 #[derive(Debug, Clone)]
 pub struct ProjectTodoStats {
@@ -80,39 +92,38 @@ pub struct ProjectTodoStats {
 
 /// Todo data manager
 pub struct TodoManager {
-    todos_dir: PathBuf,
+    /// Claude data directories whose `todos/` and `projects/` folders are read
+    claude_dirs: Vec<PathBuf>,
 }
 
 impl TodoManager {
-    /// Create a new TodoManager
-    pub fn new() -> Result<Self> {
-        let home_dir =
-            dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?;
-        let todos_dir = home_dir.join(".claude").join("todos");
-
-        Ok(Self { todos_dir })
+    /// Create a new TodoManager reading from the given Claude data directories
+    pub fn new(claude_dirs: &[PathBuf]) -> Result<Self> {
+        Ok(Self {
+            claude_dirs: claude_dirs.to_vec(),
+        })
     }
 
     /// Scan all todo files and organize by project
     pub fn scan_todos(&self) -> Result<HashMap<String, Vec<SessionTodos>>> {
         let mut project_todos: HashMap<String, Vec<SessionTodos>> = HashMap::new();
 
-        if !self.todos_dir.exists() {
-            return Ok(project_todos);
-        }
-
-        let entries = fs::read_dir(&self.todos_dir)?;
+        for todos_dir in self.claude_dirs.iter().map(|root| root.join("todos")) {
+            if !todos_dir.exists() {
+                continue;
+            }
 
-        for entry in entries {
-            let entry = entry?;
-            let path = entry.path();
+            for entry in fs::read_dir(&todos_dir)? {
+                let entry = entry?;
+                let path = entry.path();
 
-            if path.extension().and_then(|s| s.to_str()) == Some("json") {
-                if let Some(session_todos) = self.parse_todo_file(&path)? {
-                    project_todos
-                        .entry(session_todos.project_path.clone())
-                        .or_default()
-                        .push(session_todos);
+                if path.extension().and_then(|s| s.to_str()) == Some("json") {
+                    if let Some(session_todos) = self.parse_todo_file(&path)? {
+                        project_todos
+                            .entry(session_todos.project_path.clone())
+                            .or_default()
+                            .push(session_todos);
+                    }
                 }
             }
         }
@@ -157,79 +168,39 @@ impl TodoManager {
 
     /// Reconstruct project path from session ID
     fn reconstruct_project_path(&self, session_id: &str) -> Result<String> {
-        // Check in projects directory for this session ID
-        let home_dir =
-            dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?;
-        let projects_dir = home_dir.join(".claude").join("projects");
-
-        if !projects_dir.exists() {
-            return Ok("unknown".to_string());
-        }
-
-        for entry in fs::read_dir(&projects_dir)? {
-            let entry = entry?;
-            let project_dir = entry.path();
-
-            if project_dir.is_dir() {
-                // Check if this project contains our session
-                let session_file = project_dir.join(format!("{session_id}.jsonl"));
-                if session_file.exists() {
-                    // Try to get the real path from the JSONL file's "cwd" field
-                    if let Ok(cwd_path) = self.get_project_path_from_session_file(&session_file) {
-                        return Ok(cwd_path);
-                    }
-
-                    // Fallback: Reconstruct from directory name
-                    if let Some(dir_name) = project_dir.file_name().and_then(|s| s.to_str()) {
-                        return Ok(self
-                            .reconstruct_path_from_sanitized_name(dir_name)
-                            .unwrap_or_else(|_| dir_name.to_string()));
-                    }
-                }
+        // Check each root's projects directory for this session ID
+        for projects_dir in self.claude_dirs.iter().map(|root| root.join("projects")) {
+            if !projects_dir.exists() {
+                continue;
             }
-        }
 
-        Ok("unknown".to_string())
-    }
-
-    /// Get the real project path from a specific JSONL session file's "cwd" field
-    fn get_project_path_from_session_file(&self, session_file: &Path) -> Result<String> {
-        let content = std::fs::read_to_string(session_file)?;
+            for entry in fs::read_dir(&projects_dir)? {
+                let entry = entry?;
+                let project_dir = entry.path();
+
+                if project_dir.is_dir() {
+                    // Check if this project contains our session
+                    let session_file = project_dir.join(format!("{session_id}.jsonl"));
+                    if session_file.exists() {
+                        // Resolve the project the same way the project list does, so the
+                        // todos are attached to the project that owns the session
+                        let resolved = path_resolver::resolve_project_path(&project_dir, || {
+                            path_resolver::recent_session_cwd(&project_dir)
+                        });
+                        if let Some(path) = resolved {
+                            return Ok(path.to_string_lossy().to_string());
+                        }
 
-        // Read lines from the end to find the most recent "cwd"
-        for line in content.lines().rev() {
-            if let Ok(json_value) = serde_json::from_str::<serde_json::Value>(line) {
-                if let Some(cwd) = json_value.get("cwd") {
-                    if let Some(cwd_str) = cwd.as_str() {
-                        return Ok(cwd_str.to_string());
+                        // Fallback: the data directory name itself
+                        if let Some(dir_name) = project_dir.file_name().and_then(|s| s.to_str()) {
+                            return Ok(dir_name.to_string());
+                        }
                     }
                 }
             }
         }
 
-        Err(anyhow::anyhow!("No 'cwd' field found in JSONL file"))
-    }
-
-    /// Reconstruct the original filesystem path from Claude's sanitized directory name
-    fn reconstruct_path_from_sanitized_name(&self, sanitized_name: &str) -> Result<String> {
-        if !sanitized_name.starts_with('-') {
-            return Err(anyhow::anyhow!(
-                "Cannot reconstruct path from non-standard name"
-            ));
-        }
-
-        // Drop the leading '-' and replace remaining '-' with '/'
-        let path_str: String = sanitized_name
-            .chars()
-            .skip(1)
-            .map(|c| if c == '-' { '/' } else { c })
-            .collect();
-
-        // Create absolute path
-        let reconstructed_path = format!("/{path_str}");
-
-        // Return the reconstructed path (simpler than claude.rs version for todos)
-        Ok(reconstructed_path)
+        Ok("unknown".to_string())
     }
 
     /// Calculate project-level todo statistics
@@ -283,6 +254,18 @@ impl TodoManager {
         }
     }
 
+    /// Find the todo file written by one agent of a session, linking the todos to
+    /// that agent's usage (`agent_id` is the session id for the main thread)
+    pub fn agent_todos<'a>(
+        project_todos: &'a [SessionTodos],
+        session_id: &str,
+        agent_id: &str,
+    ) -> Option<&'a SessionTodos> {
+        project_todos
+            .iter()
+            .find(|todos| todos.session_id == session_id && todos.agent_id == agent_id)
+    }
+
     /// Get todos for a specific project, sorted by priority and status
     /// Only shows todos from the most recent session (by last modified time)
     pub fn get_project_todos_sorted(
@@ -365,14 +348,14 @@ mod tests {
 
     #[test]
     fn test_todo_manager_creation() {
-        let todo_manager = TodoManager::new().unwrap();
+        let todo_manager = TodoManager::new(&[std::env::temp_dir()]).unwrap();
         // Basic smoke test - should be able to create manager
         let _ = todo_manager; // Use the variable to avoid warnings
     }
 
     #[test]
     fn test_project_stats_calculation() {
-        let todo_manager = TodoManager::new().unwrap();
+        let todo_manager = TodoManager::new(&[std::env::temp_dir()]).unwrap();
 
         // Create mock session todos
         let session_todos = vec![
@@ -422,7 +405,7 @@ mod tests {
 
     #[test]
     fn test_sorted_todos_ordering() {
-        let todo_manager = TodoManager::new().unwrap();
+        let todo_manager = TodoManager::new(&[std::env::temp_dir()]).unwrap();
 
         // Create test todos with different priorities and statuses
         let session_todos = vec![SessionTodos {
@@ -468,10 +451,39 @@ mod tests {
         assert_eq!(sorted_todos[3].1.content, "Low pending");
     }
 
+    #[test]
+    fn test_agent_todos_are_found_by_session_and_agent() {
+        let todos = |session: &str, agent: &str, status: TodoStatus| SessionTodos {
+            session_id: session.to_string(),
+            agent_id: agent.to_string(),
+            project_path: "/test".to_string(),
+            last_modified: std::time::SystemTime::now(),
+            todos: vec![TodoItem {
+                content: "Task".to_string(),
+                priority: TodoPriority::Medium,
+                status,
+                id: "1".to_string(),
+            }],
+        };
+        let project_todos = vec![
+            todos("s1", "s1", TodoStatus::Pending),
+            todos("s1", "a7", TodoStatus::Completed),
+            todos("s2", "a7", TodoStatus::Pending),
+        ];
+
+        let main = TodoManager::agent_todos(&project_todos, "s1", "s1").unwrap();
+        assert_eq!(main.completed_count(), 0);
+
+        let subagent = TodoManager::agent_todos(&project_todos, "s1", "a7").unwrap();
+        assert_eq!(subagent.completed_count(), 1);
+
+        assert!(TodoManager::agent_todos(&project_todos, "s3", "s3").is_none());
+    }
+
     #[test]
     fn test_empty_session_handling() {
         let temp_dir = create_temp_dir();
-        let _todo_manager = TodoManager::new().unwrap();
+        let _todo_manager = TodoManager::new(&[std::env::temp_dir()]).unwrap();
 
         // Create empty session file
         let session_file = temp_dir.join("empty_session.jsonl");
@@ -482,4 +494,37 @@ mod tests {
 
         let _ = fs::remove_dir_all(&temp_dir);
     }
+
+    #[test]
+    fn test_scan_todos_merges_roots() {
+        let base = std::env::temp_dir().join(format!("todo_roots_test_{}", std::process::id()));
+        let todo_json = r#"[{"content":"Task","status":"pending","priority":"high","id":"1"}]"#;
+
+        for (root, session, cwd) in [("a", "s1", "/work/a"), ("b", "s2", "/work/b")] {
+            let root_dir = base.join(root);
+            let project_dir = root_dir.join("projects").join(format!("-work-{root}"));
+            fs::create_dir_all(root_dir.join("todos")).unwrap();
+            fs::create_dir_all(&project_dir).unwrap();
+
+            create_test_jsonl_file(
+                &root_dir
+                    .join("todos")
+                    .join(format!("{session}-agent-{session}.json")),
+                todo_json,
+            );
+            create_test_jsonl_file(
+                &project_dir.join(format!("{session}.jsonl")),
+                &format!(r#"{{"cwd":"{cwd}"}}"#),
+            );
+        }
+
+        let todo_manager = TodoManager::new(&[base.join("a"), base.join("b")]).unwrap();
+        let project_todos = todo_manager.scan_todos().unwrap();
+
+        assert_eq!(project_todos.len(), 2);
+        assert_eq!(project_todos["/work/a"][0].session_id, "s1");
+        assert_eq!(project_todos["/work/b"][0].session_id, "s2");
+
+        let _ = fs::remove_dir_all(&base);
+    }
 }
@@ -3,6 +3,7 @@ use anyhow::Result;
 mod app;
 mod claude;
 mod claude_legacy;
+mod cli;
 mod features;
 mod ide;
 mod shared;
@@ -14,8 +15,16 @@ mod widgets_tests;
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let cli = cli::parse_args(std::env::args().skip(1))?;
+    let claude_dirs = shared::claude_dirs(&cli.claude_dirs);
+
+    // Headless commands (reports etc.) run without touching the terminal
+    if cli.command != cli::Command::Tui {
+        return cli::run(cli.command, &claude_dirs);
+    }
+
     // Initialize the application
-    let mut app = app::App::new().await?;
+    let mut app = app::App::new(claude_dirs).await?;
 
     // Run the TUI
     app.run().await?;
@@ -0,0 +1,51 @@
+//! Replacing files atomically
+//!
+//! Caches and indexes are shared by every running dashboard and statusline, so a
+//! file is never written in place: the new contents go to a temporary file next
+//! to it, which is then renamed over the old one. Readers see either the old or
+//! the new file, and a crash mid-write leaves the old one intact.
+
+use anyhow::Result;
+use std::fs;
+use std::path::Path;
+
+/// Replace `path` with `contents`, creating its directory if needed
+pub fn write_atomically(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    // The process id keeps concurrent writers from sharing a temporary file
+    let mut tmp_name = path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Not a file path: {}", path.display()))?
+        .to_os_string();
+    tmp_name.push(format!(".{}", std::process::id()));
+    let tmp_path = path.with_file_name(tmp_name);
+
+    fs::write(&tmp_path, contents)?;
+    if let Err(e) = fs::rename(&tmp_path, path) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_atomically_replaces_the_file() {
+        let dir = std::env::temp_dir().join(format!("atomic_file_{}", std::process::id()));
+        let path = dir.join("nested").join("cache.json");
+
+        write_atomically(&path, "first").unwrap();
+        write_atomically(&path, b"second").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        // Only the file itself is left behind
+        assert_eq!(fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
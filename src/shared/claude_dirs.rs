@@ -0,0 +1,142 @@
+//! Locating Claude Code data directories
+//!
+//! Claude Code keeps projects and todos in `~/.claude` by default. Newer installs
+//! use `~/.config/claude`, and `CLAUDE_CONFIG_DIR` can move it anywhere, so the
+//! directories to read are resolved here once and passed to the data managers.
+
+use std::path::{Path, PathBuf};
+
+use super::Config;
+
+/// Environment variable Claude Code reads to relocate its data directory
+pub const CLAUDE_CONFIG_DIR_ENV: &str = "CLAUDE_CONFIG_DIR";
+
+/// Resolve the Claude data directories to read, primary first.
+///
+/// The first non-empty source wins: `--claude-dir` flags, `CLAUDE_CONFIG_DIR`
+/// (comma-separated), the `claude_dirs` config entry, then the default locations.
+pub fn claude_dirs(cli_dirs: &[PathBuf]) -> Vec<PathBuf> {
+    let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+    let env_value = std::env::var(CLAUDE_CONFIG_DIR_ENV).ok();
+    let configured = Config::load()
+        .map(|config| config.claude_dirs)
+        .unwrap_or_default();
+
+    resolve(
+        cli_dirs,
+        env_value.as_deref(),
+        &configured,
+        &default_dirs(&home),
+        &home,
+    )
+}
+
+/// Default locations: `~/.claude`, plus `$XDG_CONFIG_HOME/claude` when it holds projects
+fn default_dirs(home: &Path) -> Vec<PathBuf> {
+    let config_dir = std::env::var("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| home.join(".config"));
+    let xdg_claude_dir = config_dir.join("claude");
+
+    let mut dirs = vec![home.join(".claude")];
+    if xdg_claude_dir.join("projects").is_dir() {
+        dirs.push(xdg_claude_dir);
+    }
+    dirs
+}
+
+/// Pick the highest-priority source that names at least one directory
+fn resolve(
+    cli_dirs: &[PathBuf],
+    env_value: Option<&str>,
+    configured: &[PathBuf],
+    defaults: &[PathBuf],
+    home: &Path,
+) -> Vec<PathBuf> {
+    let env_dirs: Vec<PathBuf> = env_value
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .collect();
+
+    let chosen = [cli_dirs, &env_dirs, configured]
+        .into_iter()
+        .find(|dirs| !dirs.is_empty())
+        .unwrap_or(defaults);
+
+    let mut resolved: Vec<PathBuf> = Vec::new();
+    for dir in chosen {
+        let dir = expand_home(dir, home);
+        if !resolved.contains(&dir) {
+            resolved.push(dir);
+        }
+    }
+    resolved
+}
+
+/// Expand a leading `~` so config and env entries can be written like shell paths
+fn expand_home(dir: &Path, home: &Path) -> PathBuf {
+    match dir.strip_prefix("~") {
+        Ok(rest) => home.join(rest),
+        Err(_) => dir.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(dirs: &[&str]) -> Vec<PathBuf> {
+        dirs.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn test_source_priority() {
+        let home = Path::new("/home/me");
+        let defaults = paths(&["/home/me/.claude"]);
+        let cli = paths(&["/cli"]);
+        let configured = paths(&["/configured"]);
+
+        assert_eq!(
+            resolve(&cli, Some("/env"), &configured, &defaults, home),
+            paths(&["/cli"])
+        );
+        assert_eq!(
+            resolve(&[], Some("/env"), &configured, &defaults, home),
+            paths(&["/env"])
+        );
+        assert_eq!(
+            resolve(&[], None, &configured, &defaults, home),
+            paths(&["/configured"])
+        );
+        assert_eq!(resolve(&[], None, &[], &defaults, home), defaults);
+    }
+
+    #[test]
+    fn test_env_list_and_home_expansion() {
+        let home = Path::new("/home/me");
+
+        let dirs = resolve(
+            &[],
+            Some("~/.claude, ~/.config/claude,,/srv/claude,~/.claude"),
+            &[],
+            &[],
+            home,
+        );
+
+        assert_eq!(
+            dirs,
+            paths(&["/home/me/.claude", "/home/me/.config/claude", "/srv/claude"])
+        );
+    }
+
+    #[test]
+    fn test_empty_env_falls_through() {
+        let home = Path::new("/home/me");
+        let defaults = paths(&["/home/me/.claude"]);
+
+        assert_eq!(resolve(&[], Some(" "), &[], &defaults, home), defaults);
+    }
+}
@@ -4,6 +4,12 @@ use std::fs;
 use std::path::PathBuf;
 use std::time::Duration;
 
+use crate::claude::alerts::AlertSettings;
+use crate::claude::budget::BudgetSettings;
+use crate::claude::periods::PeriodSettings;
+use crate::claude::pricing_provider::PricingSettings;
+use crate::claude::quota::QuotaSettings;
+
 /// Theme options
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 pub enum ThemeMode {
@@ -38,6 +44,27 @@ pub struct Config {
     pub language: Language,
     /// Show help overlay
     pub show_help: bool,
+    /// Template for `cc-enhanced statusline` (None uses the built-in default)
+    #[serde(default)]
+    pub statusline_format: Option<String>,
+    /// Claude data directories to read (empty uses `~/.claude`)
+    #[serde(default)]
+    pub claude_dirs: Vec<PathBuf>,
+    /// Pricing providers, their priority, timeout and cache lifetime
+    #[serde(default)]
+    pub pricing: PricingSettings,
+    /// Plan whose limits the Quota tab measures usage against
+    #[serde(default)]
+    pub quota: QuotaSettings,
+    /// Daily, weekly and monthly cost budgets
+    #[serde(default)]
+    pub budgets: BudgetSettings,
+    /// Thresholds that raise alerts and how they are delivered
+    #[serde(default)]
+    pub alerts: AlertSettings,
+    /// First day of the week and billing day of the month for weekly and monthly totals
+    #[serde(default)]
+    pub periods: PeriodSettings,
 }
 
 impl Default for Config {
@@ -48,6 +75,13 @@ impl Default for Config {
             theme_mode: ThemeMode::default(),
             language: Language::default(),
             show_help: false,
+            statusline_format: None,
+            claude_dirs: Vec::new(),
+            pricing: PricingSettings::default(),
+            quota: QuotaSettings::default(),
+            budgets: BudgetSettings::default(),
+            alerts: AlertSettings::default(),
+            periods: PeriodSettings::default(),
         }
     }
 }
@@ -97,8 +131,8 @@ impl Config {
         Ok(())
     }
 
-    /// Get the configuration file path
-    fn config_path() -> Result<PathBuf> {
+    /// Get the cc-enhanced configuration directory (not created if missing)
+    pub fn config_dir() -> Result<PathBuf> {
         let home_dir =
             dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?;
 
@@ -109,7 +143,12 @@ impl Config {
             home_dir.join(".config")
         };
 
-        let app_config_dir = config_dir.join("cc-enhanced");
+        Ok(config_dir.join("cc-enhanced"))
+    }
+
+    /// Get the configuration file path
+    fn config_path() -> Result<PathBuf> {
+        let app_config_dir = Self::config_dir()?;
 
         // Create config directory if it doesn't exist
         fs::create_dir_all(&app_config_dir)?;
@@ -267,6 +306,24 @@ mod tests {
             theme_mode: ThemeMode::Ocean,
             language: Language::Korean,
             show_help: true,
+            statusline_format: Some("{cost_today} | {model}".to_string()),
+            claude_dirs: vec![PathBuf::from("/data/claude")],
+            pricing: PricingSettings::default(),
+            quota: QuotaSettings::default(),
+            budgets: BudgetSettings {
+                daily_usd: Some(25.0),
+                weekly_usd: None,
+                monthly_usd: Some(400.0),
+            },
+            alerts: AlertSettings {
+                desktop: true,
+                command: Some("logger cc-enhanced".to_string()),
+                ..Default::default()
+            },
+            periods: PeriodSettings {
+                week_start: chrono::Weekday::Sun,
+                billing_day: 15,
+            },
         };
 
         let serialized = serde_json::to_string_pretty(&config).unwrap();
@@ -280,6 +337,34 @@ mod tests {
         assert_eq!(config.theme_mode, deserialized.theme_mode);
         assert_eq!(config.language, deserialized.language);
         assert_eq!(config.show_help, deserialized.show_help);
+        assert_eq!(config.statusline_format, deserialized.statusline_format);
+        assert_eq!(config.claude_dirs, deserialized.claude_dirs);
+        assert_eq!(config.pricing, deserialized.pricing);
+        assert_eq!(config.quota, deserialized.quota);
+        assert_eq!(config.budgets, deserialized.budgets);
+        assert_eq!(config.alerts, deserialized.alerts);
+        assert_eq!(config.periods, deserialized.periods);
+    }
+
+    #[test]
+    fn test_config_without_new_fields_still_loads() {
+        let old_config = r#"{
+            "refresh_interval_secs": 5,
+            "current_tab": 1,
+            "theme_mode": "Dark",
+            "language": "English",
+            "show_help": false
+        }"#;
+
+        let config: Config = serde_json::from_str(old_config).unwrap();
+        assert_eq!(config.current_tab, 1);
+        assert_eq!(config.statusline_format, None);
+        assert!(config.claude_dirs.is_empty());
+        assert_eq!(config.pricing, PricingSettings::default());
+        assert_eq!(config.quota, QuotaSettings::default());
+        assert_eq!(config.budgets, BudgetSettings::default());
+        assert_eq!(config.alerts, AlertSettings::default());
+        assert_eq!(config.periods, PeriodSettings::default());
     }
 
     #[test]
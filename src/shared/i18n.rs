@@ -19,6 +19,7 @@ impl I18n {
             "tab.sessions" => "Sessions",
             "tab.todos" => "Todos",
             "tab.quota" => "Quota",
+            "tab.blocks" => "Blocks",
 
             // Overview tab
             "overview.project" => "Project:",
@@ -163,15 +164,24 @@ impl I18n {
             "help.quota.title" => "Usage Quota",
             "help.quota.line1" => "Claude usage restrictions:",
             "help.quota.line2" => "",
-            "help.quota.line3" => "• Daily limits: Max tokens per day",
-            "help.quota.line4" => "• Hourly limits: Short-term restrictions",
-            "help.quota.line5" => "• 5-hour blocks: Primary mechanism",
+            "help.quota.line3" => "• 5-hour blocks: Primary mechanism",
+            "help.quota.line4" => "• Weekly limits: Rolling 7 days",
+            "help.quota.line5" => "• Limits follow the plan in config",
             "help.quota.line6" => "• Reset times: When limits refresh",
-            "help.quota.line7" => "",
+            "help.quota.line7" => "• p: Pin or unpin the inferred block limit",
             "help.quota.line8" => "Status indicators:",
             "help.quota.line9" => "Green: Well within limits",
             "help.quota.line10" => "Yellow: Approaching limits",
             "help.quota.line11" => "Red: At or near limit",
+            // Blocks tab help
+            "help.blocks.title" => "Block Timeline",
+            "help.blocks.line1" => "Every 5-hour usage block, newest first:",
+            "help.blocks.line2" => "",
+            "help.blocks.line3" => "• Start, end, tokens and cost per block",
+            "help.blocks.line4" => "• Models and projects that used it",
+            "help.blocks.line5" => "• Idle gaps between blocks",
+            "help.blocks.line6" => "• Active block projected to its end",
+            "help.blocks.line7" => "• PgUp/PgDn: Scroll the timeline",
             // Todos tab help
             "help.todos.title" => "Todo System",
             "help.todos.line1" => "Task tracking from Claude sessions:",
@@ -190,6 +200,10 @@ impl I18n {
             "help.sessions.line5" => "• Message count and token usage",
             "help.sessions.line6" => "• Last modified timestamps",
             "help.sessions.line7" => "• Sorted by most recent first",
+            "help.sessions.line8" => "• [/]: Select a session, Enter: Open its transcript",
+            "help.sessions.line9" => "• Transcript: j/k scroll, n/p jump between turns",
+            "help.sessions.line10" => "• e: Expand tool output, g/G: Top/bottom, Esc: Close",
+            "help.sessions.line11" => "• /: Search every session (project:, since:, until:, re:)",
 
             // General
             "no_projects" => "No Claude projects found.",
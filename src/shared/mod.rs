@@ -1,9 +1,15 @@
 /// Shared modules used across the application
+pub mod atomic_file;
+pub mod claude_dirs;
 pub mod config;
 pub mod i18n;
 pub mod theme;
+pub mod watcher;
 
 // Re-export commonly used items
+pub use atomic_file::write_atomically;
+pub use claude_dirs::claude_dirs;
 pub use config::{Config, ThemeMode};
 pub use i18n::I18n;
 pub use theme::ModernTheme;
+pub use watcher::{DataChange, DataWatcher};
@@ -236,6 +236,7 @@ impl ModernIcons {
     pub const SESSIONS: &'static str = "⚡"; // Lightning bolt
     pub const TODOS: &'static str = "✓"; // Check mark
     pub const QUOTA: &'static str = "⬢"; // Hexagon
+    pub const BLOCKS: &'static str = "▦"; // Squared grid
 
     // UI elements
     pub const REFRESH: &'static str = "⟳"; // Rotating arrows
@@ -0,0 +1,158 @@
+//! Filesystem watching of Claude data directories
+//!
+//! Claude Code appends to session files and rewrites todo files as it works.
+//! Watching `projects/` and `todos/` (inotify on Linux) lets the dashboard
+//! update as soon as a line is written instead of on the next polling refresh.
+
+use anyhow::Result;
+use notify::event::ModifyKind;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+
+/// A change to one file the dashboard reads
+#[derive(Debug, Clone, PartialEq)]
+pub enum DataChange {
+    /// A session file under `projects/<project>/` was created, appended to or removed
+    Session(PathBuf),
+    /// A todo file under `todos/` was created, rewritten or removed
+    Todos(PathBuf),
+}
+
+/// Watches the Claude data directories until dropped
+pub struct DataWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+impl DataWatcher {
+    /// Start watching every data directory, calling `on_change` from the watcher thread.
+    /// Fails if nothing could be watched, in which case callers fall back to polling.
+    pub fn start<F>(claude_dirs: &[PathBuf], on_change: F) -> Result<Self>
+    where
+        F: Fn(DataChange) + Send + 'static,
+    {
+        let roots = claude_dirs.to_vec();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            let Ok(event) = event else {
+                return;
+            };
+            if !is_content_change(&event.kind) {
+                return;
+            }
+            for path in &event.paths {
+                if let Some(change) = classify(&roots, path) {
+                    on_change(change);
+                }
+            }
+        })?;
+
+        let mut watched = 0;
+        for root in claude_dirs {
+            // New project directories appear under projects/, so it is watched recursively
+            for (dir, mode) in [
+                (root.join("projects"), RecursiveMode::Recursive),
+                (root.join("todos"), RecursiveMode::NonRecursive),
+            ] {
+                if dir.is_dir() && watcher.watch(&dir, mode).is_ok() {
+                    watched += 1;
+                }
+            }
+        }
+
+        if watched == 0 {
+            return Err(anyhow::anyhow!("No Claude data directory could be watched"));
+        }
+        Ok(Self { _watcher: watcher })
+    }
+}
+
+/// Whether an event can change what a file contains (reads and chmods cannot)
+fn is_content_change(kind: &EventKind) -> bool {
+    match kind {
+        EventKind::Create(_) | EventKind::Remove(_) => true,
+        EventKind::Modify(ModifyKind::Metadata(_)) => false,
+        EventKind::Modify(_) => true,
+        _ => false,
+    }
+}
+
+/// Map a changed path to the data it affects, ignoring unrelated files
+fn classify(claude_dirs: &[PathBuf], path: &Path) -> Option<DataChange> {
+    let extension = path.extension().and_then(|s| s.to_str());
+
+    claude_dirs.iter().find_map(|root| {
+        if let Ok(relative) = path.strip_prefix(root.join("projects")) {
+            // projects/<project>/<session>.jsonl
+            return (relative.components().count() == 2 && extension == Some("jsonl"))
+                .then(|| DataChange::Session(path.to_path_buf()));
+        }
+        if let Ok(relative) = path.strip_prefix(root.join("todos")) {
+            return (relative.components().count() == 1 && extension == Some("json"))
+                .then(|| DataChange::Todos(path.to_path_buf()));
+        }
+        None
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    #[test]
+    fn test_classify_paths() {
+        let roots = vec![
+            PathBuf::from("/home/me/.claude"),
+            PathBuf::from("/srv/claude"),
+        ];
+        let session = Path::new("/srv/claude/projects/-work-app/abc.jsonl");
+        let todo = Path::new("/home/me/.claude/todos/abc-agent-abc.json");
+
+        assert_eq!(
+            classify(&roots, session),
+            Some(DataChange::Session(session.to_path_buf()))
+        );
+        assert_eq!(
+            classify(&roots, todo),
+            Some(DataChange::Todos(todo.to_path_buf()))
+        );
+
+        for ignored in [
+            "/home/me/.claude/projects/-work-app",
+            "/home/me/.claude/projects/-work-app/notes.txt",
+            "/home/me/.claude/projects/-work-app/sub/abc.jsonl",
+            "/home/me/.claude/todos/nested/abc.json",
+            "/home/me/.claude/settings.json",
+            "/elsewhere/projects/-work-app/abc.jsonl",
+        ] {
+            assert_eq!(classify(&roots, Path::new(ignored)), None, "{ignored}");
+        }
+    }
+
+    #[test]
+    fn test_appended_session_is_reported() {
+        let root = std::env::temp_dir().join(format!("watcher_test_{}", std::process::id()));
+        let project_dir = root.join("projects").join("-tmp-app");
+        std::fs::create_dir_all(&project_dir).unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        let _watcher = DataWatcher::start(std::slice::from_ref(&root), move |change| {
+            let _ = tx.send(change);
+        })
+        .unwrap();
+
+        let session = project_dir.join("s1.jsonl");
+        std::fs::write(&session, "{}\n").unwrap();
+
+        let change = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(change, DataChange::Session(session));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_missing_directories_fail_to_start() {
+        let root = std::env::temp_dir().join("watcher_test_missing_root");
+        assert!(DataWatcher::start(&[root], |_| {}).is_err());
+    }
+}
@@ -2,14 +2,15 @@ use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Modifier, Style},
     text::{Line, Span, Text},
-    widgets::{Clear, Tabs, Widget},
+    widgets::{Clear, Paragraph, Tabs, Widget},
     Frame,
 };
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 use crate::{
-    app::{App, Tab, ViewMode},
+    app::{App, Tab, TranscriptView, ViewMode, RECENT_SESSIONS},
     claude,
-    features::todos::{TodoPriority, TodoStatus},
+    features::todos::{SessionTodos, TodoManager, TodoPriority, TodoStatus},
     shared::theme::{ModernIcons, ModernTheme},
     widgets::{
         icon_span, metric_span, modern_block, priority_icon, status_icon, ModernCard, ModernGauge,
@@ -20,7 +21,7 @@ use crate::{
 /// Format currency with adaptive precision based on amount size
 /// This addresses the issue of over-representation of micro-costs in AI model pricing
 /// Uses a custom adaptive formatting algorithm for better user experience
-fn format_cost(amount: f64) -> String {
+pub(crate) fn format_cost(amount: f64) -> String {
     if amount == 0.0 {
         "$0.00".to_string()
     } else if amount < 0.001 {
@@ -41,6 +42,19 @@ fn format_cost(amount: f64) -> String {
     }
 }
 
+/// Format a span of time as its two largest units, e.g. "2d 3h", "3h 20m" or "45m"
+pub(crate) fn format_duration(duration: chrono::Duration) -> String {
+    let minutes = duration.num_minutes().max(0);
+    let (days, hours, minutes) = (minutes / 1440, minutes / 60 % 24, minutes % 60);
+    if days > 0 {
+        format!("{days}d {hours}h")
+    } else if hours > 0 {
+        format!("{hours}h {minutes}m")
+    } else {
+        format!("{minutes}m")
+    }
+}
+
 /// Format cost range for context (e.g., "$0.001 - $0.015")
 #[allow(dead_code)]
 fn format_cost_range(min_cost: f64, max_cost: f64) -> String {
@@ -87,6 +101,10 @@ pub fn draw(f: &mut Frame, app: &mut App) {
         draw_help_overlay(f, f.size(), app, &theme);
     }
 
+    if app.search.is_some() {
+        draw_search_overlay(f, f.size(), app, &theme);
+    }
+
     // Draw IDE selection overlay if enabled
     if app.ide_selection_state.is_some() {
         draw_ide_selection_overlay(f, f.size(), app, &theme);
@@ -96,10 +114,7 @@ pub fn draw(f: &mut Frame, app: &mut App) {
 /// Draw the modern header with enhanced styling
 fn draw_modern_header(f: &mut Frame, area: Rect, app: &mut App, theme: &ModernTheme) {
     let stats = &app.usage_stats;
-    let total_tokens = stats.input_tokens
-        + stats.output_tokens
-        + stats.cache_creation_tokens
-        + stats.cache_read_tokens;
+    let total_tokens = stats.total_tokens();
 
     // Create header content with modern icons
     let mut header_spans = vec![
@@ -123,6 +138,33 @@ fn draw_modern_header(f: &mut Frame, area: Rect, app: &mut App, theme: &ModernTh
         Span::styled(" │ ", theme.border_style()),
         icon_span(ModernIcons::TIME, Style::default().fg(theme.info)),
         Span::styled(format!(" {}", app.reset_time_str), theme.warning_style()),
+    ]);
+
+    // Share of the plan's block and weekly limits used so far
+    let quota = &app.quota;
+    for (label, ratio) in [
+        (
+            "Block",
+            quota.block.as_ref().and_then(|block| block.ratio()),
+        ),
+        ("Week", quota.week.ratio()),
+    ] {
+        if let Some(ratio) = ratio {
+            let style = match quota_variant(ratio) {
+                ProgressVariant::Danger => theme.danger_style(),
+                ProgressVariant::Warning => theme.warning_style(),
+                _ => theme.success_style(),
+            };
+            header_spans.push(Span::styled(" │ ", theme.border_style()));
+            header_spans.push(Span::styled(
+                format!("{label} "),
+                theme.secondary_text_style(),
+            ));
+            header_spans.push(Span::styled(format!("{:.0}%", ratio * 100.0), style));
+        }
+    }
+
+    header_spans.extend_from_slice(&[
         Span::styled(" │ ", theme.border_style()),
         Span::styled(
             format!("⟳ {}", app.config.refresh_interval_display()),
@@ -139,6 +181,13 @@ fn draw_modern_header(f: &mut Frame, area: Rect, app: &mut App, theme: &ModernTh
             format!("{spinner_char}"),
             theme.warning_style(),
         ));
+
+        if let Some(progress) = app.loading_states.scan_progress {
+            header_spans.push(Span::styled(
+                format!(" {}/{} files", progress.scanned, progress.total),
+                theme.info_style(),
+            ));
+        }
     }
 
     // Add status message to header if present
@@ -297,6 +346,7 @@ fn draw_modern_tab_bar(f: &mut Frame, area: Rect, app: &App, theme: &ModernTheme
         format!("{} {}", ModernIcons::SESSIONS, app.i18n.t("tab.sessions")),
         format!("{} {}", ModernIcons::TODOS, app.i18n.t("tab.todos")),
         format!("{} {}", ModernIcons::QUOTA, app.i18n.t("tab.quota")),
+        format!("{} {}", ModernIcons::BLOCKS, app.i18n.t("tab.blocks")),
     ];
 
     let tabs = Tabs::new(tab_titles)
@@ -317,6 +367,7 @@ fn draw_modern_tab_content(f: &mut Frame, area: Rect, app: &mut App, theme: &Mod
         Tab::Sessions => draw_modern_sessions_tab(f, area, app, theme),
         Tab::Todos => draw_modern_todos_tab(f, area, app, theme),
         Tab::Quota => draw_modern_quota_tab(f, area, app, theme),
+        Tab::Blocks => draw_modern_blocks_tab(f, area, app, theme),
     }
 }
 
@@ -330,12 +381,7 @@ fn draw_modern_overview_tab(f: &mut Frame, area: Rect, app: &mut App, theme: &Mo
                 .unwrap_or_else(|| claude::ProjectAnalytics {
                     total_sessions: project.sessions.len(),
                     total_messages: project.sessions.iter().map(|s| s.message_count).sum(),
-                    total_tokens: 0,
-                    estimated_cost: 0.0,
-                    first_session: None,
-                    last_session: None,
-                    cache_efficiency: 0.0,
-                    session_blocks: Vec::new(),
+                    ..Default::default()
                 });
 
         let todo_stats = app.selected_project_todo_stats();
@@ -351,6 +397,17 @@ fn draw_modern_overview_tab(f: &mut Frame, area: Rect, app: &mut App, theme: &Mo
                 Span::styled("📁 Path: ", theme.secondary_text_style()),
                 Span::styled(project.path.display().to_string(), theme.info_style()),
             ]),
+        ];
+
+        // Only worth showing when projects from several data directories are merged
+        if app.claude_dirs.len() > 1 {
+            content_lines.push(Line::from(vec![
+                Span::styled("🗂 Data: ", theme.secondary_text_style()),
+                Span::styled(project.root.display().to_string(), theme.dimmed_style()),
+            ]));
+        }
+
+        content_lines.extend(vec![
             Line::from(""),
             Line::from(vec![
                 Span::styled("📊 Sessions: ", theme.secondary_text_style()),
@@ -368,7 +425,7 @@ fn draw_modern_overview_tab(f: &mut Frame, area: Rect, app: &mut App, theme: &Mo
                 Span::styled("⚡ Cache Efficiency: ", theme.secondary_text_style()),
                 metric_span(format!("{:.1}%", analytics.cache_efficiency), theme),
             ]),
-        ];
+        ]);
 
         if let Some(stats) = todo_stats {
             content_lines.extend(vec![
@@ -427,8 +484,17 @@ fn draw_modern_usage_tab(f: &mut Frame, area: Rect, app: &mut App, theme: &Moder
         // Left: Project Usage Statistics
         draw_project_usage_card(f, chunks[0], &project, app, theme);
 
-        // Right: Recent Session Activity
-        draw_project_sessions_card(f, chunks[1], &project, theme);
+        // Right: Recent Session Activity above the tools the project's sessions called
+        let right_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
+            .split(chunks[1]);
+        draw_project_sessions_card(f, right_chunks[0], &project, theme);
+        let tool_usage = app
+            .get_project_analytics(&project)
+            .map(|analytics| analytics.tool_usage)
+            .unwrap_or_default();
+        draw_tool_usage_card(f, right_chunks[1], &tool_usage, theme);
     } else {
         let no_project_content = Text::from(vec![
             Line::from(vec![Span::styled(
@@ -456,13 +522,38 @@ fn draw_modern_usage_tab(f: &mut Frame, area: Rect, app: &mut App, theme: &Moder
 }
 
 /// Draw the modern Sessions tab
-fn draw_modern_sessions_tab(f: &mut Frame, area: Rect, app: &App, theme: &ModernTheme) {
-    if let Some(project) = app.selected_project() {
+fn draw_modern_sessions_tab(f: &mut Frame, area: Rect, app: &mut App, theme: &ModernTheme) {
+    if let Some(view) = app.transcript.as_mut() {
+        draw_session_transcript(f, area, view, theme);
+        return;
+    }
+
+    let project_clone = app.selected_project().cloned();
+    if let Some(project) = project_clone {
+        let analytics = app.get_project_analytics(&project);
+        let session_todos = app
+            .selected_project_session_todos()
+            .cloned()
+            .unwrap_or_default();
         let mut list_items = Vec::new();
+        app.selected_session = app.selected_session.min(
+            project
+                .sessions
+                .len()
+                .min(RECENT_SESSIONS)
+                .saturating_sub(1),
+        );
+        let mut selected_row = 0;
 
-        for session in project.sessions.iter().take(20) {
+        for (i, session) in project.sessions.iter().take(RECENT_SESSIONS).enumerate() {
             let time_str = format_time_ago(session.last_modified);
-            let line = Line::from(vec![
+            let agents = analytics
+                .as_ref()
+                .and_then(|a| a.session_agents.get(&session.id))
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+
+            let mut spans = vec![
                 icon_span(ModernIcons::SESSIONS, Style::default().fg(theme.info)),
                 Span::styled(
                     format!(" {}", &session.id[..8]),
@@ -473,9 +564,38 @@ fn draw_modern_sessions_tab(f: &mut Frame, area: Rect, app: &App, theme: &Modern
                     theme.secondary_text_style(),
                 ),
                 Span::styled(format!(" • {time_str}"), theme.dimmed_style()),
-            ]);
+            ];
+            if i == app.selected_session {
+                selected_row = list_items.len();
+            }
 
-            list_items.push(ModernListItem::new(line));
+            // Sessions without subagents keep to one line
+            let has_subagents = agents.iter().any(|agent| agent.is_sidechain);
+            if !has_subagents {
+                if let Some(main) = agents.first() {
+                    spans.extend(agent_usage_spans(main, &session_todos, &session.id, theme));
+                }
+            }
+            if let Some(tools) = analytics
+                .as_ref()
+                .and_then(|a| a.session_tools.get(&session.id))
+            {
+                spans.extend(tool_call_spans(tools, theme));
+            }
+            list_items.push(ModernListItem::new(Line::from(spans)));
+
+            if has_subagents {
+                for agent in agents {
+                    let label = if agent.is_sidechain {
+                        format!("   ↳ agent {}", short_id(&agent.agent_id))
+                    } else {
+                        "   ↳ main".to_string()
+                    };
+                    let mut spans = vec![Span::styled(label, theme.secondary_text_style())];
+                    spans.extend(agent_usage_spans(agent, &session_todos, &session.id, theme));
+                    list_items.push(ModernListItem::new(Line::from(spans)));
+                }
+            }
         }
 
         if list_items.is_empty() {
@@ -483,7 +603,13 @@ fn draw_modern_sessions_tab(f: &mut Frame, area: Rect, app: &App, theme: &Modern
             let empty_card = ModernCard::new(empty_content, theme).title("Recent Sessions");
             empty_card.render(area, f.buffer_mut());
         } else {
-            let sessions_list = ModernList::new(list_items, theme).title("Recent Sessions");
+            // Keep the selected session in view
+            let rows = area.height.saturating_sub(2).max(1) as usize;
+            let skip = (selected_row + 1).saturating_sub(rows);
+            let visible: Vec<ModernListItem> = list_items.into_iter().skip(skip).collect();
+            let sessions_list = ModernList::new(visible, theme)
+                .title("Recent Sessions ([/] select, Enter open)")
+                .selected(Some(selected_row - skip));
             sessions_list.render(area, f.buffer_mut());
         }
     } else {
@@ -493,6 +619,457 @@ fn draw_modern_sessions_tab(f: &mut Frame, area: Rect, app: &App, theme: &Modern
     }
 }
 
+/// Tokens, cost and linked todo progress of one session agent
+fn agent_usage_spans(
+    agent: &claude::AgentUsage,
+    session_todos: &[SessionTodos],
+    session_id: &str,
+    theme: &ModernTheme,
+) -> Vec<Span<'static>> {
+    let stats = &agent.usage_stats;
+    let mut spans = vec![
+        Span::styled(
+            format!(" • {} tokens", format_number(stats.input_output_tokens())),
+            theme.secondary_text_style(),
+        ),
+        Span::styled(
+            format!(" • {}", format_cost(stats.total_cost)),
+            theme.warning_style(),
+        ),
+    ];
+
+    if let Some(todos) = TodoManager::agent_todos(session_todos, session_id, &agent.agent_id) {
+        spans.push(Span::styled(
+            format!(" • todos {}/{}", todos.completed_count(), todos.todos.len()),
+            theme.info_style(),
+        ));
+    }
+
+    spans
+}
+
+/// Tool calls of a session and how many of them failed
+fn tool_call_spans(tools: &claude::tools::ToolUsage, theme: &ModernTheme) -> Vec<Span<'static>> {
+    let total = tools.total();
+    if total.calls == 0 {
+        return Vec::new();
+    }
+
+    let mut spans = vec![Span::styled(
+        format!(" • {} tool calls", total.calls),
+        theme.info_style(),
+    )];
+    if total.errors > 0 {
+        spans.push(Span::styled(
+            format!(" ({} failed)", total.errors),
+            theme.danger_style(),
+        ));
+    }
+    spans
+}
+
+/// Draw the most called tools with their error rates and the estimated tokens of
+/// their results
+fn draw_tool_usage_card(
+    f: &mut Frame,
+    area: Rect,
+    tool_usage: &claude::tools::ToolUsage,
+    theme: &ModernTheme,
+) {
+    let mut content_lines = vec![
+        Line::from(vec![
+            Span::styled("🛠 ", theme.success_style()),
+            Span::styled("Tool Usage", theme.header_style()),
+        ]),
+        Line::from(""),
+    ];
+
+    if tool_usage.is_empty() {
+        content_lines.push(Line::from(Span::styled(
+            "No tool calls recorded",
+            theme.secondary_text_style(),
+        )));
+    } else {
+        let total = tool_usage.total();
+        content_lines.push(Line::from(vec![
+            metric_span(format_number(total.calls), theme),
+            Span::styled(" calls • ", theme.secondary_text_style()),
+            Span::styled(
+                format!("{:.1}% err", total.error_rate()),
+                theme.warning_style(),
+            ),
+            Span::styled(
+                format!(" • ≈{} tok", format_number(total.result_tokens)),
+                theme.dimmed_style(),
+            ),
+        ]));
+        content_lines.push(Line::from(""));
+
+        // Borders and the four lines above take six rows; the name column gets the
+        // width the counts leave over
+        let rows = (area.height as usize).saturating_sub(6).max(1);
+        let name_width = (area.width as usize)
+            .saturating_sub(2 + 6 + 11 + 12)
+            .clamp(8, 24);
+        let tools = tool_usage.by_calls();
+        let shown = if tools.len() > rows {
+            rows.saturating_sub(1)
+        } else {
+            rows
+        };
+        for (name, stats) in tools.iter().take(shown) {
+            let name =
+                crate::widgets::format_project_name(&claude::tools::display_name(name), name_width);
+            let error_style = if stats.errors > 0 {
+                theme.danger_style()
+            } else {
+                theme.dimmed_style()
+            };
+            content_lines.push(Line::from(vec![
+                Span::styled(
+                    format!(
+                        "{name}{} ",
+                        " ".repeat(name_width.saturating_sub(name.width()))
+                    ),
+                    theme.secondary_text_style(),
+                ),
+                metric_span(format!("{:>6}", format_number(stats.calls)), theme),
+                Span::styled(format!("  {:>5.1}% err", stats.error_rate()), error_style),
+                Span::styled(
+                    format!("  ≈{} tok", format_number(stats.result_tokens)),
+                    theme.dimmed_style(),
+                ),
+            ]));
+        }
+        if tools.len() > shown {
+            content_lines.push(Line::from(Span::styled(
+                format!("… and {} more tools", tools.len() - shown),
+                theme.dimmed_style(),
+            )));
+        }
+    }
+
+    let card = ModernCard::new(Text::from(content_lines), theme).title("🛠 Tool Usage");
+    card.render(area, f.buffer_mut());
+}
+
+/// First eight characters of an id, for compact display
+fn short_id(id: &str) -> &str {
+    id.char_indices().nth(8).map_or(id, |(end, _)| &id[..end])
+}
+
+/// Rows of tool output shown before the rest is collapsed
+const TOOL_OUTPUT_ROWS: usize = 6;
+
+/// Draw an open session transcript in place of the session list
+fn draw_session_transcript(
+    f: &mut Frame,
+    area: Rect,
+    view: &mut TranscriptView,
+    theme: &ModernTheme,
+) {
+    let inner = modern_block(None, theme, true).inner(area);
+    let (lines, turn_offsets, message_offsets) =
+        transcript_lines(view, inner.width as usize, theme);
+
+    // The key handlers scroll by what was rendered last
+    view.line_count = lines.len();
+    view.page_height = inner.height as usize;
+    if let Some(message) = view.focus_message.take() {
+        view.scroll = message_offsets.get(message).copied().unwrap_or(0);
+    }
+    view.scroll = view
+        .scroll
+        .min(view.line_count.saturating_sub(view.page_height));
+    let turn = turn_offsets
+        .iter()
+        .take_while(|&&offset| offset <= view.scroll)
+        .count();
+    let title = format!(
+        "Transcript {} • turn {}/{} • j/k scroll, n/p turn, e expand, Esc close",
+        short_id(&view.session_id),
+        turn,
+        turn_offsets.len()
+    );
+    view.turn_offsets = turn_offsets;
+
+    let visible: Vec<Line> = lines
+        .into_iter()
+        .skip(view.scroll)
+        .take(view.page_height)
+        .collect();
+    f.render_widget(modern_block(Some(&title), theme, true), area);
+    f.render_widget(Paragraph::new(visible), inner);
+}
+
+/// Lay out a transcript as lines of at most `width` columns, with the line each
+/// turn starts on and the line each message starts on
+fn transcript_lines(
+    view: &TranscriptView,
+    width: usize,
+    theme: &ModernTheme,
+) -> (Vec<Line<'static>>, Vec<usize>, Vec<usize>) {
+    use claude::transcript::{Role, TranscriptBlock};
+
+    let mut lines = Vec::new();
+    let mut turn_offsets = Vec::new();
+    let mut message_offsets = Vec::new();
+    let body_width = width.saturating_sub(4).max(1);
+    let indented = |lines: &mut Vec<Line<'static>>, prefix: &str, rows: Vec<String>, style| {
+        for (i, row) in rows.into_iter().enumerate() {
+            let lead = if i == 0 {
+                prefix.to_string()
+            } else {
+                " ".repeat(prefix.width())
+            };
+            lines.push(Line::from(Span::styled(format!("{lead}{row}"), style)));
+        }
+    };
+
+    for message in &view.messages {
+        let time = message
+            .time
+            .map(|time| {
+                chrono::DateTime::<chrono::Local>::from(time)
+                    .format("%m-%d %H:%M:%S")
+                    .to_string()
+            })
+            .unwrap_or_default();
+
+        // Tool results sent back on the user's behalf follow the call without a header
+        let has_header = message.is_prompt() || message.role == Role::Assistant;
+        if has_header && !lines.is_empty() {
+            lines.push(Line::from(""));
+        }
+        message_offsets.push(lines.len());
+        if message.is_prompt() {
+            turn_offsets.push(lines.len());
+            lines.push(Line::from(vec![
+                Span::styled("▶ You", theme.header_style()),
+                Span::styled(format!(" • {time}"), theme.dimmed_style()),
+            ]));
+        } else if message.role == Role::Assistant {
+            let speaker = if message.is_sidechain {
+                "◆ Agent"
+            } else {
+                "◆ Claude"
+            };
+            let mut header = vec![Span::styled(speaker, Style::default().fg(theme.accent))];
+            if let Some(model) = &message.model {
+                let model = model.strip_prefix("claude-").unwrap_or(model);
+                header.push(Span::styled(format!(" • {model}"), theme.info_style()));
+            }
+            header.push(Span::styled(format!(" • {time}"), theme.dimmed_style()));
+            if let Some(usage) = message
+                .usage
+                .as_ref()
+                .and_then(|entry| entry.usage.as_ref())
+            {
+                header.push(Span::styled(
+                    format!(
+                        " • {} in • {} out",
+                        format_number(usage.input_tokens),
+                        format_number(usage.output_tokens)
+                    ),
+                    theme.secondary_text_style(),
+                ));
+                header.push(Span::styled(
+                    format!(" • {}", format_cost(message.cost)),
+                    theme.warning_style(),
+                ));
+            }
+            lines.push(Line::from(header));
+        }
+
+        for block in &message.blocks {
+            match block {
+                TranscriptBlock::Text(text) => {
+                    let style = if message.role == Role::User {
+                        Style::default().fg(theme.text_primary)
+                    } else {
+                        theme.secondary_text_style()
+                    };
+                    indented(&mut lines, "  ", wrap_text(text, body_width), style);
+                }
+                TranscriptBlock::Thinking(text) => {
+                    let style = theme.dimmed_style().add_modifier(Modifier::ITALIC);
+                    indented(&mut lines, "  ✻ ", wrap_text(text, body_width), style);
+                }
+                TranscriptBlock::ToolUse { name, input } => {
+                    let call = format!("{name} {input}");
+                    let mut rows = wrap_text(&call, body_width);
+                    if !view.expand_tool_output && rows.len() > 1 {
+                        rows.truncate(1);
+                        rows[0].push('…');
+                    }
+                    indented(&mut lines, "  ⚙ ", rows, theme.info_style());
+                }
+                TranscriptBlock::ToolResult { output, is_error } => {
+                    let style = if *is_error {
+                        theme.danger_style()
+                    } else {
+                        theme.dimmed_style()
+                    };
+                    let mut rows = wrap_text(output, body_width);
+                    let hidden = rows.len().saturating_sub(TOOL_OUTPUT_ROWS);
+                    if !view.expand_tool_output && hidden > 0 {
+                        rows.truncate(TOOL_OUTPUT_ROWS);
+                    }
+                    indented(&mut lines, "  ⎿ ", rows, style);
+                    if !view.expand_tool_output && hidden > 0 {
+                        lines.push(Line::from(Span::styled(
+                            format!("    … {hidden} more lines (e to expand)"),
+                            theme.dimmed_style(),
+                        )));
+                    }
+                }
+            }
+        }
+    }
+
+    if lines.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "No messages in this session",
+            theme.secondary_text_style(),
+        )));
+    }
+    (lines, turn_offsets, message_offsets)
+}
+
+/// Draw the session search overlay: the query, then each hit with its snippet
+fn draw_search_overlay(f: &mut Frame, area: Rect, app: &mut App, theme: &ModernTheme) {
+    let Some(view) = app.search.as_ref() else {
+        return;
+    };
+
+    let popup_area = Rect {
+        x: area.width / 10,
+        y: area.height / 8,
+        width: area.width * 4 / 5,
+        height: area.height * 3 / 4,
+    };
+    f.render_widget(Clear, popup_area);
+
+    let width = popup_area.width.saturating_sub(4) as usize;
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("/ ", theme.info_style().add_modifier(Modifier::BOLD)),
+            Span::styled(
+                format!("{}▏", view.input),
+                Style::default().fg(theme.text_primary),
+            ),
+        ]),
+        Line::from(""),
+    ];
+
+    if view.searching {
+        let spinner = app.loading_states.get_spinner_char();
+        let status = match view.progress {
+            Some(progress) => format!(
+                "{spinner} Indexing sessions… {}/{} files",
+                progress.scanned, progress.total
+            ),
+            None => format!("{spinner} Searching sessions…"),
+        };
+        lines.push(Line::from(Span::styled(status, theme.info_style())));
+    } else if let Some(error) = &view.error {
+        lines.push(Line::from(Span::styled(
+            error.clone(),
+            theme.danger_style(),
+        )));
+    } else if view.last_query.is_empty() {
+        for hint in [
+            "Type words to find, then press Enter",
+            "project:<name>  since:YYYY-MM-DD  until:YYYY-MM-DD  re:<regex>",
+        ] {
+            lines.push(Line::from(Span::styled(hint, theme.secondary_text_style())));
+        }
+    } else if view.hits.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "No matches",
+            theme.secondary_text_style(),
+        )));
+    } else {
+        // Each hit takes two rows; keep the selected one in view
+        let rows = popup_area.height.saturating_sub(5) as usize / 2;
+        let skip = (view.selected + 1).saturating_sub(rows.max(1));
+        for (i, hit) in view.hits.iter().enumerate().skip(skip).take(rows) {
+            let selected = i == view.selected;
+            let (marker, name_style) = if selected {
+                ("▶ ", theme.info_style().add_modifier(Modifier::BOLD))
+            } else {
+                ("  ", Style::default().fg(theme.accent))
+            };
+            let time = hit
+                .time
+                .map(|time| {
+                    chrono::DateTime::<chrono::Local>::from(time)
+                        .format("%Y-%m-%d %H:%M")
+                        .to_string()
+                })
+                .unwrap_or_default();
+            let role = match hit.role {
+                claude::transcript::Role::User => "You",
+                claude::transcript::Role::Assistant => "Claude",
+            };
+            lines.push(Line::from(vec![
+                Span::styled(format!("{marker}{}", hit.project_name), name_style),
+                Span::styled(
+                    format!(" • {} • {time} • {role}", short_id(&hit.session_id)),
+                    theme.dimmed_style(),
+                ),
+            ]));
+            let rows = wrap_text(&hit.snippet, width.saturating_sub(5));
+            let mut snippet = rows.first().cloned().unwrap_or_default();
+            if rows.len() > 1 {
+                snippet.push('…');
+            }
+            lines.push(Line::from(Span::styled(
+                format!("    {snippet}"),
+                theme.secondary_text_style(),
+            )));
+        }
+    }
+
+    let title = match view.hits.len() {
+        0 => "🔍 Search Sessions • Enter search, Esc close".to_string(),
+        n => format!("🔍 Search Sessions • {n} hits • ↑/↓ select, Enter open, Esc close"),
+    };
+    let card = ModernCard::new(Text::from(lines), theme).title(&title);
+    card.render(popup_area, f.buffer_mut());
+}
+
+/// Wrap `text` into rows of at most `width` columns, breaking after spaces where
+/// possible. Tabs become spaces and other control characters are dropped.
+fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut rows = Vec::new();
+
+    for line in text.lines() {
+        let line = line.replace('\t', "    ");
+        let mut row = String::new();
+        let mut row_width = 0;
+        for ch in line.chars().filter(|ch| !ch.is_control()) {
+            let ch_width = ch.width().unwrap_or(0);
+            while row_width + ch_width > width && !row.is_empty() {
+                match row.rfind(' ') {
+                    Some(space) if space > 0 => {
+                        let rest = row.split_off(space + 1);
+                        rows.push(row.trim_end().to_string());
+                        row = rest;
+                    }
+                    _ => rows.push(std::mem::take(&mut row)),
+                }
+                row_width = row.width();
+            }
+            row.push(ch);
+            row_width += ch_width;
+        }
+        rows.push(row);
+    }
+    rows
+}
+
 /// Draw the modern Todos tab with progress bars
 fn draw_modern_todos_tab(f: &mut Frame, area: Rect, app: &App, theme: &ModernTheme) {
     let todos = app.selected_project_todos();
@@ -669,64 +1246,187 @@ fn draw_modern_todos_tab(f: &mut Frame, area: Rect, app: &App, theme: &ModernThe
     }
 }
 
-/// Draw the modern Quota tab with gauges
-fn draw_modern_quota_tab(f: &mut Frame, area: Rect, app: &App, theme: &ModernTheme) {
-    let stats = &app.usage_stats;
-    let total_tokens = stats.input_tokens
-        + stats.output_tokens
-        + stats.cache_creation_tokens
-        + stats.cache_read_tokens;
-
-    let estimated_daily_limit = 20_000_000;
-    let _quota_percentage = (total_tokens as f64 / estimated_daily_limit as f64) * 100.0;
+/// Gauge colour for a share of a quota limit
+fn quota_variant(ratio: f64) -> ProgressVariant {
+    if ratio > 0.9 {
+        ProgressVariant::Danger
+    } else if ratio > 0.7 {
+        ProgressVariant::Warning
+    } else {
+        ProgressVariant::Success
+    }
+}
 
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Length(6), // Quota gauge
-            Constraint::Min(0),    // Details
-        ])
-        .split(area);
+/// Draw one quota window (block or week) as a card with a gauge along its bottom
+fn draw_quota_window(
+    f: &mut Frame,
+    area: Rect,
+    title: &str,
+    window: Option<&claude::quota::WindowUsage>,
+    footnote: &str,
+    theme: &ModernTheme,
+) {
+    let usage_line = match window {
+        Some(window) => {
+            let mut spans = vec![metric_span(format_number(window.tokens), theme)];
+            if let Some(limit) = window.limit {
+                spans.push(Span::styled(" / ", theme.secondary_text_style()));
+                spans.push(metric_span(format_number(limit), theme));
+            }
+            spans.push(Span::styled(
+                format!(" tokens{footnote}"),
+                theme.secondary_text_style(),
+            ));
+            Line::from(spans)
+        }
+        None => Line::from(Span::styled(footnote, theme.secondary_text_style())),
+    };
 
-    // Draw quota gauge
     let gauge_content = Text::from(vec![
         Line::from(vec![
             icon_span(ModernIcons::QUOTA, Style::default().fg(theme.accent)),
-            Span::styled(" Daily Quota Usage", theme.header_style()),
+            Span::styled(format!(" {title}"), theme.header_style()),
         ]),
         Line::from(""),
-        Line::from(vec![
-            metric_span(format_number(total_tokens), theme),
-            Span::styled(" / ", theme.secondary_text_style()),
-            metric_span(format_number(estimated_daily_limit), theme),
-            Span::styled(" tokens", theme.secondary_text_style()),
-        ]),
+        usage_line,
     ]);
 
     let gauge_card = ModernCard::new(gauge_content, theme).title("Quota");
-    gauge_card.render(chunks[0], f.buffer_mut());
+    gauge_card.render(area, f.buffer_mut());
 
-    // Render quota gauge
+    let Some(ratio) = window.and_then(|window| window.ratio()) else {
+        return;
+    };
     let gauge_area = Rect {
-        x: chunks[0].x + 2,
-        y: chunks[0].y + chunks[0].height - 2,
-        width: chunks[0].width - 4,
+        x: area.x + 2,
+        y: area.y + area.height - 2,
+        width: area.width.saturating_sub(4),
         height: 1,
     };
+    ModernGauge::new(ratio.min(1.0), theme)
+        .variant(quota_variant(ratio))
+        .render(gauge_area, f.buffer_mut());
+}
 
-    let quota_ratio = (total_tokens as f64 / estimated_daily_limit as f64).min(1.0);
-    let quota_gauge = ModernGauge::new(quota_ratio, theme).variant(if quota_ratio > 0.9 {
-        ProgressVariant::Danger
-    } else if quota_ratio > 0.7 {
-        ProgressVariant::Warning
-    } else {
-        ProgressVariant::Success
-    });
+/// Draw the modern Quota tab with gauges
+fn draw_modern_quota_tab(f: &mut Frame, area: Rect, app: &App, theme: &ModernTheme) {
+    let stats = &app.usage_stats;
+    let quota = &app.quota;
 
-    quota_gauge.render(gauge_area, f.buffer_mut());
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(6), // Block gauge
+            Constraint::Length(6), // Weekly gauge
+            Constraint::Length(6), // Forecast
+            Constraint::Min(0),    // Details
+        ])
+        .split(area);
+
+    let remaining = |window: &claude::quota::WindowUsage| match window.remaining() {
+        Some(left) => format!(", {} left", format_number(left)),
+        None => format!(" (no limit on the {} plan)", quota.plan),
+    };
+    let block_note = match &quota.block {
+        Some(block) => format!("{}, resets in {}", remaining(block), app.reset_time_str),
+        None => "No active block".to_string(),
+    };
+    let block_title = match quota.block_limit_source {
+        claude::quota::LimitSource::Configured => "5-Hour Block (configured limit)".to_string(),
+        claude::quota::LimitSource::Inferred => "5-Hour Block (inferred limit)".to_string(),
+        _ => format!("5-Hour Block ({} plan)", quota.plan),
+    };
+    draw_quota_window(
+        f,
+        chunks[0],
+        &block_title,
+        quota.block.as_ref(),
+        &block_note,
+        theme,
+    );
+    draw_quota_window(
+        f,
+        chunks[1],
+        "Last 7 Days",
+        Some(&quota.week),
+        &remaining(&quota.week),
+        theme,
+    );
+    draw_quota_forecast(f, chunks[2], app, theme);
+
+    // Block limit inferred from history, and whether one is pinned
+    let inferred_line = match &quota.inferred {
+        Some(inferred) => Line::from(vec![
+            metric_span(format_number(inferred.tokens), theme),
+            Span::styled(
+                format!(
+                    " tokens ({} of {} past blocks, {} confidence)",
+                    inferred.method, inferred.samples, inferred.confidence
+                ),
+                theme.secondary_text_style(),
+            ),
+        ]),
+        None => Line::from(Span::styled(
+            "Not enough history yet",
+            theme.secondary_text_style(),
+        )),
+    };
+    let pin_hint = match app.config.quota.block_tokens {
+        Some(pinned) => format!("Block limit set to {} (p to unpin)", format_number(pinned)),
+        None if quota.inferred.is_some() => "Press p to pin it as your block limit".to_string(),
+        None => String::new(),
+    };
+
+    // Spend in each budget period, against its budget where one is set
+    let mut budget_line = Vec::new();
+    for period in claude::budget::BudgetPeriod::ALL {
+        let label = match period {
+            claude::budget::BudgetPeriod::Day => "Today: ",
+            claude::budget::BudgetPeriod::Week => "  Week: ",
+            claude::budget::BudgetPeriod::Month => "  Month: ",
+        };
+        let spent = quota.spend.spent(period);
+        budget_line.push(Span::styled(label, theme.secondary_text_style()));
+        match app.config.budgets.limit(period) {
+            Some(budget) => {
+                let style = if spent >= budget {
+                    theme.danger_style()
+                } else if spent >= budget * 0.8 {
+                    theme.warning_style()
+                } else {
+                    theme.success_style()
+                };
+                budget_line.push(Span::styled(format_cost(spent), style));
+                budget_line.push(Span::styled(
+                    format!(" / {}", format_cost(budget)),
+                    theme.dimmed_style(),
+                ));
+            }
+            None => budget_line.push(metric_span(format_cost(spent), theme)),
+        }
+    }
 
     // Draw details
     let details_content = Text::from(vec![
+        Line::from(vec![
+            icon_span(ModernIcons::QUOTA, Style::default().fg(theme.accent)),
+            Span::styled(
+                " Inferred Block Limit:",
+                theme.warning_style().add_modifier(Modifier::BOLD),
+            ),
+        ]),
+        inferred_line,
+        Line::from(Span::styled(pin_hint, theme.dimmed_style())),
+        Line::from(""),
+        Line::from(vec![
+            icon_span(ModernIcons::TIME, Style::default().fg(theme.warning)),
+            Span::styled(
+                " Budgets:",
+                theme.warning_style().add_modifier(Modifier::BOLD),
+            ),
+        ]),
+        Line::from(budget_line),
+        Line::from(""),
         Line::from(vec![
             icon_span(ModernIcons::USAGE, Style::default().fg(theme.info)),
             Span::styled(
@@ -785,7 +1485,295 @@ fn draw_modern_quota_tab(f: &mut Frame, area: Rect, app: &App, theme: &ModernThe
     ]);
 
     let details_card = ModernCard::new(details_content, theme).title("Usage Details");
-    details_card.render(chunks[1], f.buffer_mut());
+    details_card.render(chunks[3], f.buffer_mut());
+}
+
+/// Describe when a limit is expected to be reached, with the confidence band
+fn forecast_spans(
+    forecast: &claude::forecast::LimitForecast,
+    theme: &ModernTheme,
+) -> Vec<Span<'static>> {
+    use claude::forecast::LimitForecast;
+
+    match forecast {
+        LimitForecast::Reached => vec![Span::styled("reached", theme.danger_style())],
+        LimitForecast::At {
+            earliest,
+            expected,
+            latest,
+            deadline,
+        } => {
+            let expected = match expected {
+                Some(expected) => format!("at ~{}", block_clock(*expected)),
+                None => format!("possibly before {}", block_clock(*deadline)),
+            };
+            let latest =
+                latest.map_or_else(|| format!("after {}", block_clock(*deadline)), block_clock);
+            vec![
+                Span::styled(expected, theme.warning_style()),
+                Span::styled(
+                    format!(" ({} - {latest})", block_clock(*earliest)),
+                    theme.secondary_text_style(),
+                ),
+            ]
+        }
+        LimitForecast::NotBefore(deadline) => vec![Span::styled(
+            format!("not before {}", block_clock(*deadline)),
+            theme.success_style(),
+        )],
+    }
+}
+
+/// A bar from now to the deadline with the confidence band shaded and the
+/// expected time marked
+fn forecast_band(
+    forecast: &claude::forecast::LimitForecast,
+    now: chrono::DateTime<chrono::Utc>,
+    width: usize,
+    theme: &ModernTheme,
+) -> Vec<Span<'static>> {
+    use claude::forecast::LimitForecast;
+
+    let LimitForecast::At {
+        earliest,
+        expected,
+        latest,
+        deadline,
+    } = *forecast
+    else {
+        return Vec::new();
+    };
+    let span_secs = (deadline - now).num_seconds().max(1) as f64;
+    let column = |time: chrono::DateTime<chrono::Utc>| {
+        let share = (time - now).num_seconds() as f64 / span_secs;
+        ((share.clamp(0.0, 1.0) * width.saturating_sub(1) as f64).round()) as usize
+    };
+    let band = column(earliest)..=latest.map_or(width.saturating_sub(1), column);
+    let marker = expected.map(column);
+
+    (0..width)
+        .map(|i| {
+            if Some(i) == marker {
+                Span::styled("┃", theme.danger_style())
+            } else if band.contains(&i) {
+                Span::styled("▒", theme.warning_style())
+            } else {
+                Span::styled("─", theme.dimmed_style())
+            }
+        })
+        .collect()
+}
+
+/// Draw when the block limit and the daily budget will be reached at the current burn rate
+fn draw_quota_forecast(f: &mut Frame, area: Rect, app: &App, theme: &ModernTheme) {
+    let burn = &app.quota.burn;
+    let rate_note = |rate: String| Span::styled(format!(" • {rate}"), theme.dimmed_style());
+
+    let mut block_line = vec![
+        icon_span(ModernIcons::QUOTA, Style::default().fg(theme.accent)),
+        Span::styled(" Block limit: ", theme.secondary_text_style()),
+    ];
+    let mut band_line = Vec::new();
+    let block_forecast = app
+        .quota
+        .block
+        .as_ref()
+        .and_then(|block| burn.block_forecast(block));
+    match (&app.quota.block, block_forecast) {
+        (Some(block), Some(forecast)) => {
+            block_line.extend(forecast_spans(&forecast, theme));
+            if let Some(rate) = burn.tokens_per_minute() {
+                block_line.push(rate_note(format!("{} tok/min", format_number(rate as u64))));
+            }
+            let label = format!(" {}", block_clock(block.end));
+            let width = (area.width as usize).saturating_sub(4 + 4 + label.len());
+            band_line.push(Span::styled("now ", theme.dimmed_style()));
+            band_line.extend(forecast_band(&forecast, burn.now, width, theme));
+            band_line.push(Span::styled(label, theme.dimmed_style()));
+        }
+        (Some(_), None) => block_line.push(Span::styled(
+            "no limit to forecast",
+            theme.secondary_text_style(),
+        )),
+        (None, _) => block_line.push(Span::styled(
+            "no active block",
+            theme.secondary_text_style(),
+        )),
+    }
+
+    let mut budget_line = vec![
+        icon_span(ModernIcons::TIME, Style::default().fg(theme.warning)),
+        Span::styled(" Daily budget", theme.secondary_text_style()),
+    ];
+    match app.config.budgets.daily_usd {
+        Some(budget) => {
+            budget_line.push(Span::styled(
+                format!(" {}: ", format_cost(budget)),
+                theme.secondary_text_style(),
+            ));
+            budget_line.extend(forecast_spans(&burn.budget_forecast(budget), theme));
+            if let Some(rate) = burn.cost_per_hour() {
+                budget_line.push(rate_note(format!("{}/h", format_cost(rate))));
+            }
+        }
+        None => budget_line.push(Span::styled(
+            ": not set (budgets.daily_usd in config)",
+            theme.dimmed_style(),
+        )),
+    }
+
+    let content = Text::from(vec![
+        Line::from(block_line),
+        Line::from(band_line),
+        Line::from(""),
+        Line::from(budget_line),
+    ]);
+    ModernCard::new(content, theme)
+        .title("Forecast")
+        .render(area, f.buffer_mut());
+}
+
+/// Local `HH:MM` of a block boundary
+fn block_clock(time: chrono::DateTime<chrono::Utc>) -> String {
+    chrono::DateTime::<chrono::Local>::from(time)
+        .format("%H:%M")
+        .to_string()
+}
+
+/// Draw the Blocks tab: the active block's projection above the timeline of every block
+fn draw_modern_blocks_tab(f: &mut Frame, area: Rect, app: &mut App, theme: &ModernTheme) {
+    let blocks = app.get_usage_blocks().to_vec();
+    let block_limit = app.quota.block.as_ref().and_then(|block| block.limit);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(6), // Active block
+            Constraint::Min(0),    // Timeline
+        ])
+        .split(area);
+
+    let active = blocks.iter().rev().find(|block| block.is_active);
+    let active_lines = match active {
+        Some(block) => {
+            let mut projected = vec![Span::styled("Projected: ", theme.secondary_text_style())];
+            if let Some(projection) = block.projection {
+                projected.push(metric_span(format_number(projection.tokens), theme));
+                projected.push(Span::styled(
+                    format!(
+                        " tokens, {} by {}",
+                        format_cost(projection.cost),
+                        block_clock(block.end)
+                    ),
+                    theme.secondary_text_style(),
+                ));
+                if let Some(limit) = block_limit.filter(|limit| *limit > 0) {
+                    let ratio = projection.tokens as f64 / limit as f64;
+                    let style = match quota_variant(ratio) {
+                        ProgressVariant::Danger => theme.danger_style(),
+                        ProgressVariant::Warning => theme.warning_style(),
+                        _ => theme.success_style(),
+                    };
+                    projected.push(Span::styled(
+                        format!(" ({:.0}% of limit)", ratio * 100.0),
+                        style,
+                    ));
+                }
+            }
+            vec![
+                Line::from(vec![
+                    icon_span(ModernIcons::ACTIVE, Style::default().fg(theme.success)),
+                    Span::styled(
+                        format!(" {} - {}", block_clock(block.start), block_clock(block.end)),
+                        theme.header_style(),
+                    ),
+                ]),
+                Line::from(vec![
+                    Span::styled("So far: ", theme.secondary_text_style()),
+                    metric_span(format_number(block.tokens), theme),
+                    Span::styled(
+                        format!(" tokens, {}", format_cost(block.cost)),
+                        theme.secondary_text_style(),
+                    ),
+                ]),
+                Line::from(projected),
+            ]
+        }
+        None => vec![Line::from(Span::styled(
+            match blocks.last() {
+                Some(last) => format!(
+                    "No active block; the last one ended {} ago",
+                    format_duration(chrono::Utc::now() - last.end)
+                ),
+                None => "No blocks yet".to_string(),
+            },
+            theme.secondary_text_style(),
+        ))],
+    };
+    ModernCard::new(Text::from(active_lines), theme)
+        .title("Active Block")
+        .render(chunks[0], f.buffer_mut());
+
+    // Newest first, with the idle gap before each block below it
+    let mut list_items = Vec::new();
+    for (i, block) in blocks.iter().enumerate().rev() {
+        let (icon, icon_color) = if block.is_active {
+            (ModernIcons::ACTIVE, theme.success)
+        } else {
+            (ModernIcons::INACTIVE, theme.info)
+        };
+        let models: Vec<&str> = block
+            .models
+            .iter()
+            .map(|model| model.strip_prefix("claude-").unwrap_or(model))
+            .collect();
+        let projects: Vec<&str> = block.projects.iter().map(|p| p.name.as_str()).collect();
+
+        list_items.push(ModernListItem::new(Line::from(vec![
+            icon_span(icon, Style::default().fg(icon_color)),
+            Span::styled(
+                format!(
+                    " {} - {}",
+                    chrono::DateTime::<chrono::Local>::from(block.start).format("%m-%d %H:%M"),
+                    block_clock(block.end)
+                ),
+                Style::default().fg(theme.accent),
+            ),
+            Span::styled(
+                format!(" • {} tokens", format_number(block.tokens)),
+                theme.secondary_text_style(),
+            ),
+            Span::styled(
+                format!(" • {}", format_cost(block.cost)),
+                theme.warning_style(),
+            ),
+            Span::styled(format!(" • {}", models.join(", ")), theme.info_style()),
+            Span::styled(format!(" • {}", projects.join(", ")), theme.dimmed_style()),
+        ])));
+
+        if let Some(previous) = i.checked_sub(1).map(|prev| &blocks[prev]) {
+            if block.start > previous.end {
+                list_items.push(ModernListItem::new(Line::from(Span::styled(
+                    format!("   idle {}", format_duration(block.start - previous.end)),
+                    theme.dimmed_style(),
+                ))));
+            }
+        }
+    }
+
+    if list_items.is_empty() {
+        let empty_card =
+            ModernCard::new(Text::from("No usage blocks found"), theme).title("Block Timeline");
+        empty_card.render(chunks[1], f.buffer_mut());
+        return;
+    }
+
+    app.blocks_scroll = app.blocks_scroll.min(list_items.len() - 1);
+    let visible: Vec<ModernListItem> = list_items.into_iter().skip(app.blocks_scroll).collect();
+    let title = format!("Block Timeline ({} blocks)", blocks.len());
+    ModernList::new(visible, theme)
+        .title(&title)
+        .render(chunks[1], f.buffer_mut());
 }
 
 /// Draw the modern footer with enhanced styling
@@ -859,6 +1847,8 @@ fn draw_modern_footer(f: &mut Frame, area: Rect, app: &App, theme: &ModernTheme)
                     .add_modifier(Modifier::BOLD),
             ),
             Span::styled(" | ", theme.secondary_text_style()),
+            Span::styled("/", theme.info_style().add_modifier(Modifier::BOLD)),
+            Span::styled(" search | ", theme.secondary_text_style()),
             Span::styled("q", theme.danger_style().add_modifier(Modifier::BOLD)),
             Span::styled(
                 format!(" {} | ", app.i18n.t("controls.quit")),
@@ -1127,6 +2117,22 @@ fn draw_help_overlay(f: &mut Frame, area: Rect, app: &App, theme: &ModernTheme)
                 app.i18n.t("help.sessions.line7"),
                 theme.secondary_text_style(),
             )]),
+            Line::from(vec![Span::styled(
+                app.i18n.t("help.sessions.line8"),
+                theme.secondary_text_style(),
+            )]),
+            Line::from(vec![Span::styled(
+                app.i18n.t("help.sessions.line9"),
+                theme.secondary_text_style(),
+            )]),
+            Line::from(vec![Span::styled(
+                app.i18n.t("help.sessions.line10"),
+                theme.secondary_text_style(),
+            )]),
+            Line::from(vec![Span::styled(
+                app.i18n.t("help.sessions.line11"),
+                theme.secondary_text_style(),
+            )]),
         ]),
         crate::app::Tab::Quota => Text::from(vec![
             Line::from(vec![Span::styled(
@@ -1178,6 +2184,40 @@ fn draw_help_overlay(f: &mut Frame, area: Rect, app: &App, theme: &ModernTheme)
                 theme.secondary_text_style(),
             )]),
         ]),
+        crate::app::Tab::Blocks => Text::from(vec![
+            Line::from(vec![Span::styled(
+                app.i18n.t("help.blocks.title"),
+                theme.header_style(),
+            )]),
+            Line::from(vec![Span::styled(
+                app.i18n.t("help.blocks.line1"),
+                theme.secondary_text_style(),
+            )]),
+            Line::from(vec![Span::styled(
+                app.i18n.t("help.blocks.line2"),
+                theme.secondary_text_style(),
+            )]),
+            Line::from(vec![Span::styled(
+                app.i18n.t("help.blocks.line3"),
+                theme.secondary_text_style(),
+            )]),
+            Line::from(vec![Span::styled(
+                app.i18n.t("help.blocks.line4"),
+                theme.secondary_text_style(),
+            )]),
+            Line::from(vec![Span::styled(
+                app.i18n.t("help.blocks.line5"),
+                theme.secondary_text_style(),
+            )]),
+            Line::from(vec![Span::styled(
+                app.i18n.t("help.blocks.line6"),
+                theme.secondary_text_style(),
+            )]),
+            Line::from(vec![Span::styled(
+                app.i18n.t("help.blocks.line7"),
+                theme.secondary_text_style(),
+            )]),
+        ]),
         crate::app::Tab::Todos => Text::from(vec![
             Line::from(vec![Span::styled(
                 app.i18n.t("help.todos.title"),
@@ -1221,11 +2261,13 @@ fn draw_help_overlay(f: &mut Frame, area: Rect, app: &App, theme: &ModernTheme)
 }
 
 /// Format numbers with K/M suffixes for readability
-fn format_number(num: u32) -> String {
-    if num >= 1_000_000 {
-        format!("{:.1}M", num as f32 / 1_000_000.0)
+fn format_number(num: u64) -> String {
+    if num >= 1_000_000_000 {
+        format!("{:.1}B", num as f64 / 1_000_000_000.0)
+    } else if num >= 1_000_000 {
+        format!("{:.1}M", num as f64 / 1_000_000.0)
     } else if num >= 1_000 {
-        format!("{:.1}K", num as f32 / 1_000.0)
+        format!("{:.1}K", num as f64 / 1_000.0)
     } else {
         num.to_string()
     }
@@ -1262,12 +2304,7 @@ fn draw_project_usage_card(
             .unwrap_or_else(|| crate::claude::ProjectAnalytics {
                 total_sessions: project.sessions.len(),
                 total_messages: project.sessions.iter().map(|s| s.message_count).sum(),
-                total_tokens: 0,
-                estimated_cost: 0.0,
-                first_session: None,
-                last_session: None,
-                cache_efficiency: 0.0,
-                session_blocks: Vec::new(),
+                ..Default::default()
             });
 
     // Calculate recent activity (last 7 days)
@@ -1626,7 +2663,10 @@ fn draw_model_distribution_card(
                 Span::styled(
                     format!(
                         "{}K",
-                        (model.total_input_tokens + model.total_output_tokens) / 1000
+                        model
+                            .total_input_tokens
+                            .saturating_add(model.total_output_tokens)
+                            / 1000
                     ),
                     theme.info_style(),
                 ),
@@ -1678,13 +2718,13 @@ fn draw_hourly_pattern_card(
         // Group hours for better visualization (every 4 hours)
         for chunk_start in (0..24).step_by(4) {
             let chunk_end = (chunk_start + 4).min(24);
-            let chunk_tokens: u32 = analytics
+            let chunk_tokens: u64 = analytics
                 .hourly_patterns
                 .iter()
                 .skip(chunk_start)
                 .take(chunk_end - chunk_start)
                 .map(|h| h.total_tokens)
-                .sum();
+                .fold(0, u64::saturating_add);
 
             let intensity = (chunk_tokens as f64 / max_tokens as f64).min(1.0);
             let heat_level = (intensity * 5.0) as usize;
@@ -1911,7 +2951,7 @@ fn draw_top_sessions_card(
                 Span::styled(format!(" ({duration_str})"), theme.dimmed_style()),
             ]));
 
-            content_lines.push(Line::from(vec![
+            let mut cost_line = vec![
                 Span::styled("   ", Style::default()),
                 Span::styled(format_cost(session.total_cost), theme.warning_style()),
                 Span::styled(
@@ -1922,7 +2962,14 @@ fn draw_top_sessions_card(
                     ),
                     theme.secondary_text_style(),
                 ),
-            ]));
+            ];
+            if session.subagent_cost > 0.0 {
+                cost_line.push(Span::styled(
+                    format!(" • subagents {}", format_cost(session.subagent_cost)),
+                    theme.dimmed_style(),
+                ));
+            }
+            content_lines.push(Line::from(cost_line));
         }
 
         // Total sessions summary
@@ -1955,10 +3002,7 @@ fn draw_top_sessions_card(
 /// Draw the global dashboard header
 fn draw_global_header(f: &mut Frame, area: Rect, app: &App, theme: &ModernTheme) {
     let stats = &app.usage_stats;
-    let _total_tokens = stats.input_tokens
-        + stats.output_tokens
-        + stats.cache_creation_tokens
-        + stats.cache_read_tokens;
+    let _total_tokens = stats.total_tokens();
 
     let header_text = vec![
         Span::styled("🌍 ", theme.success_style()),
@@ -1986,6 +3030,7 @@ fn draw_global_header(f: &mut Frame, area: Rect, app: &App, theme: &ModernTheme)
 
 /// Draw the global dashboard main content (full-screen analytics)
 fn draw_global_dashboard(f: &mut Frame, area: Rect, app: &mut App, theme: &ModernTheme) {
+    let periods = app.config.periods.clone();
     // Get global analytics (this may trigger computation if cache is stale)
     if let Ok(analytics) = app.get_global_analytics() {
         // Create a comprehensive full-screen layout for global analytics
@@ -2008,9 +3053,10 @@ fn draw_global_dashboard(f: &mut Frame, area: Rect, app: &mut App, theme: &Moder
         let bottom_chunks = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([
-                Constraint::Percentage(33), // Project rankings
-                Constraint::Percentage(33), // Cache performance
-                Constraint::Percentage(34), // Session insights
+                Constraint::Percentage(25), // Project rankings
+                Constraint::Percentage(25), // Cache performance
+                Constraint::Percentage(25), // Session insights
+                Constraint::Percentage(25), // Tool usage
             ])
             .split(main_chunks[1]);
 
@@ -2023,7 +3069,7 @@ fn draw_global_dashboard(f: &mut Frame, area: Rect, app: &mut App, theme: &Moder
             .split(top_chunks[1]);
 
         // Render all sections
-        draw_global_daily_trends(f, top_chunks[0], &analytics.daily_usage, theme);
+        draw_global_daily_trends(f, top_chunks[0], &analytics.daily_usage, &periods, theme);
         draw_global_model_distribution(
             f,
             right_top_chunks[0],
@@ -2034,6 +3080,7 @@ fn draw_global_dashboard(f: &mut Frame, area: Rect, app: &mut App, theme: &Moder
         draw_global_project_rankings(f, bottom_chunks[0], &analytics.project_usage, theme);
         draw_global_cache_performance(f, bottom_chunks[1], &analytics.cache_efficiency, theme);
         draw_global_session_insights(f, bottom_chunks[2], &analytics.session_analytics, theme);
+        draw_tool_usage_card(f, bottom_chunks[3], &analytics.tool_usage, theme);
     } else {
         // Show loading or error state
         let error_card = ModernCard::new(
@@ -2068,6 +3115,7 @@ fn draw_global_daily_trends(
     f: &mut Frame,
     area: Rect,
     daily_usage: &[claude::DailyUsageDetail],
+    periods: &claude::periods::PeriodSettings,
     theme: &ModernTheme,
 ) {
     let mut content_lines = vec![
@@ -2121,15 +3169,15 @@ fn draw_global_daily_trends(
 
         // Summary statistics
         let total_cost: f64 = recent_usage.iter().map(|u| u.total_cost).sum();
-        let total_tokens: u32 = recent_usage
+        let total_tokens: u64 = recent_usage
             .iter()
             .map(|u| {
                 u.total_input_tokens
-                    + u.total_output_tokens
-                    + u.total_cache_creation_tokens
-                    + u.total_cache_read_tokens
+                    .saturating_add(u.total_output_tokens)
+                    .saturating_add(u.total_cache_creation_tokens)
+                    .saturating_add(u.total_cache_read_tokens)
             })
-            .sum();
+            .fold(0, u64::saturating_add);
         let avg_cost = total_cost / recent_usage.len() as f64;
 
         content_lines.extend(vec![
@@ -2155,6 +3203,43 @@ fn draw_global_daily_trends(
         ]);
     }
 
+    // This week and the billing month so far, over the whole history
+    let today = chrono::Local::now().date_naive();
+    let days: Vec<(chrono::NaiveDate, f64)> = daily_usage
+        .iter()
+        .filter_map(|usage| {
+            chrono::NaiveDate::parse_from_str(&usage.date, "%Y-%m-%d")
+                .ok()
+                .map(|date| (date, usage.total_cost))
+        })
+        .collect();
+    let week = periods.period(claude::periods::PeriodKind::Week, today);
+    let week_cost: f64 = days
+        .iter()
+        .filter(|(date, _)| week.contains(*date))
+        .map(|(_, cost)| cost)
+        .sum();
+    let mtd = claude::periods::MonthToDate::compute(days, periods, today);
+    content_lines.extend(vec![
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("This week: ", theme.secondary_text_style()),
+            Span::styled(format_cost(week_cost), theme.warning_style()),
+        ]),
+        Line::from(vec![
+            Span::styled("Month to date: ", theme.secondary_text_style()),
+            Span::styled(format_cost(mtd.spent), theme.warning_style()),
+            Span::styled(
+                format!(" (day {} of {})", mtd.days_elapsed, mtd.period.days()),
+                theme.dimmed_style(),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("Projected month: ", theme.secondary_text_style()),
+            Span::styled(format_cost(mtd.projected), theme.danger_style()),
+        ]),
+    ]);
+
     let card = ModernCard::new(Text::from(content_lines), theme).title("📈 Global Daily Trends");
     card.render(area, f.buffer_mut());
 }
@@ -2440,14 +3525,21 @@ fn draw_global_session_insights(
                 Span::styled(session_id_short, theme.secondary_text_style()),
                 Span::styled("…", theme.secondary_text_style()),
             ]));
-            content_lines.push(Line::from(vec![
+            let mut cost_line = vec![
                 Span::styled("   ", theme.secondary_text_style()),
                 Span::styled(format_cost(session.total_cost), theme.warning_style()),
                 Span::styled(
                     format!(" ({}m)", session.duration_minutes as u32),
                     theme.secondary_text_style(),
                 ),
-            ]));
+            ];
+            if session.subagent_cost > 0.0 {
+                cost_line.push(Span::styled(
+                    format!(" subagents {}", format_cost(session.subagent_cost)),
+                    theme.dimmed_style(),
+                ));
+            }
+            content_lines.push(Line::from(cost_line));
         }
 
         // Summary stats